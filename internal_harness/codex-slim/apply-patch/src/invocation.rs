@@ -15,10 +15,11 @@ use crate::ApplyPatchFileChange;
 use crate::ApplyPatchFileUpdate;
 use crate::IoError;
 use crate::MaybeApplyPatchVerified;
+use crate::looks_binary;
 use crate::parser::Hunk;
 use crate::parser::ParseError;
 use crate::parser::parse_patch;
-use crate::unified_diff_from_chunks;
+use crate::unified_diff_from_chunks_with_base;
 use std::str::Utf8Error;
 use tree_sitter::LanguageError;
 
@@ -167,9 +168,21 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                     Hunk::AddFile { contents, .. } => {
                         changes.insert(path, ApplyPatchFileChange::Add { content: contents });
                     }
+                    Hunk::AddBinaryFile { contents, .. } => {
+                        changes.insert(
+                            path,
+                            ApplyPatchFileChange::Add {
+                                content: binary_placeholder(contents.len()),
+                            },
+                        );
+                    }
                     Hunk::DeleteFile { .. } => {
-                        let content = match std::fs::read_to_string(&path) {
-                            Ok(content) => content,
+                        let content = match std::fs::read(&path) {
+                            Ok(content) => match String::from_utf8(content) {
+                                Ok(content) if !looks_binary(content.as_bytes()) => content,
+                                Ok(content) => binary_placeholder(content.len()),
+                                Err(err) => binary_placeholder(err.as_bytes().len()),
+                            },
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(
                                     ApplyPatchError::IoError(IoError {
@@ -182,12 +195,21 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                         changes.insert(path, ApplyPatchFileChange::Delete { content });
                     }
                     Hunk::UpdateFile {
-                        move_path, chunks, ..
+                        move_path,
+                        base,
+                        chunks,
+                        ..
                     } => {
+                        let base = base.map(|base| base.resolve(&effective_cwd));
                         let ApplyPatchFileUpdate {
                             unified_diff,
                             content: contents,
-                        } = match unified_diff_from_chunks(&path, &chunks) {
+                        } = match unified_diff_from_chunks_with_base(
+                            &path,
+                            &chunks,
+                            base.as_ref(),
+                            1,
+                        ) {
                             Ok(diff) => diff,
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(e);
@@ -216,6 +238,11 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
     }
 }
 
+/// Stand-in content shown for binary files, which cannot be displayed as text.
+fn binary_placeholder(len: usize) -> String {
+    format!("<binary file, {len} bytes>")
+}
+
 /// Extract the heredoc body (and optional `cd` workdir) from a `bash -lc` script
 /// that invokes the apply_patch tool using a heredoc.
 ///
@@ -371,6 +398,7 @@ fn extract_apply_patch_from_bash(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::unified_diff_from_chunks;
     use assert_matches::assert_matches;
     use pretty_assertions::assert_eq;
     use std::fs;
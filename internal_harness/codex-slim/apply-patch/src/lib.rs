@@ -1,21 +1,30 @@
 mod invocation;
+mod merge;
 mod parser;
 mod seek_sequence;
 mod standalone_executable;
+mod streaming;
+mod transaction;
+mod unified_diff;
 
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 
-use anyhow::Context;
 use anyhow::Result;
 pub use parser::Hunk;
+pub use parser::MergeBase;
 pub use parser::ParseError;
 use parser::ParseError::*;
 use parser::UpdateFileChunk;
 pub use parser::parse_patch;
+use seek_sequence::FuzzyCandidate;
+use seek_sequence::FuzzyMatch;
+use similar::ChangeTag;
 use similar::TextDiff;
 use thiserror::Error;
+use transaction::PatchPlan;
+use transaction::stream_threshold;
 
 pub use invocation::maybe_parse_apply_patch_verified;
 pub use standalone_executable::main;
@@ -34,6 +43,17 @@ pub const APPLY_PATCH_TOOL_INSTRUCTIONS: &str = include_str!("../apply_patch_too
 /// dispatcher.
 pub const CODEX_CORE_APPLY_PATCH_ARG1: &str = "--codex-run-as-apply-patch";
 
+/// Files larger than this are updated by streaming them line by line instead
+/// of reading them into memory.
+pub const DEFAULT_STREAM_THRESHOLD_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Environment variable overriding [`DEFAULT_STREAM_THRESHOLD_BYTES`].
+pub const STREAM_THRESHOLD_ENV_VAR: &str = "CODEX_APPLY_PATCH_STREAM_THRESHOLD";
+
+/// Number of leading bytes inspected for NUL bytes when deciding whether a file
+/// is binary, matching git's heuristic.
+const BINARY_SNIFF_LEN: usize = 8000;
+
 #[derive(Debug, Error, PartialEq)]
 pub enum ApplyPatchError {
     #[error(transparent)]
@@ -43,6 +63,12 @@ pub enum ApplyPatchError {
     /// Error that occurs while computing replacements when applying patch chunks
     #[error("{0}")]
     ComputeReplacements(String),
+    /// A chunk's expected lines matched no location, or several equally well.
+    #[error(transparent)]
+    HunkMismatch(#[from] HunkMismatch),
+    /// A text update targets a file that is not valid UTF-8 text.
+    #[error("Cannot apply text hunks to binary file {}", .0.display())]
+    BinaryFile(PathBuf),
     /// A raw patch body was provided without an explicit `apply_patch` invocation.
     #[error(
         "patch detected without explicit call to apply_patch. Rerun as [\"apply_patch\", \"<patch>\"]"
@@ -82,6 +108,77 @@ impl PartialEq for IoError {
     }
 }
 
+/// Reports where a chunk's `old_lines` came closest to matching so the caller
+/// can retry with precise context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkMismatch {
+    pub path: PathBuf,
+    pub expected_lines: Vec<String>,
+    /// True when several locations matched equally well rather than none.
+    pub ambiguous: bool,
+    /// Nearest locations in the file, most similar first.
+    pub candidates: Vec<HunkCandidate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkCandidate {
+    /// 1-based line number where the candidate location starts.
+    pub line_number: usize,
+    /// Average per-line similarity to the expected lines, from 0.0 to 1.0.
+    pub similarity: f64,
+    /// Unified diff from the expected lines to the file's lines at this location.
+    pub diff: String,
+}
+
+impl std::fmt::Display for HunkMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ambiguous {
+            write!(
+                f,
+                "Expected lines match {} locations equally well in {}; add more context lines:\n{}",
+                self.candidates.len(),
+                self.path.display(),
+                self.expected_lines.join("\n"),
+            )?;
+        } else {
+            write!(
+                f,
+                "Failed to find expected lines in {}:\n{}",
+                self.path.display(),
+                self.expected_lines.join("\n"),
+            )?;
+        }
+        if !self.candidates.is_empty() {
+            write!(f, "\n\nNearest matches:")?;
+        }
+        for candidate in &self.candidates {
+            write!(
+                f,
+                "\n- line {} ({:.0}% similar):\n{}",
+                candidate.line_number,
+                candidate.similarity * 100.0,
+                candidate.diff.trim_end(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HunkMismatch {}
+
+/// A region where the working copy and the patch changed the same lines of the
+/// merge base differently. The merged file holds conflict markers there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// 1-based line of the opening conflict marker in the merged file.
+    pub line_number: usize,
+    pub base: Vec<String>,
+    /// The working copy's version of the region.
+    pub current: Vec<String>,
+    /// The patch's version of the region.
+    pub patch: Vec<String>,
+}
+
 /// Both the raw PATCH argument to `apply_patch` as well as the PATCH argument
 /// parsed into hunks.
 #[derive(Debug, PartialEq)]
@@ -100,9 +197,13 @@ pub enum ApplyPatchFileChange {
         content: String,
     },
     Update {
+        /// For files larger than the streaming threshold, which are checked
+        /// without being read into memory, this lists each chunk's changes
+        /// without line numbers.
         unified_diff: String,
         move_path: Option<PathBuf>,
         /// new_content that will result after the unified_diff is applied.
+        /// Empty for files larger than the streaming threshold.
         new_content: String,
     },
 }
@@ -179,11 +280,33 @@ impl ApplyPatchAction {
     }
 }
 
+/// How [`apply_patch_with_mode`] treats the filesystem.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Apply every hunk or none of them.
+    #[default]
+    Transactional,
+    /// Validate every hunk and print the resulting unified diff without
+    /// writing anything.
+    DryRun,
+}
+
 /// Applies the patch and prints the result to stdout/stderr.
 pub fn apply_patch(
     patch: &str,
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
+) -> Result<(), ApplyPatchError> {
+    apply_patch_with_mode(patch, ApplyMode::Transactional, stdout, stderr)
+}
+
+/// Like [`apply_patch`], but in [`ApplyMode::DryRun`] prints the diff the patch
+/// would produce instead of applying it.
+pub fn apply_patch_with_mode(
+    patch: &str,
+    mode: ApplyMode,
+    stdout: &mut impl std::io::Write,
+    stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
     let hunks = match parse_patch(patch) {
         Ok(source) => source.hunks,
@@ -207,7 +330,15 @@ pub fn apply_patch(
         }
     };
 
-    apply_hunks(&hunks, stdout, stderr)?;
+    match mode {
+        ApplyMode::Transactional => apply_hunks(&hunks, stdout, stderr)?,
+        ApplyMode::DryRun => {
+            match PatchPlan::from_hunks(&hunks).and_then(|plan| plan.unified_diff()) {
+                Ok(diff) => write!(stdout, "{diff}").map_err(ApplyPatchError::from)?,
+                Err(err) => return Err(report_apply_error(err, stderr)),
+            }
+        }
+    }
 
     Ok(())
 }
@@ -221,7 +352,7 @@ pub fn apply_hunks(
     let _existing_paths: Vec<&Path> = hunks
         .iter()
         .filter_map(|hunk| match hunk {
-            Hunk::AddFile { .. } => {
+            Hunk::AddFile { .. } | Hunk::AddBinaryFile { .. } => {
                 // The file is being added, so it doesn't exist yet.
                 None
             }
@@ -250,18 +381,28 @@ pub fn apply_hunks(
             print_summary(&affected, stdout).map_err(ApplyPatchError::from)?;
             Ok(())
         }
-        Err(err) => {
-            let msg = err.to_string();
-            writeln!(stderr, "{msg}").map_err(ApplyPatchError::from)?;
-            if let Some(io) = err.downcast_ref::<std::io::Error>() {
-                Err(ApplyPatchError::from(io))
-            } else {
-                Err(ApplyPatchError::IoError(IoError {
-                    context: msg,
-                    source: std::io::Error::other(err),
-                }))
-            }
-        }
+        Err(err) => Err(report_apply_error(err, stderr)),
+    }
+}
+
+/// Prints a failure to apply hunks to `stderr` and converts it for the caller.
+fn report_apply_error(err: anyhow::Error, stderr: &mut impl std::io::Write) -> ApplyPatchError {
+    let msg = err.to_string();
+    if let Err(write_err) = writeln!(stderr, "{msg}") {
+        return ApplyPatchError::from(write_err);
+    }
+    let err = match err.downcast::<ApplyPatchError>() {
+        Ok(ApplyPatchError::HunkMismatch(mismatch)) => return mismatch.into(),
+        Ok(other) => anyhow::Error::from(other),
+        Err(err) => err,
+    };
+    if let Some(io) = err.downcast_ref::<std::io::Error>() {
+        ApplyPatchError::from(io)
+    } else {
+        ApplyPatchError::IoError(IoError {
+            context: msg,
+            source: std::io::Error::other(err),
+        })
     }
 }
 
@@ -272,85 +413,41 @@ pub struct AffectedPaths {
     pub added: Vec<PathBuf>,
     pub modified: Vec<PathBuf>,
     pub deleted: Vec<PathBuf>,
+    /// Files written with merge conflict markers, and where the conflicts are.
+    pub conflicted: Vec<(PathBuf, Vec<MergeConflict>)>,
 }
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
-/// Returns an error if the patch could not be applied.
+/// Returns an error if the patch could not be applied, in which case no file is changed.
 fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
 
-    let mut added: Vec<PathBuf> = Vec::new();
-    let mut modified: Vec<PathBuf> = Vec::new();
-    let mut deleted: Vec<PathBuf> = Vec::new();
-    for hunk in hunks {
-        match hunk {
-            Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
-                added.push(path.clone());
-            }
-            Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
-                    .with_context(|| format!("Failed to delete file {}", path.display()))?;
-                deleted.push(path.clone());
-            }
-            Hunk::UpdateFile {
-                path,
-                move_path,
-                chunks,
-            } => {
-                let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
-                if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
-                    modified.push(dest.clone());
-                } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
-                    modified.push(path.clone());
-                }
-            }
-        }
-    }
-    Ok(AffectedPaths {
-        added,
-        modified,
-        deleted,
-    })
+    let plan = PatchPlan::from_hunks(hunks)?;
+    plan.stage()?.commit()?;
+    Ok(plan.into_affected())
 }
 
 struct AppliedPatch {
     original_contents: String,
     new_contents: String,
+    /// Non-empty only when a merge base was given and the merge conflicted.
+    conflicts: Vec<MergeConflict>,
 }
 
 /// Return *only* the new file contents (joined into a single `String`) after
 /// applying the chunks to the file at `path`.
+///
+/// With a `base` that differs from the file on disk, the chunks are applied to
+/// the base and the result is three-way merged into the current contents.
 fn derive_new_contents_from_chunks(
     path: &Path,
     chunks: &[UpdateFileChunk],
+    base: Option<&MergeBase>,
 ) -> std::result::Result<AppliedPatch, ApplyPatchError> {
-    let original_contents = match std::fs::read_to_string(path) {
-        Ok(contents) => contents,
+    let original_contents = match std::fs::read(path) {
+        Ok(contents) => decode_text(path, contents)?,
         Err(err) => {
             return Err(ApplyPatchError::IoError(IoError {
                 context: format!("Failed to read file to update {}", path.display()),
@@ -358,28 +455,118 @@ fn derive_new_contents_from_chunks(
             }));
         }
     };
+    derive_new_contents(path, original_contents, chunks, base)
+}
 
-    let mut original_lines: Vec<String> = original_contents.split('\n').map(String::from).collect();
-
-    // Drop the trailing empty element that results from the final newline so
-    // that line counts match the behaviour of standard `diff`.
-    if original_lines.last().is_some_and(String::is_empty) {
-        original_lines.pop();
+/// Returns `contents` as text, or [`ApplyPatchError::BinaryFile`] if it looks
+/// binary.
+fn decode_text(path: &Path, contents: Vec<u8>) -> std::result::Result<String, ApplyPatchError> {
+    if looks_binary(&contents) {
+        return Err(ApplyPatchError::BinaryFile(path.to_path_buf()));
     }
+    String::from_utf8(contents).map_err(|_| ApplyPatchError::BinaryFile(path.to_path_buf()))
+}
+
+fn looks_binary(contents: &[u8]) -> bool {
+    contents[..contents.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
 
-    let replacements = compute_replacements(&original_lines, path, chunks)?;
-    let new_lines = apply_replacements(original_lines, &replacements);
+/// Applies `chunks` to `original_contents`, the current contents of `path`.
+fn derive_new_contents(
+    path: &Path,
+    original_contents: String,
+    chunks: &[UpdateFileChunk],
+    base: Option<&MergeBase>,
+) -> std::result::Result<AppliedPatch, ApplyPatchError> {
+    let (original_lines, line_ending) = split_file_lines(&original_contents);
+
+    let base_contents = match base {
+        Some(base) => Some(read_merge_base(path, base)?),
+        None => None,
+    };
+    let (new_lines, conflicts) = match base_contents {
+        Some(base_contents) if base_contents != original_contents => {
+            let (base_lines, _) = split_file_lines(&base_contents);
+            let replacements = compute_replacements(&base_lines, path, chunks)?;
+            let patched_lines = apply_replacements(base_lines.clone(), &replacements);
+            let merged = merge::merge_three_way(&base_lines, &original_lines, &patched_lines);
+            (merged.lines, merged.conflicts)
+        }
+        _ => {
+            let replacements = compute_replacements(&original_lines, path, chunks)?;
+            (
+                apply_replacements(original_lines, &replacements),
+                Vec::new(),
+            )
+        }
+    };
     let mut new_lines = new_lines;
-    if !new_lines.last().is_some_and(String::is_empty) {
+    if chunks.iter().any(|chunk| chunk.missing_final_newline) {
+        if new_lines.last().is_some_and(String::is_empty) {
+            new_lines.pop();
+        }
+    } else if !new_lines.last().is_some_and(String::is_empty) {
         new_lines.push(String::new());
     }
-    let new_contents = new_lines.join("\n");
+    let new_contents = new_lines.join(line_ending);
     Ok(AppliedPatch {
         original_contents,
         new_contents,
+        conflicts,
     })
 }
 
+/// Splits `contents` into lines, returning them with the line ending to join
+/// them back with. A file whose first line ends in CRLF is treated as CRLF
+/// throughout: the `\r` is stripped from every line so patch lines match, and
+/// restored when the file is written.
+fn split_file_lines(contents: &str) -> (Vec<String>, &'static str) {
+    let crlf = contents
+        .split_once('\n')
+        .is_some_and(|(first, _)| first.ends_with('\r'));
+    let mut lines: Vec<String> = contents
+        .split('\n')
+        .map(|line| match line.strip_suffix('\r') {
+            Some(line) if crlf => line.to_string(),
+            _ => line.to_string(),
+        })
+        .collect();
+
+    // Drop the trailing empty element that results from the final newline so
+    // that line counts match the behaviour of standard `diff`.
+    if lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    (lines, if crlf { "\r\n" } else { "\n" })
+}
+
+fn read_merge_base(path: &Path, base: &MergeBase) -> std::result::Result<String, ApplyPatchError> {
+    match base {
+        MergeBase::GitBlob(object) => {
+            let repo_dir = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."));
+            codex_git::read_blob(repo_dir, object).map_err(|err| {
+                ApplyPatchError::IoError(IoError {
+                    context: format!("Failed to read merge base {object} for {}", path.display()),
+                    source: std::io::Error::other(err),
+                })
+            })
+        }
+        MergeBase::File(snapshot) => std::fs::read_to_string(snapshot).map_err(|err| {
+            ApplyPatchError::IoError(IoError {
+                context: format!(
+                    "Failed to read merge base {} for {}",
+                    snapshot.display(),
+                    path.display()
+                ),
+                source: err,
+            })
+        }),
+    }
+}
+
 /// Compute a list of replacements needed to transform `original_lines` into the
 /// new lines, given the patch `chunks`. Each replacement is returned as
 /// `(start_index, old_len, new_lines)`.
@@ -412,12 +599,19 @@ fn compute_replacements(
         }
 
         if chunk.old_lines.is_empty() {
-            // Pure addition (no old lines). We'll add them at the end or just
-            // before the final empty line if one exists.
-            let insertion_idx = if original_lines.last().is_some_and(String::is_empty) {
-                original_lines.len() - 1
-            } else {
-                original_lines.len()
+            // Pure addition (no old lines). A unified diff hunk names the line
+            // to insert at; otherwise we add them at the end or just before
+            // the final empty line if one exists.
+            let insertion_idx = match chunk.insert_at_line {
+                Some(line) => {
+                    let idx = line.min(original_lines.len());
+                    line_index = line_index.max(idx);
+                    idx
+                }
+                None if original_lines.last().is_some_and(String::is_empty) => {
+                    original_lines.len() - 1
+                }
+                None => original_lines.len(),
             };
             replacements.push((insertion_idx, 0, chunk.new_lines.clone()));
             continue;
@@ -456,16 +650,40 @@ fn compute_replacements(
             );
         }
 
-        if let Some(start_idx) = found {
-            replacements.push((start_idx, pattern.len(), new_slice.to_vec()));
-            line_index = start_idx + pattern.len();
-        } else {
-            return Err(ApplyPatchError::ComputeReplacements(format!(
-                "Failed to find expected lines in {}:\n{}",
-                path.display(),
-                chunk.old_lines.join("\n"),
-            )));
-        }
+        let found = match found {
+            Some(start_idx) => start_idx,
+            None => match seek_sequence::fuzzy_seek_sequence(original_lines, pattern, line_index) {
+                FuzzyMatch::Unique(start_idx) => {
+                    // The context only resembles the file, so keep the file's
+                    // own text for it and replace just the removed lines.
+                    let actual = &original_lines[start_idx..start_idx + pattern.len()];
+                    replacements.push((
+                        start_idx,
+                        pattern.len(),
+                        splice_actual_context(actual, pattern, new_slice),
+                    ));
+                    line_index = start_idx + pattern.len();
+                    continue;
+                }
+                FuzzyMatch::NoUniqueMatch {
+                    ambiguous,
+                    candidates,
+                } => {
+                    return Err(HunkMismatch {
+                        path: path.to_path_buf(),
+                        expected_lines: chunk.old_lines.clone(),
+                        ambiguous,
+                        candidates: candidates
+                            .into_iter()
+                            .map(|candidate| hunk_candidate(original_lines, pattern, candidate))
+                            .collect(),
+                    }
+                    .into());
+                }
+            },
+        };
+        replacements.push((found, pattern.len(), new_slice.to_vec()));
+        line_index = found + pattern.len();
     }
 
     replacements.sort_by(|(lhs_idx, _, _), (rhs_idx, _, _)| lhs_idx.cmp(rhs_idx));
@@ -473,6 +691,51 @@ fn compute_replacements(
     Ok(replacements)
 }
 
+/// Rebuilds `new_lines` for a window matched fuzzily: lines the hunk keeps
+/// from `old_lines` are taken from `actual` instead, so only the removed and
+/// added lines differ from the file on disk.
+fn splice_actual_context(
+    actual: &[String],
+    old_lines: &[String],
+    new_lines: &[String],
+) -> Vec<String> {
+    similar::capture_diff_slices(similar::Algorithm::Myers, old_lines, new_lines)
+        .into_iter()
+        .flat_map(|op| match op {
+            similar::DiffOp::Equal { old_index, len, .. } => {
+                actual[old_index..old_index + len].to_vec()
+            }
+            similar::DiffOp::Delete { .. } => Vec::new(),
+            similar::DiffOp::Insert {
+                new_index, new_len, ..
+            }
+            | similar::DiffOp::Replace {
+                new_index, new_len, ..
+            } => new_lines[new_index..new_index + new_len].to_vec(),
+        })
+        .collect()
+}
+
+fn hunk_candidate(
+    original_lines: &[String],
+    pattern: &[String],
+    candidate: FuzzyCandidate,
+) -> HunkCandidate {
+    let actual = &original_lines[candidate.start..candidate.start + pattern.len()];
+    let expected = format!("{}\n", pattern.join("\n"));
+    let actual = format!("{}\n", actual.join("\n"));
+    let diff = TextDiff::from_lines(&expected, &actual)
+        .unified_diff()
+        .context_radius(pattern.len())
+        .header("expected", "actual")
+        .to_string();
+    HunkCandidate {
+        line_number: candidate.start + 1,
+        similarity: candidate.similarity,
+        diff,
+    }
+}
+
 /// Apply the `(start_index, old_len, new_lines)` replacements to `original_lines`,
 /// returning the modified file contents as a vector of lines.
 fn apply_replacements(
@@ -520,10 +783,26 @@ pub fn unified_diff_from_chunks_with_context(
     chunks: &[UpdateFileChunk],
     context: usize,
 ) -> std::result::Result<ApplyPatchFileUpdate, ApplyPatchError> {
+    unified_diff_from_chunks_with_base(path, chunks, None, context)
+}
+
+/// Like [`unified_diff_from_chunks_with_context`], but three-way merges against
+/// `base` when the file has diverged from it. Conflicts appear in the diff as
+/// conflict markers.
+pub fn unified_diff_from_chunks_with_base(
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+    base: Option<&MergeBase>,
+    context: usize,
+) -> std::result::Result<ApplyPatchFileUpdate, ApplyPatchError> {
+    if std::fs::metadata(path).is_ok_and(|metadata| metadata.len() > stream_threshold()) {
+        return streamed_file_update(path, chunks, base);
+    }
     let AppliedPatch {
         original_contents,
         new_contents,
-    } = derive_new_contents_from_chunks(path, chunks)?;
+        ..
+    } = derive_new_contents_from_chunks(path, chunks, base)?;
     let text_diff = TextDiff::from_lines(&original_contents, &new_contents);
     let unified_diff = text_diff.unified_diff().context_radius(context).to_string();
     Ok(ApplyPatchFileUpdate {
@@ -532,6 +811,39 @@ pub fn unified_diff_from_chunks_with_context(
     })
 }
 
+/// Checks that `chunks` apply to `path`, a file over the streaming threshold,
+/// by streaming it through them without keeping the result. The returned diff
+/// lists each chunk's changes without line numbers, and the content is empty.
+fn streamed_file_update(
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+    base: Option<&MergeBase>,
+) -> std::result::Result<ApplyPatchFileUpdate, ApplyPatchError> {
+    streaming::ensure_streamable(path, path, chunks, base.is_some())?;
+    streaming::stream_update(path, path, chunks, &mut std::io::sink())?;
+    let mut unified_diff = String::new();
+    for chunk in chunks {
+        match &chunk.change_context {
+            Some(context) => unified_diff.push_str(&format!("@@ {context}\n")),
+            None => unified_diff.push_str("@@\n"),
+        }
+        let old_lines: Vec<&str> = chunk.old_lines.iter().map(String::as_str).collect();
+        let new_lines: Vec<&str> = chunk.new_lines.iter().map(String::as_str).collect();
+        for change in TextDiff::from_slices(&old_lines, &new_lines).iter_all_changes() {
+            let sign = match change.tag() {
+                ChangeTag::Equal => ' ',
+                ChangeTag::Delete => '-',
+                ChangeTag::Insert => '+',
+            };
+            unified_diff.push_str(&format!("{sign}{}\n", change.value()));
+        }
+    }
+    Ok(ApplyPatchFileUpdate {
+        unified_diff,
+        content: String::new(),
+    })
+}
+
 /// Print the summary of changes in git-style format.
 /// Write a summary of changes to the given writer.
 pub fn print_summary(
@@ -548,6 +860,22 @@ pub fn print_summary(
     for path in &affected.deleted {
         writeln!(out, "D {}", path.display())?;
     }
+    for (path, _) in &affected.conflicted {
+        writeln!(out, "C {}", path.display())?;
+    }
+    if !affected.conflicted.is_empty() {
+        writeln!(
+            out,
+            "Merge conflicts were written between {} and {} markers at:",
+            merge::CONFLICT_MARKER_CURRENT,
+            merge::CONFLICT_MARKER_PATCH
+        )?;
+        for (path, conflicts) in &affected.conflicted {
+            for conflict in conflicts {
+                writeln!(out, "  {}:{}", path.display(), conflict.line_number)?;
+            }
+        }
+    }
     Ok(())
 }
 
@@ -833,6 +1161,133 @@ mod tests {
         assert_eq!(String::from_utf8(stderr).unwrap(), "");
     }
 
+    #[test]
+    fn test_update_applies_to_drifted_lines_via_fuzzy_match() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("drift.rs");
+        fs::write(
+            &path,
+            "fn main() {\n    let greeting = \"hello, world\";\n    println!(\"{greeting}\");\n}\n",
+        )
+        .unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+     let greeting = "hello world";
+-    println!("{{greeting}}");
++    eprintln!("{{greeting}}");"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "fn main() {\n    let greeting = \"hello, world\";\n    eprintln!(\"{greeting}\");\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_update_reports_nearest_candidates_when_no_match() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mismatch.txt");
+        fs::write(&path, "alpha\nbeta\ngamma\ndelta\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+ gamma
+-omega
++OMEGA"#,
+            path.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let err = apply_patch(&patch, &mut stdout, &mut stderr).unwrap_err();
+
+        let ApplyPatchError::HunkMismatch(mismatch) = err else {
+            panic!("expected HunkMismatch, got {err:?}");
+        };
+        assert!(!mismatch.ambiguous);
+        assert_eq!(mismatch.expected_lines, vec!["gamma", "omega"]);
+        assert_eq!(mismatch.candidates[0].line_number, 3);
+        assert_eq!(
+            mismatch.candidates[0].diff,
+            "--- expected\n+++ actual\n@@ -1,2 +1,2 @@\n gamma\n-omega\n+delta\n"
+        );
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert!(stderr.contains("Nearest matches:\n- line 3"), "{stderr}");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "alpha\nbeta\ngamma\ndelta\n"
+        );
+    }
+
+    #[test]
+    fn test_update_with_base_merges_diverged_working_copy() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("merge.txt");
+        let base = dir.path().join("merge.base");
+        fs::write(&base, "one\ntwo\nthree\nfour\n").unwrap();
+        fs::write(&path, "ONE\ntwo\nthree\nfour\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+*** Base File: {}
+@@
+ three
+-four
++FOUR"#,
+            path.display(),
+            base.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "ONE\ntwo\nthree\nFOUR\n"
+        );
+    }
+
+    #[test]
+    fn test_update_with_base_writes_conflict_markers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("conflict.txt");
+        let base = dir.path().join("conflict.base");
+        fs::write(&base, "a\nb\nc\n").unwrap();
+        fs::write(&path, "a\nmine\nc\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+*** Base File: {}
+@@
+ a
+-b
++theirs"#,
+            path.display(),
+            base.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a\n<<<<<<< current\nmine\n||||||| base\nb\n=======\ntheirs\n>>>>>>> patch\nc\n"
+        );
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "Success. Updated the following files:\nC {0}\nMerge conflicts were written between <<<<<<< current and >>>>>>> patch markers at:\n  {0}:2\n",
+                path.display()
+            )
+        );
+    }
+
     #[test]
     fn test_unified_diff() {
         // Start with a file containing four lines.
@@ -873,6 +1328,43 @@ mod tests {
         assert_eq!(expected, diff);
     }
 
+    #[test]
+    fn test_streamed_file_update_lists_chunks_without_line_numbers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("large.txt");
+        fs::write(&path, "foo\nbar\nbaz\nqux\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@ fn main
+ baz
+-qux
++QUX"#,
+            path.display()
+        ));
+        let patch = parse_patch(&patch).unwrap();
+        let chunks = match patch.hunks.as_slice() {
+            [Hunk::UpdateFile { chunks, .. }] => chunks,
+            _ => panic!("Expected a single UpdateFile hunk"),
+        };
+
+        // `fn main` is not in the file, so streaming rejects the chunk.
+        assert!(matches!(
+            streamed_file_update(&path, chunks, None),
+            Err(ApplyPatchError::HunkMismatch(_))
+        ));
+
+        fs::write(&path, "fn main\nbar\nbaz\nqux\n").unwrap();
+        let expected = ApplyPatchFileUpdate {
+            unified_diff: "@@ fn main\n baz\n-qux\n+QUX\n".to_string(),
+            content: String::new(),
+        };
+        assert_eq!(streamed_file_update(&path, chunks, None).unwrap(), expected);
+        assert!(matches!(
+            streamed_file_update(&path, chunks, Some(&MergeBase::File(path.clone()))),
+            Err(ApplyPatchError::ComputeReplacements(_))
+        ));
+    }
+
     #[test]
     fn test_unified_diff_first_line_replacement() {
         // Replace the very first line of the file.
@@ -1071,4 +1563,200 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_failing_hunk_leaves_earlier_hunks_unapplied() {
+        let dir = tempdir().unwrap();
+        let added = dir.path().join("added.txt");
+        let existing = dir.path().join("existing.txt");
+        fs::write(&existing, "keep\n").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Add File: {}\n+new\n*** Update File: {}\n@@\n-keep\n+changed\n*** Update File: {}\n@@\n-nope\n+never",
+            added.display(),
+            existing.display(),
+            existing.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = apply_patch(&patch, &mut stdout, &mut stderr);
+
+        assert!(matches!(result, Err(ApplyPatchError::HunkMismatch(_))));
+        assert!(!added.exists());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "keep\n");
+        assert!(stdout.is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_prints_diff_without_writing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dry.txt");
+        fs::write(&path, "foo\nbar\n").unwrap();
+        let added = dir.path().join("sub/added.txt");
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n foo\n-bar\n+baz\n*** Add File: {}\n+hello",
+            path.display(),
+            added.display()
+        ));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch_with_mode(&patch, ApplyMode::DryRun, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "--- {path}\n+++ {path}\n@@ -1,2 +1,2 @@\n foo\n-bar\n+baz\n\
+                 --- /dev/null\n+++ {added}\n@@ -0,0 +1 @@\n+hello\n",
+                path = path.display(),
+                added = added.display()
+            )
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo\nbar\n");
+        assert!(!dir.path().join("sub").exists());
+    }
+
+    #[test]
+    fn test_add_binary_file_and_refuse_text_hunks_on_it() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("blob.bin");
+        let patch = wrap_patch(&format!(
+            "*** Add Binary File: {}\n+AAECAw==",
+            path.display()
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), vec![0, 1, 2, 3]);
+
+        let patch = wrap_patch(&format!("*** Update File: {}\n@@\n-a\n+b", path.display()));
+        let mut stderr = Vec::new();
+        let result = apply_patch(&patch, &mut Vec::new(), &mut stderr);
+
+        assert_eq!(
+            String::from_utf8(stderr).unwrap(),
+            format!(
+                "Cannot apply text hunks to binary file {}\n",
+                path.display()
+            )
+        );
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_update_preserves_crlf_line_endings() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("windows.txt");
+        fs::write(&path, "one\r\ntwo\r\nthree\r\n").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n one\n-two\n+TWO\n+2",
+            path.display()
+        ));
+
+        apply_patch(&patch, &mut Vec::new(), &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "one\r\nTWO\r\n2\r\nthree\r\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_update_preserves_exec_bit_across_moves() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("run.sh");
+        let moved = dir.path().join("bin/run.sh");
+        fs::write(&path, "#!/bin/sh\necho hi\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n*** Move to: {}\n@@\n-echo hi\n+echo bye",
+            path.display(),
+            moved.display()
+        ));
+
+        apply_patch(&patch, &mut Vec::new(), &mut Vec::new()).unwrap();
+
+        let mode = fs::metadata(&moved).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "#!/bin/sh\necho bye\n");
+    }
+
+    #[test]
+    fn test_apply_unified_diff() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("code.py");
+        fs::write(&path, "def f():\n    return 1\n\nprint(f())\n").unwrap();
+        let moved = dir.path().join("renamed.py");
+        let diff = format!(
+            "--- {path}\n+++ {moved}\n@@ -1,2 +1,2 @@\n def f():\n-    return 1\n+    return 2\n",
+            path = path.display(),
+            moved = moved.display()
+        );
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&diff, &mut stdout, &mut stderr).unwrap();
+
+        assert!(!path.exists());
+        assert_eq!(
+            fs::read_to_string(&moved).unwrap(),
+            "def f():\n    return 2\n\nprint(f())\n"
+        );
+        assert_eq!(
+            String::from_utf8(stdout).unwrap(),
+            format!(
+                "Success. Updated the following files:\nM {}\n",
+                moved.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_apply_unified_diff_keeps_line_numbers_and_newline_markers() {
+        let dir = tempdir().unwrap();
+        let last = dir.path().join("last.txt");
+        fs::write(&last, "x\ny\n").unwrap();
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "a\nb\nc\n\nd\n").unwrap();
+        // A final line without a newline, a `-U0` insertion after line 1 and a
+        // blank context line at the very end of the patch.
+        let diff = format!(
+            "--- {last}\n+++ {last}\n@@ -2 +2 @@\n-y\n+Y\n\\ No newline at end of file\n\
+             --- {notes}\n+++ {notes}\n@@ -1,0 +2 @@\n+inserted\n@@ -3,2 +4,2 @@\n-c\n+C\n \n",
+            last = last.display(),
+            notes = notes.display(),
+        );
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&diff, &mut stdout, &mut stderr).unwrap();
+
+        assert_eq!(fs::read_to_string(&last).unwrap(), "x\nY");
+        assert_eq!(
+            fs::read_to_string(&notes).unwrap(),
+            "a\ninserted\nb\nC\n\nd\n"
+        );
+    }
+
+    #[test]
+    fn test_dry_run_reports_hunk_errors() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("missing.txt");
+        let patch = wrap_patch(&format!("*** Update File: {}\n@@\n-a\n+b", path.display()));
+
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = apply_patch_with_mode(&patch, ApplyMode::DryRun, &mut stdout, &mut stderr);
+
+        assert!(result.is_err());
+        assert!(
+            String::from_utf8(stderr)
+                .unwrap()
+                .starts_with("Failed to read file to update")
+        );
+    }
 }
@@ -0,0 +1,209 @@
+//! Line-level three-way merge used when an update hunk names a base revision
+//! and the working copy has diverged from it.
+//!
+//! The patch is applied to the base, then the base→working-copy and
+//! base→patched edits are combined. Edits that touch separate regions are both
+//! kept; edits that overlap (or abut) the same base lines are kept once when
+//! identical and otherwise emitted between diff3-style conflict markers.
+
+use similar::Algorithm;
+use similar::DiffTag;
+use similar::capture_diff_slices;
+
+use crate::MergeConflict;
+
+pub(crate) const CONFLICT_MARKER_CURRENT: &str = "<<<<<<< current";
+pub(crate) const CONFLICT_MARKER_BASE: &str = "||||||| base";
+pub(crate) const CONFLICT_MARKER_SEPARATOR: &str = "=======";
+pub(crate) const CONFLICT_MARKER_PATCH: &str = ">>>>>>> patch";
+
+pub(crate) struct MergedLines {
+    pub(crate) lines: Vec<String>,
+    pub(crate) conflicts: Vec<MergeConflict>,
+}
+
+/// Replacement of `base[base_start..base_end]` with `lines`.
+struct Edit<'a> {
+    base_start: usize,
+    base_end: usize,
+    lines: &'a [String],
+}
+
+/// Merges the edits that turned `base` into `current` (the working copy) with
+/// those that turned it into `patched`.
+pub(crate) fn merge_three_way(
+    base: &[String],
+    current: &[String],
+    patched: &[String],
+) -> MergedLines {
+    let current_edits = edits(base, current);
+    let patched_edits = edits(base, patched);
+
+    let mut lines = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut base_pos = 0;
+    let (mut ci, mut pi) = (0, 0);
+    loop {
+        let next_current = current_edits.get(ci);
+        let next_patched = patched_edits.get(pi);
+        let group_start = match (next_current, next_patched) {
+            (None, None) => break,
+            (Some(edit), None) | (None, Some(edit)) => edit.base_start,
+            (Some(lhs), Some(rhs)) => lhs.base_start.min(rhs.base_start),
+        };
+
+        // Grow the group until no edit on either side starts inside or right
+        // at the end of it.
+        let mut group_end = group_start;
+        let (current_from, patched_from) = (ci, pi);
+        loop {
+            if let Some(edit) = current_edits.get(ci)
+                && edit.base_start <= group_end
+            {
+                group_end = group_end.max(edit.base_end);
+                ci += 1;
+                continue;
+            }
+            if let Some(edit) = patched_edits.get(pi)
+                && edit.base_start <= group_end
+            {
+                group_end = group_end.max(edit.base_end);
+                pi += 1;
+                continue;
+            }
+            break;
+        }
+
+        lines.extend_from_slice(&base[base_pos..group_start]);
+        let current_side = render(
+            base,
+            group_start,
+            group_end,
+            &current_edits[current_from..ci],
+        );
+        let patched_side = render(
+            base,
+            group_start,
+            group_end,
+            &patched_edits[patched_from..pi],
+        );
+        if current_from == ci || current_side == patched_side {
+            lines.extend(patched_side);
+        } else if patched_from == pi {
+            lines.extend(current_side);
+        } else {
+            let base_side = base[group_start..group_end].to_vec();
+            conflicts.push(MergeConflict {
+                line_number: lines.len() + 1,
+                base: base_side.clone(),
+                current: current_side.clone(),
+                patch: patched_side.clone(),
+            });
+            lines.push(CONFLICT_MARKER_CURRENT.to_string());
+            lines.extend(current_side);
+            lines.push(CONFLICT_MARKER_BASE.to_string());
+            lines.extend(base_side);
+            lines.push(CONFLICT_MARKER_SEPARATOR.to_string());
+            lines.extend(patched_side);
+            lines.push(CONFLICT_MARKER_PATCH.to_string());
+        }
+        base_pos = group_end;
+    }
+    lines.extend_from_slice(&base[base_pos..]);
+
+    MergedLines { lines, conflicts }
+}
+
+/// Non-equal regions of the diff from `base` to `other`, with adjacent
+/// regions coalesced.
+fn edits<'a>(base: &[String], other: &'a [String]) -> Vec<Edit<'a>> {
+    let mut edits: Vec<Edit<'a>> = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        match edits.last_mut() {
+            Some(last) if last.base_end == old_range.start => {
+                let start = new_range.start - last.lines.len();
+                last.base_end = old_range.end;
+                last.lines = &other[start..new_range.end];
+            }
+            _ => edits.push(Edit {
+                base_start: old_range.start,
+                base_end: old_range.end,
+                lines: &other[new_range],
+            }),
+        }
+    }
+    edits
+}
+
+/// `base[start..end]` with `edits` (all within that range) applied.
+fn render(base: &[String], start: usize, end: usize, edits: &[Edit<'_>]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut pos = start;
+    for edit in edits {
+        out.extend_from_slice(&base[pos..edit.base_start]);
+        out.extend_from_slice(edit.lines);
+        pos = edit.base_end;
+    }
+    out.extend_from_slice(&base[pos..end]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn keeps_edits_to_separate_regions_from_both_sides() {
+        let base = lines("a\nb\nc\nd\ne");
+        let current = lines("A\nb\nc\nd\ne");
+        let patched = lines("a\nb\nc\nd\nE\nf");
+
+        let merged = merge_three_way(&base, &current, &patched);
+
+        assert_eq!(merged.lines, lines("A\nb\nc\nd\nE\nf"));
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn identical_edits_on_both_sides_do_not_conflict() {
+        let base = lines("a\nb\nc");
+        let both = lines("a\nB\nc");
+
+        let merged = merge_three_way(&base, &both, &both);
+
+        assert_eq!(merged.lines, both);
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn overlapping_edits_emit_conflict_markers() {
+        let base = lines("a\nb\nc");
+        let current = lines("a\nmine\nc");
+        let patched = lines("a\ntheirs\nc");
+
+        let merged = merge_three_way(&base, &current, &patched);
+
+        assert_eq!(
+            merged.lines,
+            lines("a\n<<<<<<< current\nmine\n||||||| base\nb\n=======\ntheirs\n>>>>>>> patch\nc")
+        );
+        assert_eq!(
+            merged.conflicts,
+            vec![MergeConflict {
+                line_number: 2,
+                base: lines("b"),
+                current: lines("mine"),
+                patch: lines("theirs"),
+            }]
+        );
+    }
+}
@@ -7,13 +7,16 @@
 //! begin_patch: "*** Begin Patch" LF
 //! end_patch: "*** End Patch" LF?
 //!
-//! hunk: add_hunk | delete_hunk | update_hunk
+//! hunk: add_hunk | add_binary_hunk | delete_hunk | update_hunk
 //! add_hunk: "*** Add File: " filename LF add_line+
+//! add_binary_hunk: "*** Add Binary File: " filename LF base64_line+
 //! delete_hunk: "*** Delete File: " filename LF
-//! update_hunk: "*** Update File: " filename LF change_move? change?
+//! update_hunk: "*** Update File: " filename LF change_base? change_move? change?
 //! filename: /(.+)/
 //! add_line: "+" /(.+)/ LF -> line
+//! base64_line: "+" /[A-Za-z0-9+\/=]+/ LF
 //!
+//! change_base: ("*** Base Blob: " /(.+)/ | "*** Base File: " filename) LF
 //! change_move: "*** Move to: " filename LF
 //! change: (change_context | change_line)+ eof_line?
 //! change_context: ("@@" | "@@ " /(.+)/) LF
@@ -22,7 +25,13 @@
 //!
 //! The parser below is a little more lenient than the explicit spec and allows for
 //! leading/trailing whitespace around patch markers.
+//!
+//! Standard unified diffs (e.g. `git diff` output) are accepted as well and
+//! converted into the same hunks; see [`crate::unified_diff`].
 use crate::ApplyPatchArgs;
+use crate::unified_diff;
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -31,9 +40,12 @@ use thiserror::Error;
 const BEGIN_PATCH_MARKER: &str = "*** Begin Patch";
 const END_PATCH_MARKER: &str = "*** End Patch";
 const ADD_FILE_MARKER: &str = "*** Add File: ";
+const ADD_BINARY_FILE_MARKER: &str = "*** Add Binary File: ";
 const DELETE_FILE_MARKER: &str = "*** Delete File: ";
 const UPDATE_FILE_MARKER: &str = "*** Update File: ";
 const MOVE_TO_MARKER: &str = "*** Move to: ";
+const BASE_BLOB_MARKER: &str = "*** Base Blob: ";
+const BASE_FILE_MARKER: &str = "*** Base File: ";
 const EOF_MARKER: &str = "*** End of File";
 const CHANGE_CONTEXT_MARKER: &str = "@@ ";
 const EMPTY_CHANGE_CONTEXT_MARKER: &str = "@@";
@@ -62,6 +74,11 @@ pub enum Hunk {
         path: PathBuf,
         contents: String,
     },
+    /// A file added from a base64 payload, for contents that are not text.
+    AddBinaryFile {
+        path: PathBuf,
+        contents: Vec<u8>,
+    },
     DeleteFile {
         path: PathBuf,
     },
@@ -69,6 +86,10 @@ pub enum Hunk {
         path: PathBuf,
         move_path: Option<PathBuf>,
 
+        /// Revision the chunks were written against. When the file has since
+        /// diverged from it, the update is applied with a three-way merge.
+        base: Option<MergeBase>,
+
         /// Chunks should be in order, i.e. the `change_context` of one chunk
         /// should occur later in the file than the previous chunk.
         chunks: Vec<UpdateFileChunk>,
@@ -79,6 +100,7 @@ impl Hunk {
     pub fn resolve_path(&self, cwd: &Path) -> PathBuf {
         match self {
             Hunk::AddFile { path, .. } => cwd.join(path),
+            Hunk::AddBinaryFile { path, .. } => cwd.join(path),
             Hunk::DeleteFile { path } => cwd.join(path),
             Hunk::UpdateFile { path, .. } => cwd.join(path),
         }
@@ -87,6 +109,25 @@ impl Hunk {
 
 use Hunk::*;
 
+/// The revision of a file that an update hunk was authored against.
+#[derive(Debug, PartialEq, Clone)]
+pub enum MergeBase {
+    /// A git object name accepted by `git cat-file blob`, such as a blob OID or
+    /// `HEAD:path/to/file`.
+    GitBlob(String),
+    /// A snapshot of the file's earlier contents.
+    File(PathBuf),
+}
+
+impl MergeBase {
+    pub fn resolve(&self, cwd: &Path) -> MergeBase {
+        match self {
+            MergeBase::GitBlob(object) => MergeBase::GitBlob(object.clone()),
+            MergeBase::File(path) => MergeBase::File(cwd.join(path)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct UpdateFileChunk {
     /// A single line of context used to narrow down the position of the chunk
@@ -101,6 +142,15 @@ pub struct UpdateFileChunk {
     /// If set to true, `old_lines` must occur at the end of the source file.
     /// (Tolerance around trailing newlines should be encouraged.)
     pub is_end_of_file: bool,
+
+    /// For a chunk with no `old_lines`, the 0-based index of the original line
+    /// to insert `new_lines` before, from the `N` of a unified diff's
+    /// `@@ -N,0` header. `None` appends at the end of the file.
+    pub insert_at_line: Option<usize>,
+
+    /// The updated file ends right after this chunk's `new_lines`, without a
+    /// trailing newline (a unified diff's `\ No newline at end of file`).
+    pub missing_final_newline: bool,
 }
 
 pub fn parse_patch(patch: &str) -> Result<ApplyPatchArgs, ParseError> {
@@ -153,6 +203,12 @@ enum ParseMode {
 
 fn parse_patch_text(patch: &str, mode: ParseMode) -> Result<ApplyPatchArgs, ParseError> {
     let lines: Vec<&str> = patch.trim().lines().collect();
+    if unified_diff::is_unified_diff(&lines) {
+        // Trimming the end could drop a trailing context line that is a single
+        // space, so the diff itself is parsed untrimmed.
+        let lines: Vec<&str> = patch.trim_start().lines().collect();
+        return unified_diff::parse_unified_diff(&lines);
+    }
     let lines: &[&str] = match check_patch_boundaries_strict(&lines) {
         Ok(()) => &lines,
         Err(e) => match mode {
@@ -268,6 +324,31 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             },
             parsed_lines,
         ));
+    } else if let Some(path) = first_line.strip_prefix(ADD_BINARY_FILE_MARKER) {
+        // Add Binary File
+        let mut encoded = String::new();
+        let mut parsed_lines = 1;
+        for add_line in &lines[1..] {
+            if let Some(line_to_add) = add_line.strip_prefix('+') {
+                encoded.push_str(line_to_add.trim());
+                parsed_lines += 1;
+            } else {
+                break;
+            }
+        }
+        let contents = BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|err| InvalidHunkError {
+                message: format!("Invalid base64 contents for binary file '{path}': {err}"),
+                line_number,
+            })?;
+        return Ok((
+            AddBinaryFile {
+                path: PathBuf::from(path),
+                contents,
+            },
+            parsed_lines,
+        ));
     } else if let Some(path) = first_line.strip_prefix(DELETE_FILE_MARKER) {
         // Delete File
         return Ok((
@@ -281,6 +362,21 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
         let mut remaining_lines = &lines[1..];
         let mut parsed_lines = 1;
 
+        // Optional: merge base line
+        let base = remaining_lines.first().and_then(|line| {
+            if let Some(object) = line.strip_prefix(BASE_BLOB_MARKER) {
+                Some(MergeBase::GitBlob(object.trim().to_string()))
+            } else {
+                line.strip_prefix(BASE_FILE_MARKER)
+                    .map(|path| MergeBase::File(PathBuf::from(path)))
+            }
+        });
+
+        if base.is_some() {
+            remaining_lines = &remaining_lines[1..];
+            parsed_lines += 1;
+        }
+
         // Optional: move file line
         let move_path = remaining_lines
             .first()
@@ -326,6 +422,7 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
             UpdateFile {
                 path: PathBuf::from(path),
                 move_path: move_path.map(PathBuf::from),
+                base,
                 chunks,
             },
             parsed_lines,
@@ -334,7 +431,7 @@ fn parse_one_hunk(lines: &[&str], line_number: usize) -> Result<(Hunk, usize), P
 
     Err(InvalidHunkError {
         message: format!(
-            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}'"
+            "'{first_line}' is not a valid hunk header. Valid hunk headers: '*** Add File: {{path}}', '*** Add Binary File: {{path}}', '*** Delete File: {{path}}', '*** Update File: {{path}}'"
         ),
         line_number,
     })
@@ -380,6 +477,8 @@ fn parse_update_file_chunk(
         old_lines: Vec::new(),
         new_lines: Vec::new(),
         is_end_of_file: false,
+        insert_at_line: None,
+        missing_final_newline: false,
     };
     let mut parsed_lines = 0;
     for line in &lines[start_index..] {
@@ -516,11 +615,14 @@ fn test_parse_patch() {
             UpdateFile {
                 path: PathBuf::from("path/update.py"),
                 move_path: Some(PathBuf::from("path/update2.py")),
+                base: None,
                 chunks: vec![UpdateFileChunk {
                     change_context: Some("def f():".to_string()),
                     old_lines: vec!["    pass".to_string()],
                     new_lines: vec!["    return 123".to_string()],
-                    is_end_of_file: false
+                    is_end_of_file: false,
+                    insert_at_line: None,
+                    missing_final_newline: false,
                 }]
             }
         ]
@@ -543,11 +645,14 @@ fn test_parse_patch() {
             UpdateFile {
                 path: PathBuf::from("file.py"),
                 move_path: None,
+                base: None,
                 chunks: vec![UpdateFileChunk {
                     change_context: None,
                     old_lines: vec![],
                     new_lines: vec!["line".to_string()],
-                    is_end_of_file: false
+                    is_end_of_file: false,
+                    insert_at_line: None,
+                    missing_final_newline: false,
                 }],
             },
             AddFile {
@@ -573,11 +678,14 @@ fn test_parse_patch() {
         vec![UpdateFile {
             path: PathBuf::from("file2.py"),
             move_path: None,
+            base: None,
             chunks: vec![UpdateFileChunk {
                 change_context: None,
                 old_lines: vec!["import foo".to_string()],
                 new_lines: vec!["import foo".to_string(), "bar".to_string()],
                 is_end_of_file: false,
+                insert_at_line: None,
+                missing_final_newline: false,
             }],
         }]
     );
@@ -593,11 +701,14 @@ fn test_parse_patch_lenient() {
     let expected_patch = vec![UpdateFile {
         path: PathBuf::from("file2.py"),
         move_path: None,
+        base: None,
         chunks: vec![UpdateFileChunk {
             change_context: None,
             old_lines: vec!["import foo".to_string()],
             new_lines: vec!["import foo".to_string(), "bar".to_string()],
             is_end_of_file: false,
+            insert_at_line: None,
+            missing_final_newline: false,
         }],
     }];
     let expected_error =
@@ -675,13 +786,102 @@ fn test_parse_one_hunk() {
         parse_one_hunk(&["bad"], 234),
         Err(InvalidHunkError {
             message: "'bad' is not a valid hunk header. \
-            Valid hunk headers: '*** Add File: {path}', '*** Delete File: {path}', '*** Update File: {path}'".to_string(),
+            Valid hunk headers: '*** Add File: {path}', '*** Add Binary File: {path}', '*** Delete File: {path}', '*** Update File: {path}'".to_string(),
             line_number: 234
         })
     );
     // Other edge cases are already covered by tests above/below.
 }
 
+#[test]
+fn test_parse_add_binary_file_hunk() {
+    assert_eq!(
+        parse_one_hunk(
+            &[
+                "*** Add Binary File: logo.png",
+                "+iVBORw0K",
+                "+GgoAAA==",
+                "*** End Patch"
+            ],
+            1
+        ),
+        Ok((
+            AddBinaryFile {
+                path: PathBuf::from("logo.png"),
+                contents: vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', 0, 0],
+            },
+            3
+        ))
+    );
+    assert_eq!(
+        parse_one_hunk(&["*** Add Binary File: bad.bin", "+not base64!"], 7),
+        Err(InvalidHunkError {
+            message:
+                "Invalid base64 contents for binary file 'bad.bin': Invalid symbol 32, offset 3."
+                    .to_string(),
+            line_number: 7
+        })
+    );
+}
+
+#[test]
+fn test_parse_update_hunk_with_merge_base() {
+    assert_eq!(
+        parse_one_hunk(
+            &[
+                "*** Update File: src/lib.rs",
+                "*** Base Blob: HEAD~1:src/lib.rs",
+                "*** Move to: src/main.rs",
+                "@@",
+                "-old",
+                "+new",
+            ],
+            1
+        ),
+        Ok((
+            UpdateFile {
+                path: PathBuf::from("src/lib.rs"),
+                move_path: Some(PathBuf::from("src/main.rs")),
+                base: Some(MergeBase::GitBlob("HEAD~1:src/lib.rs".to_string())),
+                chunks: vec![UpdateFileChunk {
+                    change_context: None,
+                    old_lines: vec!["old".to_string()],
+                    new_lines: vec!["new".to_string()],
+                    is_end_of_file: false,
+                    insert_at_line: None,
+                    missing_final_newline: false,
+                }],
+            },
+            6
+        ))
+    );
+    assert_eq!(
+        parse_one_hunk(
+            &[
+                "*** Update File: a.txt",
+                "*** Base File: .snapshots/a.txt",
+                "@@",
+                "+line",
+            ],
+            1
+        )
+        .map(|(hunk, _)| hunk),
+        Ok(UpdateFile {
+            path: PathBuf::from("a.txt"),
+            move_path: None,
+            base: Some(MergeBase::File(PathBuf::from(".snapshots/a.txt"))),
+            chunks: vec![UpdateFileChunk {
+                change_context: None,
+                old_lines: vec![],
+                new_lines: vec!["line".to_string()],
+                is_end_of_file: false,
+                insert_at_line: None,
+                missing_final_newline: false,
+            }],
+        })
+    );
+}
+
 #[test]
 fn test_update_file_chunk() {
     assert_eq!(
@@ -743,7 +943,9 @@ fn test_update_file_chunk() {
                     "add".to_string(),
                     "context2".to_string()
                 ],
-                is_end_of_file: false
+                is_end_of_file: false,
+                insert_at_line: None,
+                missing_final_newline: false,
             }),
             6
         ))
@@ -755,7 +957,9 @@ fn test_update_file_chunk() {
                 change_context: None,
                 old_lines: vec![],
                 new_lines: vec!["line".to_string()],
-                is_end_of_file: true
+                is_end_of_file: true,
+                insert_at_line: None,
+                missing_final_newline: false,
             }),
             3
         ))
@@ -1,7 +1,8 @@
 /// Attempt to find the sequence of `pattern` lines within `lines` beginning at or after `start`.
 /// Returns the starting index of the match or `None` if not found. Matches are attempted with
 /// decreasing strictness: exact match, then ignoring trailing whitespace, then ignoring leading
-/// and trailing whitespace. When `eof` is true, we first try starting at the end-of-file (so that
+/// and trailing whitespace, then normalising Unicode punctuation, and finally collapsing runs of
+/// interior whitespace. When `eof` is true, we first try starting at the end-of-file (so that
 /// patterns intended to match file endings are applied at the end), and fall back to searching
 /// from `start` if needed.
 ///
@@ -64,39 +65,27 @@ pub(crate) fn seek_sequence(
         }
     }
 
-    // ------------------------------------------------------------------
-    // Final, most permissive pass – attempt to match after *normalising*
-    // common Unicode punctuation to their ASCII equivalents so that diffs
-    // authored with plain ASCII characters can still be applied to source
-    // files that contain typographic dashes / quotes, etc.  This mirrors the
-    // fuzzy behaviour of `git apply` which ignores minor byte-level
-    // differences when locating context lines.
-    // ------------------------------------------------------------------
-
-    fn normalise(s: &str) -> String {
-        s.trim()
-            .chars()
-            .map(|c| match c {
-                // Various dash / hyphen code-points → ASCII '-'
-                '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
-                | '\u{2212}' => '-',
-                // Fancy single quotes → '\''
-                '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
-                // Fancy double quotes → '"'
-                '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
-                // Non-breaking space and other odd spaces → normal space
-                '\u{00A0}' | '\u{2002}' | '\u{2003}' | '\u{2004}' | '\u{2005}' | '\u{2006}'
-                | '\u{2007}' | '\u{2008}' | '\u{2009}' | '\u{200A}' | '\u{202F}' | '\u{205F}'
-                | '\u{3000}' => ' ',
-                other => other,
-            })
-            .collect::<String>()
+    // Then match after *normalising* common Unicode punctuation (see
+    // `normalise` below).
+    for i in search_start..=lines.len().saturating_sub(pattern.len()) {
+        let mut ok = true;
+        for (p_idx, pat) in pattern.iter().enumerate() {
+            if normalise(&lines[i + p_idx]) != normalise(pat) {
+                ok = false;
+                break;
+            }
+        }
+        if ok {
+            return Some(i);
+        }
     }
 
+    // Same again, but with every run of interior whitespace collapsed to a
+    // single space so re-indented or re-aligned code still anchors.
     for i in search_start..=lines.len().saturating_sub(pattern.len()) {
         let mut ok = true;
         for (p_idx, pat) in pattern.iter().enumerate() {
-            if normalise(&lines[i + p_idx]) != normalise(pat) {
+            if collapse_whitespace(&lines[i + p_idx]) != collapse_whitespace(pat) {
                 ok = false;
                 break;
             }
@@ -109,8 +98,169 @@ pub(crate) fn seek_sequence(
     None
 }
 
+/// Minimum average per-line similarity for [`fuzzy_seek_sequence`] to accept a window.
+pub(crate) const FUZZY_MATCH_THRESHOLD: f64 = 0.9;
+
+/// Minimum similarity every line of a window must reach on its own, so that a
+/// long hunk cannot absorb one unrelated line into a high average.
+pub(crate) const FUZZY_LINE_THRESHOLD: f64 = 0.75;
+
+/// Windows scoring within this margin of the best one make the match ambiguous.
+const FUZZY_AMBIGUITY_MARGIN: f64 = 0.01;
+
+/// How many nearest windows [`fuzzy_seek_sequence`] reports when it cannot match.
+const MAX_FUZZY_CANDIDATES: usize = 3;
+
+/// A window of `lines` scored against a pattern.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FuzzyCandidate {
+    /// Index of the first line of the window.
+    pub(crate) start: usize,
+    /// Average per-line similarity of the window, from 0.0 to 1.0.
+    pub(crate) similarity: f64,
+    /// Similarity of the least similar line in the window.
+    pub(crate) min_line_similarity: f64,
+}
+
+impl FuzzyCandidate {
+    fn is_acceptable(&self) -> bool {
+        self.similarity >= FUZZY_MATCH_THRESHOLD && self.min_line_similarity >= FUZZY_LINE_THRESHOLD
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FuzzyMatch {
+    /// Exactly one window scored at least [`FUZZY_MATCH_THRESHOLD`] on average,
+    /// with no line below [`FUZZY_LINE_THRESHOLD`], and clearly above the rest.
+    Unique(usize),
+    /// No window cleared the threshold, or several tied for best. Holds the
+    /// best-scoring windows, most similar first.
+    NoUniqueMatch {
+        ambiguous: bool,
+        candidates: Vec<FuzzyCandidate>,
+    },
+}
+
+/// Last-resort search used when [`seek_sequence`] finds nothing: scores every
+/// window at or after `start` by the average similarity of its lines to
+/// `pattern`, after normalising punctuation and collapsing whitespace. A window
+/// with any single line below [`FUZZY_LINE_THRESHOLD`] is never accepted.
+pub(crate) fn fuzzy_seek_sequence(
+    lines: &[String],
+    pattern: &[String],
+    start: usize,
+) -> FuzzyMatch {
+    if pattern.is_empty() || pattern.len() > lines.len() || start > lines.len() - pattern.len() {
+        return FuzzyMatch::NoUniqueMatch {
+            ambiguous: false,
+            candidates: Vec::new(),
+        };
+    }
+
+    let pattern: Vec<String> = pattern
+        .iter()
+        .map(|line| collapse_whitespace(line))
+        .collect();
+    let lines: Vec<String> = lines.iter().map(|line| collapse_whitespace(line)).collect();
+    let mut scored: Vec<FuzzyCandidate> = (start..=lines.len() - pattern.len())
+        .map(|window_start| {
+            let (total, min_line_similarity) = pattern
+                .iter()
+                .zip(&lines[window_start..])
+                .map(|(expected, actual)| line_similarity(expected, actual))
+                .fold((0.0, f64::INFINITY), |(total, min), similarity| {
+                    (total + similarity, min.min(similarity))
+                });
+            FuzzyCandidate {
+                start: window_start,
+                similarity: total / pattern.len() as f64,
+                min_line_similarity,
+            }
+        })
+        .collect();
+    // Stable sort keeps earlier windows first among equal scores.
+    scored.sort_by(|lhs, rhs| rhs.similarity.total_cmp(&lhs.similarity));
+
+    if let Some(best) = scored.iter().copied().find(FuzzyCandidate::is_acceptable) {
+        // Any other window scoring within the margin, or higher but rejected
+        // for a weak line, makes the match ambiguous.
+        let rivals: Vec<FuzzyCandidate> = scored
+            .iter()
+            .copied()
+            .filter(|candidate| {
+                candidate.start != best.start
+                    && best.similarity - candidate.similarity < FUZZY_AMBIGUITY_MARGIN
+            })
+            .collect();
+        if rivals.is_empty() {
+            return FuzzyMatch::Unique(best.start);
+        }
+        let candidates = std::iter::once(best)
+            .chain(rivals)
+            .take(MAX_FUZZY_CANDIDATES)
+            .collect();
+        return FuzzyMatch::NoUniqueMatch {
+            ambiguous: true,
+            candidates,
+        };
+    }
+
+    scored.truncate(MAX_FUZZY_CANDIDATES);
+    FuzzyMatch::NoUniqueMatch {
+        ambiguous: false,
+        candidates: scored,
+    }
+}
+
+fn line_similarity(expected: &str, actual: &str) -> f64 {
+    if expected == actual {
+        return 1.0;
+    }
+    f64::from(similar::TextDiff::from_chars(expected, actual).ratio())
+}
+
+// ----------------------------------------------------------------------
+// Normalisation shared by the permissive passes – maps common Unicode
+// punctuation to their ASCII equivalents so that diffs authored with plain
+// ASCII characters can still be applied to source files that contain
+// typographic dashes / quotes, etc.  This mirrors the fuzzy behaviour of
+// `git apply` which ignores minor byte-level differences when locating
+// context lines.
+// ----------------------------------------------------------------------
+
+fn normalise(s: &str) -> String {
+    s.trim()
+        .chars()
+        .map(|c| match c {
+            // Various dash / hyphen code-points → ASCII '-'
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}'
+            | '\u{2212}' => '-',
+            // Fancy single quotes → '\''
+            '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => '\'',
+            // Fancy double quotes → '"'
+            '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' => '"',
+            // Non-breaking space and other odd spaces → normal space
+            '\u{00A0}' | '\u{2002}' | '\u{2003}' | '\u{2004}' | '\u{2005}' | '\u{2006}'
+            | '\u{2007}' | '\u{2008}' | '\u{2009}' | '\u{200A}' | '\u{202F}' | '\u{205F}'
+            | '\u{3000}' => ' ',
+            other => other,
+        })
+        .collect::<String>()
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    normalise(s)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 #[cfg(test)]
 mod tests {
+    use super::FUZZY_LINE_THRESHOLD;
+    use super::FUZZY_MATCH_THRESHOLD;
+    use super::FuzzyMatch;
+    use super::fuzzy_seek_sequence;
     use super::seek_sequence;
     use std::string::ToString;
 
@@ -141,6 +291,87 @@ mod tests {
         assert_eq!(seek_sequence(&lines, &pattern, 0, false), Some(0));
     }
 
+    #[test]
+    fn test_collapsed_whitespace_match_ignores_interior_alignment() {
+        let lines = to_vec(&["let  x =   1;", "let y\t= 2;"]);
+        let pattern = to_vec(&["let x = 1;", "let y = 2;"]);
+        assert_eq!(seek_sequence(&lines, &pattern, 0, false), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_match_accepts_unique_near_match() {
+        let lines = to_vec(&[
+            "fn main() {",
+            "    let greeting = \"hello, world\";",
+            "    println!(\"{greeting}\");",
+            "}",
+        ]);
+        let pattern = to_vec(&[
+            "    let greeting = \"hello world\";",
+            "    println!(\"{greeting}\");",
+        ]);
+        assert_eq!(seek_sequence(&lines, &pattern, 0, false), None);
+        assert_eq!(
+            fuzzy_seek_sequence(&lines, &pattern, 0),
+            FuzzyMatch::Unique(1)
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_match_reports_ties_as_ambiguous() {
+        let lines = to_vec(&["a = 1;", "b = 2;", "a = 1;", "b = 2;"]);
+        let pattern = to_vec(&["a = 1;", "b = 3;"]);
+        match fuzzy_seek_sequence(&lines, &pattern, 0) {
+            FuzzyMatch::NoUniqueMatch {
+                ambiguous,
+                candidates,
+            } => {
+                assert!(ambiguous);
+                let starts: Vec<usize> = candidates.iter().map(|c| c.start).collect();
+                assert_eq!(starts, vec![0, 2]);
+            }
+            other => panic!("expected ambiguous match, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match_returns_nearest_candidates_below_threshold() {
+        let lines = to_vec(&["alpha", "beta", "gamma", "delta"]);
+        let pattern = to_vec(&["gamma", "omega"]);
+        match fuzzy_seek_sequence(&lines, &pattern, 0) {
+            FuzzyMatch::NoUniqueMatch {
+                ambiguous,
+                candidates,
+            } => {
+                assert!(!ambiguous);
+                assert_eq!(candidates.len(), 3);
+                assert_eq!(candidates[0].start, 2);
+                assert!(candidates[0].similarity < FUZZY_MATCH_THRESHOLD);
+            }
+            other => panic!("expected no match, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_match_rejects_one_unrelated_line_in_long_hunk() {
+        let mut lines: Vec<String> = (0..12).map(|i| format!("let value_{i} = {i};")).collect();
+        let pattern = lines.clone();
+        lines[6] = "panic!(\"completely different\");".to_string();
+        match fuzzy_seek_sequence(&lines, &pattern, 0) {
+            FuzzyMatch::NoUniqueMatch {
+                ambiguous,
+                candidates,
+            } => {
+                assert!(!ambiguous);
+                assert_eq!(candidates[0].start, 0);
+                // The average alone would have cleared the threshold.
+                assert!(candidates[0].similarity >= FUZZY_MATCH_THRESHOLD);
+                assert!(candidates[0].min_line_similarity < FUZZY_LINE_THRESHOLD);
+            }
+            other => panic!("expected no match, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_pattern_longer_than_input_returns_none() {
         let lines = to_vec(&["just one line"]);
@@ -9,10 +9,17 @@ pub fn main() -> ! {
 /// We would prefer to return `std::process::ExitCode`, but its `exit_process()`
 /// method is still a nightly API and we want main() to return !.
 pub fn run_main() -> i32 {
-    // Expect either one argument (the full apply_patch payload) or read it from stdin.
-    let mut args = std::env::args_os();
+    // Expect an optional `--dry-run` flag followed by either one argument (the
+    // full apply_patch payload) or the payload on stdin.
+    let mut args = std::env::args_os().peekable();
     let _argv0 = args.next();
 
+    let mode = if args.next_if(|arg| arg == "--dry-run").is_some() {
+        crate::ApplyMode::DryRun
+    } else {
+        crate::ApplyMode::Transactional
+    };
+
     let patch_arg = match args.next() {
         Some(arg) => match arg.into_string() {
             Ok(s) => s,
@@ -27,7 +34,9 @@ pub fn run_main() -> i32 {
             match std::io::stdin().read_to_string(&mut buf) {
                 Ok(_) => {
                     if buf.is_empty() {
-                        eprintln!("Usage: apply_patch 'PATCH'\n       echo 'PATCH' | apply_patch");
+                        eprintln!(
+                            "Usage: apply_patch [--dry-run] 'PATCH'\n       echo 'PATCH' | apply_patch [--dry-run]"
+                        );
                         return 2;
                     }
                     buf
@@ -48,7 +57,7 @@ pub fn run_main() -> i32 {
 
     let mut stdout = std::io::stdout();
     let mut stderr = std::io::stderr();
-    match crate::apply_patch(&patch_arg, &mut stdout, &mut stderr) {
+    match crate::apply_patch_with_mode(&patch_arg, mode, &mut stdout, &mut stderr) {
         Ok(()) => {
             // Flush to ensure output ordering when used in pipelines.
             let _ = stdout.flush();
@@ -0,0 +1,253 @@
+//! Line-by-line application of update chunks to files too large to buffer.
+//!
+//! Only a window of `old_lines.len()` lines is held in memory at a time, so
+//! matching is simpler than for buffered files: each chunk's lines are found in
+//! a single forward pass, compared ignoring trailing whitespace, with no fuzzy
+//! fallback. Chunks with no old lines are appended at the end of the file. The
+//! file's line ending (LF or CRLF) is detected from its first line and used for
+//! every line written.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+use crate::ApplyPatchError;
+use crate::BINARY_SNIFF_LEN;
+use crate::HunkMismatch;
+use crate::IoError;
+use crate::UpdateFileChunk;
+use crate::looks_binary;
+
+/// Checks that `chunks` can be streamed into `source`, the current contents of
+/// `path`: streaming supports neither merge bases nor line-numbered
+/// insertions or missing final newlines, and skips binary files.
+pub(crate) fn ensure_streamable(
+    path: &Path,
+    source: &Path,
+    chunks: &[UpdateFileChunk],
+    has_base: bool,
+) -> Result<(), ApplyPatchError> {
+    if has_base {
+        return Err(ApplyPatchError::ComputeReplacements(format!(
+            "Merge bases are not supported for files larger than the streaming threshold: {}",
+            path.display()
+        )));
+    }
+    if chunks
+        .iter()
+        .any(|chunk| chunk.insert_at_line.is_some() || chunk.missing_final_newline)
+    {
+        return Err(ApplyPatchError::ComputeReplacements(format!(
+            "Line-numbered insertions and missing final newlines are not supported for files larger than the streaming threshold: {}",
+            path.display()
+        )));
+    }
+    let mut head = Vec::with_capacity(BINARY_SNIFF_LEN);
+    File::open(source)
+        .and_then(|file| file.take(BINARY_SNIFF_LEN as u64).read_to_end(&mut head))
+        .map_err(|err| {
+            ApplyPatchError::IoError(IoError {
+                context: format!("Failed to read file to update {}", path.display()),
+                source: err,
+            })
+        })?;
+    if looks_binary(&head) {
+        return Err(ApplyPatchError::BinaryFile(path.to_path_buf()));
+    }
+    Ok(())
+}
+
+/// Writes the contents of `source` with `chunks` applied to `out`. `path` is
+/// the file being updated, used in error messages.
+pub(crate) fn stream_update(
+    path: &Path,
+    source: &Path,
+    chunks: &[UpdateFileChunk],
+    out: &mut impl Write,
+) -> Result<(), ApplyPatchError> {
+    let file = File::open(source).map_err(|err| {
+        ApplyPatchError::IoError(IoError {
+            context: format!("Failed to read file to update {}", source.display()),
+            source: err,
+        })
+    })?;
+    let mut reader = BufReader::new(file);
+    let (anchored, appended): (Vec<&UpdateFileChunk>, Vec<&UpdateFileChunk>) =
+        chunks.iter().partition(|chunk| !chunk.old_lines.is_empty());
+    let mut pending = anchored.into_iter().peekable();
+    let mut writer = LineWriter {
+        path,
+        out,
+        line_ending: None,
+    };
+
+    let mut context_found = false;
+    let mut window: VecDeque<String> = VecDeque::new();
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).map_err(|err| {
+            ApplyPatchError::IoError(IoError {
+                context: format!("Failed to read file to update {}", source.display()),
+                source: err,
+            })
+        })?;
+        if read == 0 {
+            break;
+        }
+        let line = writer.decode_line(&buf)?;
+
+        let Some(chunk) = pending.peek() else {
+            writer.write_line(&line)?;
+            continue;
+        };
+        if let Some(context) = &chunk.change_context
+            && !context_found
+        {
+            context_found = lines_match(&line, context);
+            writer.write_line(&line)?;
+            continue;
+        }
+        window.push_back(line);
+        if window.len() > chunk.old_lines.len()
+            && let Some(line) = window.pop_front()
+        {
+            writer.write_line(&line)?;
+        }
+        if !chunk.is_end_of_file && window_matches(&window, &chunk.old_lines) {
+            writer.write_lines(&chunk.new_lines)?;
+            window.clear();
+            pending.next();
+            context_found = false;
+        }
+    }
+
+    if let Some(chunk) = pending.peek()
+        && chunk.is_end_of_file
+        && (chunk.change_context.is_none() || context_found)
+        && window_matches(&window, &chunk.old_lines)
+    {
+        writer.write_lines(&chunk.new_lines)?;
+        window.clear();
+        pending.next();
+    }
+    if let Some(chunk) = pending.next() {
+        return Err(HunkMismatch {
+            path: path.to_path_buf(),
+            expected_lines: chunk.old_lines.clone(),
+            ambiguous: false,
+            candidates: Vec::new(),
+        }
+        .into());
+    }
+    for line in window {
+        writer.write_line(&line)?;
+    }
+    for chunk in appended {
+        writer.write_lines(&chunk.new_lines)?;
+    }
+    Ok(())
+}
+
+struct LineWriter<'a, W> {
+    path: &'a Path,
+    out: &'a mut W,
+    /// Detected from the first line read.
+    line_ending: Option<&'static str>,
+}
+
+impl<W: Write> LineWriter<'_, W> {
+    fn decode_line(&mut self, raw: &[u8]) -> Result<String, ApplyPatchError> {
+        let (text, ending) = match raw.strip_suffix(b"\r\n") {
+            Some(text) => (text, "\r\n"),
+            None => (raw.strip_suffix(b"\n").unwrap_or(raw), "\n"),
+        };
+        self.line_ending.get_or_insert(ending);
+        if text.contains(&0) {
+            return Err(ApplyPatchError::BinaryFile(self.path.to_path_buf()));
+        }
+        String::from_utf8(text.to_vec())
+            .map_err(|_| ApplyPatchError::BinaryFile(self.path.to_path_buf()))
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), ApplyPatchError> {
+        let ending = self.line_ending.unwrap_or("\n");
+        write!(self.out, "{line}{ending}").map_err(|err| {
+            ApplyPatchError::IoError(IoError {
+                context: format!("Failed to write file {}", self.path.display()),
+                source: err,
+            })
+        })
+    }
+
+    fn write_lines(&mut self, lines: &[String]) -> Result<(), ApplyPatchError> {
+        lines.iter().try_for_each(|line| self.write_line(line))
+    }
+}
+
+fn lines_match(line: &str, expected: &str) -> bool {
+    line.trim_end() == expected.trim_end()
+}
+
+fn window_matches(window: &VecDeque<String>, pattern: &[String]) -> bool {
+    window.len() == pattern.len()
+        && window
+            .iter()
+            .zip(pattern)
+            .all(|(line, expected)| lines_match(line, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn chunk(context: Option<&str>, old: &[&str], new: &[&str], eof: bool) -> UpdateFileChunk {
+        UpdateFileChunk {
+            change_context: context.map(ToString::to_string),
+            old_lines: old.iter().map(ToString::to_string).collect(),
+            new_lines: new.iter().map(ToString::to_string).collect(),
+            is_end_of_file: eof,
+            insert_at_line: None,
+            missing_final_newline: false,
+        }
+    }
+
+    fn stream(contents: &[u8], chunks: &[UpdateFileChunk]) -> Result<String, ApplyPatchError> {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("big.txt");
+        std::fs::write(&path, contents).unwrap();
+        let mut out = Vec::new();
+        stream_update(&path, &path, chunks, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn applies_chunks_in_one_pass_preserving_crlf() {
+        let contents = b"fn a() {\r\n    1\r\n}\r\nfn b() {\r\n    1\r\n}\r\ntail\r\n";
+        let chunks = [
+            chunk(Some("fn b() {"), &["    1"], &["    2"], false),
+            chunk(None, &["tail"], &["end"], true),
+            chunk(None, &[], &["appended"], false),
+        ];
+
+        assert_eq!(
+            stream(contents, &chunks).unwrap(),
+            "fn a() {\r\n    1\r\n}\r\nfn b() {\r\n    2\r\n}\r\nend\r\nappended\r\n"
+        );
+    }
+
+    #[test]
+    fn reports_unmatched_chunks_and_binary_contents() {
+        let err = stream(b"one\ntwo\n", &[chunk(None, &["three"], &["3"], false)]).unwrap_err();
+        assert!(matches!(err, ApplyPatchError::HunkMismatch(_)), "{err}");
+
+        let err = stream(b"one\0\ntwo\n", &[chunk(None, &["two"], &["2"], false)]).unwrap_err();
+        assert!(matches!(err, ApplyPatchError::BinaryFile(_)), "{err}");
+    }
+}
@@ -0,0 +1,827 @@
+//! All-or-nothing application of a multi-file patch.
+//!
+//! Every hunk is first applied to an in-memory view of the files it touches
+//! ([`PatchPlan`]), so a hunk that does not apply fails the patch before
+//! anything is written. Files over the streaming threshold are the exception:
+//! their chunks are only checked while streaming them during staging, which
+//! still happens before anything is committed. The planned contents are then staged as temp files
+//! next to their targets ([`StagedPatch`]) and committed with renames. Files
+//! being replaced or deleted are renamed aside first, so any failure during the
+//! commit can be rolled back by renaming them back. Symlinks, hard-linked files
+//! and files owned by someone else are overwritten in place instead, from a
+//! backup copy, so the link or owner survives.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use anyhow::Context;
+use similar::TextDiff;
+
+use crate::AffectedPaths;
+use crate::AppliedPatch;
+use crate::DEFAULT_STREAM_THRESHOLD_BYTES;
+use crate::Hunk;
+use crate::STREAM_THRESHOLD_ENV_VAR;
+use crate::UpdateFileChunk;
+use crate::decode_text;
+use crate::derive_new_contents;
+use crate::looks_binary;
+use crate::streaming::ensure_streamable;
+use crate::streaming::stream_update;
+
+/// Context lines around each change in dry-run diffs.
+const DRY_RUN_DIFF_CONTEXT: usize = 3;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// The state every touched path will have once the patch is applied.
+pub(crate) struct PatchPlan {
+    files: Vec<PlannedFile>,
+    affected: AffectedPaths,
+}
+
+struct PlannedFile {
+    path: PathBuf,
+    /// Contents on disk before the patch.
+    original: Contents,
+    /// Contents after the patch.
+    contents: Contents,
+    /// Permissions for the written file, carried over from the file it replaces.
+    permissions: Option<fs::Permissions>,
+}
+
+impl PlannedFile {
+    fn is_unchanged(&self) -> bool {
+        !self.original.exists() && !self.contents.exists()
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum Contents {
+    Missing,
+    InMemory(Vec<u8>),
+    /// A file over the streaming threshold: `source` on disk with each batch
+    /// of `updates` (one per update hunk) applied to it in turn, line by line,
+    /// while staging.
+    Streamed {
+        source: PathBuf,
+        updates: Vec<Vec<UpdateFileChunk>>,
+    },
+}
+
+impl Contents {
+    fn exists(&self) -> bool {
+        !matches!(self, Contents::Missing)
+    }
+}
+
+/// The streaming threshold from [`STREAM_THRESHOLD_ENV_VAR`], falling back to
+/// [`DEFAULT_STREAM_THRESHOLD_BYTES`] when unset or invalid.
+pub(crate) fn stream_threshold() -> u64 {
+    std::env::var(STREAM_THRESHOLD_ENV_VAR)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_STREAM_THRESHOLD_BYTES)
+}
+
+impl PatchPlan {
+    /// Applies `hunks` in order to an in-memory view of the files they touch.
+    pub(crate) fn from_hunks(hunks: &[Hunk]) -> anyhow::Result<Self> {
+        Self::from_hunks_with_stream_threshold(hunks, stream_threshold())
+    }
+
+    /// Like [`PatchPlan::from_hunks`], streaming existing files larger than
+    /// `stream_threshold` bytes instead of reading them.
+    fn from_hunks_with_stream_threshold(
+        hunks: &[Hunk],
+        stream_threshold: u64,
+    ) -> anyhow::Result<Self> {
+        let mut plan = PatchPlan {
+            files: Vec::new(),
+            affected: AffectedPaths {
+                added: Vec::new(),
+                modified: Vec::new(),
+                deleted: Vec::new(),
+                conflicted: Vec::new(),
+            },
+        };
+        let mut index: HashMap<PathBuf, usize> = HashMap::new();
+
+        for hunk in hunks {
+            match hunk {
+                Hunk::AddFile { path, contents } => {
+                    plan.file(&mut index, path, stream_threshold)?.contents =
+                        Contents::InMemory(contents.clone().into_bytes());
+                    plan.affected.added.push(path.clone());
+                }
+                Hunk::AddBinaryFile { path, contents } => {
+                    plan.file(&mut index, path, stream_threshold)?.contents =
+                        Contents::InMemory(contents.clone());
+                    plan.affected.added.push(path.clone());
+                }
+                Hunk::DeleteFile { path } => {
+                    let file = plan.file(&mut index, path, stream_threshold)?;
+                    if !std::mem::replace(&mut file.contents, Contents::Missing).exists() {
+                        anyhow::bail!(
+                            "Failed to delete file {}: No such file or directory",
+                            path.display()
+                        );
+                    }
+                    plan.affected.deleted.push(path.clone());
+                }
+                Hunk::UpdateFile {
+                    path,
+                    move_path,
+                    base,
+                    chunks,
+                } => {
+                    let source = plan.file(&mut index, path, stream_threshold)?;
+                    if source
+                        .permissions
+                        .as_ref()
+                        .is_some_and(fs::Permissions::readonly)
+                    {
+                        anyhow::bail!("Failed to write file {}: file is read-only", path.display());
+                    }
+                    let (new_contents, conflicts) = match &source.contents {
+                        Contents::Missing => anyhow::bail!(
+                            "Failed to read file to update {}: No such file or directory",
+                            path.display()
+                        ),
+                        Contents::InMemory(current) => {
+                            let current = decode_text(path, current.clone())?;
+                            let AppliedPatch {
+                                new_contents,
+                                conflicts,
+                                ..
+                            } = derive_new_contents(path, current, chunks, base.as_ref())?;
+                            (Contents::InMemory(new_contents.into_bytes()), conflicts)
+                        }
+                        Contents::Streamed {
+                            source: streamed_from,
+                            updates,
+                        } => {
+                            ensure_streamable(path, streamed_from, chunks, base.is_some())?;
+                            let mut updates = updates.clone();
+                            updates.push(chunks.clone());
+                            let contents = Contents::Streamed {
+                                source: streamed_from.clone(),
+                                updates,
+                            };
+                            (contents, Vec::new())
+                        }
+                    };
+
+                    let target = match move_path {
+                        Some(dest) => {
+                            let permissions = source.permissions.clone();
+                            source.contents = Contents::Missing;
+                            let dest_file = plan.file(&mut index, dest, stream_threshold)?;
+                            dest_file.permissions = permissions;
+                            dest_file.contents = new_contents;
+                            dest
+                        }
+                        None => {
+                            source.contents = new_contents;
+                            path
+                        }
+                    };
+                    if conflicts.is_empty() {
+                        plan.affected.modified.push(target.clone());
+                    } else {
+                        plan.affected.conflicted.push((target.clone(), conflicts));
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    pub(crate) fn into_affected(self) -> AffectedPaths {
+        self.affected
+    }
+
+    /// Unified diffs from the current contents of each touched file to its
+    /// planned contents, in the order the files were first touched. Binary and
+    /// streamed files get a one-line summary instead; streamed updates are
+    /// still run to check that their chunks apply.
+    pub(crate) fn unified_diff(&self) -> anyhow::Result<String> {
+        let mut out = String::new();
+        for file in &self.files {
+            if file.original == file.contents {
+                continue;
+            }
+            let path = file.path.display().to_string();
+            let old_name = if file.original.exists() {
+                path.as_str()
+            } else {
+                "/dev/null"
+            };
+            let new_name = if file.contents.exists() {
+                path.as_str()
+            } else {
+                "/dev/null"
+            };
+            let (original, contents) = match (&file.original, &file.contents) {
+                (Contents::Streamed { .. }, _) | (_, Contents::Streamed { .. }) => {
+                    if let Contents::Streamed { source, updates } = &file.contents {
+                        stream_updates(&file.path, source, updates, &mut std::io::sink())?;
+                    }
+                    out.push_str(&format!("Large files {old_name} and {new_name} differ\n"));
+                    continue;
+                }
+                (original, contents) => (in_memory_bytes(original), in_memory_bytes(contents)),
+            };
+            let (Ok(original), Ok(contents)) =
+                (std::str::from_utf8(original), std::str::from_utf8(contents))
+            else {
+                out.push_str(&format!("Binary files {old_name} and {new_name} differ\n"));
+                continue;
+            };
+            if looks_binary(original.as_bytes()) || looks_binary(contents.as_bytes()) {
+                out.push_str(&format!("Binary files {old_name} and {new_name} differ\n"));
+                continue;
+            }
+            let diff = TextDiff::from_lines(original, contents);
+            out.push_str(
+                &diff
+                    .unified_diff()
+                    .context_radius(DRY_RUN_DIFF_CONTEXT)
+                    .header(old_name, new_name)
+                    .to_string(),
+            );
+        }
+        Ok(out)
+    }
+
+    /// Writes every planned file to a temp file beside its target. Nothing
+    /// visible changes until [`StagedPatch::commit`].
+    pub(crate) fn stage(&self) -> anyhow::Result<StagedPatch<'_>> {
+        let mut staged = StagedPatch {
+            plan: self,
+            temp_paths: vec![None; self.files.len()],
+            created_dirs: Vec::new(),
+        };
+        for (idx, file) in self.files.iter().enumerate() {
+            if !file.contents.exists() {
+                continue;
+            }
+            if let Err(err) = staged.stage_file(idx, file) {
+                staged.discard();
+                return Err(err);
+            }
+        }
+        Ok(staged)
+    }
+
+    /// Returns the planned file for `path`, loading its current state from disk
+    /// the first time the patch touches it.
+    fn file(
+        &mut self,
+        index: &mut HashMap<PathBuf, usize>,
+        path: &Path,
+        stream_threshold: u64,
+    ) -> anyhow::Result<&mut PlannedFile> {
+        let idx = match index.get(path) {
+            Some(idx) => *idx,
+            None => {
+                let (original, permissions) = match fs::metadata(path) {
+                    Ok(metadata) if metadata.is_file() && metadata.len() > stream_threshold => {
+                        let original = Contents::Streamed {
+                            source: path.to_path_buf(),
+                            updates: Vec::new(),
+                        };
+                        (original, Some(metadata.permissions()))
+                    }
+                    Ok(metadata) if metadata.is_file() => {
+                        let original = fs::read(path)
+                            .with_context(|| format!("Failed to read file {}", path.display()))?;
+                        (Contents::InMemory(original), Some(metadata.permissions()))
+                    }
+                    _ => (Contents::Missing, None),
+                };
+                self.files.push(PlannedFile {
+                    path: path.to_path_buf(),
+                    contents: original.clone(),
+                    original,
+                    permissions,
+                });
+                index.insert(path.to_path_buf(), self.files.len() - 1);
+                self.files.len() - 1
+            }
+        };
+        Ok(&mut self.files[idx])
+    }
+}
+
+/// Streams `source` through each batch of `updates` in turn into `out`, so an
+/// update hunk matches against the output of the ones before it, as it does
+/// for files held in memory. Intermediate results are spilled to temp files
+/// beside `source` and removed afterwards.
+fn stream_updates(
+    path: &Path,
+    source: &Path,
+    updates: &[Vec<UpdateFileChunk>],
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let Some((last, earlier)) = updates.split_last() else {
+        stream_update(path, source, &[], out)?;
+        return Ok(());
+    };
+    let mut spilled: Option<PathBuf> = None;
+    let result = (|| {
+        for chunks in earlier {
+            let input = spilled.clone().unwrap_or_else(|| source.to_path_buf());
+            let next = sibling_temp_path(source, "step");
+            let previous = spilled.replace(next.clone());
+            let written = fs::File::create(&next)
+                .with_context(|| format!("Failed to write file {}", path.display()))
+                .and_then(|file| {
+                    let mut writer = BufWriter::new(file);
+                    stream_update(path, &input, chunks, &mut writer)?;
+                    writer
+                        .flush()
+                        .with_context(|| format!("Failed to write file {}", path.display()))
+                });
+            if let Some(previous) = previous {
+                let _ = fs::remove_file(previous);
+            }
+            written?;
+        }
+        let input = spilled.clone().unwrap_or_else(|| source.to_path_buf());
+        stream_update(path, &input, last, out)?;
+        Ok(())
+    })();
+    if let Some(spilled) = spilled {
+        let _ = fs::remove_file(spilled);
+    }
+    result
+}
+
+fn in_memory_bytes(contents: &Contents) -> &[u8] {
+    match contents {
+        Contents::InMemory(bytes) => bytes,
+        Contents::Missing | Contents::Streamed { .. } => &[],
+    }
+}
+
+/// A [`PatchPlan`] whose new contents have been written to temp files.
+pub(crate) struct StagedPatch<'a> {
+    plan: &'a PatchPlan,
+    /// Temp file holding the new contents, per entry of `plan.files`.
+    temp_paths: Vec<Option<PathBuf>>,
+    /// Directories created for new files, outermost first.
+    created_dirs: Vec<PathBuf>,
+}
+
+/// One committed step, kept so it can be undone.
+struct CommittedFile<'a> {
+    path: &'a Path,
+    /// Where the previous file was moved aside to, if there was one. For an
+    /// in-place write this is a copy of its contents instead.
+    backup: Option<PathBuf>,
+    wrote: bool,
+    /// The new contents were copied into the existing file rather than
+    /// renamed over it.
+    in_place: bool,
+}
+
+impl StagedPatch<'_> {
+    /// Moves every staged file into place and removes deleted files. On error,
+    /// every change made so far is undone.
+    pub(crate) fn commit(mut self) -> anyhow::Result<()> {
+        let mut committed: Vec<CommittedFile<'_>> = Vec::new();
+        for (idx, file) in self.plan.files.iter().enumerate() {
+            if file.is_unchanged() {
+                continue;
+            }
+            match commit_file(file, self.temp_paths[idx].as_deref()) {
+                Ok(step) => {
+                    self.temp_paths[idx] = None;
+                    committed.push(step);
+                }
+                Err(err) => {
+                    // The temp file is still there if the failed step did not
+                    // consume it; `discard` below removes it.
+                    roll_back(committed);
+                    self.discard();
+                    return Err(err);
+                }
+            }
+        }
+        for step in committed {
+            if let Some(backup) = step.backup {
+                let _ = fs::remove_file(backup);
+            }
+        }
+        Ok(())
+    }
+
+    fn stage_file(&mut self, idx: usize, file: &PlannedFile) -> anyhow::Result<()> {
+        if let Some(parent) = file.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            // Recorded before creating them so a partial failure is cleaned up
+            // too; `discard` ignores directories that were never made.
+            let first_new = self.created_dirs.len();
+            self.created_dirs.extend(
+                parent
+                    .ancestors()
+                    .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+                    .map(Path::to_path_buf),
+            );
+            self.created_dirs[first_new..].reverse();
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "Failed to create parent directories for {}",
+                    file.path.display()
+                )
+            })?;
+        }
+
+        let temp_path = sibling_temp_path(&file.path, "tmp");
+        // Recorded first so `discard` removes a partially written file.
+        self.temp_paths[idx] = Some(temp_path.clone());
+        match &file.contents {
+            Contents::Missing => {}
+            Contents::InMemory(contents) => fs::write(&temp_path, contents)
+                .with_context(|| format!("Failed to write file {}", file.path.display()))?,
+            Contents::Streamed { source, updates } => {
+                let out = fs::File::create(&temp_path)
+                    .with_context(|| format!("Failed to write file {}", file.path.display()))?;
+                let mut out = BufWriter::new(out);
+                stream_updates(&file.path, source, updates, &mut out)?;
+                out.flush()
+                    .with_context(|| format!("Failed to write file {}", file.path.display()))?;
+            }
+        }
+        if let Some(permissions) = &file.permissions {
+            fs::set_permissions(&temp_path, permissions.clone())
+                .with_context(|| format!("Failed to set permissions on {}", file.path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Removes staged temp files and the directories created for them.
+    fn discard(&mut self) {
+        for temp_path in self.temp_paths.iter_mut().filter_map(Option::take) {
+            let _ = fs::remove_file(temp_path);
+        }
+        for dir in self.created_dirs.drain(..).rev() {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+}
+
+fn commit_file<'a>(
+    file: &'a PlannedFile,
+    temp_path: Option<&Path>,
+) -> anyhow::Result<CommittedFile<'a>> {
+    let path = file.path.as_path();
+    let mut step = CommittedFile {
+        path,
+        backup: None,
+        wrote: false,
+        in_place: false,
+    };
+    if let Some(temp_path) = temp_path
+        && file.original.exists()
+        && needs_in_place_write(path, temp_path)?
+    {
+        let backup = sibling_temp_path(path, "orig");
+        fs::copy(path, &backup)
+            .with_context(|| format!("Failed to replace file {}", path.display()))?;
+        step.backup = Some(backup);
+        step.in_place = true;
+        if let Err(err) = copy_contents(temp_path, path) {
+            roll_back(vec![step]);
+            return Err(err).with_context(|| format!("Failed to write file {}", path.display()));
+        }
+        let _ = fs::remove_file(temp_path);
+        step.wrote = true;
+        return Ok(step);
+    }
+    if file.original.exists() {
+        let backup = sibling_temp_path(path, "orig");
+        let action = if file.contents.exists() {
+            "replace"
+        } else {
+            "delete"
+        };
+        fs::rename(path, &backup)
+            .with_context(|| format!("Failed to {action} file {}", path.display()))?;
+        step.backup = Some(backup);
+    }
+    if let Some(temp_path) = temp_path {
+        if let Err(err) = fs::rename(temp_path, path) {
+            roll_back(vec![step]);
+            return Err(err).with_context(|| format!("Failed to write file {}", path.display()));
+        }
+        step.wrote = true;
+    }
+    Ok(step)
+}
+
+/// Whether the file at `path` has to be overwritten in place instead of
+/// having the staged file renamed over it: renaming would turn a symlink into
+/// a regular file, split a hard link, or drop an owner the staged file does not
+/// share.
+fn needs_in_place_write(path: &Path, temp_path: &Path) -> anyhow::Result<bool> {
+    let metadata = fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read metadata for {}", path.display()))?;
+    if metadata.file_type().is_symlink() {
+        return Ok(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_metadata = fs::metadata(temp_path)
+            .with_context(|| format!("Failed to read metadata for {}", temp_path.display()))?;
+        if metadata.nlink() > 1
+            || metadata.uid() != temp_metadata.uid()
+            || metadata.gid() != temp_metadata.gid()
+        {
+            return Ok(true);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = temp_path;
+    Ok(false)
+}
+
+/// Overwrites the file `to` points at with the contents of `from`, keeping its
+/// inode, links and ownership.
+fn copy_contents(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut source = fs::File::open(from)?;
+    let mut target = fs::OpenOptions::new().write(true).truncate(true).open(to)?;
+    std::io::copy(&mut source, &mut target)?;
+    target.flush()
+}
+
+/// Undoes committed steps, newest first. Best effort: a failure to restore
+/// one file does not stop the others.
+fn roll_back(committed: Vec<CommittedFile<'_>>) {
+    for step in committed.into_iter().rev() {
+        if step.in_place {
+            if let Some(backup) = step.backup
+                && copy_contents(&backup, step.path).is_ok()
+            {
+                let _ = fs::remove_file(backup);
+            }
+            continue;
+        }
+        if step.wrote {
+            let _ = fs::remove_file(step.path);
+        }
+        if let Some(backup) = step.backup {
+            let _ = fs::rename(backup, step.path);
+        }
+    }
+}
+
+/// A unique hidden path in the same directory as `path`, so renames between
+/// the two never cross filesystems.
+fn sibling_temp_path(path: &Path, suffix: &str) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{file_name}.apply_patch-{}-{counter}.{suffix}",
+        std::process::id()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApplyPatchError;
+    use crate::parse_patch;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn plan(patch_body: &str) -> anyhow::Result<PatchPlan> {
+        let patch = parse_patch(&format!("*** Begin Patch\n{patch_body}\n*** End Patch"))?;
+        PatchPlan::from_hunks(&patch.hunks)
+    }
+
+    fn dir_entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn later_hunks_see_earlier_updates_to_the_same_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("twice.txt");
+        fs::write(&path, "one\ntwo\n").unwrap();
+
+        let plan = plan(&format!(
+            "*** Update File: {0}\n@@\n-one\n+ONE\n*** Update File: {0}\n@@\n-ONE\n+uno",
+            path.display()
+        ))
+        .unwrap();
+        plan.stage().unwrap().commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "uno\ntwo\n");
+    }
+
+    #[test]
+    fn failed_commit_restores_every_file() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "first\n").unwrap();
+        fs::write(&second, "second\n").unwrap();
+        let plan = plan(&format!(
+            "*** Update File: {}\n@@\n-first\n+FIRST\n*** Delete File: {}\n*** Add File: {}\n+new",
+            first.display(),
+            second.display(),
+            dir.path().join("nested/new.txt").display()
+        ))
+        .unwrap();
+
+        let staged = plan.stage().unwrap();
+        // Pull the rug out from under the last file so its rename fails after
+        // the first two steps have been committed.
+        let last_temp = staged.temp_paths[2].clone().unwrap();
+        fs::remove_file(&last_temp).unwrap();
+        let err = staged.commit().expect_err("commit must fail");
+
+        assert!(err.to_string().contains("Failed to write file"), "{err:#}");
+        assert_eq!(fs::read_to_string(&first).unwrap(), "first\n");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "second\n");
+        assert_eq!(dir_entries(dir.path()), vec!["first.txt", "second.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn updates_through_symlinks_and_hard_links_in_place() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link.txt");
+        let shared = dir.path().join("shared.txt");
+        let hard_link = dir.path().join("shared-link.txt");
+        fs::write(&target, "one\n").unwrap();
+        fs::write(&shared, "two\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        fs::hard_link(&shared, &hard_link).unwrap();
+        let shared_inode = fs::metadata(&shared).unwrap().ino();
+
+        let plan = plan(&format!(
+            "*** Update File: {}\n@@\n-one\n+ONE\n*** Update File: {}\n@@\n-two\n+TWO",
+            link.display(),
+            shared.display()
+        ))
+        .unwrap();
+        plan.stage().unwrap().commit().unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_link(&link).unwrap(), target);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "ONE\n");
+        assert_eq!(fs::metadata(&shared).unwrap().ino(), shared_inode);
+        assert_eq!(fs::read_to_string(&hard_link).unwrap(), "TWO\n");
+        // No temp or backup files are left behind.
+        assert_eq!(
+            dir_entries(dir.path()),
+            vec!["link.txt", "shared-link.txt", "shared.txt", "target.txt"]
+        );
+    }
+
+    #[test]
+    fn streams_files_over_the_threshold() {
+        let dir = tempdir().unwrap();
+        let big = dir.path().join("generated.rs");
+        let moved = dir.path().join("moved.rs");
+        let body: String = (0..200)
+            .map(|n| format!("const C{n}: u32 = {n};\n"))
+            .collect();
+        fs::write(&big, &body).unwrap();
+        let hunks = parse_patch(&format!(
+            "*** Begin Patch\n*** Update File: {0}\n@@\n-const C100: u32 = 100;\n+const C100: u32 = 0;\n\
+             *** Update File: {0}\n*** Move to: {1}\n@@\n-const C199: u32 = 199;\n+// end\n*** End Patch",
+            big.display(),
+            moved.display()
+        ))
+        .unwrap()
+        .hunks;
+
+        let plan = PatchPlan::from_hunks_with_stream_threshold(&hunks, 64).unwrap();
+        assert!(matches!(plan.files[1].contents, Contents::Streamed { .. }));
+        assert_eq!(
+            plan.unified_diff().unwrap(),
+            format!(
+                "Large files {} and /dev/null differ\nLarge files /dev/null and {} differ\n",
+                big.display(),
+                moved.display()
+            )
+        );
+        plan.stage().unwrap().commit().unwrap();
+
+        let expected = body
+            .replace("const C100: u32 = 100;", "const C100: u32 = 0;")
+            .replace("const C199: u32 = 199;", "// end");
+        assert!(!big.exists());
+        assert_eq!(fs::read_to_string(&moved).unwrap(), expected);
+    }
+
+    #[test]
+    fn streamed_updates_see_earlier_updates_to_the_same_file() {
+        let dir = tempdir().unwrap();
+        let big = dir.path().join("big.txt");
+        fs::write(&big, "one\ntwo\nthree\n").unwrap();
+        let hunks = parse_patch(&format!(
+            "*** Begin Patch\n*** Update File: {0}\n@@\n-two\n+TWO\n\
+             *** Update File: {0}\n@@\n-one\n-TWO\n+uno\n+dos\n*** End Patch",
+            big.display()
+        ))
+        .unwrap()
+        .hunks;
+
+        let plan = PatchPlan::from_hunks_with_stream_threshold(&hunks, 1).unwrap();
+        plan.unified_diff().unwrap();
+        plan.stage().unwrap().commit().unwrap();
+
+        assert_eq!(fs::read_to_string(&big).unwrap(), "uno\ndos\nthree\n");
+        assert_eq!(dir_entries(dir.path()), vec!["big.txt"]);
+    }
+
+    #[test]
+    fn streamed_mismatch_fails_before_commit() {
+        let dir = tempdir().unwrap();
+        let big = dir.path().join("big.txt");
+        let added = dir.path().join("added.txt");
+        fs::write(&big, "a\nb\nc\n").unwrap();
+        let hunks = parse_patch(&format!(
+            "*** Begin Patch\n*** Add File: {}\n+new\n*** Update File: {}\n@@\n-missing\n+x\n*** End Patch",
+            added.display(),
+            big.display()
+        ))
+        .unwrap()
+        .hunks;
+
+        let plan = PatchPlan::from_hunks_with_stream_threshold(&hunks, 1).unwrap();
+        let err = plan.stage().err().expect("staging must fail");
+
+        assert!(matches!(
+            err.downcast_ref::<ApplyPatchError>(),
+            Some(ApplyPatchError::HunkMismatch(_))
+        ));
+        assert_eq!(dir_entries(dir.path()), vec!["big.txt"]);
+    }
+
+    #[test]
+    fn unified_diff_covers_adds_deletes_and_moves() {
+        let dir = tempdir().unwrap();
+        let old = dir.path().join("old.txt");
+        let gone = dir.path().join("gone.txt");
+        fs::write(&old, "a\nb\n").unwrap();
+        fs::write(&gone, "bye\n").unwrap();
+        let new = dir.path().join("new.txt");
+        let added = dir.path().join("added.txt");
+
+        let plan = plan(&format!(
+            "*** Update File: {}\n*** Move to: {}\n@@\n a\n-b\n+B\n*** Delete File: {}\n*** Add File: {}\n+hi",
+            old.display(),
+            new.display(),
+            gone.display(),
+            added.display()
+        ))
+        .unwrap();
+
+        assert_eq!(
+            plan.unified_diff().unwrap(),
+            format!(
+                "--- {old}\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-a\n-b\n\
+                 --- /dev/null\n+++ {new}\n@@ -0,0 +1,2 @@\n+a\n+B\n\
+                 --- {gone}\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n\
+                 --- /dev/null\n+++ {added}\n@@ -0,0 +1 @@\n+hi\n",
+                old = old.display(),
+                new = new.display(),
+                gone = gone.display(),
+                added = added.display()
+            )
+        );
+        assert_eq!(dir_entries(dir.path()), vec!["gone.txt", "old.txt"]);
+    }
+}
@@ -0,0 +1,737 @@
+//! Conversion of standard unified diffs (`diff --git` output, or bare
+//! `---`/`+++` headers followed by `@@` hunks) into the same [`Hunk`]s the
+//! `*** Begin Patch` format produces.
+//!
+//! The line numbers in `@@` headers are mostly used to find where each hunk
+//! ends; chunks are located in the file by their content, exactly like
+//! `*** Update File` chunks. The exception is a hunk without old lines (as
+//! `diff -U0` emits for pure insertions), which is inserted at the line its
+//! header names. Mode changes, and new files with any mode but `100644`, are
+//! rejected, since hunks cannot express them. A `\ No newline at end of file`
+//! marker anchors a chunk to the end of the file when it follows an old line,
+//! and drops the final newline of the added or updated file when it follows a
+//! new line.
+//!
+//! As with `git apply`, lines outside of a file diff (commit messages, email
+//! headers, signatures) are ignored, so `git format-patch` and `git show`
+//! output is accepted as well as plain diffs.
+
+use std::path::PathBuf;
+
+use crate::ApplyPatchArgs;
+use crate::parser::Hunk;
+use crate::parser::ParseError;
+use crate::parser::ParseError::*;
+use crate::parser::UpdateFileChunk;
+
+const GIT_DIFF_HEADER: &str = "diff --git ";
+const OLD_FILE_HEADER: &str = "--- ";
+const NEW_FILE_HEADER: &str = "+++ ";
+const HUNK_HEADER: &str = "@@ -";
+const RENAME_FROM: &str = "rename from ";
+const RENAME_TO: &str = "rename to ";
+const NEW_FILE_MODE: &str = "new file mode ";
+const DELETED_FILE_MODE: &str = "deleted file mode ";
+const OLD_MODE: &str = "old mode ";
+const NEW_MODE: &str = "new mode ";
+const REGULAR_FILE_MODE: &str = "100644";
+const DEV_NULL: &str = "/dev/null";
+/// First line of each message in `git format-patch` output.
+const FORMAT_PATCH_HEADER: &str = "From ";
+/// First line of each commit in `git show` and `git log -p` output.
+const COMMIT_HEADER: &str = "commit ";
+
+/// Extended `diff --git` header lines that carry nothing the hunk model can
+/// represent.
+const IGNORED_GIT_HEADERS: &[&str] = &["similarity index ", "dissimilarity index ", "index "];
+
+/// Returns true if `lines` (already trimmed as a whole) start like a unified
+/// diff, or like commit output containing one, rather than a
+/// `*** Begin Patch` envelope.
+pub(crate) fn is_unified_diff(lines: &[&str]) -> bool {
+    match lines {
+        [first, ..] if first.starts_with(GIT_DIFF_HEADER) => true,
+        [first, rest @ ..] if is_commit_header(first) => {
+            rest.iter().any(|line| line.starts_with(GIT_DIFF_HEADER))
+        }
+        [first, second, ..] => {
+            first.starts_with(OLD_FILE_HEADER) && second.starts_with(NEW_FILE_HEADER)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `line` starts a commit in `git format-patch` (`From <sha> ...`) or
+/// `git show` (`commit <sha>`) output.
+fn is_commit_header(line: &str) -> bool {
+    [FORMAT_PATCH_HEADER, COMMIT_HEADER].iter().any(|prefix| {
+        line.strip_prefix(prefix)
+            .and_then(|rest| rest.split_whitespace().next())
+            .is_some_and(|sha| sha.len() >= 7 && sha.chars().all(|c| c.is_ascii_hexdigit()))
+    })
+}
+
+pub(crate) fn parse_unified_diff(lines: &[&str]) -> Result<ApplyPatchArgs, ParseError> {
+    let mut hunks = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let starts_file_diff = lines[index].starts_with(GIT_DIFF_HEADER)
+            || (lines[index].starts_with(OLD_FILE_HEADER)
+                && lines
+                    .get(index + 1)
+                    .is_some_and(|line| line.starts_with(NEW_FILE_HEADER)));
+        if !starts_file_diff {
+            index += 1;
+            continue;
+        }
+        let (file, consumed) = parse_file_diff(&lines[index..], index + 1)?;
+        if let Some(hunk) = file.into_hunk(index + 1)? {
+            hunks.push(hunk);
+        }
+        index += consumed;
+    }
+    Ok(ApplyPatchArgs {
+        patch: lines.join("\n"),
+        hunks,
+        workdir: None,
+    })
+}
+
+/// Everything a single file's section of the diff says about it.
+#[derive(Default)]
+struct FileDiff {
+    /// Paths from the `diff --git a/... b/...` line, prefixes included.
+    git_paths: Option<(String, String)>,
+    /// Paths from the `---`/`+++` lines, prefixes included; `None` for
+    /// `/dev/null`.
+    header_paths: Option<(Option<String>, Option<String>)>,
+    rename_from: Option<String>,
+    rename_to: Option<String>,
+    is_new: bool,
+    is_deleted: bool,
+    chunks: Vec<UpdateFileChunk>,
+    /// The last new-side line was followed by `\ No newline at end of file`.
+    new_missing_newline: bool,
+}
+
+impl FileDiff {
+    fn into_hunk(self, line_number: usize) -> Result<Option<Hunk>, ParseError> {
+        let (header_old, header_new) = match self.header_paths {
+            Some((old, new)) => {
+                let strip = old.as_deref().is_none_or(|path| path.starts_with("a/"))
+                    && new.as_deref().is_none_or(|path| path.starts_with("b/"));
+                (
+                    old.map(|path| strip_prefix_if(path, strip)),
+                    new.map(|path| strip_prefix_if(path, strip)),
+                )
+            }
+            None => (None, None),
+        };
+        let (git_old, git_new) = match self.git_paths {
+            Some((old, new)) => (
+                Some(strip_prefix_if(old, true)),
+                Some(strip_prefix_if(new, true)),
+            ),
+            None => (None, None),
+        };
+        let old_path = self.rename_from.or(header_old).or(git_old);
+        let new_path = self.rename_to.or(header_new).or(git_new);
+        let missing_path = || InvalidHunkError {
+            message: "Could not determine the file path of this diff".to_string(),
+            line_number,
+        };
+
+        if self.is_new {
+            let path = new_path.ok_or_else(missing_path)?;
+            let mut contents = self
+                .chunks
+                .iter()
+                .flat_map(|chunk| chunk.new_lines.iter())
+                .map(|line| format!("{line}\n"))
+                .collect::<String>();
+            if self.new_missing_newline {
+                contents.pop();
+            }
+            return Ok(Some(Hunk::AddFile {
+                path: PathBuf::from(path),
+                contents,
+            }));
+        }
+
+        let path = old_path.ok_or_else(missing_path)?;
+        if self.is_deleted {
+            return Ok(Some(Hunk::DeleteFile {
+                path: PathBuf::from(path),
+            }));
+        }
+
+        let move_path = new_path.filter(|new_path| *new_path != path);
+        if self.chunks.is_empty() && move_path.is_none() {
+            // Headers without hunks, which leave the file unchanged.
+            return Ok(None);
+        }
+        Ok(Some(Hunk::UpdateFile {
+            path: PathBuf::from(path),
+            move_path: move_path.map(PathBuf::from),
+            base: None,
+            chunks: self.chunks,
+        }))
+    }
+}
+
+/// Parses one file's section of the diff from the start of `lines`, returning
+/// it and the number of lines consumed.
+fn parse_file_diff(lines: &[&str], line_number: usize) -> Result<(FileDiff, usize), ParseError> {
+    let mut file = FileDiff::default();
+    let mut index = 0;
+
+    if let Some(paths) = lines[0].strip_prefix(GIT_DIFF_HEADER) {
+        file.git_paths = split_git_header_paths(paths);
+        index += 1;
+        while let Some(line) = lines.get(index) {
+            if let Some(path) = line.strip_prefix(RENAME_FROM) {
+                file.rename_from = Some(unquote(path));
+            } else if let Some(path) = line.strip_prefix(RENAME_TO) {
+                file.rename_to = Some(unquote(path));
+            } else if let Some(mode) = line.strip_prefix(NEW_FILE_MODE) {
+                if mode != REGULAR_FILE_MODE {
+                    return Err(InvalidHunkError {
+                        message: format!(
+                            "New files with mode {mode} are not supported in unified diffs; add the file and change its mode separately"
+                        ),
+                        line_number: line_number + index,
+                    });
+                }
+                file.is_new = true;
+            } else if line.starts_with(OLD_MODE) || line.starts_with(NEW_MODE) {
+                return Err(InvalidHunkError {
+                    message: "Mode changes are not supported in unified diffs; change the mode separately"
+                        .to_string(),
+                    line_number: line_number + index,
+                });
+            } else if line.starts_with(DELETED_FILE_MODE) {
+                file.is_deleted = true;
+            } else if line.starts_with("copy from ") || line.starts_with("copy to ") {
+                return Err(InvalidHunkError {
+                    message: "Copies are not supported in unified diffs; add the new file instead"
+                        .to_string(),
+                    line_number: line_number + index,
+                });
+            } else if line.starts_with("Binary files ") || *line == "GIT binary patch" {
+                return Err(InvalidHunkError {
+                    message: "Binary diffs are not supported".to_string(),
+                    line_number: line_number + index,
+                });
+            } else if !IGNORED_GIT_HEADERS
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+            {
+                break;
+            }
+            index += 1;
+        }
+    }
+
+    if let Some(old) = lines
+        .get(index)
+        .and_then(|line| line.strip_prefix(OLD_FILE_HEADER))
+    {
+        let Some(new) = lines
+            .get(index + 1)
+            .and_then(|line| line.strip_prefix(NEW_FILE_HEADER))
+        else {
+            return Err(InvalidHunkError {
+                message: "Expected a '+++ ' line after the '--- ' line".to_string(),
+                line_number: line_number + index + 1,
+            });
+        };
+        let (old, new) = (header_path(old), header_path(new));
+        file.is_new |= old.is_none();
+        file.is_deleted |= new.is_none();
+        file.header_paths = Some((old, new));
+        index += 2;
+    }
+
+    while let Some(header) = lines
+        .get(index)
+        .and_then(|line| line.strip_prefix(HUNK_HEADER))
+    {
+        let Some((old_start, old_count, new_count)) = parse_hunk_counts(header) else {
+            return Err(InvalidHunkError {
+                message: format!("Invalid hunk header: '{}'", lines[index]),
+                line_number: line_number + index,
+            });
+        };
+        index += 1;
+        let consumed = parse_hunk_body(
+            &lines[index..],
+            line_number + index,
+            old_count,
+            new_count,
+            &mut file,
+        )?;
+        if old_count == 0
+            && let Some(chunk) = file.chunks.last_mut()
+        {
+            // `@@ -N,0` inserts after old line N, i.e. before 0-based index N.
+            chunk.insert_at_line = Some(old_start);
+        }
+        index += consumed;
+    }
+
+    Ok((file, index))
+}
+
+/// Which side(s) of the diff the previous hunk line belonged to.
+#[derive(Clone, Copy)]
+enum Side {
+    Old,
+    New,
+    Both,
+}
+
+/// Parses the lines of one `@@` hunk into a chunk of `file`, returning the
+/// number of lines consumed.
+fn parse_hunk_body(
+    lines: &[&str],
+    line_number: usize,
+    mut old_left: usize,
+    mut new_left: usize,
+    file: &mut FileDiff,
+) -> Result<usize, ParseError> {
+    let mut chunk = UpdateFileChunk {
+        change_context: None,
+        old_lines: Vec::new(),
+        new_lines: Vec::new(),
+        is_end_of_file: false,
+        insert_at_line: None,
+        missing_final_newline: false,
+    };
+    let mut last_side = None;
+    let mut index = 0;
+    loop {
+        let at_no_newline_marker = lines.get(index).is_some_and(|line| line.starts_with('\\'));
+        if old_left == 0 && new_left == 0 && !at_no_newline_marker {
+            break;
+        }
+        let Some(line) = lines.get(index) else {
+            return Err(InvalidHunkError {
+                message: format!(
+                    "Hunk ended early: expected {old_left} more old and {new_left} more new lines"
+                ),
+                line_number: line_number + index,
+            });
+        };
+        let too_many_lines = || InvalidHunkError {
+            message: format!("Hunk contains more lines than its header declares: '{line}'"),
+            line_number: line_number + index,
+        };
+        match line.chars().next() {
+            // Some tools strip the trailing space off empty context lines.
+            None | Some(' ') => {
+                if old_left == 0 || new_left == 0 {
+                    return Err(too_many_lines());
+                }
+                let text = line.get(1..).unwrap_or_default().to_string();
+                chunk.old_lines.push(text.clone());
+                chunk.new_lines.push(text);
+                old_left -= 1;
+                new_left -= 1;
+                last_side = Some(Side::Both);
+            }
+            Some('-') => {
+                if old_left == 0 {
+                    return Err(too_many_lines());
+                }
+                chunk.old_lines.push(line[1..].to_string());
+                old_left -= 1;
+                last_side = Some(Side::Old);
+            }
+            Some('+') => {
+                if new_left == 0 {
+                    return Err(too_many_lines());
+                }
+                chunk.new_lines.push(line[1..].to_string());
+                new_left -= 1;
+                last_side = Some(Side::New);
+            }
+            Some('\\') => {
+                if matches!(last_side, Some(Side::Old | Side::Both)) {
+                    chunk.is_end_of_file = true;
+                }
+                if matches!(last_side, Some(Side::New | Side::Both)) {
+                    file.new_missing_newline = true;
+                    chunk.missing_final_newline = true;
+                }
+            }
+            Some(_) => {
+                return Err(InvalidHunkError {
+                    message: format!(
+                        "Unexpected line found in unified diff hunk: '{line}'. Every line should start with ' ', '+', '-' or '\\'"
+                    ),
+                    line_number: line_number + index,
+                });
+            }
+        }
+        index += 1;
+    }
+    file.chunks.push(chunk);
+    Ok(index)
+}
+
+/// Parses the old start line and the old and new line counts out of
+/// `l[,s] +l[,s] @@ ...`, the part of a hunk header after `@@ -`.
+fn parse_hunk_counts(header: &str) -> Option<(usize, usize, usize)> {
+    let (ranges, _section) = header.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |range: &str| match range.split_once(',') {
+        Some((start, count)) => Some((start.parse::<usize>().ok()?, count.parse::<usize>().ok()?)),
+        None => range.parse::<usize>().ok().map(|start| (start, 1)),
+    };
+    let (old_start, old_count) = range(old)?;
+    let (_, new_count) = range(new)?;
+    Some((old_start, old_count, new_count))
+}
+
+/// The path on a `---`/`+++` line, without any trailing timestamp; `None` for
+/// `/dev/null`.
+fn header_path(text: &str) -> Option<String> {
+    let path = if text.starts_with('"') {
+        unquote(text)
+    } else {
+        text.split('\t')
+            .next()
+            .unwrap_or(text)
+            .trim_end()
+            .to_string()
+    };
+    (path != DEV_NULL).then_some(path)
+}
+
+/// Splits the `a/<old> b/<new>` part of a `diff --git` line. Unquoted paths
+/// containing spaces are only split correctly when old and new are the same,
+/// which is the only case where git relies on this line for the path.
+fn split_git_header_paths(paths: &str) -> Option<(String, String)> {
+    if paths.starts_with('"') {
+        let end = closing_quote(paths)?;
+        let (old, new) = paths.split_at(end + 1);
+        return Some((unquote(old), unquote(new.trim_start())));
+    }
+    let separators: Vec<usize> = paths.match_indices(" b/").map(|(idx, _)| idx).collect();
+    let split = separators
+        .iter()
+        .copied()
+        .find(|&idx| paths.get(2..idx) == paths.get(idx + 3..))
+        .or_else(|| separators.first().copied())?;
+    Some((paths[..split].to_string(), unquote(&paths[split + 1..])))
+}
+
+/// Index of the quote closing the C-style quoted string at the start of `text`.
+fn closing_quote(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(idx),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Undoes git's C-style quoting of paths with unusual characters. Octal
+/// escapes are decoded as UTF-8 bytes.
+fn unquote(text: &str) -> String {
+    let Some(inner) = text
+        .strip_prefix('"')
+        .and_then(|rest| closing_quote(text).map(|end| &rest[..end - 1]))
+    else {
+        return text.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some(digit @ '0'..='7') => {
+                let mut value = digit.to_digit(8).unwrap_or_default();
+                for _ in 0..2 {
+                    if let Some(next) = chars.next_if(|ch| ch.is_digit(8)) {
+                        value = value * 8 + next.to_digit(8).unwrap_or_default();
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(other.encode_utf8(&mut buf).as_bytes());
+            }
+            None => bytes.push(b'\\'),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn strip_prefix_if(path: String, strip: bool) -> String {
+    match path.split_once('/') {
+        Some((_, rest)) if strip => rest.to_string(),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn parse(diff: &str) -> Result<Vec<Hunk>, ParseError> {
+        let lines: Vec<&str> = diff.trim().lines().collect();
+        assert!(is_unified_diff(&lines));
+        parse_unified_diff(&lines).map(|args| args.hunks)
+    }
+
+    fn chunk(old: &[&str], new: &[&str], is_end_of_file: bool) -> UpdateFileChunk {
+        UpdateFileChunk {
+            change_context: None,
+            old_lines: old.iter().map(ToString::to_string).collect(),
+            new_lines: new.iter().map(ToString::to_string).collect(),
+            is_end_of_file,
+            insert_at_line: None,
+            missing_final_newline: false,
+        }
+    }
+
+    #[test]
+    fn converts_git_diff_with_adds_deletes_and_renames() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 83db48f..bf269f4 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@ fn main() {
+ one
+-two
++TWO
+ three
+@@ -10,2 +10,3 @@
+ ten
+ eleven
++twelve
+diff --git a/old name.txt b/new name.txt
+similarity index 90%
+rename from old name.txt
+rename to new name.txt
+index 1111111..2222222
+--- a/old name.txt
++++ b/new name.txt
+@@ -1 +1 @@
+-before
++after
+\\ No newline at end of file
+diff --git a/added.txt b/added.txt
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1,2 @@
++hello
++world
+\\ No newline at end of file
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index 4444444..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+
+        assert_eq!(
+            parse(diff).unwrap(),
+            vec![
+                Hunk::UpdateFile {
+                    path: PathBuf::from("src/lib.rs"),
+                    move_path: None,
+                    base: None,
+                    chunks: vec![
+                        chunk(&["one", "two", "three"], &["one", "TWO", "three"], false),
+                        chunk(&["ten", "eleven"], &["ten", "eleven", "twelve"], false),
+                    ],
+                },
+                Hunk::UpdateFile {
+                    path: PathBuf::from("old name.txt"),
+                    move_path: Some(PathBuf::from("new name.txt")),
+                    base: None,
+                    chunks: vec![UpdateFileChunk {
+                        missing_final_newline: true,
+                        ..chunk(&["before"], &["after"], false)
+                    }],
+                },
+                Hunk::AddFile {
+                    path: PathBuf::from("added.txt"),
+                    contents: "hello\nworld".to_string(),
+                },
+                Hunk::DeleteFile {
+                    path: PathBuf::from("gone.txt"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_plain_unified_diff_and_pure_renames() {
+        let diff = "\
+--- notes.txt\t2024-01-01 00:00:00.000000000 +0000
++++ notes.txt\t2024-01-02 00:00:00.000000000 +0000
+@@ -2,2 +2,2 @@
+
+-last
+\\ No newline at end of file
++LAST
+diff --git \"a/tab\\there.txt\" \"b/moved.txt\"
+similarity index 100%
+rename from \"tab\\there.txt\"
+rename to moved.txt
+";
+
+        assert_eq!(
+            parse(diff).unwrap(),
+            vec![
+                Hunk::UpdateFile {
+                    path: PathBuf::from("notes.txt"),
+                    move_path: None,
+                    base: None,
+                    chunks: vec![chunk(&["", "last"], &["", "LAST"], true)],
+                },
+                Hunk::UpdateFile {
+                    path: PathBuf::from("tab\there.txt"),
+                    move_path: Some(PathBuf::from("moved.txt")),
+                    base: None,
+                    chunks: Vec::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn anchors_insertions_without_old_lines_at_their_header_line() {
+        let diff = "--- a/x\n+++ b/x\n@@ -3,0 +4,2 @@\n+four\n+five\n@@ -0,0 +1 @@\n+zero";
+
+        assert_eq!(
+            parse(diff).unwrap(),
+            vec![Hunk::UpdateFile {
+                path: PathBuf::from("x"),
+                move_path: None,
+                base: None,
+                chunks: vec![
+                    UpdateFileChunk {
+                        insert_at_line: Some(3),
+                        ..chunk(&[], &["four", "five"], false)
+                    },
+                    UpdateFileChunk {
+                        insert_at_line: Some(0),
+                        ..chunk(&[], &["zero"], false)
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_hunks_that_disagree_with_their_header() {
+        assert_eq!(
+            parse("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-one\n+ONE"),
+            Err(InvalidHunkError {
+                message: "Hunk ended early: expected 1 more old and 1 more new lines".to_string(),
+                line_number: 6,
+            })
+        );
+        assert_eq!(
+            parse("diff --git a/x b/x\nBinary files a/x and b/x differ"),
+            Err(InvalidHunkError {
+                message: "Binary diffs are not supported".to_string(),
+                line_number: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_mode_changes() {
+        assert_eq!(
+            parse("diff --git a/x.sh b/x.sh\nold mode 100644\nnew mode 100755"),
+            Err(InvalidHunkError {
+                message:
+                    "Mode changes are not supported in unified diffs; change the mode separately"
+                        .to_string(),
+                line_number: 2,
+            })
+        );
+        assert_eq!(
+            parse(
+                "diff --git a/x.sh b/x.sh\nnew file mode 100755\n--- /dev/null\n+++ b/x.sh\n@@ -0,0 +1 @@\n+echo"
+            ),
+            Err(InvalidHunkError {
+                message: "New files with mode 100755 are not supported in unified diffs; add the file and change its mode separately"
+                    .to_string(),
+                line_number: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn converts_format_patch_and_git_show_output() {
+        let format_patch = "\
+From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001
+From: A U Thor <author@example.com>
+Date: Mon, 1 Jan 2024 00:00:00 +0000
+Subject: [PATCH] Shout
+
+---
+ x | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-hello
++HELLO
+-- 
+2.43.0
+";
+        let git_show = "\
+commit 0123456789abcdef0123456789abcdef01234567
+Author: A U Thor <author@example.com>
+Date:   Mon Jan 1 00:00:00 2024 +0000
+
+    Shout
+
+diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-hello
++HELLO
+";
+        let expected = vec![Hunk::UpdateFile {
+            path: PathBuf::from("x"),
+            move_path: None,
+            base: None,
+            chunks: vec![chunk(&["hello"], &["HELLO"], false)],
+        }];
+        assert_eq!(parse(format_patch).unwrap(), expected);
+        assert_eq!(parse(git_show).unwrap(), expected);
+
+        let lines: Vec<&str> = "From someone\nHello".lines().collect();
+        assert!(!is_unified_diff(&lines));
+    }
+}
@@ -1149,6 +1149,7 @@ mod tests {
                     decision: Decision::Forbidden,
                     justification: None,
                 }],
+                decided_by: None,
             }
         );
 
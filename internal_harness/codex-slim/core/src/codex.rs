@@ -49,8 +49,16 @@ use crate::util::error_or_panic;
 use crate::ws_version_from_features;
 use async_channel::Receiver;
 use async_channel::Sender;
+use codex_hooks::HookApprovalRequest;
+use codex_hooks::HookCommand;
+use codex_hooks::HookCompactionTrigger;
 use codex_hooks::HookEvent;
 use codex_hooks::HookEventAfterAgent;
+use codex_hooks::HookEventApprovalRequested;
+use codex_hooks::HookEventBeforeCompaction;
+use codex_hooks::HookEventError;
+use codex_hooks::HookEventSessionStart;
+use codex_hooks::HookEventTurnStarted;
 use codex_hooks::HookPayload;
 use codex_hooks::HookResult;
 use codex_hooks::Hooks;
@@ -91,6 +99,7 @@ use codex_protocol::request_user_input::RequestUserInputResponse;
 use codex_protocol::skill_approval::SkillApprovalResponse;
 use codex_rmcp_client::ElicitationResponse;
 use codex_rmcp_client::OAuthCredentialsStoreMode;
+use codex_secrets::SecretRedactor;
 use futures::future::BoxFuture;
 use futures::prelude::*;
 use futures::stream::FuturesOrdered;
@@ -132,6 +141,7 @@ use crate::config::ConstraintResult;
 use crate::config::GhostSnapshotConfig;
 use crate::config::StartedNetworkProxy;
 use crate::config::resolve_web_search_mode_for_turn;
+use crate::config::types::HookCommandToml;
 use crate::config::types::McpServerConfig;
 use crate::config::types::ShellEnvironmentPolicy;
 use crate::context_manager::ContextManager;
@@ -248,6 +258,7 @@ use crate::tools::network_approval::NetworkApprovalService;
 use crate::tools::network_approval::build_blocked_request_observer;
 use crate::tools::network_approval::build_network_policy_decider;
 use crate::tools::parallel::ToolCallRuntime;
+use crate::tools::registry::sandbox_policy_tag;
 use crate::tools::sandboxing::ApprovalStore;
 use crate::tools::spec::ToolsConfig;
 use crate::tools::spec::ToolsConfigParams;
@@ -863,6 +874,7 @@ impl Session {
 
     async fn start_managed_network_proxy(
         spec: &crate::config::NetworkProxySpec,
+        codex_home: &Path,
         sandbox_policy: &SandboxPolicy,
         network_policy_decider: Option<Arc<dyn codex_network_proxy::NetworkPolicyDecider>>,
         blocked_request_observer: Option<Arc<dyn codex_network_proxy::BlockedRequestObserver>>,
@@ -870,6 +882,7 @@ impl Session {
     ) -> anyhow::Result<(StartedNetworkProxy, SessionNetworkProxyRuntime)> {
         let network_proxy = spec
             .start_proxy(
+                codex_home,
                 sandbox_policy,
                 network_policy_decider,
                 blocked_request_observer,
@@ -1300,6 +1313,7 @@ impl Session {
             if let Some(spec) = config.permissions.network.as_ref() {
                 let (network_proxy, session_network_proxy) = Self::start_managed_network_proxy(
                     spec,
+                    &config.codex_home,
                     config.permissions.sandbox_policy.get(),
                     network_policy_decider.as_ref().map(Arc::clone),
                     blocked_request_observer.as_ref().map(Arc::clone),
@@ -1337,9 +1351,12 @@ impl Session {
                 Arc::clone(&config),
                 Arc::clone(&auth_manager),
             ),
-            hooks: Hooks::new(HooksConfig {
-                legacy_notify_argv: config.notify.clone(),
-            }),
+            hooks: Hooks::new(hooks_config(&config)),
+            secret_redactor: Arc::new(
+                SecretRedactor::from_config(&config.redaction)
+                    .unwrap_or_default()
+                    .for_model_output(),
+            ),
             rollout: Mutex::new(rollout_recorder),
             user_shell: Arc::new(default_shell),
             shell_snapshot_tx,
@@ -1426,6 +1443,21 @@ impl Session {
         for event in events {
             sess.send_event_raw(event).await;
         }
+        sess.dispatch_lifecycle_hook(
+            session_configuration.cwd.clone(),
+            HookEvent::SessionStart {
+                event: HookEventSessionStart {
+                    thread_id: conversation_id,
+                    forked_from_id,
+                    model: session_configuration.collaboration_mode.model().to_string(),
+                    model_provider_id: config.model_provider_id.clone(),
+                    approval_policy: session_configuration.approval_policy.value().to_string(),
+                    sandbox_policy: sandbox_policy_tag(session_configuration.sandbox_policy.get())
+                        .to_string(),
+                },
+            },
+        )
+        .await;
 
         // Start the watcher after SessionConfigured so it cannot emit earlier events.
         sess.start_file_watcher_listener();
@@ -2247,6 +2279,9 @@ impl Session {
 
     /// Persist the event to rollout and send it to clients.
     pub(crate) async fn send_event(&self, turn_context: &TurnContext, msg: EventMsg) {
+        if let Some(hook_event) = lifecycle_hook_event(turn_context, &msg) {
+            self.spawn_lifecycle_hook(turn_context.cwd.clone(), hook_event);
+        }
         let legacy_source = msg.clone();
         let event = Event {
             id: turn_context.sub_id.clone(),
@@ -3437,6 +3472,42 @@ impl Session {
         &self.services.hooks
     }
 
+    /// Runs the hooks registered for a lifecycle event. These hooks only observe the
+    /// session, so their failures are logged and never abort anything.
+    pub(crate) async fn dispatch_lifecycle_hook(&self, cwd: PathBuf, hook_event: HookEvent) {
+        run_lifecycle_hooks(self.hooks(), self.conversation_id, cwd, hook_event).await;
+    }
+
+    /// Like [`Session::dispatch_lifecycle_hook`], but runs the hooks on a background task so
+    /// a slow hook command never holds up event delivery.
+    fn spawn_lifecycle_hook(&self, cwd: PathBuf, hook_event: HookEvent) {
+        let hooks = self.hooks().clone();
+        let session_id = self.conversation_id;
+        tokio::spawn(async move {
+            run_lifecycle_hooks(&hooks, session_id, cwd, hook_event).await;
+        });
+    }
+
+    pub(crate) async fn dispatch_before_compaction_hook(
+        &self,
+        turn_context: &TurnContext,
+        trigger: HookCompactionTrigger,
+    ) {
+        let total_tokens = self.get_total_token_usage().await;
+        self.dispatch_lifecycle_hook(
+            turn_context.cwd.clone(),
+            HookEvent::BeforeCompaction {
+                event: HookEventBeforeCompaction {
+                    turn_id: turn_context.sub_id.clone(),
+                    trigger,
+                    total_tokens,
+                    model_context_window: turn_context.model_context_window(),
+                },
+            },
+        )
+        .await;
+    }
+
     pub(crate) fn user_shell(&self) -> Arc<shell::Shell> {
         Arc::clone(&self.services.user_shell)
     }
@@ -3749,6 +3820,8 @@ mod handlers {
     use crate::tasks::UserShellCommandMode;
     use crate::tasks::UserShellCommandTask;
     use crate::tasks::execute_user_shell_command;
+    use codex_hooks::HookEvent;
+    use codex_hooks::HookEventSessionEnd;
     use codex_protocol::custom_prompts::CustomPrompt;
     use codex_protocol::protocol::CodexErrorInfo;
     use codex_protocol::protocol::ErrorEvent;
@@ -4452,6 +4525,19 @@ mod handlers {
             i64::try_from(turn_count).unwrap_or(0),
             &[],
         );
+        let cwd = sess.state.lock().await.session_configuration.cwd.clone();
+        let total_tokens = sess.get_total_token_usage().await;
+        sess.dispatch_lifecycle_hook(
+            cwd,
+            HookEvent::SessionEnd {
+                event: HookEventSessionEnd {
+                    thread_id: sess.conversation_id,
+                    turn_count: u64::try_from(turn_count).unwrap_or(u64::MAX),
+                    total_tokens,
+                },
+            },
+        )
+        .await;
 
         // Gracefully flush and shutdown rollout recorder on session end so tests
         // that inspect the rollout file do not race with the background writer.
@@ -4634,12 +4720,21 @@ async fn spawn_review_thread(
     // TODO(ccunningham): Review turns currently rely on `spawn_task` for TurnComplete but do not
     // emit a parent TurnStarted. Consider giving review a full parent turn lifecycle
     // (TurnStarted + TurnComplete) for consistency with other standalone tasks.
-    sess.spawn_task(tc.clone(), input, ReviewTask::new()).await;
+    sess.spawn_task(
+        tc.clone(),
+        input,
+        ReviewTask::with_incremental(
+            crate::review_incremental::reviewed_revision(&resolved.target).map(str::to_string),
+            resolved.incremental,
+        ),
+    )
+    .await;
 
     // Announce entering review mode so UIs can switch modes.
     let review_request = ReviewRequest {
         target: resolved.target,
         user_facing_hint: Some(resolved.user_facing_hint),
+        previous_review: None,
     };
     sess.send_event(&tc, EventMsg::EnteredReviewMode(review_request))
         .await;
@@ -5011,7 +5106,7 @@ pub(crate) async fn run_turn(
                     for hook_outcome in hook_outcomes {
                         let hook_name = hook_outcome.hook_name;
                         match hook_outcome.result {
-                            HookResult::Success => {}
+                            HookResult::Success | HookResult::Intervene(_) => {}
                             HookResult::FailedContinue(error) => {
                                 warn!(
                                     turn_id = %turn_context.sub_id,
@@ -5157,6 +5252,8 @@ async fn run_auto_compact(
     turn_context: &Arc<TurnContext>,
     initial_context_injection: InitialContextInjection,
 ) -> CodexResult<()> {
+    sess.dispatch_before_compaction_hook(turn_context, HookCompactionTrigger::Auto)
+        .await;
     if should_use_remote_compact_task(&turn_context.provider) {
         run_inline_remote_auto_compact_task(
             Arc::clone(sess),
@@ -5697,6 +5794,103 @@ fn agent_message_text(item: &codex_protocol::items::AgentMessageItem) -> String
         .collect()
 }
 
+async fn run_lifecycle_hooks(
+    hooks: &Hooks,
+    session_id: ThreadId,
+    cwd: PathBuf,
+    hook_event: HookEvent,
+) {
+    let hook_outcomes = hooks
+        .dispatch(HookPayload {
+            session_id,
+            cwd,
+            triggered_at: chrono::Utc::now(),
+            hook_event,
+        })
+        .await;
+    for hook_outcome in hook_outcomes {
+        match hook_outcome.result {
+            HookResult::Success | HookResult::Intervene(_) => {}
+            HookResult::FailedContinue(error) | HookResult::FailedAbort(error) => {
+                warn!(
+                    hook_name = %hook_outcome.hook_name,
+                    error = %error,
+                    "lifecycle hook failed; continuing"
+                );
+            }
+        }
+    }
+}
+
+fn hooks_config(config: &Config) -> HooksConfig {
+    let command = |command: &Option<HookCommandToml>| {
+        command.as_ref().map(|command| HookCommand {
+            argv: command.argv().to_vec(),
+            timeout: command.timeout(),
+            fail_open: command.fail_open(),
+        })
+    };
+    let hooks = &config.hooks;
+    HooksConfig {
+        legacy_notify_argv: config.notify.clone(),
+        session_start: command(&hooks.session_start),
+        session_end: command(&hooks.session_end),
+        turn_started: command(&hooks.turn_started),
+        before_compaction: command(&hooks.before_compaction),
+        before_tool_use: command(&hooks.before_tool_use),
+        approval_requested: command(&hooks.approval_requested),
+        error: command(&hooks.error),
+    }
+}
+
+/// The hook event to fire for `msg`, if it marks a lifecycle point hooks can observe.
+/// Session start/end and compaction have no matching event and are dispatched
+/// where they happen.
+fn lifecycle_hook_event(turn_context: &TurnContext, msg: &EventMsg) -> Option<HookEvent> {
+    match msg {
+        EventMsg::TurnStarted(event) => Some(HookEvent::TurnStarted {
+            event: HookEventTurnStarted {
+                turn_id: event.turn_id.clone(),
+                model: turn_context.model_info.slug.clone(),
+                model_context_window: event.model_context_window,
+            },
+        }),
+        EventMsg::ExecApprovalRequest(event) => Some(HookEvent::ApprovalRequested {
+            event: HookEventApprovalRequested {
+                turn_id: event.turn_id.clone(),
+                call_id: event.call_id.clone(),
+                request: HookApprovalRequest::Exec {
+                    command: event.command.clone(),
+                    cwd: event.cwd.clone(),
+                },
+                reason: event.reason.clone(),
+            },
+        }),
+        EventMsg::ApplyPatchApprovalRequest(event) => {
+            let mut changed_paths: Vec<PathBuf> = event.changes.keys().cloned().collect();
+            changed_paths.sort();
+            Some(HookEvent::ApprovalRequested {
+                event: HookEventApprovalRequested {
+                    turn_id: event.turn_id.clone(),
+                    call_id: event.call_id.clone(),
+                    request: HookApprovalRequest::ApplyPatch {
+                        changed_paths,
+                        grant_root: event.grant_root.clone(),
+                    },
+                    reason: event.reason.clone(),
+                },
+            })
+        }
+        EventMsg::Error(event) => Some(HookEvent::Error {
+            event: HookEventError {
+                turn_id: turn_context.sub_id.clone(),
+                message: event.message.clone(),
+            },
+        }),
+        _ => None,
+    }
+}
+
 fn realtime_text_for_event(msg: &EventMsg) -> Option<String> {
     match msg {
         EventMsg::AgentMessage(event) => Some(event.message.clone()),
@@ -6608,6 +6802,54 @@ mod tests {
         assert_eq!(selected, Vec::new());
     }
 
+    #[tokio::test]
+    async fn lifecycle_hook_event_maps_approvals_and_errors() {
+        let (_session, turn_context) = make_session_and_context().await;
+
+        let patch_approval = EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
+            call_id: "call-1".to_string(),
+            turn_id: turn_context.sub_id.clone(),
+            changes: HashMap::from([
+                (
+                    PathBuf::from("b.rs"),
+                    FileChange::Delete {
+                        content: String::new(),
+                    },
+                ),
+                (
+                    PathBuf::from("a.rs"),
+                    FileChange::Delete {
+                        content: String::new(),
+                    },
+                ),
+            ]),
+            reason: None,
+            grant_root: None,
+        });
+        let Some(HookEvent::ApprovalRequested { event }) =
+            lifecycle_hook_event(&turn_context, &patch_approval)
+        else {
+            panic!("expected approval_requested hook event");
+        };
+        assert_eq!(
+            event.request,
+            HookApprovalRequest::ApplyPatch {
+                changed_paths: vec![PathBuf::from("a.rs"), PathBuf::from("b.rs")],
+                grant_root: None,
+            }
+        );
+
+        let error = EventMsg::Error(ErrorEvent {
+            message: "stream disconnected".to_string(),
+            codex_error_info: None,
+        });
+        assert!(matches!(
+            lifecycle_hook_event(&turn_context, &error),
+            Some(HookEvent::Error { event }) if event.message == "stream disconnected"
+        ));
+        assert!(lifecycle_hook_event(&turn_context, &EventMsg::ShutdownComplete).is_none());
+    }
+
     #[test]
     fn filter_connectors_for_input_skips_when_skill_name_conflicts() {
         let connectors = vec![make_connector("one", "Todoist")];
@@ -8152,9 +8394,12 @@ mod tests {
                 Arc::clone(&config),
                 Arc::clone(&auth_manager),
             ),
-            hooks: Hooks::new(HooksConfig {
-                legacy_notify_argv: config.notify.clone(),
-            }),
+            hooks: Hooks::new(hooks_config(&config)),
+            secret_redactor: Arc::new(
+                SecretRedactor::from_config(&config.redaction)
+                    .unwrap_or_default()
+                    .for_model_output(),
+            ),
             rollout: Mutex::new(None),
             user_shell: Arc::new(default_user_shell()),
             shell_snapshot_tx: watch::channel(None).0,
@@ -8307,9 +8552,12 @@ mod tests {
                 Arc::clone(&config),
                 Arc::clone(&auth_manager),
             ),
-            hooks: Hooks::new(HooksConfig {
-                legacy_notify_argv: config.notify.clone(),
-            }),
+            hooks: Hooks::new(hooks_config(&config)),
+            secret_redactor: Arc::new(
+                SecretRedactor::from_config(&config.redaction)
+                    .unwrap_or_default()
+                    .for_model_output(),
+            ),
             rollout: Mutex::new(None),
             user_shell: Arc::new(default_user_shell()),
             shell_snapshot_tx: watch::channel(None).0,
@@ -4,6 +4,7 @@ use crate::config::edit::ConfigEditsBuilder;
 use crate::config::types::AppsConfigToml;
 use crate::config::types::DEFAULT_OTEL_ENVIRONMENT;
 use crate::config::types::History;
+use crate::config::types::HookCommands;
 use crate::config::types::McpServerConfig;
 use crate::config::types::McpServerDisabledReason;
 use crate::config::types::McpServerTransportConfig;
@@ -69,6 +70,8 @@ use codex_protocol::config_types::WindowsSandboxLevel;
 use codex_protocol::openai_models::ModelsResponse;
 use codex_protocol::openai_models::ReasoningEffort;
 use codex_rmcp_client::OAuthCredentialsStoreMode;
+use codex_secrets::RedactionConfig;
+use codex_secrets::SecretRedactor;
 use codex_utils_absolute_path::AbsolutePathBuf;
 use codex_utils_absolute_path::AbsolutePathBufGuard;
 use schemars::JsonSchema;
@@ -249,6 +252,9 @@ pub struct Config {
     /// If unset the feature is disabled.
     pub notify: Option<Vec<String>>,
 
+    /// External commands run as hooks around agent events.
+    pub hooks: HookCommands,
+
     /// TUI notifications preference. When set, the TUI will send terminal notifications on
     /// approvals and turn completions when not focused.
     pub tui_notifications: Notifications,
@@ -340,6 +346,9 @@ pub struct Config {
     /// Memories subsystem settings.
     pub memories: MemoriesConfig,
 
+    /// Secret redaction rules applied to rollout files and tool outputs.
+    pub redaction: RedactionConfig,
+
     /// Directory containing all Codex state (defaults to `~/.codex` but can be
     /// overridden by the `CODEX_HOME` environment variable).
     pub codex_home: PathBuf,
@@ -1011,6 +1020,10 @@ pub struct ConfigToml {
     #[serde(default)]
     pub notify: Option<Vec<String>>,
 
+    /// External commands run as hooks around agent events.
+    #[serde(default)]
+    pub hooks: Option<HookCommands>,
+
     /// System instructions.
     pub instructions: Option<String>,
 
@@ -1168,6 +1181,10 @@ pub struct ConfigToml {
     /// Memories subsystem settings.
     pub memories: Option<MemoriesToml>,
 
+    /// Secret redaction applied to rollout files and tool outputs.
+    #[serde(default)]
+    pub redaction: Option<RedactionConfig>,
+
     /// User-level skill config entries keyed by SKILL.md path.
     pub skills: Option<SkillsConfig>,
 
@@ -1768,6 +1785,14 @@ impl Config {
 
         let history = cfg.history.unwrap_or_default();
 
+        let redaction = cfg.redaction.unwrap_or_default();
+        SecretRedactor::from_config(&redaction).map_err(|err| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid [redaction] config: {err:#}"),
+            )
+        })?;
+
         let agent_max_threads = cfg
             .agents
             .as_ref()
@@ -2018,6 +2043,7 @@ impl Config {
             enforce_residency: enforce_residency.value,
             did_user_set_custom_approval_policy_or_sandbox_mode,
             notify: cfg.notify,
+            hooks: cfg.hooks.unwrap_or_default(),
             user_instructions,
             base_instructions,
             personality,
@@ -2053,6 +2079,7 @@ impl Config {
             agent_max_depth,
             agent_roles,
             memories: cfg.memories.unwrap_or_default().into(),
+            redaction,
             codex_home,
             log_dir,
             config_layer_stack,
@@ -2442,6 +2469,49 @@ phase_2_model = "gpt-5"
         );
     }
 
+    #[test]
+    fn config_toml_deserializes_hook_commands() {
+        let toml = r#"
+[hooks]
+session_start = ["audit-log", "start"]
+before_tool_use = { command = ["tool-policy"], timeout_ms = 2500, fail_open = true }
+"#;
+        let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
+        let hooks = cfg.hooks.expect("hooks table");
+
+        let session_start = hooks.session_start.expect("session_start hook");
+        assert_eq!(session_start.argv(), ["audit-log", "start"]);
+        assert_eq!(session_start.timeout(), None);
+        assert!(!session_start.fail_open());
+        let before_tool_use = hooks.before_tool_use.expect("before_tool_use hook");
+        assert_eq!(before_tool_use.argv(), ["tool-policy"]);
+        assert_eq!(
+            before_tool_use.timeout(),
+            Some(std::time::Duration::from_millis(2500))
+        );
+        assert!(before_tool_use.fail_open());
+        assert_eq!(hooks.error, None);
+    }
+
+    #[test]
+    fn config_rejects_invalid_redaction_rule() {
+        let toml = r#"
+[redaction.rules.internal_token]
+pattern = "itk_[a-z"
+"#;
+        let cfg: ConfigToml = toml::from_str(toml).expect("TOML deserialization should succeed");
+
+        let err = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            tempdir().expect("tempdir").path().to_path_buf(),
+        )
+        .expect_err("invalid redaction pattern");
+
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("internal_token"), "{err}");
+    }
+
     #[test]
     fn config_toml_deserializes_permissions_network() {
         let toml = r#"
@@ -2471,10 +2541,13 @@ allowed_domains = ["openai.com"]
                 dangerously_allow_non_loopback_admin: None,
                 dangerously_allow_all_unix_sockets: None,
                 mode: None,
+                mitm: None,
                 allowed_domains: Some(vec!["openai.com".to_string()]),
                 denied_domains: None,
+                rules: None,
                 allow_unix_sockets: None,
                 allow_local_binding: None,
+                cassette: None,
             }
         );
     }
@@ -4647,6 +4720,7 @@ model_verbosity = "high"
                 did_user_set_custom_approval_policy_or_sandbox_mode: true,
                 user_instructions: None,
                 notify: None,
+                hooks: HookCommands::default(),
                 cwd: fixture.cwd(),
                 cli_auth_credentials_store_mode: Default::default(),
                 mcp_servers: Constrained::allow_any(HashMap::new()),
@@ -4661,6 +4735,7 @@ model_verbosity = "high"
                 agent_max_depth: DEFAULT_AGENT_MAX_DEPTH,
                 agent_roles: BTreeMap::new(),
                 memories: MemoriesConfig::default(),
+                redaction: RedactionConfig::default(),
                 codex_home: fixture.codex_home(),
                 log_dir: fixture.codex_home().join("log"),
                 config_layer_stack: Default::default(),
@@ -4770,6 +4845,7 @@ model_verbosity = "high"
             did_user_set_custom_approval_policy_or_sandbox_mode: true,
             user_instructions: None,
             notify: None,
+            hooks: HookCommands::default(),
             cwd: fixture.cwd(),
             cli_auth_credentials_store_mode: Default::default(),
             mcp_servers: Constrained::allow_any(HashMap::new()),
@@ -4784,6 +4860,7 @@ model_verbosity = "high"
             agent_max_depth: DEFAULT_AGENT_MAX_DEPTH,
             agent_roles: BTreeMap::new(),
             memories: MemoriesConfig::default(),
+            redaction: RedactionConfig::default(),
             codex_home: fixture.codex_home(),
             log_dir: fixture.codex_home().join("log"),
             config_layer_stack: Default::default(),
@@ -4891,6 +4968,7 @@ model_verbosity = "high"
             did_user_set_custom_approval_policy_or_sandbox_mode: true,
             user_instructions: None,
             notify: None,
+            hooks: HookCommands::default(),
             cwd: fixture.cwd(),
             cli_auth_credentials_store_mode: Default::default(),
             mcp_servers: Constrained::allow_any(HashMap::new()),
@@ -4905,6 +4983,7 @@ model_verbosity = "high"
             agent_max_depth: DEFAULT_AGENT_MAX_DEPTH,
             agent_roles: BTreeMap::new(),
             memories: MemoriesConfig::default(),
+            redaction: RedactionConfig::default(),
             codex_home: fixture.codex_home(),
             log_dir: fixture.codex_home().join("log"),
             config_layer_stack: Default::default(),
@@ -4998,6 +5077,7 @@ model_verbosity = "high"
             did_user_set_custom_approval_policy_or_sandbox_mode: true,
             user_instructions: None,
             notify: None,
+            hooks: HookCommands::default(),
             cwd: fixture.cwd(),
             cli_auth_credentials_store_mode: Default::default(),
             mcp_servers: Constrained::allow_any(HashMap::new()),
@@ -5012,6 +5092,7 @@ model_verbosity = "high"
             agent_max_depth: DEFAULT_AGENT_MAX_DEPTH,
             agent_roles: BTreeMap::new(),
             memories: MemoriesConfig::default(),
+            redaction: RedactionConfig::default(),
             codex_home: fixture.codex_home(),
             log_dir: fixture.codex_home().join("log"),
             config_layer_stack: Default::default(),
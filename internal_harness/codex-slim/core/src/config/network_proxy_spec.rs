@@ -14,6 +14,7 @@ use codex_network_proxy::build_config_state;
 use codex_network_proxy::host_and_port_from_network_addr;
 use codex_network_proxy::validate_policy_against_constraints;
 use codex_protocol::protocol::SandboxPolicy;
+use std::path::Path;
 use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -102,6 +103,7 @@ impl NetworkProxySpec {
 
     pub async fn start_proxy(
         &self,
+        codex_home: &Path,
         sandbox_policy: &SandboxPolicy,
         policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
         blocked_request_observer: Option<Arc<dyn BlockedRequestObserver>>,
@@ -113,7 +115,9 @@ impl NetworkProxySpec {
             })?;
         let reloader = Arc::new(StaticNetworkProxyReloader::new(state.clone()));
         let state = NetworkProxyState::with_reloader(state, reloader);
-        let mut builder = NetworkProxy::builder().state(Arc::new(state));
+        let mut builder = NetworkProxy::builder()
+            .state(Arc::new(state))
+            .codex_home(codex_home);
         if enable_network_approval_flow
             && matches!(
                 sandbox_policy,
@@ -1,5 +1,7 @@
+use codex_network_proxy::NetworkCassette;
 use codex_network_proxy::NetworkMode;
 use codex_network_proxy::NetworkProxyConfig;
+use codex_network_proxy::NetworkRule;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
@@ -27,10 +29,15 @@ pub struct NetworkToml {
     pub dangerously_allow_all_unix_sockets: Option<bool>,
     #[schemars(with = "Option<NetworkModeSchema>")]
     pub mode: Option<NetworkMode>,
+    pub mitm: Option<bool>,
     pub allowed_domains: Option<Vec<String>>,
     pub denied_domains: Option<Vec<String>>,
+    #[schemars(with = "Option<Vec<NetworkRuleSchema>>")]
+    pub rules: Option<Vec<NetworkRule>>,
     pub allow_unix_sockets: Option<Vec<String>>,
     pub allow_local_binding: Option<bool>,
+    #[schemars(with = "Option<NetworkCassetteSchema>")]
+    pub cassette: Option<NetworkCassette>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
@@ -40,6 +47,37 @@ enum NetworkModeSchema {
     Full,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct NetworkRuleSchema {
+    action: NetworkRuleActionSchema,
+    methods: Option<Vec<String>>,
+    host: String,
+    port: Option<u16>,
+    path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum NetworkRuleActionSchema {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+struct NetworkCassetteSchema {
+    mode: CassetteModeSchema,
+    path: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum CassetteModeSchema {
+    Record,
+    Replay,
+}
+
 impl NetworkToml {
     pub(crate) fn apply_to_network_proxy_config(&self, config: &mut NetworkProxyConfig) {
         if let Some(enabled) = self.enabled {
@@ -81,18 +119,27 @@ impl NetworkToml {
         if let Some(mode) = self.mode {
             config.network.mode = mode;
         }
+        if let Some(mitm) = self.mitm {
+            config.network.mitm = mitm;
+        }
         if let Some(allowed_domains) = self.allowed_domains.as_ref() {
             config.network.allowed_domains = allowed_domains.clone();
         }
         if let Some(denied_domains) = self.denied_domains.as_ref() {
             config.network.denied_domains = denied_domains.clone();
         }
+        if let Some(rules) = self.rules.as_ref() {
+            config.network.rules = rules.clone();
+        }
         if let Some(allow_unix_sockets) = self.allow_unix_sockets.as_ref() {
             config.network.allow_unix_sockets = allow_unix_sockets.clone();
         }
         if let Some(allow_local_binding) = self.allow_local_binding {
             config.network.allow_local_binding = allow_local_binding;
         }
+        if let Some(cassette) = self.cassette.as_ref() {
+            config.network.cassette = Some(cassette.clone());
+        }
     }
 
     pub(crate) fn to_network_proxy_config(&self) -> NetworkProxyConfig {
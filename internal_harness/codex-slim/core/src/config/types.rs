@@ -357,6 +357,70 @@ pub struct FeedbackConfigToml {
     pub enabled: Option<bool>,
 }
 
+/// External commands run as hooks around agent events, from the `[hooks]` table.
+/// Each command receives the event as JSON on stdin.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct HookCommands {
+    /// Run once the session is configured.
+    pub session_start: Option<HookCommandToml>,
+    /// Run when the session shuts down, with its turn count and token usage.
+    pub session_end: Option<HookCommandToml>,
+    /// Run when a turn starts.
+    pub turn_started: Option<HookCommandToml>,
+    /// Run before the conversation history is compacted.
+    pub before_compaction: Option<HookCommandToml>,
+    /// Run before every tool call. It may print a JSON decision on stdout to deny the
+    /// call, replace its input or add context to its output, e.g.
+    /// `{"decision":"deny","message":"..."}`.
+    pub before_tool_use: Option<HookCommandToml>,
+    /// Run when a command or patch needs the user's approval.
+    pub approval_requested: Option<HookCommandToml>,
+    /// Run when a turn reports an error.
+    pub error: Option<HookCommandToml>,
+}
+
+/// A hook command: either a bare argv list or a table with a timeout.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(untagged)]
+pub enum HookCommandToml {
+    Argv(Vec<String>),
+    Detailed {
+        command: Vec<String>,
+        /// Kill the command after this many milliseconds. Defaults to 10 seconds.
+        timeout_ms: Option<u64>,
+        /// Only for `before_tool_use`: let the call proceed when the command fails or
+        /// times out. Defaults to false, which blocks the call.
+        #[serde(default)]
+        fail_open: bool,
+    },
+}
+
+impl HookCommandToml {
+    pub fn argv(&self) -> &[String] {
+        match self {
+            Self::Argv(argv) | Self::Detailed { command: argv, .. } => argv,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Argv(_) => None,
+            Self::Detailed { timeout_ms, .. } => timeout_ms.map(Duration::from_millis),
+        }
+    }
+
+    pub fn fail_open(&self) -> bool {
+        matches!(
+            self,
+            Self::Detailed {
+                fail_open: true,
+                ..
+            }
+        )
+    }
+}
+
 /// Memories settings loaded from config.toml.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, JsonSchema)]
 #[schemars(deny_unknown_fields)]
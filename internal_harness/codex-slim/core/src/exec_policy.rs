@@ -9,16 +9,21 @@ use crate::config_loader::ConfigLayerStack;
 use crate::config_loader::ConfigLayerStackOrdering;
 use crate::is_dangerous_command::command_might_be_dangerous;
 use crate::is_safe_command::is_known_safe_command;
+use codex_app_server_protocol::ConfigLayerSource;
 use codex_execpolicy::AmendError;
 use codex_execpolicy::Decision;
 use codex_execpolicy::Error as ExecPolicyRuleError;
 use codex_execpolicy::Evaluation;
 use codex_execpolicy::NetworkRuleProtocol;
 use codex_execpolicy::Policy;
+use codex_execpolicy::PolicyLayer;
 use codex_execpolicy::PolicyParser;
 use codex_execpolicy::RuleMatch;
+use codex_execpolicy::RuleRef;
+use codex_execpolicy::RuleSource;
 use codex_execpolicy::blocking_append_allow_prefix_rule;
 use codex_execpolicy::blocking_append_network_rule;
+use codex_execpolicy::rule::PrefixRule;
 use codex_protocol::approvals::ExecPolicyAmendment;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::SandboxPolicy;
@@ -429,16 +434,24 @@ async fn load_exec_policy_with_warning(
 }
 
 pub async fn load_exec_policy(config_stack: &ConfigLayerStack) -> Result<Policy, ExecPolicyError> {
-    // Iterate the layers in increasing order of precedence, adding the *.rules
-    // from each layer, so that higher-precedence layers can override
-    // rules defined in lower-precedence ones.
+    // Iterate the layers in increasing order of precedence, tagging the *.rules
+    // from each layer with the matching policy layer so that evaluation can
+    // apply layer precedence: project rules may only tighten, while user rules
+    // override the rest.
     let mut policy_paths = Vec::new();
     for layer in config_stack.get_layers(ConfigLayerStackOrdering::LowestPrecedenceFirst, false) {
+        let Some(policy_layer) = policy_layer_for(&layer.name) else {
+            continue;
+        };
         if let Some(config_folder) = layer.config_folder() {
             #[expect(clippy::expect_used)]
             let policy_dir = config_folder.join(RULES_DIR_NAME).expect("safe join");
             let layer_policy_paths = collect_policy_files(&policy_dir).await?;
-            policy_paths.extend(layer_policy_paths);
+            policy_paths.extend(
+                layer_policy_paths
+                    .into_iter()
+                    .map(|policy_path| (policy_layer, policy_path)),
+            );
         }
     }
     tracing::trace!(
@@ -447,7 +460,7 @@ pub async fn load_exec_policy(config_stack: &ConfigLayerStack) -> Result<Policy,
     );
 
     let mut parser = PolicyParser::new();
-    for policy_path in &policy_paths {
+    for (policy_layer, policy_path) in &policy_paths {
         let contents =
             fs::read_to_string(policy_path)
                 .await
@@ -457,7 +470,7 @@ pub async fn load_exec_policy(config_stack: &ConfigLayerStack) -> Result<Policy,
                 })?;
         let identifier = policy_path.to_string_lossy().to_string();
         parser
-            .parse(&identifier, &contents)
+            .parse_layer(*policy_layer, &identifier, &contents)
             .map_err(|source| ExecPolicyError::ParsePolicy {
                 path: identifier,
                 source,
@@ -468,23 +481,53 @@ pub async fn load_exec_policy(config_stack: &ConfigLayerStack) -> Result<Policy,
     tracing::debug!("loaded rules from {} files", policy_paths.len());
     tracing::trace!(rules = ?policy, "exec policy rules loaded");
 
-    let Some(requirements_policy) = config_stack.requirements().exec_policy.as_deref() else {
+    let Some(requirements_policy) = config_stack.requirements().exec_policy.as_ref() else {
         return Ok(policy);
     };
 
+    // Requirements are enforced by an administrator, so their rules join the
+    // admin layer.
+    let requirements_source = RuleSource {
+        layer: PolicyLayer::Admin,
+        path: requirements_policy.source.to_string(),
+    };
+    let requirements_rules: &Policy = requirements_policy.value.as_ref();
     let mut combined_rules = policy.rules().clone();
-    for (program, rules) in requirements_policy.as_ref().rules().iter_all() {
+    for (program, rules) in requirements_rules.rules().iter_all() {
         for rule in rules {
-            combined_rules.insert(program.clone(), rule.clone());
+            let rule = match rule.as_any().downcast_ref::<PrefixRule>() {
+                Some(prefix_rule) => Arc::new(PrefixRule {
+                    source: Some(requirements_source.clone()),
+                    ..prefix_rule.clone()
+                }) as RuleRef,
+                None => rule.clone(),
+            };
+            combined_rules.insert(program.clone(), rule);
         }
     }
 
     let mut combined_network_rules = policy.network_rules().to_vec();
-    combined_network_rules.extend(requirements_policy.as_ref().network_rules().iter().cloned());
+    combined_network_rules.extend(requirements_rules.network_rules().iter().cloned());
 
     Ok(Policy::from_parts(combined_rules, combined_network_rules))
 }
 
+/// Policy layer for the `.rules` files of a config layer. System config is the
+/// lowest-precedence layer, so its rules are machine-wide defaults that user
+/// rules may override; rules an administrator enforces come from requirements
+/// instead. Project `.codex/` folders arrive with the repository.
+fn policy_layer_for(source: &ConfigLayerSource) -> Option<PolicyLayer> {
+    match source {
+        ConfigLayerSource::System { .. } => Some(PolicyLayer::Default),
+        ConfigLayerSource::User { .. } => Some(PolicyLayer::User),
+        ConfigLayerSource::Project { .. } => Some(PolicyLayer::Repo),
+        ConfigLayerSource::Mdm { .. }
+        | ConfigLayerSource::SessionFlags
+        | ConfigLayerSource::LegacyManagedConfigTomlFromFile { .. }
+        | ConfigLayerSource::LegacyManagedConfigTomlFromMdm => None,
+    }
+}
+
 /// If a command is not matched by any execpolicy rule, derive a [`Decision`].
 pub fn render_decision_for_unmatched_command(
     approval_policy: AskForApproval,
@@ -864,6 +907,7 @@ mod tests {
                     command: vec!["rm".to_string()],
                     decision: Decision::Allow
                 }],
+                decided_by: None,
             },
             policy.check_multiple(commands.iter(), &|_| Decision::Allow)
         );
@@ -951,6 +995,10 @@ mod tests {
                     decision: Decision::Forbidden,
                     justification: None,
                 }],
+                decided_by: Some(RuleSource {
+                    layer: PolicyLayer::Repo,
+                    path: policy_dir.join("deny.rules").to_string_lossy().to_string(),
+                }),
             },
             policy.check_multiple(command.iter(), &|_| Decision::Allow)
         );
@@ -1012,6 +1060,7 @@ mod tests {
                     command: vec!["ls".to_string()],
                     decision: Decision::Allow
                 }],
+                decided_by: None,
             },
             policy.check_multiple(command.iter(), &|_| Decision::Allow)
         );
@@ -1050,6 +1099,7 @@ mod tests {
                     command: vec!["ls".to_string()],
                     decision: Decision::Allow,
                 }],
+                decided_by: None,
             },
             policy.check_multiple([vec!["ls".to_string()]].iter(), &|_| Decision::Allow)
         );
@@ -1108,6 +1158,13 @@ mod tests {
                     decision: Decision::Forbidden,
                     justification: None,
                 }],
+                decided_by: Some(RuleSource {
+                    layer: PolicyLayer::User,
+                    path: user_policy_dir
+                        .join("user.rules")
+                        .to_string_lossy()
+                        .to_string(),
+                }),
             },
             policy.check_multiple([vec!["rm".to_string()]].iter(), &|_| Decision::Allow)
         );
@@ -1119,12 +1176,166 @@ mod tests {
                     decision: Decision::Prompt,
                     justification: None,
                 }],
+                decided_by: Some(RuleSource {
+                    layer: PolicyLayer::Repo,
+                    path: project_policy_dir
+                        .join("project.rules")
+                        .to_string_lossy()
+                        .to_string(),
+                }),
             },
             policy.check_multiple([vec!["ls".to_string()]].iter(), &|_| Decision::Allow)
         );
         Ok(())
     }
 
+    #[tokio::test]
+    async fn project_rules_only_tighten_user_and_requirements_rules() -> anyhow::Result<()> {
+        let user_dir = tempdir()?;
+        let project_dir = tempdir()?;
+
+        let user_policy_dir = user_dir.path().join(RULES_DIR_NAME);
+        fs::create_dir_all(&user_policy_dir)?;
+        fs::write(
+            user_policy_dir.join("user.rules"),
+            r#"
+prefix_rule(pattern=["rm"], decision="forbidden")
+prefix_rule(pattern=["git"])
+"#,
+        )?;
+
+        let project_policy_dir = project_dir.path().join(RULES_DIR_NAME);
+        fs::create_dir_all(&project_policy_dir)?;
+        fs::write(
+            project_policy_dir.join("project.rules"),
+            r#"
+prefix_rule(pattern=["rm"])
+prefix_rule(pattern=["git", "push"], decision="prompt")
+prefix_rule(pattern=["curl"])
+"#,
+        )?;
+
+        let mut requirements_parser = PolicyParser::new();
+        requirements_parser.parse(
+            "requirements",
+            r#"prefix_rule(pattern=["curl"], decision="forbidden")"#,
+        )?;
+        let requirements = ConfigRequirements {
+            exec_policy: Some(codex_config::Sourced::new(
+                codex_config::RequirementsExecPolicy::new(requirements_parser.build()),
+                codex_config::RequirementSource::Unknown,
+            )),
+            ..ConfigRequirements::default()
+        };
+        let layers = vec![
+            ConfigLayerEntry::new(
+                ConfigLayerSource::User {
+                    file: AbsolutePathBuf::from_absolute_path(user_dir.path().join("config.toml"))?,
+                },
+                TomlValue::Table(Default::default()),
+            ),
+            ConfigLayerEntry::new(
+                ConfigLayerSource::Project {
+                    dot_codex_folder: AbsolutePathBuf::from_absolute_path(project_dir.path())?,
+                },
+                TomlValue::Table(Default::default()),
+            ),
+        ];
+        let config_stack =
+            ConfigLayerStack::new(layers, requirements, ConfigRequirementsToml::default())?;
+
+        let policy = load_exec_policy(&config_stack).await?;
+        let decide = |command: &[&str]| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            policy.evaluate(&command).map(|evaluation| {
+                (
+                    evaluation.decision,
+                    evaluation.decided_by.map(|source| source.layer),
+                )
+            })
+        };
+
+        assert_eq!(
+            decide(&["rm", "-rf", "target"]),
+            Some((Decision::Forbidden, Some(PolicyLayer::User)))
+        );
+        assert_eq!(
+            decide(&["git", "push"]),
+            Some((Decision::Prompt, Some(PolicyLayer::Repo)))
+        );
+        assert_eq!(
+            decide(&["curl", "example.com"]),
+            Some((Decision::Forbidden, Some(PolicyLayer::Admin)))
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn user_rules_override_system_default_rules() -> anyhow::Result<()> {
+        let system_dir = tempdir()?;
+        let user_dir = tempdir()?;
+
+        let system_policy_dir = system_dir.path().join(RULES_DIR_NAME);
+        fs::create_dir_all(&system_policy_dir)?;
+        fs::write(
+            system_policy_dir.join("system.rules"),
+            r#"
+prefix_rule(pattern=["git", "push"], decision="forbidden")
+prefix_rule(pattern=["curl"], decision="prompt")
+"#,
+        )?;
+
+        let user_policy_dir = user_dir.path().join(RULES_DIR_NAME);
+        fs::create_dir_all(&user_policy_dir)?;
+        fs::write(
+            user_policy_dir.join("user.rules"),
+            r#"prefix_rule(pattern=["git", "push"])"#,
+        )?;
+
+        let layers = vec![
+            ConfigLayerEntry::new(
+                ConfigLayerSource::System {
+                    file: AbsolutePathBuf::from_absolute_path(
+                        system_dir.path().join("config.toml"),
+                    )?,
+                },
+                TomlValue::Table(Default::default()),
+            ),
+            ConfigLayerEntry::new(
+                ConfigLayerSource::User {
+                    file: AbsolutePathBuf::from_absolute_path(user_dir.path().join("config.toml"))?,
+                },
+                TomlValue::Table(Default::default()),
+            ),
+        ];
+        let config_stack = ConfigLayerStack::new(
+            layers,
+            ConfigRequirements::default(),
+            ConfigRequirementsToml::default(),
+        )?;
+
+        let policy = load_exec_policy(&config_stack).await?;
+        let decide = |command: &[&str]| {
+            let command: Vec<String> = command.iter().map(ToString::to_string).collect();
+            policy.evaluate(&command).map(|evaluation| {
+                (
+                    evaluation.decision,
+                    evaluation.decided_by.map(|source| source.layer),
+                )
+            })
+        };
+
+        assert_eq!(
+            decide(&["git", "push"]),
+            Some((Decision::Allow, Some(PolicyLayer::User)))
+        );
+        assert_eq!(
+            decide(&["curl", "example.com"]),
+            Some((Decision::Prompt, Some(PolicyLayer::Default)))
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn evaluates_bash_lc_inner_commands() {
         let policy_src = r#"
@@ -1672,6 +1883,7 @@ prefix_rule(
             evaluation,
             Evaluation {
                 decision: Decision::Allow,
+                decided_by: None,
                 ..
             }
         ));
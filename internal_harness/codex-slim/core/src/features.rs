@@ -82,6 +82,8 @@ pub enum Feature {
     JsRepl,
     /// Only expose js_repl tools directly to the model.
     JsReplToolsOnly,
+    /// Enable the tree-sitter backed definition, reference and outline tools.
+    CodeNavigation,
     /// Use the single unified PTY-backed exec tool.
     UnifiedExec,
     /// Route shell tool execution through the zsh exec bridge.
@@ -465,6 +467,12 @@ pub const FEATURES: &[FeatureSpec] = &[
         stage: Stage::UnderDevelopment,
         default_enabled: false,
     },
+    FeatureSpec {
+        id: Feature::CodeNavigation,
+        key: "code_navigation",
+        stage: Stage::UnderDevelopment,
+        default_enabled: false,
+    },
     FeatureSpec {
         id: Feature::WebSearchRequest,
         key: "web_search_request",
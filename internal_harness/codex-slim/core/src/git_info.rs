@@ -136,7 +136,15 @@ pub async fn get_git_remote_urls_assume_git_repo(cwd: &Path) -> Option<BTreeMap<
 
 /// Return the current HEAD commit hash without checking whether `cwd` is in a git repo.
 pub async fn get_head_commit_hash(cwd: &Path) -> Option<String> {
-    let output = run_git_command_with_timeout(&["rev-parse", "HEAD"], cwd).await?;
+    resolve_commit_hash(cwd, "HEAD").await
+}
+
+/// Resolve `rev` (a branch, tag or abbreviated SHA) to its full commit hash.
+pub async fn resolve_commit_hash(cwd: &Path, rev: &str) -> Option<String> {
+    let spec = format!("{rev}^{{commit}}");
+    let output =
+        run_git_command_with_timeout(&["rev-parse", "--verify", "--quiet", spec.as_str()], cwd)
+            .await?;
     if !output.status.success() {
         return None;
     }
@@ -78,7 +78,9 @@ pub use model_provider_info::WireApi;
 pub use model_provider_info::built_in_model_providers;
 pub use model_provider_info::create_oss_provider_with_base_url;
 mod event_mapping;
+pub mod review_fingerprint;
 pub mod review_format;
+pub mod review_incremental;
 pub mod review_prompts;
 #[cfg(target_os = "macos")]
 mod seatbelt_permissions;
@@ -0,0 +1,298 @@
+//! Content-based identity for review findings, and deduplication of findings
+//! the reviewer reported more than once in a single run.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use codex_git::read_blob;
+use codex_protocol::protocol::ReviewFinding;
+use codex_protocol::protocol::ReviewOutputEvent;
+use sha2::Digest;
+use sha2::Sha256;
+
+/// Minimum word overlap (Jaccard index) for two titles to count as the same issue.
+const SIMILAR_TITLE_THRESHOLD: f64 = 0.6;
+
+/// Fingerprints every finding that lacks one and collapses near-duplicates.
+/// Runs git, so call it off the async runtime.
+pub fn finalize_review_findings(
+    mut output: ReviewOutputEvent,
+    repo_root: &Path,
+    revision: Option<&str>,
+) -> ReviewOutputEvent {
+    for finding in &mut output.findings {
+        if finding.fingerprint.is_none() {
+            finding.fingerprint = Some(finding_fingerprint(finding, repo_root, revision));
+        }
+    }
+    output.findings = dedupe_findings(output.findings);
+    output
+}
+
+/// Content-based identity of a finding: its repository-relative file, its
+/// normalized title and the normalized code it points at. Line numbers are left
+/// out so the fingerprint survives edits elsewhere in the file.
+///
+/// The code is read from `revision`, the commit that was reviewed, or from the
+/// working tree when the review covered uncommitted changes (`None`). Runs git,
+/// so call it off the async runtime.
+pub fn finding_fingerprint(
+    finding: &ReviewFinding,
+    repo_root: &Path,
+    revision: Option<&str>,
+) -> String {
+    let location = &finding.code_location;
+    let path = location
+        .absolute_file_path
+        .strip_prefix(repo_root)
+        .unwrap_or(&location.absolute_file_path);
+    let contents = match revision {
+        Some(revision) => {
+            read_blob(repo_root, &format!("{revision}:{}", path.to_string_lossy())).ok()
+        }
+        None => std::fs::read_to_string(&location.absolute_file_path).ok(),
+    };
+    let snippet = contents
+        .map(|contents| {
+            normalized_snippet(
+                &contents,
+                location.line_range.start,
+                location.line_range.end,
+            )
+        })
+        .unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(b"\n");
+    hasher.update(normalized_title(&finding.title).as_bytes());
+    hasher.update(b"\n");
+    hasher.update(snippet.as_bytes());
+    let hex = format!("{:x}", hasher.finalize());
+    hex.get(..16).unwrap_or(&hex).to_string()
+}
+
+/// Keeps one finding per group of near-duplicates, preferring the most urgent
+/// and then the most confident one. Order of first appearance is preserved.
+pub fn dedupe_findings(findings: Vec<ReviewFinding>) -> Vec<ReviewFinding> {
+    let mut kept: Vec<ReviewFinding> = Vec::with_capacity(findings.len());
+    for finding in findings {
+        match kept
+            .iter_mut()
+            .find(|existing| is_near_duplicate(existing, &finding))
+        {
+            Some(existing) => {
+                if outranks(&finding, existing) {
+                    *existing = finding;
+                }
+            }
+            None => kept.push(finding),
+        }
+    }
+    kept
+}
+
+fn is_near_duplicate(a: &ReviewFinding, b: &ReviewFinding) -> bool {
+    if a.fingerprint.is_some() && a.fingerprint == b.fingerprint {
+        return true;
+    }
+    let (a_loc, b_loc) = (&a.code_location, &b.code_location);
+    a_loc.absolute_file_path == b_loc.absolute_file_path
+        && a_loc.line_range.start <= b_loc.line_range.end.max(b_loc.line_range.start)
+        && b_loc.line_range.start <= a_loc.line_range.end.max(a_loc.line_range.start)
+        && title_similarity(&a.title, &b.title) >= SIMILAR_TITLE_THRESHOLD
+}
+
+fn outranks(candidate: &ReviewFinding, existing: &ReviewFinding) -> bool {
+    candidate.priority < existing.priority
+        || (candidate.priority == existing.priority
+            && candidate.confidence_score > existing.confidence_score)
+}
+
+/// Lowercases the title, drops a leading `[P1]`-style priority tag and collapses
+/// whitespace, so re-prioritized or reformatted findings still match.
+fn normalized_title(title: &str) -> String {
+    let title = title.trim();
+    let title = match title
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    {
+        Some((tag, rest)) if tag.len() <= 3 && tag.to_ascii_uppercase().starts_with('P') => rest,
+        _ => title,
+    };
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalized_title(a), normalized_title(b));
+    let words = |title: &str| -> HashSet<String> {
+        title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let (a, b) = (words(&a), words(&b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// The lines in `start..=end` (1-based, clamped to the file) with indentation
+/// and blank lines removed, so reformatting does not change the fingerprint.
+fn normalized_snippet(contents: &str, start: u32, end: u32) -> String {
+    let start = usize::try_from(start.max(1)).unwrap_or(usize::MAX);
+    let end = usize::try_from(end).unwrap_or(usize::MAX).max(start);
+    contents
+        .lines()
+        .skip(start - 1)
+        .take(end - start + 1)
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ReviewCodeLocation;
+    use codex_protocol::protocol::ReviewLineRange;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn finding(title: &str, path: &Path, start: u32, end: u32) -> ReviewFinding {
+        ReviewFinding {
+            title: title.to_string(),
+            body: String::new(),
+            confidence_score: 0.5,
+            priority: 2,
+            code_location: ReviewCodeLocation {
+                absolute_file_path: path.to_path_buf(),
+                line_range: ReviewLineRange { start, end },
+            },
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn fingerprint_follows_code_not_line_numbers() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let path = repo.path().join("lib.rs");
+        std::fs::write(
+            &path,
+            "fn a() {}\n\nfn parse(input: &[u8]) {\n    input[0];\n}\n",
+        )
+        .expect("write file");
+        let original = finding("[P1] Guard  empty input", &path, 3, 4);
+        let fingerprint = finding_fingerprint(&original, repo.path(), None);
+
+        std::fs::write(
+            &path,
+            "fn a() {}\n\n// moved down\nfn parse(input: &[u8]) {\n        input[0];\n}\n",
+        )
+        .expect("rewrite file");
+        let moved = finding("[P2] guard empty input", &path, 4, 5);
+        let other_code = finding("[P1] Guard empty input", &path, 1, 1);
+
+        assert_eq!(finding_fingerprint(&moved, repo.path(), None), fingerprint);
+        assert_ne!(
+            finding_fingerprint(&other_code, repo.path(), None),
+            fingerprint
+        );
+    }
+
+    #[test]
+    fn fingerprint_reads_code_at_the_reviewed_revision() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .current_dir(repo.path())
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .status()
+                .expect("run git");
+            assert!(status.success(), "git {args:?} failed");
+        };
+        let path = repo.path().join("lib.rs");
+        git(&["init", "--initial-branch=main"]);
+        std::fs::write(&path, "fn parse(input: &[u8]) {\n    input[0];\n}\n").expect("write file");
+        git(&["add", "lib.rs"]);
+        git(&["commit", "-m", "parse"]);
+        let finding = finding("[P1] Guard empty input", &path, 1, 2);
+        let reviewed = finding_fingerprint(&finding, repo.path(), Some("HEAD"));
+
+        std::fs::write(&path, "fn parse(input: &[u8]) {\n    input.first();\n}\n")
+            .expect("rewrite file");
+
+        assert_eq!(
+            finding_fingerprint(&finding, repo.path(), Some("HEAD")),
+            reviewed
+        );
+        assert_ne!(finding_fingerprint(&finding, repo.path(), None), reviewed);
+    }
+
+    #[test]
+    fn dedupe_merges_overlapping_findings_with_similar_titles() {
+        let path = PathBuf::from("/repo/src/lib.rs");
+        let mut urgent = finding("Index out of bounds on empty input", &path, 12, 14);
+        urgent.priority = 1;
+        let findings = vec![
+            finding("Index out of bounds on empty input slice", &path, 10, 12),
+            finding("Unused import", &path, 11, 11),
+            urgent.clone(),
+            finding("Index out of bounds on empty input", &path, 40, 41),
+        ];
+
+        let deduped = dedupe_findings(findings);
+
+        let summary: Vec<(&str, u32)> = deduped
+            .iter()
+            .map(|finding| {
+                (
+                    finding.title.as_str(),
+                    finding.code_location.line_range.start,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Index out of bounds on empty input", 12),
+                ("Unused import", 11),
+                ("Index out of bounds on empty input", 40),
+            ]
+        );
+        assert_eq!(deduped[0], urgent);
+    }
+
+    #[test]
+    fn finalize_fingerprints_and_collapses_identical_findings() {
+        let path = PathBuf::from("/repo/missing.rs");
+        let output = ReviewOutputEvent {
+            findings: vec![
+                finding("[P2] Leaks handle", &path, 3, 3),
+                finding("[P2] Leaks handle", &path, 3, 3),
+            ],
+            ..Default::default()
+        };
+
+        let finalized = finalize_review_findings(output, Path::new("/repo"), None);
+
+        assert_eq!(finalized.findings.len(), 1);
+        assert!(finalized.findings[0].fingerprint.is_some());
+    }
+
+    #[test]
+    fn normalized_title_strips_priority_tag() {
+        assert_eq!(normalized_title("[P0]  Crash   on start"), "crash on start");
+        assert_eq!(normalized_title("[note] keep me"), "[note] keep me");
+    }
+}
@@ -1,5 +1,9 @@
 use crate::protocol::ReviewFinding;
 use crate::protocol::ReviewOutputEvent;
+use serde_json::Value as JsonValue;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::path::Path;
 
 // Note: We keep this module UI-agnostic. It returns plain strings that
 // higher layers (e.g., TUI) may style as needed.
@@ -80,3 +84,404 @@ pub fn render_review_output_text(output: &ReviewOutputEvent) -> String {
         sections.join("\n\n")
     }
 }
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_FINGERPRINT_KEY: &str = "codexFinding/v1";
+const TOOL_NAME: &str = "codex";
+const TOOL_INFORMATION_URI: &str = "https://github.com/openai/codex";
+
+/// Severity bucket for a finding's `priority` (0 is the most urgent).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FindingSeverity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl FindingSeverity {
+    fn from_priority(priority: i32) -> Self {
+        match priority {
+            i32::MIN..=1 => FindingSeverity::Error,
+            2 => FindingSeverity::Warning,
+            _ => FindingSeverity::Note,
+        }
+    }
+
+    fn sarif_level(self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "error",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Note => "note",
+        }
+    }
+
+    fn github_annotation_level(self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "failure",
+            FindingSeverity::Warning => "warning",
+            FindingSeverity::Note => "notice",
+        }
+    }
+
+    fn reviewdog_severity(self) -> &'static str {
+        match self {
+            FindingSeverity::Error => "ERROR",
+            FindingSeverity::Warning => "WARNING",
+            FindingSeverity::Note => "INFO",
+        }
+    }
+}
+
+fn rule_id(priority: i32) -> String {
+    format!("codex-review/P{priority}")
+}
+
+/// Inclusive 1-based line span; models occasionally report line 0 or an end
+/// before the start, neither of which code-scanning tools accept.
+fn line_span(item: &ReviewFinding) -> (u32, u32) {
+    let start = item.code_location.line_range.start.max(1);
+    let end = item.code_location.line_range.end.max(start);
+    (start, end)
+}
+
+/// Path of the finding relative to `repo_root` with `/` separators, or `None`
+/// when the file lives outside the repository.
+fn repo_relative_path(item: &ReviewFinding, repo_root: &Path) -> Option<String> {
+    let relative = item
+        .code_location
+        .absolute_file_path
+        .strip_prefix(repo_root)
+        .ok()?;
+    Some(
+        relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+fn display_path(item: &ReviewFinding, repo_root: &Path) -> String {
+    repo_relative_path(item, repo_root).unwrap_or_else(|| {
+        item.code_location
+            .absolute_file_path
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+fn finding_message(item: &ReviewFinding) -> String {
+    let body = item.body.trim();
+    if body.is_empty() {
+        item.title.clone()
+    } else {
+        format!("{}\n\n{body}", item.title)
+    }
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters and `/`.
+fn encode_uri_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(char::from(byte));
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn sarif_artifact_location(item: &ReviewFinding, repo_root: &Path) -> JsonValue {
+    match repo_relative_path(item, repo_root) {
+        Some(relative) => json!({
+            "uri": encode_uri_path(&relative),
+            "uriBaseId": "%SRCROOT%",
+        }),
+        None => {
+            let absolute = item.code_location.absolute_file_path.to_string_lossy();
+            let absolute = absolute.replace('\\', "/");
+            let absolute = absolute.trim_start_matches('/');
+            json!({ "uri": format!("file:///{}", encode_uri_path(absolute)) })
+        }
+    }
+}
+
+/// Render review findings as a SARIF 2.1.0 log with a single run.
+///
+/// Each finding becomes a result whose rule is derived from its priority, so
+/// code-scanning UIs can filter by P0–P3. Paths under `repo_root` are emitted
+/// relative to `%SRCROOT%`.
+pub fn render_review_output_sarif(output: &ReviewOutputEvent, repo_root: &Path) -> JsonValue {
+    let priorities: BTreeSet<i32> = output.findings.iter().map(|item| item.priority).collect();
+    let rules: Vec<JsonValue> = priorities
+        .into_iter()
+        .map(|priority| {
+            json!({
+                "id": rule_id(priority),
+                "name": format!("ReviewFindingP{priority}"),
+                "shortDescription": { "text": format!("Priority {priority} review finding") },
+                "defaultConfiguration": {
+                    "level": FindingSeverity::from_priority(priority).sarif_level(),
+                },
+            })
+        })
+        .collect();
+    let results: Vec<JsonValue> = output
+        .findings
+        .iter()
+        .map(|item| {
+            let (start_line, end_line) = line_span(item);
+            let mut result = json!({
+                "ruleId": rule_id(item.priority),
+                "level": FindingSeverity::from_priority(item.priority).sarif_level(),
+                "message": { "text": finding_message(item) },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": sarif_artifact_location(item, repo_root),
+                        "region": { "startLine": start_line, "endLine": end_line },
+                    },
+                }],
+                "properties": {
+                    "title": item.title,
+                    "priority": item.priority,
+                    "confidence": item.confidence_score,
+                },
+            });
+            // Lets code scanning track the same finding across runs.
+            if let Some(fingerprint) = &item.fingerprint {
+                result["partialFingerprints"] = json!({ SARIF_FINGERPRINT_KEY: fingerprint });
+            }
+            result
+        })
+        .collect();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": TOOL_NAME,
+                    "informationUri": TOOL_INFORMATION_URI,
+                    "rules": rules,
+                },
+            },
+            "results": results,
+            "properties": {
+                "overallCorrectness": output.overall_correctness,
+                "overallExplanation": output.overall_explanation,
+                "overallConfidence": output.overall_confidence_score,
+            },
+        }],
+    })
+}
+
+/// Render review findings as the `annotations` array accepted by the GitHub
+/// Checks API (`output.annotations` on check runs).
+pub fn render_review_output_github_annotations(
+    output: &ReviewOutputEvent,
+    repo_root: &Path,
+) -> JsonValue {
+    let annotations: Vec<JsonValue> = output
+        .findings
+        .iter()
+        .map(|item| {
+            let (start_line, end_line) = line_span(item);
+            let body = item.body.trim();
+            json!({
+                "path": display_path(item, repo_root),
+                "start_line": start_line,
+                "end_line": end_line,
+                "annotation_level": FindingSeverity::from_priority(item.priority)
+                    .github_annotation_level(),
+                "title": item.title,
+                "message": if body.is_empty() { item.title.as_str() } else { body },
+            })
+        })
+        .collect();
+    JsonValue::Array(annotations)
+}
+
+/// Render review findings as reviewdog diagnostics, one JSON object per line
+/// (`reviewdog -f=rdjsonl`).
+pub fn render_review_output_rdjsonl(output: &ReviewOutputEvent, repo_root: &Path) -> String {
+    output
+        .findings
+        .iter()
+        .map(|item| {
+            let (start_line, end_line) = line_span(item);
+            let diagnostic = json!({
+                "message": finding_message(item),
+                "location": {
+                    "path": display_path(item, repo_root),
+                    "range": {
+                        "start": { "line": start_line },
+                        "end": { "line": end_line },
+                    },
+                },
+                "severity": FindingSeverity::from_priority(item.priority).reviewdog_severity(),
+                "source": { "name": TOOL_NAME, "url": TOOL_INFORMATION_URI },
+                "code": { "value": rule_id(item.priority) },
+            });
+            format!("{diagnostic}\n")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::ReviewCodeLocation;
+    use crate::protocol::ReviewLineRange;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn finding(title: &str, path: &str, priority: i32, start: u32, end: u32) -> ReviewFinding {
+        ReviewFinding {
+            title: title.to_string(),
+            body: format!("Details for {title}."),
+            confidence_score: 0.5,
+            priority,
+            code_location: ReviewCodeLocation {
+                absolute_file_path: PathBuf::from(path),
+                line_range: ReviewLineRange { start, end },
+            },
+            fingerprint: None,
+        }
+    }
+
+    fn output() -> ReviewOutputEvent {
+        ReviewOutputEvent {
+            findings: vec![
+                finding("Crash on empty input", "/repo/src/parse.rs", 1, 10, 12),
+                finding("Stale comment", "/repo/docs/my guide.md", 3, 0, 0),
+            ],
+            overall_correctness: "patch is incorrect".to_string(),
+            overall_explanation: "One crash.".to_string(),
+            overall_confidence_score: 0.75,
+        }
+    }
+
+    #[test]
+    fn sarif_maps_priorities_regions_and_relative_uris() {
+        let sarif = render_review_output_sarif(&output(), Path::new("/repo"));
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(
+            run["tool"]["driver"]["rules"]
+                .as_array()
+                .map(|rules| rules.iter().map(|rule| rule["id"].clone()).collect()),
+            Some(vec![json!("codex-review/P1"), json!("codex-review/P3")])
+        );
+        assert_eq!(
+            run["results"][0],
+            json!({
+                "ruleId": "codex-review/P1",
+                "level": "error",
+                "message": { "text": "Crash on empty input\n\nDetails for Crash on empty input." },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/parse.rs", "uriBaseId": "%SRCROOT%" },
+                        "region": { "startLine": 10, "endLine": 12 },
+                    },
+                }],
+                "properties": {
+                    "title": "Crash on empty input",
+                    "priority": 1,
+                    "confidence": 0.5,
+                },
+            })
+        );
+        assert_eq!(run["results"][1]["level"], "note");
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "docs/my%20guide.md", "uriBaseId": "%SRCROOT%" },
+                "region": { "startLine": 1, "endLine": 1 },
+            })
+        );
+        assert_eq!(
+            run["properties"]["overallCorrectness"],
+            "patch is incorrect"
+        );
+    }
+
+    #[test]
+    fn sarif_includes_partial_fingerprint_when_known() {
+        let mut output = output();
+        output.findings[0].fingerprint = Some("0123456789abcdef".to_string());
+
+        let sarif = render_review_output_sarif(&output, Path::new("/repo"));
+
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(
+            results[0]["partialFingerprints"],
+            json!({ "codexFinding/v1": "0123456789abcdef" })
+        );
+        assert_eq!(results[1].get("partialFingerprints"), None);
+    }
+
+    #[test]
+    fn sarif_uses_file_uri_outside_repo_root() {
+        let sarif = render_review_output_sarif(&output(), Path::new("/elsewhere"));
+
+        assert_eq!(
+            sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"],
+            json!({ "uri": "file:///repo/src/parse.rs" })
+        );
+    }
+
+    #[test]
+    fn github_annotations_use_check_run_levels() {
+        let annotations = render_review_output_github_annotations(&output(), Path::new("/repo"));
+
+        assert_eq!(
+            annotations,
+            json!([
+                {
+                    "path": "src/parse.rs",
+                    "start_line": 10,
+                    "end_line": 12,
+                    "annotation_level": "failure",
+                    "title": "Crash on empty input",
+                    "message": "Details for Crash on empty input.",
+                },
+                {
+                    "path": "docs/my guide.md",
+                    "start_line": 1,
+                    "end_line": 1,
+                    "annotation_level": "notice",
+                    "title": "Stale comment",
+                    "message": "Details for Stale comment.",
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rdjsonl_emits_one_diagnostic_per_line() {
+        let rendered = render_review_output_rdjsonl(&output(), Path::new("/repo"));
+
+        let diagnostics: Vec<JsonValue> = rendered
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("valid json line"))
+            .collect();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0],
+            json!({
+                "message": "Crash on empty input\n\nDetails for Crash on empty input.",
+                "location": {
+                    "path": "src/parse.rs",
+                    "range": { "start": { "line": 10 }, "end": { "line": 12 } },
+                },
+                "severity": "ERROR",
+                "source": { "name": "codex", "url": "https://github.com/openai/codex" },
+                "code": { "value": "codex-review/P1" },
+            })
+        );
+        assert_eq!(diagnostics[1]["severity"], "INFO");
+    }
+}
@@ -0,0 +1,401 @@
+//! Incremental reviews: narrow a review to the hunks changed since a previous
+//! review of the same change, and carry forward findings in untouched code.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_git::DiffFilter;
+use codex_git::DiffHunk;
+use codex_git::diff_hunks;
+use codex_git::merge_base_with_head;
+use codex_protocol::protocol::PreviousReview;
+use codex_protocol::protocol::ReviewFinding;
+use codex_protocol::protocol::ReviewLineRange;
+use codex_protocol::protocol::ReviewOutputEvent;
+use codex_protocol::protocol::ReviewTarget;
+
+use crate::git_info::get_git_repo_root;
+use crate::review_fingerprint::finding_fingerprint;
+
+/// Findings from a previous review that still apply because none of their lines
+/// changed, ready to be merged into the output of the incremental review.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IncrementalReview {
+    repo_root: PathBuf,
+    /// The revision the new review looks at; `None` means the working tree.
+    revision: Option<String>,
+    carried_findings: Vec<ReviewFinding>,
+}
+
+impl IncrementalReview {
+    /// Fingerprints the newly reported findings and appends the carried-forward
+    /// ones the reviewer did not report again. Runs git, so call it off the
+    /// async runtime.
+    pub fn merge_into(&self, mut output: ReviewOutputEvent) -> ReviewOutputEvent {
+        for finding in &mut output.findings {
+            if finding.fingerprint.is_none() {
+                finding.fingerprint = Some(finding_fingerprint(
+                    finding,
+                    &self.repo_root,
+                    self.revision.as_deref(),
+                ));
+            }
+        }
+        let reported: HashSet<String> = output
+            .findings
+            .iter()
+            .filter_map(|finding| finding.fingerprint.clone())
+            .collect();
+        output.findings.extend(
+            self.carried_findings
+                .iter()
+                .filter(|finding| {
+                    finding
+                        .fingerprint
+                        .as_ref()
+                        .is_none_or(|fingerprint| !reported.contains(fingerprint))
+                })
+                .cloned(),
+        );
+        output
+    }
+}
+
+/// Diffs `previous.reviewed_sha` against the revision `target` reviews and
+/// returns the prompt section restricting the review to the changed hunks, along
+/// with the findings that can be carried forward as-is.
+pub fn plan_incremental_review(
+    previous: &PreviousReview,
+    target: &ReviewTarget,
+    cwd: &Path,
+) -> anyhow::Result<(String, IncrementalReview)> {
+    let Some(repo_root) = get_git_repo_root(cwd) else {
+        anyhow::bail!("Incremental reviews require a git repository");
+    };
+    let revision = reviewed_revision(target);
+    let hunks = diff_hunks(
+        &repo_root,
+        &previous.reviewed_sha,
+        revision,
+        &review_diff_filter(target),
+    )?;
+    let (carried_findings, changed_findings) =
+        partition_findings(&previous.findings, &hunks, &repo_root, revision);
+    let prompt_section = incremental_prompt_section(
+        &previous.reviewed_sha,
+        &hunks,
+        &changed_findings,
+        &repo_root,
+    );
+    Ok((
+        prompt_section,
+        IncrementalReview {
+            repo_root,
+            revision: revision.map(str::to_string),
+            carried_findings,
+        },
+    ))
+}
+
+/// The revision the new review looks at; `None` means the working tree.
+pub(crate) fn reviewed_revision(target: &ReviewTarget) -> Option<&str> {
+    match target {
+        ReviewTarget::Commit { sha, .. } => Some(sha),
+        ReviewTarget::CommitRange { head_sha, .. } => Some(head_sha),
+        ReviewTarget::UncommittedChanges
+        | ReviewTarget::BaseBranch { .. }
+        | ReviewTarget::Custom { .. } => None,
+    }
+}
+
+/// The revision to record as `reviewed_sha` once a review of `target` is done.
+///
+/// Working-tree reviews have no commit of their own, so they record the commit
+/// their diff was taken against: the merge base for a base-branch review and
+/// `HEAD` otherwise. The next incremental review then looks at those
+/// uncommitted changes again along with anything new.
+pub fn review_state_revision(target: &ReviewTarget, cwd: &Path) -> anyhow::Result<String> {
+    if let Some(revision) = reviewed_revision(target) {
+        return Ok(revision.to_string());
+    }
+    match target {
+        ReviewTarget::BaseBranch { branch } => merge_base_with_head(cwd, branch)?
+            .ok_or_else(|| anyhow::anyhow!("no merge base between HEAD and {branch}")),
+        _ => Ok("HEAD".to_string()),
+    }
+}
+
+/// The paths the review of `target` covers, so the hunk list matches its diff:
+/// commit ranges apply their path filters and uncommitted-change reviews
+/// include untracked files.
+fn review_diff_filter(target: &ReviewTarget) -> DiffFilter {
+    match target {
+        ReviewTarget::CommitRange {
+            include_paths,
+            exclude_paths,
+            ..
+        } => DiffFilter {
+            include_paths: include_paths.clone(),
+            exclude_paths: exclude_paths.clone(),
+            include_untracked: false,
+        },
+        ReviewTarget::UncommittedChanges => DiffFilter {
+            include_untracked: true,
+            ..Default::default()
+        },
+        ReviewTarget::Commit { .. }
+        | ReviewTarget::BaseBranch { .. }
+        | ReviewTarget::Custom { .. } => DiffFilter::default(),
+    }
+}
+
+fn relative_path(path: &Path, repo_root: &Path) -> Option<PathBuf> {
+    path.strip_prefix(repo_root).ok().map(Path::to_path_buf)
+}
+
+/// Splits previous findings into those whose lines are untouched (moved to
+/// their new line numbers and fingerprinted) and those overlapping a change.
+fn partition_findings(
+    findings: &[ReviewFinding],
+    hunks: &[DiffHunk],
+    repo_root: &Path,
+    revision: Option<&str>,
+) -> (Vec<ReviewFinding>, Vec<ReviewFinding>) {
+    let mut carried = Vec::new();
+    let mut changed = Vec::new();
+    for finding in findings {
+        let file_hunks: Vec<&DiffHunk> =
+            match relative_path(&finding.code_location.absolute_file_path, repo_root) {
+                Some(path) => hunks.iter().filter(|hunk| hunk.path == path).collect(),
+                None => Vec::new(),
+            };
+        match shifted_line_range(&finding.code_location.line_range, &file_hunks) {
+            Some(line_range) => {
+                let mut finding = finding.clone();
+                finding.code_location.line_range = line_range;
+                if finding.fingerprint.is_none() {
+                    finding.fingerprint = Some(finding_fingerprint(&finding, repo_root, revision));
+                }
+                carried.push(finding);
+            }
+            None => changed.push(finding.clone()),
+        }
+    }
+    (carried, changed)
+}
+
+/// Maps an old-side line range onto the new side of `hunks`, or returns `None`
+/// when one of the hunks touches the range.
+fn shifted_line_range(range: &ReviewLineRange, hunks: &[&DiffHunk]) -> Option<ReviewLineRange> {
+    let (start, end) = (range.start, range.end.max(range.start));
+    let mut offset: i64 = 0;
+    for hunk in hunks {
+        let touches = if hunk.old_lines == 0 {
+            // Pure insertion after `old_start`: only lines added inside the range count.
+            hunk.old_start >= start && hunk.old_start < end
+        } else {
+            let old_end = hunk.old_start + hunk.old_lines - 1;
+            hunk.old_start <= end && old_end >= start
+        };
+        if touches {
+            return None;
+        }
+        let before = if hunk.old_lines == 0 {
+            hunk.old_start < start
+        } else {
+            hunk.old_start + hunk.old_lines - 1 < start
+        };
+        if before {
+            offset += i64::from(hunk.new_lines) - i64::from(hunk.old_lines);
+        }
+    }
+    let shift = |line: u32| u32::try_from(i64::from(line) + offset).unwrap_or(line);
+    Some(ReviewLineRange {
+        start: shift(range.start),
+        end: shift(range.end),
+    })
+}
+
+fn incremental_prompt_section(
+    reviewed_sha: &str,
+    hunks: &[DiffHunk],
+    changed_findings: &[ReviewFinding],
+    repo_root: &Path,
+) -> String {
+    if hunks.is_empty() {
+        return format!(
+            "\n\nThis is an incremental review. Nothing changed since the previous review of {reviewed_sha}, so report no new findings."
+        );
+    }
+    let mut section = format!(
+        "\n\nThis is an incremental review. The previous review looked at {reviewed_sha}; since then only the hunks below changed (line numbers refer to the new code). Review only these hunks and do not report issues elsewhere: findings in unchanged code are carried forward automatically."
+    );
+    for hunk in hunks {
+        let path = hunk.path.display();
+        if hunk.new_lines == 0 {
+            section.push_str(&format!(
+                "\n- {path}: lines removed after line {}",
+                hunk.new_start
+            ));
+        } else {
+            let end = hunk.new_start + hunk.new_lines - 1;
+            section.push_str(&format!("\n- {path}:{}-{end}", hunk.new_start));
+        }
+    }
+    if !changed_findings.is_empty() {
+        section.push_str(&format!(
+            "\n\nThe previous review reported these findings on lines that have since changed (line numbers refer to {reviewed_sha}). Re-check each one against the current code and report it again only if it still applies:"
+        ));
+        for finding in changed_findings {
+            let location = &finding.code_location;
+            let path = relative_path(&location.absolute_file_path, repo_root)
+                .unwrap_or_else(|| location.absolute_file_path.clone());
+            section.push_str(&format!(
+                "\n- {} ({}:{}-{})",
+                finding.title,
+                path.display(),
+                location.line_range.start,
+                location.line_range.end
+            ));
+        }
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ReviewCodeLocation;
+    use pretty_assertions::assert_eq;
+
+    fn finding(title: &str, path: &str, start: u32, end: u32) -> ReviewFinding {
+        ReviewFinding {
+            title: title.to_string(),
+            body: String::new(),
+            confidence_score: 0.5,
+            priority: 1,
+            code_location: ReviewCodeLocation {
+                absolute_file_path: PathBuf::from(path),
+                line_range: ReviewLineRange { start, end },
+            },
+            fingerprint: None,
+        }
+    }
+
+    fn hunk(path: &str, old: (u32, u32), new: (u32, u32)) -> DiffHunk {
+        DiffHunk {
+            path: PathBuf::from(path),
+            old_start: old.0,
+            old_lines: old.1,
+            new_start: new.0,
+            new_lines: new.1,
+        }
+    }
+
+    #[test]
+    fn untouched_findings_shift_past_earlier_hunks() {
+        let hunks = vec![
+            hunk("src/lib.rs", (2, 0), (3, 3)),
+            hunk("src/lib.rs", (20, 2), (23, 1)),
+        ];
+        let findings = vec![
+            finding("[P1] Below insertion", "/repo/src/lib.rs", 10, 12),
+            finding("[P1] Overlaps edit", "/repo/src/lib.rs", 19, 20),
+            finding("[P2] Other file", "/repo/src/main.rs", 5, 5),
+        ];
+
+        let (carried, changed) = partition_findings(&findings, &hunks, Path::new("/repo"), None);
+
+        let carried_ranges: Vec<(String, u32, u32)> = carried
+            .iter()
+            .map(|finding| {
+                (
+                    finding.title.clone(),
+                    finding.code_location.line_range.start,
+                    finding.code_location.line_range.end,
+                )
+            })
+            .collect();
+        assert_eq!(
+            carried_ranges,
+            vec![
+                ("[P1] Below insertion".to_string(), 13, 15),
+                ("[P2] Other file".to_string(), 5, 5),
+            ]
+        );
+        assert!(carried.iter().all(|finding| finding.fingerprint.is_some()));
+        assert_eq!(changed, vec![findings[1].clone()]);
+    }
+
+    #[test]
+    fn insertion_inside_range_requires_revalidation() {
+        let hunks = [hunk("a.rs", (5, 0), (6, 1))];
+        let refs: Vec<&DiffHunk> = hunks.iter().collect();
+
+        assert_eq!(
+            shifted_line_range(&ReviewLineRange { start: 4, end: 8 }, &refs),
+            None
+        );
+        assert_eq!(
+            shifted_line_range(&ReviewLineRange { start: 1, end: 5 }, &refs),
+            Some(ReviewLineRange { start: 1, end: 5 })
+        );
+    }
+
+    #[test]
+    fn merge_skips_carried_findings_reported_again() {
+        let repo_root = PathBuf::from("/repo");
+        let mut carried = finding("[P1] Still broken", "/repo/src/lib.rs", 3, 4);
+        carried.fingerprint = Some(finding_fingerprint(&carried, &repo_root, None));
+        let mut kept = finding("[P2] Untouched", "/repo/src/util.rs", 8, 8);
+        kept.fingerprint = Some(finding_fingerprint(&kept, &repo_root, None));
+        let incremental = IncrementalReview {
+            repo_root,
+            revision: None,
+            carried_findings: vec![carried, kept.clone()],
+        };
+        let output = ReviewOutputEvent {
+            findings: vec![finding("[P1] Still broken", "/repo/src/lib.rs", 5, 6)],
+            ..Default::default()
+        };
+
+        let merged = incremental.merge_into(output);
+
+        let titles: Vec<&str> = merged
+            .findings
+            .iter()
+            .map(|finding| finding.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["[P1] Still broken", "[P2] Untouched"]);
+        assert_eq!(merged.findings[0].code_location.line_range.start, 5);
+        assert_eq!(merged.findings[1], kept);
+    }
+
+    #[test]
+    fn prompt_section_lists_hunks_and_findings_to_recheck() {
+        let section = incremental_prompt_section(
+            "abc1234",
+            &[
+                hunk("src/lib.rs", (3, 1), (3, 2)),
+                hunk("gone.rs", (1, 4), (0, 0)),
+            ],
+            &[finding("[P1] Overlaps edit", "/repo/src/lib.rs", 3, 3)],
+            Path::new("/repo"),
+        );
+
+        assert_eq!(
+            section,
+            "\n\nThis is an incremental review. The previous review looked at abc1234; since then \
+             only the hunks below changed (line numbers refer to the new code). Review only these \
+             hunks and do not report issues elsewhere: findings in unchanged code are carried \
+             forward automatically.\
+             \n- src/lib.rs:3-4\
+             \n- gone.rs: lines removed after line 0\
+             \n\nThe previous review reported these findings on lines that have since changed \
+             (line numbers refer to abc1234). Re-check each one against the current code and \
+             report it again only if it still applies:\
+             \n- [P1] Overlaps edit (src/lib.rs:3-3)"
+        );
+    }
+}
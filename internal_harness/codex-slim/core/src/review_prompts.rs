@@ -1,3 +1,6 @@
+use crate::review_incremental::IncrementalReview;
+use crate::review_incremental::plan_incremental_review;
+use codex_git::merge_base;
 use codex_git::merge_base_with_head;
 use codex_protocol::protocol::ReviewRequest;
 use codex_protocol::protocol::ReviewTarget;
@@ -8,6 +11,9 @@ pub struct ResolvedReviewRequest {
     pub target: ReviewTarget,
     pub prompt: String,
     pub user_facing_hint: String,
+    /// Set when the request carried a previous review; the prompt then only
+    /// covers the hunks changed since.
+    pub incremental: Option<IncrementalReview>,
 }
 
 const UNCOMMITTED_PROMPT: &str = "Review the current code changes (staged, unstaged, and untracked files) and provide prioritized findings.";
@@ -19,12 +25,23 @@ const COMMIT_PROMPT_WITH_TITLE: &str = "Review the code changes introduced by co
 const COMMIT_PROMPT: &str =
     "Review the code changes introduced by commit {sha}. Provide prioritized, actionable findings.";
 
+const COMMIT_RANGE_PROMPT_BACKUP: &str = "Review the code changes that commit {headSha} would merge into {baseSha}. Start by finding their merge base (`git merge-base {baseSha} {headSha}`), then run `git diff <merge-base> {headSha}{pathspec}` to see the changes.{pathFilter} Provide prioritized, actionable findings.";
+const COMMIT_RANGE_PROMPT: &str = "Review the code changes that commit {headSha} would merge into {baseSha}. The merge base commit for this comparison is {mergeBaseSha}. Run `git diff {mergeBaseSha} {headSha}{pathspec}` to inspect the changes.{pathFilter} Provide prioritized, actionable findings.";
+
 pub fn resolve_review_request(
     request: ReviewRequest,
     cwd: &Path,
 ) -> anyhow::Result<ResolvedReviewRequest> {
     let target = request.target;
-    let prompt = review_prompt(&target, cwd)?;
+    let mut prompt = review_prompt(&target, cwd)?;
+    let incremental = match request.previous_review {
+        Some(previous) => {
+            let (prompt_section, incremental) = plan_incremental_review(&previous, &target, cwd)?;
+            prompt.push_str(&prompt_section);
+            Some(incremental)
+        }
+        None => None,
+    };
     let user_facing_hint = request
         .user_facing_hint
         .unwrap_or_else(|| user_facing_hint(&target));
@@ -33,6 +50,7 @@ pub fn resolve_review_request(
         target,
         prompt,
         user_facing_hint,
+        incremental,
     })
 }
 
@@ -57,6 +75,25 @@ pub fn review_prompt(target: &ReviewTarget, cwd: &Path) -> anyhow::Result<String
                 Ok(COMMIT_PROMPT.replace("{sha}", sha))
             }
         }
+        ReviewTarget::CommitRange {
+            base_sha,
+            head_sha,
+            include_paths,
+            exclude_paths,
+        } => {
+            let template = match merge_base(cwd, base_sha, head_sha)? {
+                Some(commit) => COMMIT_RANGE_PROMPT.replace("{mergeBaseSha}", &commit),
+                None => COMMIT_RANGE_PROMPT_BACKUP.to_string(),
+            };
+            Ok(template
+                .replace("{baseSha}", base_sha)
+                .replace("{headSha}", head_sha)
+                .replace("{pathspec}", &git_pathspec(include_paths, exclude_paths))
+                .replace(
+                    "{pathFilter}",
+                    &path_filter_note(include_paths, exclude_paths),
+                ))
+        }
         ReviewTarget::Custom { instructions } => {
             let prompt = instructions.trim();
             if prompt.is_empty() {
@@ -79,15 +116,112 @@ pub fn user_facing_hint(target: &ReviewTarget) -> String {
                 format!("commit {short_sha}")
             }
         }
+        ReviewTarget::CommitRange {
+            base_sha, head_sha, ..
+        } => {
+            let short_base: String = base_sha.chars().take(7).collect();
+            let short_head: String = head_sha.chars().take(7).collect();
+            format!("changes in {short_base}..{short_head}")
+        }
         ReviewTarget::Custom { instructions } => instructions.trim().to_string(),
     }
 }
 
+/// Renders path globs as a trailing git pathspec (` -- ':(glob)src/**' ...`), or an
+/// empty string when there is nothing to filter.
+fn git_pathspec(include_paths: &[String], exclude_paths: &[String]) -> String {
+    let specs: Vec<String> = include_paths
+        .iter()
+        .map(|glob| format!("':(glob){glob}'"))
+        .chain(
+            exclude_paths
+                .iter()
+                .map(|glob| format!("':(glob,exclude){glob}'")),
+        )
+        .collect();
+    if specs.is_empty() {
+        String::new()
+    } else {
+        format!(" -- {}", specs.join(" "))
+    }
+}
+
+fn path_filter_note(include_paths: &[String], exclude_paths: &[String]) -> String {
+    let mut note = String::new();
+    if !include_paths.is_empty() {
+        note.push_str(&format!(
+            " Only review files matching {}.",
+            include_paths.join(", ")
+        ));
+    }
+    if !exclude_paths.is_empty() {
+        note.push_str(&format!(
+            " Do not review files matching {}; they are vendored or generated.",
+            exclude_paths.join(", ")
+        ));
+    }
+    note
+}
+
 impl From<ResolvedReviewRequest> for ReviewRequest {
     fn from(resolved: ResolvedReviewRequest) -> Self {
         ReviewRequest {
             target: resolved.target,
             user_facing_hint: Some(resolved.user_facing_hint),
+            previous_review: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+
+    fn commit_range(include_paths: &[&str], exclude_paths: &[&str]) -> ReviewTarget {
+        ReviewTarget::CommitRange {
+            base_sha: "1111111aaaa".to_string(),
+            head_sha: "2222222bbbb".to_string(),
+            include_paths: include_paths.iter().map(ToString::to_string).collect(),
+            exclude_paths: exclude_paths.iter().map(ToString::to_string).collect(),
         }
     }
+
+    #[test]
+    fn commit_range_prompt_falls_back_when_revisions_are_unknown() {
+        let repo = tempfile::tempdir().expect("tempdir");
+        let status = Command::new("git")
+            .current_dir(repo.path())
+            .args(["init", "--initial-branch=main"])
+            .status()
+            .expect("git init");
+        assert!(status.success());
+
+        let prompt = review_prompt(&commit_range(&["src/**"], &["vendor/**"]), repo.path())
+            .expect("commit range prompt");
+
+        assert_eq!(
+            prompt,
+            "Review the code changes that commit 2222222bbbb would merge into 1111111aaaa. \
+             Start by finding their merge base (`git merge-base 1111111aaaa 2222222bbbb`), \
+             then run `git diff <merge-base> 2222222bbbb -- ':(glob)src/**' ':(glob,exclude)vendor/**'` \
+             to see the changes. Only review files matching src/**. Do not review files matching \
+             vendor/**; they are vendored or generated. Provide prioritized, actionable findings."
+        );
+    }
+
+    #[test]
+    fn commit_range_pathspec_is_empty_without_filters() {
+        assert_eq!(git_pathspec(&[], &[]), "");
+        assert_eq!(path_filter_note(&[], &[]), "");
+    }
+
+    #[test]
+    fn commit_range_hint_uses_short_shas() {
+        assert_eq!(
+            user_facing_hint(&commit_range(&[], &[])),
+            "changes in 1111111..2222222"
+        );
+    }
 }
@@ -5,11 +5,13 @@ use std::fs::{self};
 use std::io::Error as IoError;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use chrono::SecondsFormat;
 use codex_protocol::ThreadId;
 use codex_protocol::dynamic_tools::DynamicToolSpec;
 use codex_protocol::models::BaseInstructions;
+use codex_secrets::SecretRedactor;
 use serde_json::Value;
 use time::OffsetDateTime;
 use time::format_description::FormatItem;
@@ -449,6 +451,8 @@ impl RolloutRecorder {
             state_db_ctx.clone(),
             state_builder,
             config.model_provider_id.clone(),
+            // The config was validated on load, so this only falls back in tests.
+            Arc::new(SecretRedactor::from_config(&config.redaction).unwrap_or_default()),
         ));
 
         Ok(Self {
@@ -700,8 +704,12 @@ async fn rollout_writer(
     state_db_ctx: Option<StateDbHandle>,
     mut state_builder: Option<ThreadMetadataBuilder>,
     default_provider: String,
+    redactor: Arc<SecretRedactor>,
 ) -> std::io::Result<()> {
-    let mut writer = file.map(|file| JsonlWriter { file });
+    let mut writer = file.map(|file| JsonlWriter {
+        file,
+        redactor: Arc::clone(&redactor),
+    });
     let mut buffered_items = Vec::<RolloutItem>::new();
     if let Some(builder) = state_builder.as_mut() {
         builder.rollout_path = rollout_path.clone();
@@ -762,6 +770,7 @@ async fn rollout_writer(
                         let file = open_log_file(log_file_info.path.as_path())?;
                         writer = Some(JsonlWriter {
                             file: tokio::fs::File::from_std(file),
+                            redactor: Arc::clone(&redactor),
                         });
 
                         if let Some(session_meta) = meta.take() {
@@ -884,6 +893,7 @@ async fn write_and_reconcile_items(
 
 struct JsonlWriter {
     file: tokio::fs::File,
+    redactor: Arc<SecretRedactor>,
 }
 
 #[derive(serde::Serialize)]
@@ -909,7 +919,17 @@ impl JsonlWriter {
         self.write_line(&line).await
     }
     async fn write_line(&mut self, item: &impl serde::Serialize) -> std::io::Result<()> {
-        let mut json = serde_json::to_string(item)?;
+        // Redact string values rather than the serialized line so replacements can
+        // never break JSON escaping.
+        let mut value = serde_json::to_value(item)?;
+        for hit in self.redactor.redact_json(&mut value) {
+            info!(
+                rule_id = %hit.rule_id,
+                count = hit.count,
+                "redacted secrets from rollout line"
+            );
+        }
+        let mut json = serde_json::to_string(&value)?;
         json.push('\n');
         self.file.write_all(json.as_bytes()).await?;
         self.file.flush().await?;
@@ -1045,6 +1065,49 @@ mod tests {
         Ok(path)
     }
 
+    #[tokio::test]
+    async fn recorder_redacts_secrets_before_writing() -> std::io::Result<()> {
+        let home = TempDir::new().expect("temp dir");
+        let config = ConfigBuilder::default()
+            .codex_home(home.path().to_path_buf())
+            .build()
+            .await?;
+        let recorder = RolloutRecorder::new(
+            &config,
+            RolloutRecorderParams::new(
+                ThreadId::new(),
+                None,
+                SessionSource::Exec,
+                BaseInstructions::default(),
+                Vec::new(),
+                EventPersistenceMode::Limited,
+            ),
+            None,
+            None,
+        )
+        .await?;
+        let token = format!("ghp_{}", "x7Y2".repeat(9));
+
+        recorder.persist().await?;
+        recorder
+            .record_items(&[RolloutItem::EventMsg(EventMsg::AgentMessage(
+                AgentMessageEvent {
+                    message: format!("pushed with \"{token}\""),
+                    phase: None,
+                },
+            ))])
+            .await?;
+        recorder.flush().await?;
+
+        let text = std::fs::read_to_string(recorder.rollout_path())?;
+        assert!(!text.contains(&token), "token leaked into rollout: {text}");
+        assert!(
+            text.contains(r#"pushed with \"[REDACTED_SECRET]\""#),
+            "{text}"
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn recorder_materializes_only_after_explicit_persist() -> std::io::Result<()> {
         let home = TempDir::new().expect("temp dir");
@@ -18,6 +18,7 @@ use crate::unified_exec::UnifiedExecProcessManager;
 use crate::zsh_exec_bridge::ZshExecBridge;
 use codex_hooks::Hooks;
 use codex_otel::OtelManager;
+use codex_secrets::SecretRedactor;
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::sync::watch;
@@ -30,6 +31,9 @@ pub(crate) struct SessionServices {
     pub(crate) zsh_exec_bridge: ZshExecBridge,
     pub(crate) analytics_events_client: AnalyticsEventsClient,
     pub(crate) hooks: Hooks,
+    /// Redactor for tool output returned to the model; see
+    /// [`SecretRedactor::for_model_output`].
+    pub(crate) secret_redactor: Arc<SecretRedactor>,
     pub(crate) rollout: Mutex<Option<RolloutRecorder>>,
     pub(crate) user_shell: Arc<crate::shell::Shell>,
     pub(crate) shell_snapshot_tx: watch::Sender<Option<Arc<crate::shell_snapshot::ShellSnapshot>>>,
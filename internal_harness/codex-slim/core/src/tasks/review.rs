@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -14,26 +15,75 @@ use codex_protocol::protocol::ExitedReviewModeEvent;
 use codex_protocol::protocol::ItemCompletedEvent;
 use codex_protocol::protocol::ReviewOutputEvent;
 use tokio_util::sync::CancellationToken;
+use tracing::warn;
 
 use crate::codex::Session;
 use crate::codex::TurnContext;
 use crate::codex_delegate::run_codex_thread_one_shot;
 use crate::config::Constrained;
 use crate::features::Feature;
+use crate::git_info::get_git_repo_root;
+use crate::review_fingerprint::finalize_review_findings;
 use crate::review_format::format_review_findings_block;
 use crate::review_format::render_review_output_text;
+use crate::review_incremental::IncrementalReview;
 use crate::state::TaskKind;
 use codex_protocol::user_input::UserInput;
 
 use super::SessionTask;
 use super::SessionTaskContext;
 
-#[derive(Clone, Copy)]
-pub(crate) struct ReviewTask;
+#[derive(Clone, Default)]
+pub(crate) struct ReviewTask {
+    /// The revision under review, read when fingerprinting findings; `None`
+    /// means the working tree.
+    reviewed_revision: Option<String>,
+    incremental: Option<IncrementalReview>,
+}
 
 impl ReviewTask {
     pub(crate) fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// A review task for `reviewed_revision` that merges findings carried
+    /// forward from a previous review into its output.
+    pub(crate) fn with_incremental(
+        reviewed_revision: Option<String>,
+        incremental: Option<IncrementalReview>,
+    ) -> Self {
+        Self {
+            reviewed_revision,
+            incremental,
+        }
+    }
+
+    /// Fingerprints and dedupes the findings, then merges in the carried-forward
+    /// ones. This reads the reviewed code through git, so it runs on a blocking
+    /// thread.
+    async fn finalize_output(
+        &self,
+        output: ReviewOutputEvent,
+        cwd: PathBuf,
+    ) -> Option<ReviewOutputEvent> {
+        let task = self.clone();
+        let finalized = tokio::task::spawn_blocking(move || {
+            let repo_root = get_git_repo_root(cwd.as_path()).unwrap_or(cwd);
+            let output =
+                finalize_review_findings(output, &repo_root, task.reviewed_revision.as_deref());
+            match task.incremental.as_ref() {
+                Some(incremental) => incremental.merge_into(output),
+                None => output,
+            }
+        })
+        .await;
+        match finalized {
+            Ok(output) => Some(output),
+            Err(err) => {
+                warn!("failed to finalize review findings: {err}");
+                None
+            }
+        }
     }
 }
 
@@ -68,6 +118,10 @@ impl SessionTask for ReviewTask {
             Some(receiver) => process_review_events(session.clone(), ctx.clone(), receiver).await,
             None => None,
         };
+        let output = match output {
+            Some(output) => self.finalize_output(output, ctx.cwd.clone()).await,
+            None => None,
+        };
         if !cancellation_token.is_cancelled() {
             exit_review_mode(session.clone_session(), output.clone(), ctx.clone()).await;
         }
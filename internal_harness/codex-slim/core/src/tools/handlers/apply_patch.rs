@@ -319,13 +319,15 @@ Your patch language is a stripped‑down, file‑oriented diff format designed t
 
 Within that envelope, you get a sequence of file operations.
 You MUST include a header to specify the action you are taking.
-Each operation starts with one of three headers:
+Each operation starts with one of four headers:
 
 *** Add File: <path> - create a new file. Every following line is a + line (the initial contents).
+*** Add Binary File: <path> - create a binary file. Every following line is a + line of base64 (the file's bytes).
 *** Delete File: <path> - remove an existing file. Nothing follows.
 *** Update File: <path> - patch an existing file in place (optionally with a rename).
 
-May be immediately followed by *** Move to: <new path> if you want to rename the file.
+An Update File header may be immediately followed by *** Base Blob: <git object> (such as HEAD:path/to/file) or *** Base File: <path> to name the revision your hunks were written against. If the file has changed since then, the hunks are applied to that revision and merged into the current contents, with conflict markers where the changes overlap.
+It may then be followed by *** Move to: <new path> if you want to rename the file.
 Then one or more “hunks”, each introduced by @@ (optionally followed by a hunk header).
 Within a hunk each line starts with:
 
@@ -351,10 +353,12 @@ The full grammar definition is below:
 Patch := Begin { FileOp } End
 Begin := "*** Begin Patch" NEWLINE
 End := "*** End Patch" NEWLINE
-FileOp := AddFile | DeleteFile | UpdateFile
+FileOp := AddFile | AddBinaryFile | DeleteFile | UpdateFile
 AddFile := "*** Add File: " path NEWLINE { "+" line NEWLINE }
+AddBinaryFile := "*** Add Binary File: " path NEWLINE { "+" base64 NEWLINE }
 DeleteFile := "*** Delete File: " path NEWLINE
-UpdateFile := "*** Update File: " path NEWLINE [ MoveTo ] { Hunk }
+UpdateFile := "*** Update File: " path NEWLINE [ Base ] [ MoveTo ] { Hunk }
+Base := ( "*** Base Blob: " object | "*** Base File: " basePath ) NEWLINE
 MoveTo := "*** Move to: " newPath NEWLINE
 Hunk := "@@" [ header ] NEWLINE { HunkLine } [ "*** End of File" NEWLINE ]
 HunkLine := (" " | "-" | "+") text NEWLINE
@@ -391,7 +395,9 @@ It is important to remember:
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_apply_patch::Hunk;
     use codex_apply_patch::MaybeApplyPatchVerified;
+    use codex_apply_patch::MergeBase;
     use pretty_assertions::assert_eq;
     use tempfile::TempDir;
 
@@ -418,4 +424,75 @@ mod tests {
         let keys = file_paths_for_action(&action);
         assert_eq!(keys.len(), 2);
     }
+
+    #[test]
+    fn grammar_and_instructions_cover_every_header() {
+        let ToolSpec::Function(json_tool) = create_apply_patch_json_tool() else {
+            panic!("expected a function tool");
+        };
+        let instructions = [
+            json_tool.description.as_str(),
+            codex_apply_patch::APPLY_PATCH_TOOL_INSTRUCTIONS,
+            include_str!("../../../prompt_with_apply_patch_instructions.md"),
+        ];
+        for header in [
+            "*** Add File: ",
+            "*** Add Binary File: ",
+            "*** Delete File: ",
+            "*** Update File: ",
+            "*** Base Blob: ",
+            "*** Base File: ",
+            "*** Move to: ",
+        ] {
+            assert!(
+                APPLY_PATCH_LARK_GRAMMAR.contains(&format!("\"{header}\"")),
+                "grammar is missing {header:?}"
+            );
+            for text in instructions {
+                assert!(text.contains(header), "instructions are missing {header:?}");
+            }
+        }
+
+        let patch = r#"*** Begin Patch
+*** Add Binary File: logo.png
++AAECAw==
+*** Update File: a.txt
+*** Base Blob: HEAD:a.txt
+@@
+-old
++new
+*** Update File: b.txt
+*** Base File: .snapshots/b.txt
+*** Move to: c.txt
+@@
+-old
++new
+*** End Patch"#;
+        let hunks = codex_apply_patch::parse_patch(patch)
+            .expect("patch parses")
+            .hunks;
+        assert_eq!(hunks.len(), 3);
+        assert!(
+            matches!(&hunks[0], Hunk::AddBinaryFile { contents, .. } if contents == &[0, 1, 2, 3]),
+            "{hunks:?}"
+        );
+        assert!(
+            matches!(
+                &hunks[1],
+                Hunk::UpdateFile { base: Some(MergeBase::GitBlob(object)), .. } if object == "HEAD:a.txt"
+            ),
+            "{hunks:?}"
+        );
+        assert!(
+            matches!(
+                &hunks[2],
+                Hunk::UpdateFile {
+                    base: Some(MergeBase::File(path)),
+                    move_path: Some(_),
+                    ..
+                } if path == Path::new(".snapshots/b.txt")
+            ),
+            "{hunks:?}"
+        );
+    }
 }
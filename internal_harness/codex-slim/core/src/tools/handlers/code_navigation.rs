@@ -0,0 +1,924 @@
+use codex_protocol::models::FunctionCallOutputBody;
+use std::collections::HashMap;
+use std::fmt;
+use std::num::NonZero;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex as StdMutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use codex_file_search::ContentSearchMode;
+use codex_file_search::ContentSearchOptions;
+use codex_file_search::search_content;
+use serde::Deserialize;
+use tokio::time::timeout;
+use tree_sitter::Language;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+
+use crate::function_tool::FunctionCallError;
+use crate::tools::context::ToolInvocation;
+use crate::tools::context::ToolOutput;
+use crate::tools::context::ToolPayload;
+use crate::tools::handlers::parse_arguments;
+use crate::tools::registry::ToolHandler;
+use crate::tools::registry::ToolKind;
+
+pub struct CodeNavigationHandler;
+
+pub(crate) const FIND_DEFINITION_TOOL_NAME: &str = "find_definition";
+pub(crate) const FIND_REFERENCES_TOOL_NAME: &str = "find_references";
+pub(crate) const FILE_OUTLINE_TOOL_NAME: &str = "file_outline";
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_SEARCH_THREADS: usize = 8;
+/// Parsed files kept in memory; the least recently used are evicted first.
+const MAX_CACHED_FILES: usize = 2048;
+/// Signatures and reference lines are truncated to this many characters.
+const MAX_LINE_CHARS: usize = 200;
+
+fn default_limit() -> usize {
+    DEFAULT_LIMIT
+}
+
+#[derive(Deserialize)]
+struct SymbolArgs {
+    symbol: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+#[derive(Deserialize)]
+struct FileOutlineArgs {
+    file_path: String,
+}
+
+#[async_trait]
+impl ToolHandler for CodeNavigationHandler {
+    fn kind(&self) -> ToolKind {
+        ToolKind::Function
+    }
+
+    async fn handle(&self, invocation: ToolInvocation) -> Result<ToolOutput, FunctionCallError> {
+        let ToolInvocation {
+            turn,
+            tool_name,
+            payload,
+            ..
+        } = invocation;
+
+        let arguments = match payload {
+            ToolPayload::Function { arguments } => arguments,
+            _ => {
+                return Err(FunctionCallError::RespondToModel(
+                    "code navigation handler received unsupported payload".to_string(),
+                ));
+            }
+        };
+
+        let lines = match tool_name.as_str() {
+            FILE_OUTLINE_TOOL_NAME => {
+                let args: FileOutlineArgs = parse_arguments(&arguments)?;
+                let path = turn.resolve_path(Some(args.file_path));
+                run_blocking(move |_| file_outline(&path)).await?
+            }
+            FIND_DEFINITION_TOOL_NAME | FIND_REFERENCES_TOOL_NAME => {
+                let args: SymbolArgs = parse_arguments(&arguments)?;
+                let target = SymbolTarget::parse(&args.symbol)?;
+                if args.limit == 0 {
+                    return Err(FunctionCallError::RespondToModel(
+                        "limit must be greater than zero".to_string(),
+                    ));
+                }
+                let limit = args.limit.min(MAX_LIMIT);
+                let root = turn.resolve_path(args.path);
+                tokio::fs::metadata(&root).await.map_err(|err| {
+                    FunctionCallError::RespondToModel(format!(
+                        "unable to access `{}`: {err}",
+                        root.display()
+                    ))
+                })?;
+                if tool_name == FIND_DEFINITION_TOOL_NAME {
+                    run_blocking(move |cancel_flag| {
+                        find_definitions(&target, root, limit, cancel_flag)
+                    })
+                    .await?
+                } else {
+                    run_blocking(move |cancel_flag| {
+                        find_references(&target, root, limit, cancel_flag)
+                    })
+                    .await?
+                }
+            }
+            _ => {
+                return Err(FunctionCallError::RespondToModel(format!(
+                    "unsupported code navigation tool `{tool_name}`"
+                )));
+            }
+        };
+
+        if lines.is_empty() {
+            Ok(ToolOutput::Function {
+                body: FunctionCallOutputBody::Text("No results found.".to_string()),
+                success: Some(false),
+            })
+        } else {
+            Ok(ToolOutput::Function {
+                body: FunctionCallOutputBody::Text(lines.join("\n")),
+                success: Some(true),
+            })
+        }
+    }
+}
+
+/// Runs `f` on the blocking pool, cancelling it after [`SEARCH_TIMEOUT`].
+async fn run_blocking<F>(f: F) -> Result<Vec<String>, FunctionCallError>
+where
+    F: FnOnce(Arc<AtomicBool>) -> anyhow::Result<Vec<String>> + Send + 'static,
+{
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let worker_cancel_flag = Arc::clone(&cancel_flag);
+    let task = tokio::task::spawn_blocking(move || f(worker_cancel_flag));
+    match timeout(SEARCH_TIMEOUT, task).await {
+        Ok(Ok(result)) => {
+            result.map_err(|err| FunctionCallError::RespondToModel(format!("{err:#}")))
+        }
+        Ok(Err(err)) => Err(FunctionCallError::RespondToModel(format!(
+            "code navigation failed: {err}"
+        ))),
+        Err(_) => {
+            cancel_flag.store(true, Ordering::Relaxed);
+            Err(FunctionCallError::RespondToModel(
+                "code navigation timed out after 30 seconds".to_string(),
+            ))
+        }
+    }
+}
+
+/// A symbol name, optionally qualified by its enclosing type or module as in
+/// `Type::name` or `Type.name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SymbolTarget {
+    container: Option<String>,
+    name: String,
+}
+
+impl SymbolTarget {
+    fn parse(symbol: &str) -> Result<Self, FunctionCallError> {
+        let symbol = symbol.trim();
+        let mut segments: Vec<&str> = symbol
+            .split("::")
+            .flat_map(|segment| segment.split('.'))
+            .collect();
+        let name = segments.pop().unwrap_or_default();
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(FunctionCallError::RespondToModel(format!(
+                "`{symbol}` is not a symbol name"
+            )));
+        }
+        Ok(Self {
+            container: segments
+                .pop()
+                .filter(|container| !container.is_empty())
+                .map(str::to_string),
+            name: name.to_string(),
+        })
+    }
+
+    fn matches(&self, symbol: &Symbol) -> bool {
+        symbol.name == self.name
+            && symbol.kind != SymbolKind::Impl
+            && self
+                .container
+                .as_ref()
+                .is_none_or(|container| symbol.container.as_ref() == Some(container))
+    }
+}
+
+fn file_outline(path: &Path) -> anyhow::Result<Vec<String>> {
+    let symbols = cached_symbols(path)?.with_context(|| {
+        format!(
+            "unsupported file type for `{}`; supported languages are Rust, TypeScript, \
+             JavaScript, Python, Go and Java",
+            path.display()
+        )
+    })?;
+    Ok(symbols
+        .outline
+        .iter()
+        .map(|symbol| {
+            format!(
+                "{}{} {} (L{}-L{})",
+                "  ".repeat(symbol.depth),
+                symbol.kind,
+                symbol.name,
+                symbol.start_line,
+                symbol.end_line
+            )
+        })
+        .collect())
+}
+
+fn find_definitions(
+    target: &SymbolTarget,
+    root: PathBuf,
+    limit: usize,
+    cancel_flag: Arc<AtomicBool>,
+) -> anyhow::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for path in candidate_files(&target.name, root, &cancel_flag)? {
+        let Ok(Some(symbols)) = cached_symbols(&path) else {
+            continue;
+        };
+        for symbol in symbols
+            .outline
+            .iter()
+            .filter(|symbol| target.matches(symbol))
+        {
+            let container = symbol
+                .container
+                .as_ref()
+                .map(|container| format!(" in {container}"))
+                .unwrap_or_default();
+            lines.push(format!(
+                "{}:{}-{}: {} {}{container}\n    {}",
+                path.display(),
+                symbol.start_line,
+                symbol.end_line,
+                symbol.kind,
+                symbol.name,
+                symbol.signature
+            ));
+            if lines.len() == limit {
+                return Ok(lines);
+            }
+        }
+    }
+    Ok(lines)
+}
+
+fn find_references(
+    target: &SymbolTarget,
+    root: PathBuf,
+    limit: usize,
+    cancel_flag: Arc<AtomicBool>,
+) -> anyhow::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for path in candidate_files(&target.name, root, &cancel_flag)? {
+        let Ok(Some(symbols)) = cached_symbols(&path) else {
+            continue;
+        };
+        let Some(positions) = symbols.identifiers.get(&target.name) else {
+            continue;
+        };
+        let Ok(contents) = std::fs::read(&path) else {
+            continue;
+        };
+        let source_lines: Vec<&[u8]> = contents.split(|byte| *byte == b'\n').collect();
+        for position in positions {
+            let line = source_lines
+                .get(position.line - 1)
+                .map(|line| truncate_line(&String::from_utf8_lossy(line)))
+                .unwrap_or_default();
+            lines.push(format!(
+                "{}:{}:{}: {line}",
+                path.display(),
+                position.line,
+                position.column
+            ));
+            if lines.len() == limit {
+                return Ok(lines);
+            }
+        }
+    }
+    Ok(lines)
+}
+
+/// Returns the files under `root` in a supported language that mention
+/// `name`, sorted by path. `.gitignore` rules are honored.
+fn candidate_files(
+    name: &str,
+    root: PathBuf,
+    cancel_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let threads = std::thread::available_parallelism().map_or(1, NonZero::get);
+    let options = ContentSearchOptions {
+        mode: ContentSearchMode::Literal,
+        include: SourceLanguage::GLOBS
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        max_matches: NonZero::<usize>::MAX,
+        first_match_per_file: true,
+        threads: NonZero::new(threads.min(MAX_SEARCH_THREADS)).unwrap_or(NonZero::<usize>::MIN),
+        ..Default::default()
+    };
+    let mut files: Vec<PathBuf> =
+        search_content(name, vec![root], options, Some(Arc::clone(cancel_flag)))?
+            .map(|content_match| content_match.full_path())
+            .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn truncate_line(line: &str) -> String {
+    let line = line.trim();
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((idx, _)) => format!("{}...", &line[..idx]),
+        None => line.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceLanguage {
+    Rust,
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Python,
+    Go,
+    Java,
+}
+
+impl SourceLanguage {
+    const GLOBS: &[&str] = &[
+        "*.rs", "*.ts", "*.mts", "*.cts", "*.tsx", "*.js", "*.jsx", "*.mjs", "*.cjs", "*.py",
+        "*.pyi", "*.go", "*.java",
+    ];
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let language = match path.extension()?.to_str()? {
+            "rs" => Self::Rust,
+            "ts" | "mts" | "cts" => Self::TypeScript,
+            "tsx" => Self::Tsx,
+            "js" | "jsx" | "mjs" | "cjs" => Self::JavaScript,
+            "py" | "pyi" => Self::Python,
+            "go" => Self::Go,
+            "java" => Self::Java,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::Java => tree_sitter_java::LANGUAGE.into(),
+        }
+    }
+
+    /// Returns the kind of symbol `node` defines, if any.
+    fn definition_kind(self, node: Node<'_>) -> Option<SymbolKind> {
+        use SymbolKind::*;
+        let kind = match (self, node.kind()) {
+            (Self::Rust, "function_item" | "function_signature_item") => Function,
+            (Self::Rust, "struct_item" | "union_item") => Struct,
+            (Self::Rust, "enum_item") => Enum,
+            (Self::Rust, "enum_variant") => Variant,
+            (Self::Rust, "trait_item") => Trait,
+            (Self::Rust, "impl_item") => Impl,
+            (Self::Rust, "type_item") => Type,
+            (Self::Rust, "const_item" | "static_item") => Constant,
+            (Self::Rust, "mod_item") => Module,
+            (Self::Rust, "macro_definition") => Macro,
+            (Self::Rust, "field_declaration") => Field,
+
+            (
+                Self::TypeScript | Self::Tsx | Self::JavaScript,
+                "function_declaration" | "generator_function_declaration" | "function_signature",
+            ) => Function,
+            (
+                Self::TypeScript | Self::Tsx | Self::JavaScript,
+                "class_declaration" | "abstract_class_declaration",
+            ) => Class,
+            (
+                Self::TypeScript | Self::Tsx | Self::JavaScript,
+                "method_definition" | "method_signature" | "abstract_method_signature",
+            ) => Method,
+            (
+                Self::TypeScript | Self::Tsx | Self::JavaScript,
+                "field_definition" | "public_field_definition",
+            ) => Field,
+            (Self::TypeScript | Self::Tsx, "interface_declaration") => Interface,
+            (Self::TypeScript | Self::Tsx, "type_alias_declaration") => Type,
+            (Self::TypeScript | Self::Tsx, "enum_declaration") => Enum,
+            (Self::TypeScript | Self::Tsx, "internal_module" | "module") => Module,
+            (Self::TypeScript | Self::Tsx | Self::JavaScript, "variable_declarator") => {
+                // Only `const f = () => ...` and friends define named symbols.
+                match node.child_by_field_name("value")?.kind() {
+                    "arrow_function"
+                    | "function_expression"
+                    | "function"
+                    | "generator_function" => Function,
+                    "class" => Class,
+                    _ => return None,
+                }
+            }
+
+            (Self::Python, "function_definition") => Function,
+            (Self::Python, "class_definition") => Class,
+            (Self::Python, "assignment") => {
+                // Module-level `NAME = ...` only.
+                let statement = node.parent()?;
+                if statement.kind() != "expression_statement"
+                    || statement.parent()?.kind() != "module"
+                    || node.child_by_field_name("left")?.kind() != "identifier"
+                {
+                    return None;
+                }
+                Variable
+            }
+
+            (Self::Go, "function_declaration") => Function,
+            (Self::Go, "method_declaration") => Method,
+            (Self::Go, "type_spec" | "type_alias") => {
+                match node.child_by_field_name("type").map(|ty| ty.kind()) {
+                    Some("struct_type") => Struct,
+                    Some("interface_type") => Interface,
+                    _ => Type,
+                }
+            }
+            (Self::Go, "const_spec") => Constant,
+            (Self::Go, "var_spec") => Variable,
+            (Self::Go, "field_declaration") => Field,
+            (Self::Go, "method_elem" | "method_spec") => Method,
+
+            (Self::Java, "class_declaration" | "record_declaration") => Class,
+            (Self::Java, "interface_declaration" | "annotation_type_declaration") => Interface,
+            (Self::Java, "enum_declaration") => Enum,
+            (Self::Java, "enum_constant") => Variant,
+            (Self::Java, "method_declaration") => Method,
+            (Self::Java, "constructor_declaration") => Constructor,
+            (Self::Java, "field_declaration" | "constant_declaration") => Field,
+
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// Returns the name `node`, a definition of `kind`, binds.
+    fn definition_name(self, node: Node<'_>, kind: SymbolKind, source: &[u8]) -> Option<String> {
+        let text = |node: Node<'_>| node.utf8_text(source).ok().map(str::to_string);
+        let name_node = match (self, kind) {
+            (Self::Rust, SymbolKind::Impl) => {
+                let ty = text(node.child_by_field_name("type")?)?;
+                return Some(match node.child_by_field_name("trait") {
+                    Some(trait_node) => format!("{} for {ty}", text(trait_node)?),
+                    None => ty,
+                });
+            }
+            (Self::Java, SymbolKind::Field) => node
+                .child_by_field_name("declarator")?
+                .child_by_field_name("name")?,
+            (Self::Python, SymbolKind::Variable) => node.child_by_field_name("left")?,
+            _ => node
+                .child_by_field_name("name")
+                .or_else(|| node.child_by_field_name("property"))?,
+        };
+        text(name_node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolKind {
+    Function,
+    Method,
+    Constructor,
+    Class,
+    Struct,
+    Enum,
+    Variant,
+    Interface,
+    Trait,
+    Impl,
+    Type,
+    Constant,
+    Variable,
+    Field,
+    Module,
+    Macro,
+}
+
+impl SymbolKind {
+    /// Whether functions nested directly in this symbol are methods.
+    fn has_methods(self) -> bool {
+        matches!(
+            self,
+            Self::Class | Self::Struct | Self::Enum | Self::Interface | Self::Trait | Self::Impl
+        )
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Function => "function",
+            Self::Method => "method",
+            Self::Constructor => "constructor",
+            Self::Class => "class",
+            Self::Struct => "struct",
+            Self::Enum => "enum",
+            Self::Variant => "variant",
+            Self::Interface => "interface",
+            Self::Trait => "trait",
+            Self::Impl => "impl",
+            Self::Type => "type",
+            Self::Constant => "constant",
+            Self::Variable => "variable",
+            Self::Field => "field",
+            Self::Module => "module",
+            Self::Macro => "macro",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Symbol {
+    name: String,
+    kind: SymbolKind,
+    /// Name of the enclosing symbol, if any.
+    container: Option<String>,
+    /// Nesting depth among symbols; top-level symbols are at depth 0.
+    depth: usize,
+    /// 1-based, inclusive.
+    start_line: usize,
+    end_line: usize,
+    /// The first line of the definition.
+    signature: String,
+}
+
+/// A 1-based line and column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Default)]
+struct FileSymbols {
+    /// Definitions in source order.
+    outline: Vec<Symbol>,
+    /// Positions of every identifier, by name.
+    identifiers: HashMap<String, Vec<Position>>,
+}
+
+fn parse_symbols(language: SourceLanguage, source: &[u8]) -> anyhow::Result<FileSymbols> {
+    let mut parser = Parser::new();
+    parser.set_language(&language.grammar())?;
+    let tree = parser.parse(source, None).context("failed to parse file")?;
+
+    let mut symbols = FileSymbols::default();
+    // Enclosing symbols, as indices into `outline` with the tree depth of
+    // their node.
+    let mut parents: Vec<(usize, usize)> = Vec::new();
+    let mut cursor = tree.walk();
+    let mut depth = 0;
+    loop {
+        let node = cursor.node();
+        if let Some(kind) = language.definition_kind(node)
+            && let Some(name) = language.definition_name(node, kind, source)
+        {
+            let parent = parents.last().map(|(index, _)| &symbols.outline[*index]);
+            let kind = match parent {
+                Some(parent) if kind == SymbolKind::Function && parent.kind.has_methods() => {
+                    SymbolKind::Method
+                }
+                _ => kind,
+            };
+            let signature = node
+                .utf8_text(source)
+                .ok()
+                .and_then(|text| text.lines().next())
+                .map(truncate_line)
+                .unwrap_or_default();
+            symbols.outline.push(Symbol {
+                name,
+                kind,
+                container: parent.map(|parent| parent.name.clone()),
+                depth: parents.len(),
+                start_line: node.start_position().row + 1,
+                end_line: node.end_position().row + 1,
+                signature,
+            });
+            parents.push((symbols.outline.len() - 1, depth));
+        } else if node.child_count() == 0
+            && node.kind().ends_with("identifier")
+            && let Ok(name) = node.utf8_text(source)
+        {
+            let start = node.start_position();
+            symbols
+                .identifiers
+                .entry(name.to_string())
+                .or_default()
+                .push(Position {
+                    line: start.row + 1,
+                    column: start.column + 1,
+                });
+        }
+
+        if cursor.goto_first_child() {
+            depth += 1;
+            continue;
+        }
+        loop {
+            while parents
+                .last()
+                .is_some_and(|(_, parent_depth)| *parent_depth >= depth)
+            {
+                parents.pop();
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+            if !cursor.goto_parent() {
+                return Ok(symbols);
+            }
+            depth -= 1;
+        }
+    }
+}
+
+struct CachedFile {
+    modified: Option<SystemTime>,
+    len: u64,
+    last_used: u64,
+    symbols: Arc<FileSymbols>,
+}
+
+#[derive(Default)]
+struct SymbolCache {
+    files: HashMap<PathBuf, CachedFile>,
+    clock: u64,
+}
+
+static SYMBOL_CACHE: LazyLock<StdMutex<SymbolCache>> =
+    LazyLock::new(|| StdMutex::new(SymbolCache::default()));
+
+/// Returns the symbols in `path`, reparsing it only if its modification time
+/// or size changed since it was cached. Returns `None` for unsupported
+/// languages.
+fn cached_symbols(path: &Path) -> anyhow::Result<Option<Arc<FileSymbols>>> {
+    let Some(language) = SourceLanguage::from_path(path) else {
+        return Ok(None);
+    };
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("unable to access `{}`", path.display()))?;
+    let modified = metadata.modified().ok();
+    {
+        let mut cache = SYMBOL_CACHE
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        cache.clock += 1;
+        let clock = cache.clock;
+        if let Some(cached) = cache.files.get_mut(path)
+            && cached.modified == modified
+            && cached.len == metadata.len()
+        {
+            cached.last_used = clock;
+            return Ok(Some(Arc::clone(&cached.symbols)));
+        }
+    }
+
+    // Parse without holding the lock so files are parsed in parallel.
+    let source =
+        std::fs::read(path).with_context(|| format!("unable to read `{}`", path.display()))?;
+    let symbols = Arc::new(parse_symbols(language, &source)?);
+
+    let mut cache = SYMBOL_CACHE
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    if cache.files.len() >= MAX_CACHED_FILES
+        && let Some(oldest) = cache
+            .files
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(path, _)| path.clone())
+    {
+        cache.files.remove(&oldest);
+    }
+    let last_used = cache.clock;
+    cache.files.insert(
+        path.to_path_buf(),
+        CachedFile {
+            modified,
+            len: metadata.len(),
+            last_used,
+            symbols: Arc::clone(&symbols),
+        },
+    );
+    Ok(Some(symbols))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn outline(language: SourceLanguage, source: &str) -> Vec<(String, String, Option<String>)> {
+        parse_symbols(language, source.as_bytes())
+            .expect("parse")
+            .outline
+            .into_iter()
+            .map(|symbol| (symbol.kind.to_string(), symbol.name, symbol.container))
+            .collect()
+    }
+
+    fn entry(kind: &str, name: &str, container: Option<&str>) -> (String, String, Option<String>) {
+        (
+            kind.to_string(),
+            name.to_string(),
+            container.map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn outlines_rust_items_and_methods() {
+        let source = r#"
+pub struct Config {
+    name: String,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        todo!()
+    }
+}
+
+trait Load {
+    fn load(&self);
+}
+
+fn helper() {}
+"#;
+        assert_eq!(
+            outline(SourceLanguage::Rust, source),
+            vec![
+                entry("struct", "Config", None),
+                entry("field", "name", Some("Config")),
+                entry("impl", "Config", None),
+                entry("method", "new", Some("Config")),
+                entry("trait", "Load", None),
+                entry("method", "load", Some("Load")),
+                entry("function", "helper", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn outlines_python_typescript_go_and_java() {
+        assert_eq!(
+            outline(
+                SourceLanguage::Python,
+                "LIMIT = 3\n\nclass Greeter:\n    def greet(self):\n        pass\n"
+            ),
+            vec![
+                entry("variable", "LIMIT", None),
+                entry("class", "Greeter", None),
+                entry("method", "greet", Some("Greeter")),
+            ]
+        );
+        assert_eq!(
+            outline(
+                SourceLanguage::TypeScript,
+                "interface Shape { area(): number }\nexport const make = () => 1;\nclass Box { size = 1; grow() {} }\n"
+            ),
+            vec![
+                entry("interface", "Shape", None),
+                entry("method", "area", Some("Shape")),
+                entry("function", "make", None),
+                entry("class", "Box", None),
+                entry("field", "size", Some("Box")),
+                entry("method", "grow", Some("Box")),
+            ]
+        );
+        assert_eq!(
+            outline(
+                SourceLanguage::Go,
+                "package main\n\ntype Server struct{}\n\nfunc (s *Server) Run() {}\n\nfunc main() {}\n"
+            ),
+            vec![
+                entry("struct", "Server", None),
+                entry("method", "Run", None),
+                entry("function", "main", None),
+            ]
+        );
+        assert_eq!(
+            outline(
+                SourceLanguage::Java,
+                "class App {\n  private int count;\n  App() {}\n  void run() {}\n}\n"
+            ),
+            vec![
+                entry("class", "App", None),
+                entry("field", "count", Some("App")),
+                entry("constructor", "App", Some("App")),
+                entry("method", "run", Some("App")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_qualified_symbol_targets() {
+        assert_eq!(
+            SymbolTarget::parse("Config::new").expect("parse"),
+            SymbolTarget {
+                container: Some("Config".to_string()),
+                name: "new".to_string(),
+            }
+        );
+        assert_eq!(
+            SymbolTarget::parse(" run ").expect("parse"),
+            SymbolTarget {
+                container: None,
+                name: "run".to_string(),
+            }
+        );
+        assert!(SymbolTarget::parse("two words").is_err());
+        assert!(SymbolTarget::parse("Config::").is_err());
+    }
+
+    #[test]
+    fn finds_definitions_and_references_across_files() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let lib = dir.path().join("lib.rs");
+        let main = dir.path().join("main.py");
+        std::fs::write(
+            &lib,
+            "pub struct Config;\n\nimpl Config {\n    pub fn load() -> Config {\n        Config\n    }\n}\n",
+        )?;
+        std::fs::write(&main, "from lib import load\n\nload()\n")?;
+        std::fs::write(dir.path().join("notes.txt"), "load Config\n")?;
+        let cancel_flag = Arc::new(AtomicBool::new(false));
+
+        let definitions = find_definitions(
+            &SymbolTarget::parse("Config::load").expect("parse"),
+            dir.path().to_path_buf(),
+            10,
+            Arc::clone(&cancel_flag),
+        )?;
+        assert_eq!(
+            definitions,
+            vec![format!(
+                "{}:4-6: method load in Config\n    pub fn load() -> Config {{",
+                lib.display()
+            )]
+        );
+
+        let references = find_references(
+            &SymbolTarget::parse("load").expect("parse"),
+            dir.path().to_path_buf(),
+            10,
+            cancel_flag,
+        )?;
+        assert_eq!(
+            references,
+            vec![
+                format!("{}:4:12: pub fn load() -> Config {{", lib.display()),
+                format!("{}:1:17: from lib import load", main.display()),
+                format!("{}:3:1: load()", main.display()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reparses_files_only_when_they_change() -> anyhow::Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("mod.rs");
+        std::fs::write(&path, "fn first() {}\n")?;
+
+        let before = cached_symbols(&path)?.expect("rust is supported");
+        let again = cached_symbols(&path)?.expect("rust is supported");
+        assert!(Arc::ptr_eq(&before, &again));
+
+        std::fs::write(&path, "fn first() {}\nfn second() {}\n")?;
+        let after = cached_symbols(&path)?.expect("rust is supported");
+        assert_eq!(
+            after
+                .outline
+                .iter()
+                .map(|symbol| symbol.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+        assert!(file_outline(&dir.path().join("notes.txt")).is_err());
+        Ok(())
+    }
+}
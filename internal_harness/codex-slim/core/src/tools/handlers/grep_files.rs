@@ -1,13 +1,30 @@
+use codex_file_search::ContentSearch;
+use codex_file_search::ContentSearchOptions;
+use codex_file_search::FileSearchOptions;
+use codex_file_search::TrigramIndex;
+use codex_file_search::is_in_git_dir;
+use codex_file_search::search_content;
 use codex_protocol::models::FunctionCallOutputBody;
+use std::collections::HashMap;
+use std::num::NonZero;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::Mutex as StdMutex;
+use std::sync::TryLockError;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use async_trait::async_trait;
 use serde::Deserialize;
-use tokio::process::Command;
 use tokio::time::timeout;
+use tracing::warn;
 
 use crate::function_tool::FunctionCallError;
+use crate::git_info::get_git_repo_root;
 use crate::tools::context::ToolInvocation;
 use crate::tools::context::ToolOutput;
 use crate::tools::context::ToolPayload;
@@ -19,7 +36,8 @@ pub struct GrepFilesHandler;
 
 const DEFAULT_LIMIT: usize = 100;
 const MAX_LIMIT: usize = 2000;
-const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_SEARCH_THREADS: usize = 8;
 
 fn default_limit() -> usize {
     DEFAULT_LIMIT
@@ -82,8 +100,14 @@ impl ToolHandler for GrepFilesHandler {
             }
         });
 
-        let search_results =
-            run_rg_search(pattern, include.as_deref(), &search_path, limit, &turn.cwd).await?;
+        let search_results = run_search(
+            pattern,
+            include.as_deref(),
+            &search_path,
+            limit,
+            Some(&turn.config.codex_home),
+        )
+        .await?;
 
         if search_results.is_empty() {
             Ok(ToolOutput::Function {
@@ -106,108 +130,249 @@ async fn verify_path_exists(path: &Path) -> Result<(), FunctionCallError> {
     Ok(())
 }
 
-async fn run_rg_search(
+async fn run_search(
     pattern: &str,
     include: Option<&str>,
     search_path: &Path,
     limit: usize,
-    cwd: &Path,
+    codex_home: Option<&Path>,
 ) -> Result<Vec<String>, FunctionCallError> {
-    let mut command = Command::new("rg");
-    command
-        .current_dir(cwd)
-        .arg("--files-with-matches")
-        .arg("--sortr=modified")
-        .arg("--regexp")
-        .arg(pattern)
-        .arg("--no-messages");
-
-    if let Some(glob) = include {
-        command.arg("--glob").arg(glob);
-    }
-
-    command.arg("--").arg(search_path);
-
-    let output = timeout(COMMAND_TIMEOUT, command.output())
-        .await
-        .map_err(|_| {
-            FunctionCallError::RespondToModel("rg timed out after 30 seconds".to_string())
-        })?
-        .map_err(|err| {
-            FunctionCallError::RespondToModel(format!(
-                "failed to launch rg: {err}. Ensure ripgrep is installed and on PATH."
+    let pattern = pattern.to_string();
+    let include = include.map(str::to_string).into_iter().collect();
+    let root = search_path.to_path_buf();
+    let codex_home = codex_home.map(Path::to_path_buf);
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let worker_cancel_flag = cancel_flag.clone();
+    let search = tokio::task::spawn_blocking(move || {
+        search_files_with_matches(
+            &pattern,
+            include,
+            root,
+            limit,
+            codex_home,
+            worker_cancel_flag,
+        )
+    });
+
+    match timeout(SEARCH_TIMEOUT, search).await {
+        Ok(Ok(result)) => result.map_err(|err| {
+            FunctionCallError::RespondToModel(format!("grep_files failed: {err:#}"))
+        }),
+        Ok(Err(err)) => Err(FunctionCallError::RespondToModel(format!(
+            "grep_files failed: {err}"
+        ))),
+        Err(_) => {
+            cancel_flag.store(true, Ordering::Relaxed);
+            Err(FunctionCallError::RespondToModel(
+                "search timed out after 30 seconds".to_string(),
             ))
-        })?;
-
-    match output.status.code() {
-        Some(0) => Ok(parse_results(&output.stdout, limit)),
-        Some(1) => Ok(Vec::new()),
-        _ => {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(FunctionCallError::RespondToModel(format!(
-                "rg failed: {stderr}"
-            )))
         }
     }
 }
 
-fn parse_results(stdout: &[u8], limit: usize) -> Vec<String> {
-    let mut results = Vec::new();
-    for line in stdout.split(|byte| *byte == b'\n') {
-        if line.is_empty() {
-            continue;
-        }
-        if let Ok(text) = std::str::from_utf8(line) {
-            if text.is_empty() {
-                continue;
-            }
-            results.push(text.to_string());
-            if results.len() == limit {
-                break;
+/// Persistent content index for one repository root. It is built in the
+/// background on first use and refreshed before each later one; searches that
+/// find it building or in use by another search walk the files instead.
+#[derive(Default)]
+enum SearchIndexState {
+    #[default]
+    Unopened,
+    Building,
+    Ready(TrigramIndex),
+}
+
+type SearchIndexSlot = Arc<StdMutex<SearchIndexState>>;
+
+/// Search indexes by repository root. The map lock is only held to look up a
+/// root's slot, so building or refreshing one repository's index never blocks
+/// searches in another.
+static SEARCH_INDEXES: LazyLock<StdMutex<HashMap<PathBuf, SearchIndexSlot>>> =
+    LazyLock::new(|| StdMutex::new(HashMap::new()));
+
+/// Returns up to `limit` files under `root` containing a match for `pattern`,
+/// most recently modified first. When `codex_home` is set and `root` is in a
+/// git repository, the repository's trigram index narrows the files read.
+fn search_files_with_matches(
+    pattern: &str,
+    include: Vec<String>,
+    root: PathBuf,
+    limit: usize,
+    codex_home: Option<PathBuf>,
+    cancel_flag: Arc<AtomicBool>,
+) -> anyhow::Result<Vec<String>> {
+    // Index globs are matched relative to the repository root, so only
+    // basename globs mean the same thing there as relative to `root`.
+    let repo_root = codex_home
+        .as_ref()
+        .filter(|_| root.is_dir() && include.iter().all(|glob| !glob.contains('/')))
+        .and_then(|_| get_git_repo_root(&root));
+    let options = ContentSearchOptions {
+        include,
+        // Every matching file is needed to sort by modification time.
+        max_matches: NonZero::<usize>::MAX,
+        first_match_per_file: true,
+        threads: search_threads(),
+        ..Default::default()
+    };
+    let indexed = match (codex_home, repo_root) {
+        (Some(codex_home), Some(repo_root)) => search_with_index(
+            pattern,
+            &codex_home,
+            &repo_root,
+            options.clone(),
+            &cancel_flag,
+        )
+        .inspect_err(|err| {
+            warn!(
+                "search index for {} unavailable: {err:#}",
+                repo_root.display()
+            );
+        })
+        .ok()
+        .flatten(),
+        _ => None,
+    };
+    let search = match indexed {
+        Some(search) => search,
+        None => search_content(pattern, vec![root.clone()], options, Some(cancel_flag))?,
+    };
+    let mut files: Vec<(Option<SystemTime>, PathBuf)> = search
+        .map(|content_match| content_match.full_path())
+        // The index never covers git's own files; keep the fallback walk
+        // consistent with it.
+        .filter(|path| path.starts_with(&root) && !is_in_git_dir(path))
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (modified, path)
+        })
+        .collect();
+    files.sort_by(|(a_modified, a_path), (b_modified, b_path)| {
+        b_modified.cmp(a_modified).then_with(|| a_path.cmp(b_path))
+    });
+    Ok(files
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Searches with the index for `repo_root`, or returns `None` if it is not
+/// ready yet or another search is using it. The first call starts building
+/// the index on a background thread.
+fn search_with_index(
+    pattern: &str,
+    codex_home: &Path,
+    repo_root: &Path,
+    options: ContentSearchOptions,
+    cancel_flag: &Arc<AtomicBool>,
+) -> anyhow::Result<Option<ContentSearch>> {
+    let slot = Arc::clone(
+        SEARCH_INDEXES
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .entry(repo_root.to_path_buf())
+            .or_default(),
+    );
+    let mut state = match slot.try_lock() {
+        Ok(state) => state,
+        Err(TryLockError::Poisoned(err)) => err.into_inner(),
+        Err(TryLockError::WouldBlock) => return Ok(None),
+    };
+    match &mut *state {
+        SearchIndexState::Ready(index) => {
+            // An index that fails to refresh, including when the search is
+            // cancelled, is dropped and rebuilt on the next use.
+            if let Err(err) = index.refresh(Some(Arc::clone(cancel_flag))) {
+                *state = SearchIndexState::Unopened;
+                return Err(err);
             }
+            index
+                .search(pattern, options, Some(Arc::clone(cancel_flag)))
+                .map(Some)
+        }
+        SearchIndexState::Building => Ok(None),
+        SearchIndexState::Unopened => {
+            *state = SearchIndexState::Building;
+            drop(state);
+            let index_options = FileSearchOptions {
+                threads: options.threads,
+                ..Default::default()
+            };
+            spawn_index_build(
+                slot,
+                codex_home.to_path_buf(),
+                repo_root.to_path_buf(),
+                index_options,
+            );
+            Ok(None)
         }
     }
-    results
+}
+
+/// Opens the index for `repo_root` on a background thread and stores it in
+/// `slot`. The build outlives the search that started it, so it is not tied
+/// to that search's cancellation.
+fn spawn_index_build(
+    slot: SearchIndexSlot,
+    codex_home: PathBuf,
+    repo_root: PathBuf,
+    index_options: FileSearchOptions,
+) {
+    std::thread::spawn(move || {
+        let opened = TrigramIndex::open(&codex_home, &repo_root, &index_options, None);
+        let mut state = slot
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        *state = match opened {
+            Ok(index) => SearchIndexState::Ready(index),
+            Err(err) => {
+                warn!(
+                    "failed to build search index for {}: {err:#}",
+                    repo_root.display()
+                );
+                SearchIndexState::Unopened
+            }
+        };
+    });
+}
+
+fn search_threads() -> NonZero<usize> {
+    let available = std::thread::available_parallelism().map_or(1, NonZero::get);
+    NonZero::new(available.min(MAX_SEARCH_THREADS)).unwrap_or(NonZero::<usize>::MIN)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::process::Command as StdCommand;
+    use std::time::Duration;
     use tempfile::tempdir;
 
-    #[test]
-    fn parses_basic_results() {
-        let stdout = b"/tmp/file_a.rs\n/tmp/file_b.rs\n";
-        let parsed = parse_results(stdout, 10);
-        assert_eq!(
-            parsed,
-            vec!["/tmp/file_a.rs".to_string(), "/tmp/file_b.rs".to_string()]
-        );
-    }
-
-    #[test]
-    fn parse_truncates_after_limit() {
-        let stdout = b"/tmp/file_a.rs\n/tmp/file_b.rs\n/tmp/file_c.rs\n";
-        let parsed = parse_results(stdout, 2);
-        assert_eq!(
-            parsed,
-            vec!["/tmp/file_a.rs".to_string(), "/tmp/file_b.rs".to_string()]
-        );
+    /// Waits for the background build of the index for `repo` to finish.
+    async fn wait_for_index(repo: &Path) -> SearchIndexSlot {
+        let repo_root = get_git_repo_root(repo).expect("repo root");
+        for _ in 0..500 {
+            let slot = SEARCH_INDEXES.lock().unwrap().get(&repo_root).cloned();
+            if let Some(slot) = slot
+                && matches!(*slot.lock().unwrap(), SearchIndexState::Ready(_))
+            {
+                return slot;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("search index for {} was not built", repo_root.display());
     }
 
     #[tokio::test]
     async fn run_search_returns_results() -> anyhow::Result<()> {
-        if !rg_available() {
-            return Ok(());
-        }
         let temp = tempdir().expect("create temp dir");
         let dir = temp.path();
         std::fs::write(dir.join("match_one.txt"), "alpha beta gamma").unwrap();
         std::fs::write(dir.join("match_two.txt"), "alpha delta").unwrap();
         std::fs::write(dir.join("other.txt"), "omega").unwrap();
 
-        let results = run_rg_search("alpha", None, dir, 10, dir).await?;
+        let results = run_search("alpha", None, dir, 10, None).await?;
         assert_eq!(results.len(), 2);
         assert!(results.iter().any(|path| path.ends_with("match_one.txt")));
         assert!(results.iter().any(|path| path.ends_with("match_two.txt")));
@@ -216,15 +381,12 @@ mod tests {
 
     #[tokio::test]
     async fn run_search_with_glob_filter() -> anyhow::Result<()> {
-        if !rg_available() {
-            return Ok(());
-        }
         let temp = tempdir().expect("create temp dir");
         let dir = temp.path();
         std::fs::write(dir.join("match_one.rs"), "alpha beta gamma").unwrap();
         std::fs::write(dir.join("match_two.txt"), "alpha delta").unwrap();
 
-        let results = run_rg_search("alpha", Some("*.rs"), dir, 10, dir).await?;
+        let results = run_search("alpha", Some("*.rs"), dir, 10, None).await?;
         assert_eq!(results.len(), 1);
         assert!(results.iter().all(|path| path.ends_with("match_one.rs")));
         Ok(())
@@ -232,39 +394,174 @@ mod tests {
 
     #[tokio::test]
     async fn run_search_respects_limit() -> anyhow::Result<()> {
-        if !rg_available() {
-            return Ok(());
-        }
         let temp = tempdir().expect("create temp dir");
         let dir = temp.path();
         std::fs::write(dir.join("one.txt"), "alpha one").unwrap();
         std::fs::write(dir.join("two.txt"), "alpha two").unwrap();
         std::fs::write(dir.join("three.txt"), "alpha three").unwrap();
 
-        let results = run_rg_search("alpha", None, dir, 2, dir).await?;
+        let results = run_search("alpha", None, dir, 2, None).await?;
         assert_eq!(results.len(), 2);
         Ok(())
     }
 
     #[tokio::test]
     async fn run_search_handles_no_matches() -> anyhow::Result<()> {
-        if !rg_available() {
-            return Ok(());
-        }
         let temp = tempdir().expect("create temp dir");
         let dir = temp.path();
         std::fs::write(dir.join("one.txt"), "omega").unwrap();
 
-        let results = run_rg_search("alpha", None, dir, 5, dir).await?;
+        let results = run_search("alpha", None, dir, 5, None).await?;
         assert!(results.is_empty());
         Ok(())
     }
 
-    fn rg_available() -> bool {
-        StdCommand::new("rg")
-            .arg("--version")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false)
+    #[tokio::test]
+    async fn run_search_sorts_by_modified_time() -> anyhow::Result<()> {
+        let temp = tempdir().expect("create temp dir");
+        let dir = temp.path();
+        let older = dir.join("older.txt");
+        let newer = dir.join("newer.txt");
+        std::fs::write(&older, "alpha").unwrap();
+        std::fs::write(&newer, "alpha").unwrap();
+        let now = SystemTime::now();
+        std::fs::File::options()
+            .write(true)
+            .open(&older)?
+            .set_modified(now - Duration::from_secs(60))?;
+        std::fs::File::options()
+            .write(true)
+            .open(&newer)?
+            .set_modified(now)?;
+
+        let results = run_search("alpha", None, dir, 10, None).await?;
+        assert_eq!(
+            results,
+            vec![
+                newer.to_string_lossy().into_owned(),
+                older.to_string_lossy().into_owned()
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_search_uses_repository_index() -> anyhow::Result<()> {
+        let codex_home = tempdir().expect("create codex home");
+        let temp = tempdir().expect("create temp dir");
+        let repo = temp.path();
+        std::fs::create_dir_all(repo.join(".git"))?;
+        std::fs::create_dir_all(repo.join("sub"))?;
+        std::fs::write(repo.join("top.txt"), "alpha top")?;
+        std::fs::write(repo.join("sub/inner.rs"), "alpha inner")?;
+        std::fs::write(repo.join("sub/other.txt"), "alpha other")?;
+
+        // The first search walks the files while the index is built.
+        for _ in 0..2 {
+            let results = run_search(
+                "alpha",
+                Some("*.rs"),
+                &repo.join("sub"),
+                10,
+                Some(codex_home.path()),
+            )
+            .await?;
+            assert_eq!(
+                results,
+                vec![repo.join("sub/inner.rs").to_string_lossy().into_owned()]
+            );
+            wait_for_index(repo).await;
+        }
+
+        std::fs::write(repo.join("sub/inner.rs"), "omega")?;
+        let results = run_search("alpha", None, repo, 10, Some(codex_home.path())).await?;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|path| !path.ends_with("inner.rs")));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrent_searches_share_repository_index() -> anyhow::Result<()> {
+        let codex_home = tempdir().expect("create codex home");
+        let temp = tempdir().expect("create temp dir");
+        let repo = temp.path();
+        std::fs::create_dir_all(repo.join(".git"))?;
+        std::fs::write(repo.join("one.txt"), "alpha one")?;
+        std::fs::write(repo.join("two.txt"), "omega two")?;
+        let expected = vec![repo.join("one.txt").to_string_lossy().into_owned()];
+
+        // Once while the index is being built and once after it is ready.
+        for _ in 0..2 {
+            let (first, second) = tokio::join!(
+                run_search("alpha", None, repo, 10, Some(codex_home.path())),
+                run_search("alpha", None, repo, 10, Some(codex_home.path())),
+            );
+            assert_eq!(first?, expected);
+            assert_eq!(second?, expected);
+            wait_for_index(repo).await;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_search_walks_files_while_index_is_busy() -> anyhow::Result<()> {
+        let codex_home = tempdir().expect("create codex home");
+        let temp = tempdir().expect("create temp dir");
+        let repo = temp.path();
+        std::fs::create_dir_all(repo.join(".git"))?;
+        std::fs::write(repo.join("one.txt"), "alpha one")?;
+        run_search("alpha", None, repo, 10, Some(codex_home.path())).await?;
+        let slot = wait_for_index(repo).await;
+
+        std::fs::write(repo.join("two.txt"), "alpha two")?;
+        let _busy = slot.lock().unwrap();
+        let mut results = search_files_with_matches(
+            "alpha",
+            Vec::new(),
+            repo.to_path_buf(),
+            10,
+            Some(codex_home.path().to_path_buf()),
+            Arc::new(AtomicBool::new(false)),
+        )?;
+        results.sort();
+        assert_eq!(
+            results,
+            vec![
+                repo.join("one.txt").to_string_lossy().into_owned(),
+                repo.join("two.txt").to_string_lossy().into_owned(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_search_skips_git_dir_without_index() -> anyhow::Result<()> {
+        let temp = tempdir().expect("create temp dir");
+        let repo = temp.path();
+        std::fs::create_dir_all(repo.join(".git/refs"))?;
+        std::fs::write(repo.join(".git/refs/alpha"), "alpha ref")?;
+        std::fs::write(repo.join("top.txt"), "alpha top")?;
+
+        let results = run_search("alpha", None, repo, 10, None).await?;
+        assert_eq!(
+            results,
+            vec![repo.join("top.txt").to_string_lossy().into_owned()]
+        );
+        let results = run_search("alpha", None, &repo.join(".git"), 10, None).await?;
+        assert!(results.is_empty(), "{results:?}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn run_search_reports_invalid_patterns() {
+        let temp = tempdir().expect("create temp dir");
+
+        let err = run_search("(unclosed", None, temp.path(), 5, None)
+            .await
+            .expect_err("invalid regex should fail");
+        assert!(
+            matches!(&err, FunctionCallError::RespondToModel(message) if message.starts_with("grep_files failed")),
+            "{err:?}"
+        );
     }
 }
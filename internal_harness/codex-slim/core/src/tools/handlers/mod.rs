@@ -1,4 +1,5 @@
 pub mod apply_patch;
+mod code_navigation;
 mod dynamic;
 mod grep_files;
 mod js_repl;
@@ -20,6 +21,10 @@ use serde::Deserialize;
 
 use crate::function_tool::FunctionCallError;
 pub use apply_patch::ApplyPatchHandler;
+pub use code_navigation::CodeNavigationHandler;
+pub(crate) use code_navigation::FILE_OUTLINE_TOOL_NAME;
+pub(crate) use code_navigation::FIND_DEFINITION_TOOL_NAME;
+pub(crate) use code_navigation::FIND_REFERENCES_TOOL_NAME;
 pub use dynamic::DynamicToolHandler;
 pub use grep_files::GrepFilesHandler;
 pub use js_repl::JsReplHandler;
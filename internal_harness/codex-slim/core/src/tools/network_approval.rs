@@ -116,7 +116,7 @@ fn allows_network_prompt(policy: AskForApproval) -> bool {
 impl PendingApprovalDecision {
     fn to_network_decision(self) -> NetworkDecision {
         match self {
-            Self::AllowOnce | Self::AllowForSession => NetworkDecision::Allow,
+            Self::AllowOnce | Self::AllowForSession => NetworkDecision::allow(),
             Self::Deny => NetworkDecision::deny("not_allowed"),
         }
     }
@@ -289,7 +289,7 @@ impl NetworkApprovalService {
         {
             let approved_hosts = self.session_approved_hosts.lock().await;
             if approved_hosts.contains(&key) {
-                return NetworkDecision::Allow;
+                return NetworkDecision::allow();
             }
         }
 
@@ -616,11 +616,11 @@ mod tests {
     fn allow_once_and_allow_for_session_both_allow_network() {
         assert_eq!(
             PendingApprovalDecision::AllowOnce.to_network_decision(),
-            NetworkDecision::Allow
+            NetworkDecision::allow()
         );
         assert_eq!(
             PendingApprovalDecision::AllowForSession.to_network_decision(),
-            NetworkDecision::Allow
+            NetworkDecision::allow()
         );
     }
 
@@ -642,6 +642,7 @@ mod tests {
             protocol: "http".to_string(),
             decision: Some("deny".to_string()),
             source: Some("decider".to_string()),
+            rule: None,
             port: Some(80),
         })
     }
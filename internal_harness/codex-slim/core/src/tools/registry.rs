@@ -15,13 +15,17 @@ use crate::tools::context::ToolPayload;
 use async_trait::async_trait;
 use codex_hooks::HookEvent;
 use codex_hooks::HookEventAfterToolUse;
+use codex_hooks::HookEventBeforeToolUse;
 use codex_hooks::HookPayload;
 use codex_hooks::HookResult;
 use codex_hooks::HookToolInput;
 use codex_hooks::HookToolInputLocalShell;
+use codex_hooks::HookToolIntervention;
 use codex_hooks::HookToolKind;
 use codex_protocol::models::ResponseInputItem;
+use codex_protocol::models::ShellToolCallParams;
 use codex_utils_readiness::Readiness;
+use tracing::info;
 use tracing::warn;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -78,7 +82,7 @@ impl ToolRegistry {
 
     pub async fn dispatch(
         &self,
-        invocation: ToolInvocation,
+        mut invocation: ToolInvocation,
     ) -> Result<ResponseInputItem, FunctionCallError> {
         let tool_name = invocation.tool_name.clone();
         let call_id_owned = invocation.call_id.clone();
@@ -154,9 +158,45 @@ impl ToolRegistry {
             return Err(FunctionCallError::Fatal(message));
         }
 
+        let requested_mutating = handler.is_mutating(&invocation).await;
+        let decision = dispatch_before_tool_use_hook(&invocation, requested_mutating).await?;
+        if let Some(message) = decision.denial {
+            otel.tool_result_with_tags(
+                tool_name.as_ref(),
+                &call_id_owned,
+                log_payload.as_ref(),
+                Duration::ZERO,
+                false,
+                &message,
+                &metric_tags,
+                mcp_server_ref,
+                mcp_server_origin_ref,
+            );
+            if let Some(err) = dispatch_after_tool_use_hook(AfterToolUseHookDispatch {
+                invocation: &invocation,
+                output_preview: message.clone(),
+                success: false,
+                executed: false,
+                duration: Duration::ZERO,
+                mutating: requested_mutating,
+            })
+            .await
+            {
+                return Err(err);
+            }
+            return Err(FunctionCallError::RespondToModel(message));
+        }
+        if let Some(tool_input) = decision.replacement {
+            invocation.payload = tool_payload_from_hook_input(&invocation.payload, tool_input)?;
+        }
+        let payload_for_response = invocation.payload.clone();
+        let log_payload = payload_for_response.log_payload();
+
+        // A rewritten input may change whether the call mutates the environment.
         let is_mutating = handler.is_mutating(&invocation).await;
         let output_cell = tokio::sync::Mutex::new(None);
         let invocation_for_tool = invocation.clone();
+        let secret_redactor = Arc::clone(&invocation.session.services.secret_redactor);
 
         let started = Instant::now();
         let result = otel
@@ -170,6 +210,7 @@ impl ToolRegistry {
                 || {
                     let handler = handler.clone();
                     let output_cell = &output_cell;
+                    let secret_redactor = &secret_redactor;
                     async move {
                         if is_mutating {
                             tracing::trace!("waiting for tool gate");
@@ -177,7 +218,16 @@ impl ToolRegistry {
                             tracing::trace!("tool gate released");
                         }
                         match handler.handle(invocation_for_tool).await {
-                            Ok(output) => {
+                            Ok(mut output) => {
+                                // Redact before the preview so telemetry and hooks
+                                // never see the raw secret either.
+                                for hit in output.redact_secrets(secret_redactor) {
+                                    info!(
+                                        rule_id = %hit.rule_id,
+                                        count = hit.count,
+                                        "redacted secrets from tool output"
+                                    );
+                                }
                                 let preview = output.log_preview();
                                 let success = output.success_for_logging();
                                 let mut guard = output_cell.lock().await;
@@ -216,6 +266,11 @@ impl ToolRegistry {
                 let output = guard.take().ok_or_else(|| {
                     FunctionCallError::Fatal("tool produced no output".to_string())
                 })?;
+                let output = if decision.additional_context.is_empty() {
+                    output
+                } else {
+                    output.with_additional_context(&decision.additional_context.join("\n\n"))
+                };
                 Ok(output.into_response(&call_id_owned, &payload_for_response))
             }
             Err(err) => Err(err),
@@ -306,7 +361,7 @@ fn unsupported_tool_call_message(payload: &ToolPayload, tool_name: &str) -> Stri
     }
 }
 
-fn sandbox_policy_tag(policy: &SandboxPolicy) -> &'static str {
+pub(crate) fn sandbox_policy_tag(policy: &SandboxPolicy) -> &'static str {
     match policy {
         SandboxPolicy::ReadOnly { .. } => "read-only",
         SandboxPolicy::WorkspaceWrite { .. } => "workspace-write",
@@ -349,6 +404,51 @@ impl From<&ToolPayload> for HookToolInput {
     }
 }
 
+/// Rebuilds a tool payload from a hook-rewritten input. Hooks may change the
+/// arguments of a call but not its kind or, for MCP calls, its target.
+fn tool_payload_from_hook_input(
+    original: &ToolPayload,
+    tool_input: HookToolInput,
+) -> Result<ToolPayload, FunctionCallError> {
+    match (original, tool_input) {
+        (ToolPayload::Function { .. }, HookToolInput::Function { arguments }) => {
+            Ok(ToolPayload::Function { arguments })
+        }
+        (ToolPayload::Custom { .. }, HookToolInput::Custom { input }) => {
+            Ok(ToolPayload::Custom { input })
+        }
+        (ToolPayload::LocalShell { .. }, HookToolInput::LocalShell { params }) => {
+            Ok(ToolPayload::LocalShell {
+                params: ShellToolCallParams {
+                    command: params.command,
+                    workdir: params.workdir,
+                    timeout_ms: params.timeout_ms,
+                    sandbox_permissions: params.sandbox_permissions,
+                    prefix_rule: params.prefix_rule,
+                    justification: params.justification,
+                },
+            })
+        }
+        (
+            ToolPayload::Mcp { server, tool, .. },
+            HookToolInput::Mcp {
+                server: new_server,
+                tool: new_tool,
+                arguments,
+            },
+        ) if *server == new_server && *tool == new_tool => Ok(ToolPayload::Mcp {
+            server: new_server,
+            tool: new_tool,
+            raw_arguments: arguments,
+        }),
+        (_, tool_input) => Err(FunctionCallError::Fatal(format!(
+            "before_tool_use hook replaced {:?} tool input with an incompatible {:?} input",
+            hook_tool_kind(&HookToolInput::from(original)),
+            hook_tool_kind(&tool_input)
+        ))),
+    }
+}
+
 fn hook_tool_kind(tool_input: &HookToolInput) -> HookToolKind {
     match tool_input {
         HookToolInput::Function { .. } => HookToolKind::Function,
@@ -358,6 +458,90 @@ fn hook_tool_kind(tool_input: &HookToolInput) -> HookToolKind {
     }
 }
 
+/// What the `before_tool_use` hooks decided about a call.
+#[derive(Default)]
+struct BeforeToolUseDecision {
+    denial: Option<String>,
+    replacement: Option<HookToolInput>,
+    additional_context: Vec<String>,
+}
+
+async fn dispatch_before_tool_use_hook(
+    invocation: &ToolInvocation,
+    mutating: bool,
+) -> Result<BeforeToolUseDecision, FunctionCallError> {
+    let session = invocation.session.as_ref();
+    let turn = invocation.turn.as_ref();
+    let tool_input = HookToolInput::from(&invocation.payload);
+    let hook_outcomes = session
+        .hooks()
+        .dispatch(HookPayload {
+            session_id: session.conversation_id,
+            cwd: turn.cwd.clone(),
+            triggered_at: chrono::Utc::now(),
+            hook_event: HookEvent::BeforeToolUse {
+                event: HookEventBeforeToolUse {
+                    turn_id: turn.sub_id.clone(),
+                    call_id: invocation.call_id.clone(),
+                    tool_name: invocation.tool_name.clone(),
+                    tool_kind: hook_tool_kind(&tool_input),
+                    tool_input,
+                    mutating,
+                    sandbox: sandbox_tag(
+                        &turn.sandbox_policy,
+                        turn.windows_sandbox_level,
+                        turn.features.enabled(Feature::UseLinuxSandboxBwrap),
+                    )
+                    .to_string(),
+                    sandbox_policy: sandbox_policy_tag(&turn.sandbox_policy).to_string(),
+                },
+            },
+        })
+        .await;
+
+    let mut decision = BeforeToolUseDecision::default();
+    for hook_outcome in hook_outcomes {
+        let hook_name = hook_outcome.hook_name;
+        match hook_outcome.result {
+            HookResult::Success => {}
+            HookResult::Intervene(HookToolIntervention::Deny { message }) => {
+                decision.denial = Some(message);
+            }
+            HookResult::Intervene(HookToolIntervention::ReplaceInput { tool_input }) => {
+                decision.replacement = Some(tool_input);
+            }
+            HookResult::Intervene(HookToolIntervention::AddContext { context }) => {
+                decision.additional_context.push(context);
+            }
+            // Command hooks only fail open when configured with `fail_open`; a
+            // failed policy hook otherwise denies the call.
+            HookResult::FailedContinue(error) => {
+                warn!(
+                    call_id = %invocation.call_id,
+                    tool_name = %invocation.tool_name,
+                    hook_name = %hook_name,
+                    error = %error,
+                    "before_tool_use hook failed; continuing because it is configured to fail open"
+                );
+            }
+            HookResult::FailedAbort(error) => {
+                warn!(
+                    call_id = %invocation.call_id,
+                    tool_name = %invocation.tool_name,
+                    hook_name = %hook_name,
+                    error = %error,
+                    "before_tool_use hook failed; aborting operation"
+                );
+                return Err(FunctionCallError::Fatal(format!(
+                    "before_tool_use hook '{hook_name}' failed and aborted operation: {error}"
+                )));
+            }
+        }
+    }
+
+    Ok(decision)
+}
+
 struct AfterToolUseHookDispatch<'a> {
     invocation: &'a ToolInvocation,
     output_preview: String,
@@ -407,7 +591,8 @@ async fn dispatch_after_tool_use_hook(
     for hook_outcome in hook_outcomes {
         let hook_name = hook_outcome.hook_name;
         match hook_outcome.result {
-            HookResult::Success => {}
+            // Interventions only apply before the tool runs.
+            HookResult::Success | HookResult::Intervene(_) => {}
             HookResult::FailedContinue(error) => {
                 warn!(
                     call_id = %invocation.call_id,
@@ -434,3 +619,52 @@ async fn dispatch_after_tool_use_hook(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hook_rewrites_keep_the_payload_kind() {
+        let original = ToolPayload::Function {
+            arguments: r#"{"cmd":"make migrate"}"#.to_string(),
+        };
+
+        let rewritten = tool_payload_from_hook_input(
+            &original,
+            HookToolInput::Function {
+                arguments: r#"{"cmd":"make test"}"#.to_string(),
+            },
+        )
+        .expect("same-kind rewrite");
+        assert_eq!(rewritten.log_payload(), r#"{"cmd":"make test"}"#);
+
+        let mismatched = tool_payload_from_hook_input(
+            &original,
+            HookToolInput::Custom {
+                input: "patch".to_string(),
+            },
+        );
+        assert!(matches!(mismatched, Err(FunctionCallError::Fatal(_))));
+    }
+
+    #[test]
+    fn hook_rewrites_cannot_retarget_mcp_calls() {
+        let original = ToolPayload::Mcp {
+            server: "docs".to_string(),
+            tool: "search".to_string(),
+            raw_arguments: "{}".to_string(),
+        };
+
+        let retargeted = tool_payload_from_hook_input(
+            &original,
+            HookToolInput::Mcp {
+                server: "shell".to_string(),
+                tool: "exec".to_string(),
+                arguments: "{}".to_string(),
+            },
+        );
+        assert!(matches!(retargeted, Err(FunctionCallError::Fatal(_))));
+    }
+}
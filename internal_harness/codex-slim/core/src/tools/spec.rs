@@ -6,6 +6,9 @@ use crate::config::AgentRoleConfig;
 use crate::features::Feature;
 use crate::features::Features;
 use crate::mcp_connection_manager::ToolInfo;
+use crate::tools::handlers::FILE_OUTLINE_TOOL_NAME;
+use crate::tools::handlers::FIND_DEFINITION_TOOL_NAME;
+use crate::tools::handlers::FIND_REFERENCES_TOOL_NAME;
 use crate::tools::handlers::PLAN_TOOL;
 use crate::tools::handlers::SEARCH_TOOL_BM25_DEFAULT_LIMIT;
 use crate::tools::handlers::SEARCH_TOOL_BM25_TOOL_NAME;
@@ -42,6 +45,7 @@ pub(crate) struct ToolsConfig {
     pub search_tool: bool,
     pub js_repl_enabled: bool,
     pub js_repl_tools_only: bool,
+    pub code_navigation: bool,
     pub collab_tools: bool,
     pub collaboration_modes_tools: bool,
     pub experimental_supported_tools: Vec<String>,
@@ -64,6 +68,7 @@ impl ToolsConfig {
         let include_js_repl = features.enabled(Feature::JsRepl);
         let include_js_repl_tools_only =
             include_js_repl && features.enabled(Feature::JsReplToolsOnly);
+        let include_code_navigation = features.enabled(Feature::CodeNavigation);
         let include_collab_tools = features.enabled(Feature::Collab);
         let include_collaboration_modes_tools = true;
         let include_search_tool = features.enabled(Feature::Apps);
@@ -104,6 +109,7 @@ impl ToolsConfig {
             search_tool: include_search_tool,
             js_repl_enabled: include_js_repl,
             js_repl_tools_only: include_js_repl_tools_only,
+            code_navigation: include_code_navigation,
             collab_tools: include_collab_tools,
             collaboration_modes_tools: include_collaboration_modes_tools,
             experimental_supported_tools: model_info.experimental_supported_tools.clone(),
@@ -889,6 +895,85 @@ fn create_grep_files_tool() -> ToolSpec {
     })
 }
 
+fn create_symbol_search_tool(name: &str, description: &str) -> ToolSpec {
+    let properties = BTreeMap::from([
+        (
+            "symbol".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Symbol name, optionally qualified by its enclosing type (e.g. \"Config\" or \
+                     \"Config::load\")."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "path".to_string(),
+            JsonSchema::String {
+                description: Some(
+                    "Directory or file path to search. Defaults to the session's working directory."
+                        .to_string(),
+                ),
+            },
+        ),
+        (
+            "limit".to_string(),
+            JsonSchema::Number {
+                description: Some("Maximum number of results to return (defaults to 50).".to_string()),
+            },
+        ),
+    ]);
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: name.to_string(),
+        description: description.to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["symbol".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
+fn create_find_definition_tool() -> ToolSpec {
+    create_symbol_search_tool(
+        FIND_DEFINITION_TOOL_NAME,
+        "Finds where a function, type, method or other symbol is defined in Rust, TypeScript, \
+         JavaScript, Python, Go or Java sources.",
+    )
+}
+
+fn create_find_references_tool() -> ToolSpec {
+    create_symbol_search_tool(
+        FIND_REFERENCES_TOOL_NAME,
+        "Lists every identifier occurrence of a symbol in Rust, TypeScript, JavaScript, Python, \
+         Go or Java sources, including its definitions.",
+    )
+}
+
+fn create_file_outline_tool() -> ToolSpec {
+    let properties = BTreeMap::from([(
+        "file_path".to_string(),
+        JsonSchema::String {
+            description: Some("Path to the source file to outline.".to_string()),
+        },
+    )]);
+
+    ToolSpec::Function(ResponsesApiTool {
+        name: FILE_OUTLINE_TOOL_NAME.to_string(),
+        description: "Lists the symbols defined in a source file with their kinds, nesting and \
+                      line ranges."
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["file_path".to_string()]),
+            additional_properties: Some(false.into()),
+        },
+    })
+}
+
 fn create_search_tool_bm25_tool(app_tools: &HashMap<String, ToolInfo>) -> ToolSpec {
     let properties = BTreeMap::from([
         (
@@ -1430,6 +1515,7 @@ pub(crate) fn build_specs(
     dynamic_tools: &[DynamicToolSpec],
 ) -> ToolRegistryBuilder {
     use crate::tools::handlers::ApplyPatchHandler;
+    use crate::tools::handlers::CodeNavigationHandler;
     use crate::tools::handlers::DynamicToolHandler;
     use crate::tools::handlers::GrepFilesHandler;
     use crate::tools::handlers::JsReplHandler;
@@ -1571,6 +1657,16 @@ pub(crate) fn build_specs(
         builder.register_handler("list_dir", list_dir_handler);
     }
 
+    if config.code_navigation {
+        let code_navigation_handler = Arc::new(CodeNavigationHandler);
+        builder.push_spec_with_parallel_support(create_find_definition_tool(), true);
+        builder.push_spec_with_parallel_support(create_find_references_tool(), true);
+        builder.push_spec_with_parallel_support(create_file_outline_tool(), true);
+        builder.register_handler(FIND_DEFINITION_TOOL_NAME, code_navigation_handler.clone());
+        builder.register_handler(FIND_REFERENCES_TOOL_NAME, code_navigation_handler.clone());
+        builder.register_handler(FILE_OUTLINE_TOOL_NAME, code_navigation_handler);
+    }
+
     if config
         .experimental_supported_tools
         .contains(&"test_sync_tool".to_string())
@@ -1928,6 +2024,43 @@ mod tests {
         assert_contains_tool_names(&tools, &["js_repl", "js_repl_reset"]);
     }
 
+    #[test]
+    fn code_navigation_tools_are_feature_gated() {
+        let config = test_config();
+        let model_info =
+            ModelsManager::construct_model_info_offline_for_tests("gpt-5-codex", &config);
+        let mut features = Features::with_defaults();
+        let tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_info: &model_info,
+            features: &features,
+            web_search_mode: Some(WebSearchMode::Cached),
+        });
+        let (tools, _) = build_specs(&tools_config, None, None, &[]).build();
+        assert!(
+            !tools
+                .iter()
+                .any(|tool| tool.spec.name() == FIND_DEFINITION_TOOL_NAME),
+            "code navigation tools should be disabled when the feature is off"
+        );
+
+        features.enable(Feature::CodeNavigation);
+        let tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_info: &model_info,
+            features: &features,
+            web_search_mode: Some(WebSearchMode::Cached),
+        });
+        let (tools, _) = build_specs(&tools_config, None, None, &[]).build();
+        assert_contains_tool_names(
+            &tools,
+            &[
+                FIND_DEFINITION_TOOL_NAME,
+                FIND_REFERENCES_TOOL_NAME,
+                FILE_OUTLINE_TOOL_NAME,
+            ],
+        );
+        assert!(find_tool(&tools, FILE_OUTLINE_TOOL_NAME).supports_parallel_tool_calls);
+    }
+
     #[test]
     fn js_repl_freeform_grammar_blocks_common_non_js_prefixes() {
         let ToolSpec::Freeform(FreeformTool { format, .. }) = create_js_repl_tool() else {
@@ -108,6 +108,9 @@ pub enum Command {
 
     /// Run a code review against the current repository.
     Review(ReviewArgs),
+
+    /// Run the ordered stages described by a pipeline manifest in one process.
+    Pipeline(PipelineArgs),
 }
 
 #[derive(Args, Debug)]
@@ -207,7 +210,7 @@ pub struct ReviewArgs {
     #[arg(
         long = "uncommitted",
         default_value_t = false,
-        conflicts_with_all = ["base", "commit", "prompt"]
+        conflicts_with_all = ["base", "commit", "range", "prompt"]
     )]
     pub uncommitted: bool,
 
@@ -215,7 +218,7 @@ pub struct ReviewArgs {
     #[arg(
         long = "base",
         value_name = "BRANCH",
-        conflicts_with_all = ["uncommitted", "commit", "prompt"]
+        conflicts_with_all = ["uncommitted", "commit", "range", "prompt"]
     )]
     pub base: Option<String>,
 
@@ -223,7 +226,7 @@ pub struct ReviewArgs {
     #[arg(
         long = "commit",
         value_name = "SHA",
-        conflicts_with_all = ["uncommitted", "base", "prompt"]
+        conflicts_with_all = ["uncommitted", "base", "range", "prompt"]
     )]
     pub commit: Option<String>,
 
@@ -231,11 +234,45 @@ pub struct ReviewArgs {
     #[arg(long = "title", value_name = "TITLE", requires = "commit")]
     pub commit_title: Option<String>,
 
+    /// Review what HEAD_SHA would merge into BASE_SHA, like a pull request.
+    #[arg(
+        long = "range",
+        value_name = "BASE_SHA..HEAD_SHA",
+        conflicts_with_all = ["uncommitted", "base", "commit", "prompt"]
+    )]
+    pub range: Option<String>,
+
+    /// Only review paths matching this glob. May be repeated.
+    #[arg(long = "include", value_name = "GLOB", requires = "range")]
+    pub include_paths: Vec<String>,
+
+    /// Skip paths matching this glob, e.g. vendored or generated code. May be repeated.
+    #[arg(long = "exclude", value_name = "GLOB", requires = "range")]
+    pub exclude_paths: Vec<String>,
+
+    /// Review state file for incremental reviews. When it exists, only the hunks
+    /// changed since the commit it records are reviewed and findings in untouched
+    /// code are carried forward; it is then rewritten with the new state.
+    #[arg(long = "state", value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    pub state_file: Option<PathBuf>,
+
+    /// Format of the review findings. Non-text formats are written to the
+    /// `--output-last-message` file when given, otherwise to stdout.
+    #[arg(long = "format", value_enum, default_value_t = ReviewOutputFormat::Text)]
+    pub format: ReviewOutputFormat,
+
     /// Custom review instructions. If `-` is used, read from stdin.
     #[arg(value_name = "PROMPT", value_hint = clap::ValueHint::Other)]
     pub prompt: Option<String>,
 }
 
+#[derive(Parser, Debug)]
+pub struct PipelineArgs {
+    /// Path to the TOML manifest that lists the stages to run.
+    #[arg(value_name = "MANIFEST", value_hint = clap::ValueHint::FilePath)]
+    pub manifest: PathBuf,
+}
+
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
 #[value(rename_all = "kebab-case")]
 pub enum Color {
@@ -245,6 +282,20 @@ pub enum Color {
     Auto,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "kebab-case")]
+pub enum ReviewOutputFormat {
+    /// The reviewer's final message as plain text.
+    #[default]
+    Text,
+    /// SARIF 2.1.0 log for code-scanning tools.
+    Sarif,
+    /// Annotations array for the GitHub Checks API.
+    GithubAnnotations,
+    /// reviewdog diagnostics, one JSON object per line.
+    Rdjsonl,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -302,4 +353,89 @@ mod tests {
         assert_eq!(args.session_id.as_deref(), Some("session-123"));
         assert_eq!(args.prompt.as_deref(), Some(PROMPT));
     }
+
+    #[test]
+    fn review_parses_range_with_path_filters() {
+        let cli = Cli::parse_from([
+            "codex-exec",
+            "review",
+            "--range",
+            "abc123..def456",
+            "--include",
+            "src/**",
+            "--exclude",
+            "vendor/**",
+            "--exclude",
+            "**/*.generated.ts",
+        ]);
+
+        let Some(Command::Review(args)) = cli.command else {
+            panic!("expected review command");
+        };
+        assert_eq!(args.range.as_deref(), Some("abc123..def456"));
+        assert_eq!(args.include_paths, vec!["src/**".to_string()]);
+        assert_eq!(
+            args.exclude_paths,
+            vec!["vendor/**".to_string(), "**/*.generated.ts".to_string()]
+        );
+    }
+
+    #[test]
+    fn review_parses_format_with_output_file() {
+        let cli = Cli::parse_from([
+            "codex-exec",
+            "review",
+            "--format",
+            "sarif",
+            "-o",
+            "findings.sarif",
+            "--uncommitted",
+        ]);
+
+        assert_eq!(cli.last_message_file, Some(PathBuf::from("findings.sarif")));
+        let Some(Command::Review(args)) = cli.command else {
+            panic!("expected review command");
+        };
+        assert_eq!(args.format, ReviewOutputFormat::Sarif);
+        assert!(args.uncommitted);
+        assert_eq!(args.state_file, None);
+    }
+
+    #[test]
+    fn review_parses_state_file() {
+        let cli = Cli::parse_from([
+            "codex-exec",
+            "review",
+            "--range",
+            "main..feature",
+            "--state",
+            ".codex/review-state.json",
+        ]);
+
+        let Some(Command::Review(args)) = cli.command else {
+            panic!("expected review command");
+        };
+        assert_eq!(
+            args.state_file,
+            Some(PathBuf::from(".codex/review-state.json"))
+        );
+    }
+
+    #[test]
+    fn pipeline_parses_manifest_with_global_flags() {
+        let cli = Cli::parse_from([
+            "codex-exec",
+            "pipeline",
+            "--json",
+            "--skip-git-repo-check",
+            "pr-review.toml",
+        ]);
+
+        assert!(cli.json);
+        assert!(cli.skip_git_repo_check);
+        let Some(Command::Pipeline(args)) = cli.command else {
+            panic!("expected pipeline command");
+        };
+        assert_eq!(args.manifest, PathBuf::from("pr-review.toml"));
+    }
 }
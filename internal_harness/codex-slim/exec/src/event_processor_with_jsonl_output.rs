@@ -26,6 +26,7 @@ use crate::exec_events::McpToolCallStatus;
 use crate::exec_events::PatchApplyStatus;
 use crate::exec_events::PatchChangeKind;
 use crate::exec_events::ReasoningItem;
+use crate::exec_events::StagedThreadEvent;
 use crate::exec_events::ThreadErrorEvent;
 use crate::exec_events::ThreadEvent;
 use crate::exec_events::ThreadItem;
@@ -58,6 +59,8 @@ use tracing::warn;
 
 pub struct EventProcessorWithJsonOutput {
     last_message_path: Option<PathBuf>,
+    // Pipeline stage name attached to every emitted event, if any.
+    stage: Option<String>,
     last_proposed_plan: Option<String>,
     next_event_id: AtomicU64,
     // Tracks running commands by call_id, including the associated item id.
@@ -103,6 +106,7 @@ impl EventProcessorWithJsonOutput {
     pub fn new(last_message_path: Option<PathBuf>) -> Self {
         Self {
             last_message_path,
+            stage: None,
             last_proposed_plan: None,
             next_event_id: AtomicU64::new(0),
             running_commands: HashMap::new(),
@@ -116,6 +120,12 @@ impl EventProcessorWithJsonOutput {
         }
     }
 
+    /// Tags every event emitted by this processor with a pipeline stage name.
+    pub fn with_stage(mut self, stage: String) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
     pub fn collect_thread_events(&mut self, event: &protocol::Event) -> Vec<ThreadEvent> {
         match &event.msg {
             protocol::EventMsg::SessionConfigured(ev) => self.handle_session_configured(ev),
@@ -187,6 +197,7 @@ impl EventProcessorWithJsonOutput {
                 vec![ThreadEvent::Error(ThreadErrorEvent { message })]
             }
             protocol::EventMsg::PlanUpdate(ev) => self.handle_plan_update(ev),
+            protocol::EventMsg::ExitedReviewMode(ev) => self.handle_exited_review_mode(ev),
             _ => Vec::new(),
         }
     }
@@ -673,6 +684,20 @@ impl EventProcessorWithJsonOutput {
         Vec::new()
     }
 
+    fn handle_exited_review_mode(&self, ev: &protocol::ExitedReviewModeEvent) -> Vec<ThreadEvent> {
+        // An interrupted or failed review exits without output; the error (if any)
+        // is reported through its own event.
+        let Some(review_output) = &ev.review_output else {
+            return Vec::new();
+        };
+        let item = ThreadItem {
+            id: self.get_next_item_id(),
+            details: ThreadItemDetails::ReviewResult(review_output.clone()),
+        };
+
+        vec![ThreadEvent::ItemCompleted(ItemCompletedEvent { item })]
+    }
+
     fn handle_exec_command_end(&mut self, ev: &protocol::ExecCommandEndEvent) -> Vec<ThreadEvent> {
         let Some(RunningCommand {
             command,
@@ -847,7 +872,14 @@ impl EventProcessor for EventProcessorWithJsonOutput {
     fn process_event(&mut self, event: protocol::Event) -> CodexStatus {
         let aggregated = self.collect_thread_events(&event);
         for conv_event in aggregated {
-            match serde_json::to_string(&conv_event) {
+            let line = match &self.stage {
+                Some(stage) => serde_json::to_string(&StagedThreadEvent {
+                    stage: stage.clone(),
+                    event: conv_event,
+                }),
+                None => serde_json::to_string(&conv_event),
+            };
+            match line {
                 Ok(line) => {
                     println!("{line}");
                 }
@@ -878,3 +910,80 @@ impl EventProcessor for EventProcessorWithJsonOutput {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ExitedReviewModeEvent;
+    use codex_protocol::protocol::ReviewCodeLocation;
+    use codex_protocol::protocol::ReviewFinding;
+    use codex_protocol::protocol::ReviewLineRange;
+    use codex_protocol::protocol::ReviewOutputEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn exited_review_mode(review_output: Option<ReviewOutputEvent>) -> protocol::Event {
+        protocol::Event {
+            id: "review".to_string(),
+            msg: protocol::EventMsg::ExitedReviewMode(ExitedReviewModeEvent { review_output }),
+        }
+    }
+
+    #[test]
+    fn exited_review_mode_emits_structured_review_result() {
+        let review_output = ReviewOutputEvent {
+            findings: vec![ReviewFinding {
+                title: "[P1] Guard against empty input".to_string(),
+                body: "`parse` indexes into the slice without checking its length.".to_string(),
+                confidence_score: 0.5,
+                priority: 1,
+                code_location: ReviewCodeLocation {
+                    absolute_file_path: PathBuf::from("/repo/src/parse.rs"),
+                    line_range: ReviewLineRange { start: 10, end: 12 },
+                },
+                fingerprint: None,
+            }],
+            overall_correctness: "patch is incorrect".to_string(),
+            overall_explanation: "One crash on empty input.".to_string(),
+            overall_confidence_score: 0.75,
+        };
+        let mut processor = EventProcessorWithJsonOutput::new(None);
+
+        let events = processor.collect_thread_events(&exited_review_mode(Some(review_output)));
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            serde_json::to_value(&events[0]).expect("serialize review result"),
+            json!({
+                "type": "item.completed",
+                "item": {
+                    "id": "item_0",
+                    "type": "review_result",
+                    "findings": [{
+                        "title": "[P1] Guard against empty input",
+                        "body": "`parse` indexes into the slice without checking its length.",
+                        "confidence_score": 0.5,
+                        "priority": 1,
+                        "code_location": {
+                            "absolute_file_path": "/repo/src/parse.rs",
+                            "line_range": {"start": 10, "end": 12},
+                        },
+                    }],
+                    "overall_correctness": "patch is incorrect",
+                    "overall_explanation": "One crash on empty input.",
+                    "overall_confidence_score": 0.75,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn exited_review_mode_without_output_emits_nothing() {
+        let mut processor = EventProcessorWithJsonOutput::new(None);
+
+        let events = processor.collect_thread_events(&exited_review_mode(None));
+
+        assert_eq!(events, Vec::new());
+    }
+}
@@ -1,4 +1,5 @@
 use codex_protocol::models::WebSearchAction;
+use codex_protocol::protocol::ReviewOutputEvent;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
@@ -36,6 +37,15 @@ pub enum ThreadEvent {
     Error(ThreadErrorEvent),
 }
 
+/// A [`ThreadEvent`] emitted while running `codex exec pipeline`, tagged with
+/// the name of the manifest stage that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
+pub struct StagedThreadEvent {
+    pub stage: String,
+    #[serde(flatten)]
+    pub event: ThreadEvent,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
 pub struct ThreadStartedEvent {
     /// The identified of the new thread. Can be used to resume the thread later.
@@ -123,6 +133,10 @@ pub enum ThreadItemDetails {
     /// Tracks the agent's running to-do list. It starts when the plan is first
     /// issued, updates as steps change state, and completes when the turn ends.
     TodoList(TodoListItem),
+    /// Structured result of a review: every finding with its location, priority
+    /// and confidence, plus the overall verdict. Emitted as a completed item when
+    /// the review finishes.
+    ReviewResult(ReviewOutputEvent),
     /// Describes a non-fatal error surfaced as an item.
     Error(ErrorItem),
 }
@@ -9,10 +9,13 @@ mod event_processor;
 mod event_processor_with_human_output;
 pub mod event_processor_with_jsonl_output;
 pub mod exec_events;
+mod pipeline;
 
 pub use cli::Cli;
 pub use cli::Command;
+pub use cli::PipelineArgs;
 pub use cli::ReviewArgs;
+pub use cli::ReviewOutputFormat;
 use codex_core::AuthManager;
 use codex_core::NewThread;
 use codex_core::ThreadManager;
@@ -23,13 +26,17 @@ use codex_core::config::ConfigBuilder;
 use codex_core::config::ConfigOverrides;
 use codex_core::format_exec_policy_error_with_source;
 use codex_core::git_info::get_git_repo_root;
+use codex_core::git_info::resolve_commit_hash;
 use codex_core::models_manager::manager::RefreshStrategy;
+use codex_core::review_incremental::review_state_revision;
 use codex_protocol::approvals::ElicitationAction;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::protocol::AskForApproval;
 use codex_protocol::protocol::Event;
 use codex_protocol::protocol::EventMsg;
 use codex_protocol::protocol::Op;
+use codex_protocol::protocol::PreviousReview;
+use codex_protocol::protocol::ReviewOutputEvent;
 use codex_protocol::protocol::ReviewRequest;
 use codex_protocol::protocol::ReviewTarget;
 use codex_protocol::protocol::SessionSource;
@@ -40,6 +47,7 @@ use serde_json::Value;
 use std::collections::HashSet;
 use std::io::IsTerminal;
 use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use supports_color::Stream;
@@ -172,8 +180,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     };
 
     let config = ConfigBuilder::default()
-        .cli_overrides(cli_kv_overrides)
-        .harness_overrides(overrides)
+        .cli_overrides(cli_kv_overrides.clone())
+        .harness_overrides(overrides.clone())
         .build()
         .await?;
 
@@ -220,33 +228,11 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         .with(otel_logger_layer)
         .try_init();
 
-    let mut event_processor: Box<dyn EventProcessor> = match json_mode {
-        true => Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone())),
-        _ => Box::new(EventProcessorWithHumanOutput::create_with_ansi(
-            stdout_with_ansi,
-            &config,
-            last_message_file.clone(),
-        )),
-    };
-    let required_mcp_servers: HashSet<String> = config
-        .mcp_servers
-        .get()
-        .iter()
-        .filter(|(_, server)| server.enabled && server.required)
-        .map(|(name, _)| name.clone())
-        .collect();
-
-    let default_cwd = config.cwd.to_path_buf();
-    let default_approval_policy = config.permissions.approval_policy.value();
-    let default_sandbox_policy = config.permissions.sandbox_policy.get();
-    let default_effort = config.model_reasoning_effort;
-    let default_summary = config.model_reasoning_summary;
-
     // When --yolo (dangerously_bypass_approvals_and_sandbox) is set, also skip the git repo check
     // since the user is explicitly running in an externally sandboxed environment.
     if !skip_git_repo_check
         && !dangerously_bypass_approvals_and_sandbox
-        && get_git_repo_root(&default_cwd).is_none()
+        && get_git_repo_root(config.cwd.as_path()).is_none()
     {
         eprintln!("Not inside a trusted directory and --skip-git-repo-check was not specified.");
         std::process::exit(1);
@@ -263,17 +249,54 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         SessionSource::Exec,
         config.model_catalog.clone(),
     ));
-    let default_model = thread_manager
-        .get_models_manager()
-        .get_default_model(&config.model, RefreshStrategy::OnlineIfUncached)
-        .await;
+
+    // Pipelines build a fresh config per stage on top of the same overrides, so
+    // they take over before the primary thread is started.
+    let command = match command {
+        Some(ExecCommand::Pipeline(args)) => {
+            let context = pipeline::PipelineContext {
+                cli_overrides: cli_kv_overrides,
+                harness_overrides: overrides,
+                thread_manager,
+                json_mode,
+                stdout_with_ansi,
+                last_message_file,
+            };
+            return pipeline::run_pipeline(&args.manifest, context).await;
+        }
+        command => command,
+    };
+
+    // Structured review exports take over `--output-last-message` (or stdout), so
+    // the event processor must not write the final message there as well.
+    let (review_export_format, review_state_file) = match command.as_ref() {
+        Some(ExecCommand::Review(args)) => (
+            (args.format != ReviewOutputFormat::Text).then_some(args.format),
+            args.state_file.clone(),
+        ),
+        _ => (None, None),
+    };
+    if review_export_format.is_some() && json_mode && last_message_file.is_none() {
+        anyhow::bail!("--json with a non-text review --format requires --output-last-message");
+    }
+    let processor_last_message_file = match review_export_format {
+        Some(_) => None,
+        None => last_message_file.clone(),
+    };
+
+    let mut event_processor: Box<dyn EventProcessor> = match json_mode {
+        true => Box::new(EventProcessorWithJsonOutput::new(
+            processor_last_message_file,
+        )),
+        _ => Box::new(EventProcessorWithHumanOutput::create_with_ansi(
+            stdout_with_ansi,
+            &config,
+            processor_last_message_file,
+        )),
+    };
 
     // Handle resume subcommand by resolving a rollout path and using explicit resume API.
-    let NewThread {
-        thread_id: primary_thread_id,
-        thread,
-        session_configured,
-    } = if let Some(ExecCommand::Resume(args)) = command.as_ref() {
+    let new_thread = if let Some(ExecCommand::Resume(args)) = command.as_ref() {
         let resume_path = resolve_resume_path(&config, args).await?;
 
         if let Some(path) = resume_path {
@@ -286,9 +309,11 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     } else {
         thread_manager.start_thread(config.clone()).await?
     };
+    let mut review_target = None;
     let (initial_operation, prompt_summary) = match (command, prompt, images) {
         (Some(ExecCommand::Review(review_cli)), _, _) => {
             let review_request = build_review_request(review_cli)?;
+            review_target = Some(review_request.target.clone());
             let summary = codex_core::review_prompts::user_facing_hint(&review_request.target);
             (InitialOperation::Review { review_request }, summary)
         }
@@ -324,6 +349,9 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 prompt_text,
             )
         }
+        (Some(ExecCommand::Pipeline(_)), _, _) => {
+            anyhow::bail!("pipeline manifests are dispatched before the primary thread starts")
+        }
         (None, root_prompt, imgs) => {
             let prompt_text = resolve_prompt(root_prompt);
             let mut items: Vec<UserInput> = imgs
@@ -346,9 +374,91 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
+    let outcome = run_thread(
+        &thread_manager,
+        &config,
+        new_thread,
+        initial_operation,
+        &prompt_summary,
+        event_processor.as_mut(),
+    )
+    .await?;
+    if let (Some(state_file), Some(target), Some(review_output)) = (
+        review_state_file.as_deref(),
+        review_target.as_ref(),
+        outcome.review_output.as_ref(),
+    ) {
+        write_review_state(state_file, target, config.cwd.as_path(), review_output).await?;
+    }
+    match review_export_format {
+        Some(format) => {
+            let repo_root =
+                get_git_repo_root(config.cwd.as_path()).unwrap_or_else(|| config.cwd.to_path_buf());
+            let Some(review_output) = outcome.review_output else {
+                anyhow::bail!("review ended without producing review output; nothing to export");
+            };
+            write_review_export(
+                format,
+                &review_output,
+                &repo_root,
+                last_message_file.as_deref(),
+            )?;
+        }
+        None => event_processor.print_final_output(),
+    }
+    if outcome.error_seen {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// What a thread left behind once [`run_thread`] observed its shutdown.
+struct ThreadOutcome {
+    /// Whether a fatal error was reported while the thread was running.
+    error_seen: bool,
+    /// Final message of the primary thread's most recently completed turn.
+    last_agent_message: Option<String>,
+    /// Structured findings reported when the primary thread left review mode.
+    review_output: Option<ReviewOutputEvent>,
+}
+
+/// Submits `initial_operation` to `new_thread` and feeds its events, plus those of
+/// any sub-agent threads it spawns, to `event_processor` until the thread shuts down.
+async fn run_thread(
+    thread_manager: &Arc<ThreadManager>,
+    config: &Config,
+    new_thread: NewThread,
+    initial_operation: InitialOperation,
+    prompt_summary: &str,
+    event_processor: &mut dyn EventProcessor,
+) -> anyhow::Result<ThreadOutcome> {
+    let NewThread {
+        thread_id: primary_thread_id,
+        thread,
+        session_configured,
+    } = new_thread;
+    let required_mcp_servers: HashSet<String> = config
+        .mcp_servers
+        .get()
+        .iter()
+        .filter(|(_, server)| server.enabled && server.required)
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    let default_cwd = config.cwd.to_path_buf();
+    let default_approval_policy = config.permissions.approval_policy.value();
+    let default_sandbox_policy = config.permissions.sandbox_policy.get();
+    let default_effort = config.model_reasoning_effort;
+    let default_summary = config.model_reasoning_summary;
+    let default_model = thread_manager
+        .get_models_manager()
+        .get_default_model(&config.model, RefreshStrategy::OnlineIfUncached)
+        .await;
+
     // Print the effective configuration and initial request so users can see what Codex
     // is using.
-    event_processor.print_config_summary(&config, &prompt_summary, &session_configured);
+    event_processor.print_config_summary(config, prompt_summary, &session_configured);
 
     info!("Codex initialized with event: {session_configured:?}");
 
@@ -356,7 +466,7 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     let attached_threads = Arc::new(Mutex::new(HashSet::from([primary_thread_id])));
     spawn_thread_listener(primary_thread_id, thread.clone(), tx.clone());
 
-    {
+    let interrupt_task = {
         let thread = thread.clone();
         tokio::spawn(async move {
             if tokio::signal::ctrl_c().await.is_ok() {
@@ -364,11 +474,11 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 // Immediately notify Codex to abort any in-flight task.
                 thread.submit(Op::Interrupt).await.ok();
             }
-        });
-    }
+        })
+    };
 
-    {
-        let thread_manager = Arc::clone(&thread_manager);
+    let thread_created_task = {
+        let thread_manager = Arc::clone(thread_manager);
         let attached_threads = Arc::clone(&attached_threads);
         let tx = tx.clone();
         let mut thread_created_rx = thread_manager.subscribe_thread_created();
@@ -395,8 +505,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                 }
             }
-        });
-    }
+        })
+    };
 
     match initial_operation {
         InitialOperation::UserTurn {
@@ -431,6 +541,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     // Track whether a fatal error was reported by the server so we can
     // exit with a non-zero status for automation-friendly signaling.
     let mut error_seen = false;
+    let mut last_agent_message = None;
+    let mut review_output = None;
     let mut shutdown_requested = false;
     while let Some(envelope) = rx.recv().await {
         let ThreadEventEnvelope {
@@ -470,6 +582,16 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
                 shutdown_requested = true;
             }
         }
+        if thread_id == primary_thread_id
+            && let EventMsg::TurnComplete(ev) = &event.msg
+        {
+            last_agent_message = ev.last_agent_message.clone();
+        }
+        if thread_id == primary_thread_id
+            && let EventMsg::ExitedReviewMode(ev) = &event.msg
+        {
+            review_output = ev.review_output.clone();
+        }
         if thread_id != primary_thread_id && matches!(&event.msg, EventMsg::TurnComplete(_)) {
             continue;
         }
@@ -489,12 +611,15 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
             CodexStatus::Shutdown => continue,
         }
     }
-    event_processor.print_final_output();
-    if error_seen {
-        std::process::exit(1);
-    }
 
-    Ok(())
+    interrupt_task.abort();
+    thread_created_task.abort();
+
+    Ok(ThreadOutcome {
+        error_seen,
+        last_agent_message,
+        review_output,
+    })
 }
 
 fn spawn_thread_listener(
@@ -724,6 +849,14 @@ fn build_review_request(args: ReviewArgs) -> anyhow::Result<ReviewRequest> {
             sha,
             title: args.commit_title,
         }
+    } else if let Some(range) = args.range {
+        let (base_sha, head_sha) = parse_commit_range(&range)?;
+        ReviewTarget::CommitRange {
+            base_sha,
+            head_sha,
+            include_paths: args.include_paths,
+            exclude_paths: args.exclude_paths,
+        }
     } else if let Some(prompt_arg) = args.prompt {
         let prompt = resolve_prompt(Some(prompt_arg)).trim().to_string();
         if prompt.is_empty() {
@@ -734,16 +867,122 @@ fn build_review_request(args: ReviewArgs) -> anyhow::Result<ReviewRequest> {
         }
     } else {
         anyhow::bail!(
-            "Specify --uncommitted, --base, --commit, or provide custom review instructions"
+            "Specify --uncommitted, --base, --commit, --range, or provide custom review instructions"
         );
     };
 
+    let previous_review = match args.state_file.as_deref() {
+        Some(path) => read_review_state(path)?,
+        None => None,
+    };
+
     Ok(ReviewRequest {
         target,
         user_facing_hint: None,
+        previous_review,
     })
 }
 
+/// Loads the state left by an earlier `review --state` run, or `None` when the
+/// file does not exist yet (the first review of a change).
+fn read_review_state(path: &Path) -> anyhow::Result<Option<PreviousReview>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            anyhow::bail!("failed to read review state {}: {err}", path.display())
+        }
+    };
+    let state = serde_json::from_str(&contents)
+        .map_err(|err| anyhow::anyhow!("invalid review state {}: {err}", path.display()))?;
+    Ok(Some(state))
+}
+
+/// Records the reviewed commit (or, for working-tree reviews, the commit their
+/// diff was taken against) and the resulting findings so the next run can
+/// review incrementally.
+async fn write_review_state(
+    path: &Path,
+    target: &ReviewTarget,
+    cwd: &Path,
+    review_output: &ReviewOutputEvent,
+) -> anyhow::Result<()> {
+    let revision = review_state_revision(target, cwd)?;
+    let Some(reviewed_sha) = resolve_commit_hash(cwd, &revision).await else {
+        anyhow::bail!("failed to resolve {revision} for the review state file");
+    };
+    let state = PreviousReview {
+        reviewed_sha,
+        findings: review_output.findings.clone(),
+    };
+    std::fs::write(path, format!("{}\n", serde_json::to_string_pretty(&state)?))
+        .map_err(|err| anyhow::anyhow!("failed to write review state {}: {err}", path.display()))
+}
+
+/// Renders `review_output` in a structured `format`, with finding paths made
+/// relative to `repo_root`.
+fn render_review_export(
+    format: ReviewOutputFormat,
+    review_output: &ReviewOutputEvent,
+    repo_root: &Path,
+) -> anyhow::Result<String> {
+    let rendered = match format {
+        ReviewOutputFormat::Text => {
+            codex_core::review_format::render_review_output_text(review_output)
+        }
+        ReviewOutputFormat::Sarif => serde_json::to_string_pretty(
+            &codex_core::review_format::render_review_output_sarif(review_output, repo_root),
+        )?,
+        ReviewOutputFormat::GithubAnnotations => serde_json::to_string_pretty(
+            &codex_core::review_format::render_review_output_github_annotations(
+                review_output,
+                repo_root,
+            ),
+        )?,
+        ReviewOutputFormat::Rdjsonl => {
+            return Ok(codex_core::review_format::render_review_output_rdjsonl(
+                review_output,
+                repo_root,
+            ));
+        }
+    };
+    Ok(format!("{rendered}\n"))
+}
+
+fn write_review_export(
+    format: ReviewOutputFormat,
+    review_output: &ReviewOutputEvent,
+    repo_root: &Path,
+    output_file: Option<&Path>,
+) -> anyhow::Result<()> {
+    let rendered = render_review_export(format, review_output, repo_root)?;
+    match output_file {
+        Some(path) => std::fs::write(path, rendered).map_err(|err| {
+            anyhow::anyhow!(
+                "failed to write review findings to {}: {err}",
+                path.display()
+            )
+        }),
+        None => {
+            print!("{rendered}");
+            Ok(())
+        }
+    }
+}
+
+/// Splits `BASE..HEAD` (or git's equivalent `BASE...HEAD`) into its two revisions.
+fn parse_commit_range(range: &str) -> anyhow::Result<(String, String)> {
+    let (base, head) = range
+        .split_once("...")
+        .or_else(|| range.split_once(".."))
+        .ok_or_else(|| anyhow::anyhow!("--range must look like BASE_SHA..HEAD_SHA"))?;
+    let (base, head) = (base.trim(), head.trim());
+    if base.is_empty() || head.is_empty() {
+        anyhow::bail!("--range must name both a base and a head revision");
+    }
+    Ok((base.to_string(), head.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -756,6 +995,11 @@ mod tests {
             base: None,
             commit: None,
             commit_title: None,
+            range: None,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            state_file: None,
+            format: ReviewOutputFormat::Text,
             prompt: None,
         })
         .expect("builds uncommitted review request");
@@ -763,6 +1007,7 @@ mod tests {
         let expected = ReviewRequest {
             target: ReviewTarget::UncommittedChanges,
             user_facing_hint: None,
+            previous_review: None,
         };
 
         assert_eq!(request, expected);
@@ -775,6 +1020,11 @@ mod tests {
             base: None,
             commit: Some("123456789".to_string()),
             commit_title: Some("Add review command".to_string()),
+            range: None,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            state_file: None,
+            format: ReviewOutputFormat::Text,
             prompt: None,
         })
         .expect("builds commit review request");
@@ -785,6 +1035,7 @@ mod tests {
                 title: Some("Add review command".to_string()),
             },
             user_facing_hint: None,
+            previous_review: None,
         };
 
         assert_eq!(request, expected);
@@ -797,6 +1048,11 @@ mod tests {
             base: None,
             commit: None,
             commit_title: None,
+            range: None,
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            state_file: None,
+            format: ReviewOutputFormat::Text,
             prompt: Some("  custom review instructions  ".to_string()),
         })
         .expect("builds custom review request");
@@ -806,11 +1062,117 @@ mod tests {
                 instructions: "custom review instructions".to_string(),
             },
             user_facing_hint: None,
+            previous_review: None,
+        };
+
+        assert_eq!(request, expected);
+    }
+
+    #[test]
+    fn builds_commit_range_review_request_with_path_filters() {
+        let request = build_review_request(ReviewArgs {
+            uncommitted: false,
+            base: None,
+            commit: None,
+            commit_title: None,
+            range: Some("abc123...def456".to_string()),
+            include_paths: vec!["src/**".to_string()],
+            exclude_paths: vec!["vendor/**".to_string()],
+            state_file: None,
+            format: ReviewOutputFormat::Text,
+            prompt: None,
+        })
+        .expect("builds commit range review request");
+
+        let expected = ReviewRequest {
+            target: ReviewTarget::CommitRange {
+                base_sha: "abc123".to_string(),
+                head_sha: "def456".to_string(),
+                include_paths: vec!["src/**".to_string()],
+                exclude_paths: vec!["vendor/**".to_string()],
+            },
+            user_facing_hint: None,
+            previous_review: None,
         };
 
         assert_eq!(request, expected);
     }
 
+    #[test]
+    fn parse_commit_range_rejects_missing_head() {
+        let err = parse_commit_range("abc123..").expect_err("missing head should fail");
+
+        assert_eq!(
+            err.to_string(),
+            "--range must name both a base and a head revision"
+        );
+    }
+
+    #[test]
+    fn read_review_state_is_none_for_missing_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+
+        let state = read_review_state(&dir.path().join("state.json")).expect("read state");
+
+        assert_eq!(state, None);
+    }
+
+    #[test]
+    fn builds_review_request_with_previous_review_state() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let state_file = dir.path().join("state.json");
+        std::fs::write(&state_file, r#"{"reviewed_sha": "abc123", "findings": []}"#)
+            .expect("write state");
+
+        let request = build_review_request(ReviewArgs {
+            uncommitted: false,
+            base: None,
+            commit: None,
+            commit_title: None,
+            range: Some("main..feature".to_string()),
+            include_paths: Vec::new(),
+            exclude_paths: Vec::new(),
+            state_file: Some(state_file),
+            format: ReviewOutputFormat::Text,
+            prompt: None,
+        })
+        .expect("builds incremental review request");
+
+        assert_eq!(
+            request.previous_review,
+            Some(PreviousReview {
+                reviewed_sha: "abc123".to_string(),
+                findings: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn render_review_export_rdjsonl_is_empty_without_findings() {
+        let rendered = render_review_export(
+            ReviewOutputFormat::Rdjsonl,
+            &ReviewOutputEvent::default(),
+            Path::new("/repo"),
+        )
+        .expect("renders rdjsonl");
+
+        assert_eq!(rendered, "");
+    }
+
+    #[test]
+    fn render_review_export_sarif_is_valid_json() {
+        let rendered = render_review_export(
+            ReviewOutputFormat::Sarif,
+            &ReviewOutputEvent::default(),
+            Path::new("/repo"),
+        )
+        .expect("renders sarif");
+
+        let value: Value = serde_json::from_str(&rendered).expect("sarif is json");
+        assert_eq!(value["version"], "2.1.0");
+        assert_eq!(value["runs"][0]["results"], serde_json::json!([]));
+    }
+
     #[test]
     fn decode_prompt_bytes_strips_utf8_bom() {
         let input = [0xEF, 0xBB, 0xBF, b'h', b'i', b'\n'];
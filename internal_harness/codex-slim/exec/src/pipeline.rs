@@ -0,0 +1,530 @@
+//! `codex-exec pipeline <manifest>` runs an ordered list of stages in a single
+//! process.
+//!
+//! Each stage starts a fresh thread with its own prompt, model, sandbox mode,
+//! MCP servers and output schema, layered on top of the overrides given on the
+//! command line. Prompts can reference the final message of any earlier stage
+//! with `{{stages.<name>.final_message}}`.
+//!
+//! ```toml
+//! [[stage]]
+//! name = "reviewer"
+//! prompt_file = "prompts/review.md"
+//! sandbox = "read-only"
+//! output_schema = "schemas/review.json"
+//!
+//! [[stage]]
+//! name = "editor"
+//! sandbox = "workspace-write"
+//! prompt = "Address these findings:\n{{stages.reviewer.final_message}}"
+//!
+//! [stage.mcp_servers.docs]
+//! command = "docs-mcp"
+//! ```
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Context;
+use codex_core::ThreadManager;
+use codex_core::config::ConfigBuilder;
+use codex_core::config::ConfigOverrides;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::user_input::UserInput;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::InitialOperation;
+use crate::event_processor::EventProcessor;
+use crate::event_processor_with_human_output::EventProcessorWithHumanOutput;
+use crate::event_processor_with_jsonl_output::EventProcessorWithJsonOutput;
+use crate::run_thread;
+
+const PLACEHOLDER_OPEN: &str = "{{";
+const PLACEHOLDER_CLOSE: &str = "}}";
+
+/// On-disk shape of a pipeline manifest.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct PipelineManifest {
+    #[serde(rename = "stage", default)]
+    stages: Vec<PipelineStageToml>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct PipelineStageToml {
+    name: String,
+    #[serde(default)]
+    prompt: Option<String>,
+    /// Read the prompt from a file, relative to the manifest.
+    #[serde(default)]
+    prompt_file: Option<PathBuf>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    sandbox: Option<SandboxMode>,
+    /// JSON Schema for the stage's final message, relative to the manifest.
+    #[serde(default)]
+    output_schema: Option<PathBuf>,
+    /// Extra MCP servers, using the same shape as `[mcp_servers.<name>]` in config.toml.
+    #[serde(default)]
+    mcp_servers: BTreeMap<String, toml::Value>,
+}
+
+/// A stage whose prompt and output schema have been read from disk and whose
+/// template references have been checked against the stages before it.
+#[derive(Debug, Clone, PartialEq)]
+struct PipelineStage {
+    name: String,
+    prompt: String,
+    model: Option<String>,
+    sandbox: Option<SandboxMode>,
+    output_schema: Option<Value>,
+    mcp_servers: BTreeMap<String, toml::Value>,
+}
+
+/// Process-wide state shared by every stage of a pipeline run.
+pub(crate) struct PipelineContext {
+    /// `-c key=value` overrides from the command line.
+    pub(crate) cli_overrides: Vec<(String, toml::Value)>,
+    /// Overrides derived from the remaining command-line flags.
+    pub(crate) harness_overrides: ConfigOverrides,
+    pub(crate) thread_manager: Arc<ThreadManager>,
+    pub(crate) json_mode: bool,
+    pub(crate) stdout_with_ansi: bool,
+    /// Receives the final message of the last stage.
+    pub(crate) last_message_file: Option<PathBuf>,
+}
+
+pub(crate) async fn run_pipeline(
+    manifest_path: &Path,
+    context: PipelineContext,
+) -> anyhow::Result<()> {
+    let stages = load_manifest(manifest_path)?;
+    let stage_count = stages.len();
+    let mut final_messages: HashMap<String, String> = HashMap::new();
+    let mut error_seen = false;
+
+    for (index, stage) in stages.into_iter().enumerate() {
+        let is_last_stage = index + 1 == stage_count;
+        let config = ConfigBuilder::default()
+            .cli_overrides(stage_cli_overrides(&context.cli_overrides, &stage))
+            .harness_overrides(stage_harness_overrides(&context.harness_overrides, &stage))
+            .build()
+            .await
+            .with_context(|| format!("failed to load config for stage `{}`", stage.name))?;
+        let prompt = render_prompt(&stage.prompt, &final_messages)?;
+        let last_message_file = if is_last_stage {
+            context.last_message_file.clone()
+        } else {
+            None
+        };
+
+        let mut event_processor: Box<dyn EventProcessor> = if context.json_mode {
+            Box::new(
+                EventProcessorWithJsonOutput::new(last_message_file).with_stage(stage.name.clone()),
+            )
+        } else {
+            eprintln!("pipeline stage {}/{stage_count}: {}", index + 1, stage.name);
+            Box::new(EventProcessorWithHumanOutput::create_with_ansi(
+                context.stdout_with_ansi,
+                &config,
+                last_message_file,
+            ))
+        };
+
+        let new_thread = context.thread_manager.start_thread(config.clone()).await?;
+        let initial_operation = InitialOperation::UserTurn {
+            items: vec![UserInput::Text {
+                text: prompt.clone(),
+                // Manifest prompts don't track UI element ranges, so none are available here.
+                text_elements: Vec::new(),
+            }],
+            output_schema: stage.output_schema,
+        };
+        let outcome = run_thread(
+            &context.thread_manager,
+            &config,
+            new_thread,
+            initial_operation,
+            &prompt,
+            event_processor.as_mut(),
+        )
+        .await?;
+
+        if outcome.error_seen {
+            eprintln!(
+                "Pipeline stage `{}` failed; skipping the remaining stages.",
+                stage.name
+            );
+            error_seen = true;
+            break;
+        }
+        if is_last_stage {
+            event_processor.print_final_output();
+        }
+        final_messages.insert(stage.name, outcome.last_agent_message.unwrap_or_default());
+    }
+
+    if error_seen {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn stage_cli_overrides(
+    base: &[(String, toml::Value)],
+    stage: &PipelineStage,
+) -> Vec<(String, toml::Value)> {
+    let mut overrides = base.to_vec();
+    overrides.extend(
+        stage
+            .mcp_servers
+            .iter()
+            .map(|(name, server)| (format!("mcp_servers.{name}"), server.clone())),
+    );
+    overrides
+}
+
+fn stage_harness_overrides(base: &ConfigOverrides, stage: &PipelineStage) -> ConfigOverrides {
+    let mut overrides = base.clone();
+    if let Some(model) = &stage.model {
+        overrides.model = Some(model.clone());
+    }
+    if let Some(sandbox) = stage.sandbox {
+        overrides.sandbox_mode = Some(sandbox);
+    }
+    overrides
+}
+
+/// Reads and validates a manifest, resolving paths relative to its directory.
+fn load_manifest(path: &Path) -> anyhow::Result<Vec<PipelineStage>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read pipeline manifest {}", path.display()))?;
+    let manifest: PipelineManifest = toml::from_str(&contents)
+        .with_context(|| format!("failed to parse pipeline manifest {}", path.display()))?;
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    resolve_stages(manifest, base_dir)
+}
+
+fn resolve_stages(
+    manifest: PipelineManifest,
+    base_dir: &Path,
+) -> anyhow::Result<Vec<PipelineStage>> {
+    if manifest.stages.is_empty() {
+        anyhow::bail!("pipeline manifest must declare at least one [[stage]]");
+    }
+
+    let mut seen_names = HashSet::new();
+    // Stand-ins for the final messages of earlier stages, used to reject
+    // references to unknown or later stages before anything runs.
+    let mut earlier_stages: HashMap<String, String> = HashMap::new();
+    let mut stages = Vec::with_capacity(manifest.stages.len());
+    for stage in manifest.stages {
+        let PipelineStageToml {
+            name,
+            prompt,
+            prompt_file,
+            model,
+            sandbox,
+            output_schema,
+            mcp_servers,
+        } = stage;
+
+        if !is_valid_stage_name(&name) {
+            anyhow::bail!(
+                "invalid stage name `{name}`; use only ASCII letters, digits, `-` and `_`"
+            );
+        }
+        if !seen_names.insert(name.clone()) {
+            anyhow::bail!("duplicate stage name `{name}`");
+        }
+
+        let prompt = match (prompt, prompt_file) {
+            (Some(prompt), None) => prompt,
+            (None, Some(prompt_file)) => {
+                let prompt_path = base_dir.join(prompt_file);
+                std::fs::read_to_string(&prompt_path).with_context(|| {
+                    format!(
+                        "failed to read prompt for stage `{name}` from {}",
+                        prompt_path.display()
+                    )
+                })?
+            }
+            (Some(_), Some(_)) => {
+                anyhow::bail!("stage `{name}` sets both `prompt` and `prompt_file`")
+            }
+            (None, None) => anyhow::bail!("stage `{name}` needs a `prompt` or `prompt_file`"),
+        };
+        if prompt.trim().is_empty() {
+            anyhow::bail!("stage `{name}` has an empty prompt");
+        }
+        render_prompt(&prompt, &earlier_stages)
+            .with_context(|| format!("invalid prompt for stage `{name}`"))?;
+
+        let output_schema = output_schema
+            .map(|schema_path| read_output_schema(&base_dir.join(schema_path)))
+            .transpose()
+            .with_context(|| format!("invalid output schema for stage `{name}`"))?;
+
+        earlier_stages.insert(name.clone(), String::new());
+        stages.push(PipelineStage {
+            name,
+            prompt,
+            model,
+            sandbox,
+            output_schema,
+            mcp_servers,
+        });
+    }
+
+    Ok(stages)
+}
+
+fn read_output_schema(path: &Path) -> anyhow::Result<Value> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read output schema file {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("output schema file {} is not valid JSON", path.display()))
+}
+
+fn is_valid_stage_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Substitutes every `{{stages.<name>.final_message}}` in `template` with the
+/// matching entry of `final_messages`.
+fn render_prompt(
+    template: &str,
+    final_messages: &HashMap<String, String>,
+) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(PLACEHOLDER_OPEN) {
+        rendered.push_str(&rest[..start]);
+        let after_open = &rest[start + PLACEHOLDER_OPEN.len()..];
+        let Some(end) = after_open.find(PLACEHOLDER_CLOSE) else {
+            anyhow::bail!("unterminated `{PLACEHOLDER_OPEN}` in prompt template");
+        };
+        let stage = placeholder_stage(after_open[..end].trim())?;
+        let Some(final_message) = final_messages.get(stage) else {
+            anyhow::bail!("prompt references stage `{stage}`, which does not run before it");
+        };
+        rendered.push_str(final_message);
+        rest = &after_open[end + PLACEHOLDER_CLOSE.len()..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+fn placeholder_stage(expression: &str) -> anyhow::Result<&str> {
+    expression
+        .strip_prefix("stages.")
+        .and_then(|rest| rest.strip_suffix(".final_message"))
+        .filter(|name| is_valid_stage_name(name))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "unsupported template variable `{expression}`; expected `stages.<name>.final_message`"
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec_events::StagedThreadEvent;
+    use crate::exec_events::ThreadEvent;
+    use crate::exec_events::TurnStartedEvent;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn parse(manifest: &str, base_dir: &Path) -> anyhow::Result<Vec<PipelineStage>> {
+        resolve_stages(toml::from_str(manifest)?, base_dir)
+    }
+
+    #[test]
+    fn resolves_stages_relative_to_manifest_dir() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("review.md"), "Review the diff.").expect("write prompt");
+        std::fs::write(dir.path().join("schema.json"), r#"{"type":"object"}"#)
+            .expect("write schema");
+
+        let stages = parse(
+            r#"
+[[stage]]
+name = "reviewer"
+prompt_file = "review.md"
+sandbox = "read-only"
+output_schema = "schema.json"
+
+[stage.mcp_servers.docs]
+command = "docs-mcp"
+
+[[stage]]
+name = "editor"
+model = "gpt-5.2-codex"
+sandbox = "workspace-write"
+prompt = "Fix: {{ stages.reviewer.final_message }}"
+"#,
+            dir.path(),
+        )
+        .expect("valid manifest");
+
+        let mut docs_server = toml::map::Map::new();
+        docs_server.insert(
+            "command".to_string(),
+            toml::Value::String("docs-mcp".to_string()),
+        );
+        assert_eq!(
+            stages,
+            vec![
+                PipelineStage {
+                    name: "reviewer".to_string(),
+                    prompt: "Review the diff.".to_string(),
+                    model: None,
+                    sandbox: Some(SandboxMode::ReadOnly),
+                    output_schema: Some(json!({"type": "object"})),
+                    mcp_servers: BTreeMap::from([(
+                        "docs".to_string(),
+                        toml::Value::Table(docs_server),
+                    )]),
+                },
+                PipelineStage {
+                    name: "editor".to_string(),
+                    prompt: "Fix: {{ stages.reviewer.final_message }}".to_string(),
+                    model: Some("gpt-5.2-codex".to_string()),
+                    sandbox: Some(SandboxMode::WorkspaceWrite),
+                    output_schema: None,
+                    mcp_servers: BTreeMap::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_references_to_later_stages() {
+        let err = parse(
+            r#"
+[[stage]]
+name = "reviewer"
+prompt = "Use {{stages.verifier.final_message}}"
+
+[[stage]]
+name = "verifier"
+prompt = "Verify."
+"#,
+            Path::new(""),
+        )
+        .expect_err("forward reference should be rejected");
+
+        assert_eq!(
+            format!("{err:#}"),
+            "invalid prompt for stage `reviewer`: prompt references stage `verifier`, which does not run before it"
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_stage_names() {
+        let err = parse(
+            r#"
+[[stage]]
+name = "reviewer"
+prompt = "one"
+
+[[stage]]
+name = "reviewer"
+prompt = "two"
+"#,
+            Path::new(""),
+        )
+        .expect_err("duplicate names should be rejected");
+
+        assert_eq!(err.to_string(), "duplicate stage name `reviewer`");
+    }
+
+    #[test]
+    fn render_prompt_substitutes_earlier_final_messages() {
+        let final_messages = HashMap::from([
+            ("reviewer".to_string(), "two findings".to_string()),
+            ("editor".to_string(), "patched".to_string()),
+        ]);
+
+        let rendered = render_prompt(
+            "Review said {{stages.reviewer.final_message}}; editor said {{ stages.editor.final_message }}.",
+            &final_messages,
+        )
+        .expect("render prompt");
+
+        assert_eq!(rendered, "Review said two findings; editor said patched.");
+    }
+
+    #[test]
+    fn render_prompt_rejects_unknown_variables() {
+        let err = render_prompt("{{stages.reviewer.diff}}", &HashMap::new())
+            .expect_err("unknown variable should be rejected");
+
+        assert_eq!(
+            err.to_string(),
+            "unsupported template variable `stages.reviewer.diff`; expected `stages.<name>.final_message`"
+        );
+    }
+
+    #[test]
+    fn stage_overrides_layer_on_top_of_cli_overrides() {
+        let stage = PipelineStage {
+            name: "editor".to_string(),
+            prompt: "Fix it.".to_string(),
+            model: Some("gpt-5.2-codex".to_string()),
+            sandbox: Some(SandboxMode::WorkspaceWrite),
+            output_schema: None,
+            mcp_servers: BTreeMap::from([("docs".to_string(), toml::Value::Boolean(true))]),
+        };
+        let base_cli = vec![(
+            "model_reasoning_effort".to_string(),
+            toml::Value::from("high"),
+        )];
+        let base_harness = ConfigOverrides {
+            model: Some("gpt-5.1".to_string()),
+            sandbox_mode: Some(SandboxMode::ReadOnly),
+            ..Default::default()
+        };
+
+        let cli = stage_cli_overrides(&base_cli, &stage);
+        let harness = stage_harness_overrides(&base_harness, &stage);
+
+        assert_eq!(
+            cli,
+            vec![
+                (
+                    "model_reasoning_effort".to_string(),
+                    toml::Value::from("high")
+                ),
+                ("mcp_servers.docs".to_string(), toml::Value::Boolean(true)),
+            ]
+        );
+        assert_eq!(harness.model.as_deref(), Some("gpt-5.2-codex"));
+        assert_eq!(harness.sandbox_mode, Some(SandboxMode::WorkspaceWrite));
+    }
+
+    #[test]
+    fn staged_events_serialize_with_stage_name() {
+        let event = StagedThreadEvent {
+            stage: "reviewer".to_string(),
+            event: ThreadEvent::TurnStarted(TurnStartedEvent {}),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&event).expect("serialize staged event"),
+            json!({"stage": "reviewer", "type": "turn.started"})
+        );
+    }
+}
@@ -41,6 +41,8 @@ pub enum Error {
     },
     #[error("expected example to not match rule `{rule}`: {example}")]
     ExampleDidMatch { rule: String, example: String },
+    #[error("failed to load policy pack {path}: {message}")]
+    PolicyLoad { path: String, message: String },
     #[error("starlark error: {0}")]
     Starlark(StarlarkError),
 }
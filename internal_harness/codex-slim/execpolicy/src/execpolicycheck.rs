@@ -1,22 +1,70 @@
 use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
 
 use anyhow::Context;
 use anyhow::Result;
+use clap::Args;
 use clap::Parser;
 use serde::Serialize;
 
 use crate::Decision;
+use crate::Evaluation;
 use crate::Policy;
+use crate::PolicyLayer;
 use crate::PolicyParser;
 use crate::RuleMatch;
+use crate::RuleSource;
+
+const REPO_POLICY_DIR: &str = ".codex/policy";
+const RULE_EXTENSION: &str = "rules";
+
+/// Rule files for each policy layer. Layers are loaded from lowest to highest precedence.
+#[derive(Debug, Args, Clone, Default)]
+pub struct PolicyLayerArgs {
+    /// Paths to user execpolicy rule files to evaluate (repeatable).
+    #[arg(short = 'r', long = "rules", value_name = "PATH")]
+    pub rules: Vec<PathBuf>,
+
+    /// Paths to built-in default rule files, the lowest-precedence layer (repeatable).
+    #[arg(long = "default-rules", value_name = "PATH")]
+    pub default_rules: Vec<PathBuf>,
+
+    /// Repository whose checked-in `.codex/policy/*.rules` files form the repo layer.
+    #[arg(long = "repo", value_name = "DIR")]
+    pub repo: Option<PathBuf>,
+
+    /// Paths to admin-enforced rule files, which lower layers cannot loosen (repeatable).
+    #[arg(long = "admin-rules", value_name = "PATH")]
+    pub admin_rules: Vec<PathBuf>,
+}
+
+impl PolicyLayerArgs {
+    pub fn load(&self) -> Result<Policy> {
+        let repo_rules = match &self.repo {
+            Some(repo) => repo_policy_files(repo)?,
+            None => Vec::new(),
+        };
+        let layers = [
+            (PolicyLayer::Default, self.default_rules.clone()),
+            (PolicyLayer::Repo, repo_rules),
+            (PolicyLayer::User, self.rules.clone()),
+            (PolicyLayer::Admin, self.admin_rules.clone()),
+        ];
+        if layers.iter().all(|(_, paths)| paths.is_empty()) {
+            anyhow::bail!(
+                "no policy files given; pass --rules, --default-rules, --repo or --admin-rules"
+            );
+        }
+        load_layered_policies(&layers)
+    }
+}
 
 /// Arguments for evaluating a command against one or more execpolicy files.
 #[derive(Debug, Parser, Clone)]
 pub struct ExecPolicyCheckCommand {
-    /// Paths to execpolicy rule files to evaluate (repeatable).
-    #[arg(short = 'r', long = "rules", value_name = "PATH", required = true)]
-    pub rules: Vec<PathBuf>,
+    #[command(flatten)]
+    pub layers: PolicyLayerArgs,
 
     /// Pretty-print the JSON output.
     #[arg(long)]
@@ -35,20 +83,21 @@ pub struct ExecPolicyCheckCommand {
 impl ExecPolicyCheckCommand {
     /// Load the policies for this command, evaluate the command, and render JSON output.
     pub fn run(&self) -> Result<()> {
-        let policy = load_policies(&self.rules)?;
-        let matched_rules = policy.matches_for_command(&self.command, None);
+        let policy = self.layers.load()?;
+        let evaluation = policy.evaluate(&self.command);
 
-        let json = format_matches_json(&matched_rules, self.pretty)?;
+        let json = format_evaluation_json(evaluation.as_ref(), self.pretty)?;
         println!("{json}");
 
         Ok(())
     }
 }
 
-pub fn format_matches_json(matched_rules: &[RuleMatch], pretty: bool) -> Result<String> {
+pub fn format_evaluation_json(evaluation: Option<&Evaluation>, pretty: bool) -> Result<String> {
     let output = ExecPolicyCheckOutput {
-        matched_rules,
-        decision: matched_rules.iter().map(RuleMatch::decision).max(),
+        matched_rules: evaluation.map_or(&[], |evaluation| &evaluation.matched_rules),
+        decision: evaluation.map(|evaluation| evaluation.decision),
+        decided_by: evaluation.and_then(|evaluation| evaluation.decided_by.as_ref()),
     };
 
     if pretty {
@@ -73,6 +122,52 @@ pub fn load_policies(policy_paths: &[PathBuf]) -> Result<Policy> {
     Ok(parser.build())
 }
 
+/// Loads each layer's policy files in order, tagging every rule with the layer and file that
+/// declared it. `layers` should be ordered from lowest to highest precedence so that network
+/// rules from higher layers are applied last.
+pub fn load_layered_policies(layers: &[(PolicyLayer, Vec<PathBuf>)]) -> Result<Policy> {
+    let mut parser = PolicyParser::new();
+
+    for (layer, policy_paths) in layers {
+        for policy_path in policy_paths {
+            let policy_file_contents = fs::read_to_string(policy_path)
+                .with_context(|| format!("failed to read policy at {}", policy_path.display()))?;
+            let policy_identifier = policy_path.to_string_lossy().to_string();
+            parser
+                .parse_layer(*layer, &policy_identifier, &policy_file_contents)
+                .with_context(|| format!("failed to parse policy at {}", policy_path.display()))?;
+        }
+    }
+
+    Ok(parser.build())
+}
+
+/// Returns the `.codex/policy/*.rules` files checked into `repo_root`, sorted by path.
+pub fn repo_policy_files(repo_root: &Path) -> Result<Vec<PathBuf>> {
+    let policy_dir = repo_root.join(REPO_POLICY_DIR);
+    let entries = match fs::read_dir(&policy_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| {
+                format!("failed to read policy directory {}", policy_dir.display())
+            });
+        }
+    };
+
+    let mut policy_paths = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .with_context(|| format!("failed to read policy directory {}", policy_dir.display()))?;
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == RULE_EXTENSION) && path.is_file() {
+            policy_paths.push(path);
+        }
+    }
+    policy_paths.sort();
+    Ok(policy_paths)
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ExecPolicyCheckOutput<'a> {
@@ -80,4 +175,166 @@ struct ExecPolicyCheckOutput<'a> {
     matched_rules: &'a [RuleMatch],
     #[serde(skip_serializing_if = "Option::is_none")]
     decision: Option<Decision>,
+    #[serde(rename = "decidedBy", skip_serializing_if = "Option::is_none")]
+    decided_by: Option<&'a RuleSource>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn write(path: &Path, contents: &str) -> PathBuf {
+        fs::create_dir_all(path.parent().expect("parent")).expect("create dir");
+        fs::write(path, contents).expect("write policy");
+        path.to_path_buf()
+    }
+
+    fn command(raw: &str) -> Vec<String> {
+        raw.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn source(layer: PolicyLayer, path: &Path) -> Option<RuleSource> {
+        Some(RuleSource {
+            layer,
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    #[test]
+    fn user_overrides_defaults_and_repo_and_admin_only_tighten() {
+        let tmp = tempdir().expect("create temp dir");
+        let defaults = write(
+            &tmp.path().join("defaults.rules"),
+            r#"
+prefix_rule(pattern = ["npm", "publish"], decision = "forbidden")
+prefix_rule(pattern = ["docker"], decision = "forbidden")
+"#,
+        );
+        let repo = tmp.path().join("repo");
+        let repo_rules = write(
+            &repo.join(".codex/policy/npm.rules"),
+            r#"
+prefix_rule(pattern = ["npm", "publish"], decision = "prompt")
+prefix_rule(pattern = ["rm"], decision = "forbidden")
+"#,
+        );
+        write(&repo.join(".codex/policy/notes.txt"), "not a policy");
+        let user = write(
+            &tmp.path().join("user.rules"),
+            r#"
+prefix_rule(pattern = ["curl"])
+prefix_rule(pattern = ["git", "push"], decision = "forbidden")
+prefix_rule(pattern = ["docker"])
+prefix_rule(pattern = ["rm"])
+"#,
+        );
+        let admin = write(
+            &tmp.path().join("admin.rules"),
+            r#"
+prefix_rule(pattern = ["curl"], decision = "prompt")
+prefix_rule(pattern = ["git", "push"], decision = "prompt")
+"#,
+        );
+
+        let policy = PolicyLayerArgs {
+            rules: vec![user.clone()],
+            default_rules: vec![defaults.clone()],
+            repo: Some(repo),
+            admin_rules: vec![admin.clone()],
+        }
+        .load()
+        .expect("load layered policy");
+
+        let decide = |raw: &str| {
+            policy
+                .evaluate(&command(raw))
+                .map(|evaluation| (evaluation.decision, evaluation.decided_by))
+        };
+        // A checked-in repo rule cannot loosen a built-in default...
+        assert_eq!(
+            decide("npm publish"),
+            Some((Decision::Forbidden, source(PolicyLayer::Default, &defaults)))
+        );
+        // ...or a user rule, but it can tighten either.
+        assert_eq!(
+            decide("rm -rf build"),
+            Some((Decision::Forbidden, source(PolicyLayer::Repo, &repo_rules)))
+        );
+        assert_eq!(
+            decide("docker run alpine"),
+            Some((Decision::Allow, source(PolicyLayer::User, &user)))
+        );
+        assert_eq!(
+            decide("curl example.com"),
+            Some((Decision::Prompt, source(PolicyLayer::Admin, &admin)))
+        );
+        assert_eq!(
+            decide("git push origin"),
+            Some((Decision::Forbidden, source(PolicyLayer::User, &user)))
+        );
+        assert_eq!(decide("ls"), None);
+    }
+
+    #[test]
+    fn load_imports_packs_into_the_importing_layer() {
+        let tmp = tempdir().expect("create temp dir");
+        let pack = write(
+            &tmp.path().join("packs/git.rules"),
+            r#"
+prefix_rule(pattern = ["git", "status"])
+
+def git_push_rules(decision):
+    prefix_rule(pattern = ["git", "push"], decision = decision)
+"#,
+        );
+        let user = write(
+            &tmp.path().join("user.rules"),
+            r#"
+load("packs/git.rules", "git_push_rules")
+git_push_rules("prompt")
+"#,
+        );
+        let other = write(
+            &tmp.path().join("other.rules"),
+            r#"load("packs/git.rules", "git_push_rules")"#,
+        );
+
+        let policy = load_layered_policies(&[(PolicyLayer::User, vec![user.clone(), other])])
+            .expect("load policy");
+
+        let pack_source = source(
+            PolicyLayer::User,
+            &pack.canonicalize().expect("canonical pack path"),
+        );
+        let status = policy
+            .evaluate(&command("git status"))
+            .expect("git status matches");
+        assert_eq!(status.decided_by, pack_source);
+        assert_eq!(status.matched_rules.len(), 1);
+        assert_eq!(
+            policy
+                .evaluate(&command("git push"))
+                .and_then(|evaluation| evaluation.decided_by),
+            source(PolicyLayer::User, &user)
+        );
+    }
+
+    #[test]
+    fn load_rejects_cycles() {
+        let tmp = tempdir().expect("create temp dir");
+        let a = write(&tmp.path().join("a.rules"), r#"load("b.rules", "x")"#);
+        write(
+            &tmp.path().join("b.rules"),
+            "load(\"a.rules\", \"y\")\nx = 1",
+        );
+
+        let err =
+            load_layered_policies(&[(PolicyLayer::Repo, vec![a])]).expect_err("cycle should fail");
+        assert!(
+            format!("{err:#}").contains("load() cycle detected"),
+            "unexpected error: {err:#}"
+        );
+    }
 }
@@ -0,0 +1,348 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use clap::Parser;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Decision;
+use crate::Policy;
+use crate::PolicyLayer;
+use crate::RuleSource;
+use crate::execpolicycheck::PolicyLayerArgs;
+use crate::policy::layer_applies;
+use crate::policy::render_pattern_token;
+use crate::rule::PrefixRule;
+use crate::rule::RuleRef;
+
+/// Arguments for running a corpus of commands with expected decisions against execpolicy files.
+#[derive(Debug, Parser, Clone)]
+pub struct ExecPolicyTestCommand {
+    #[command(flatten)]
+    pub layers: PolicyLayerArgs,
+
+    /// JSONL file with one `{"command": ..., "decision": ...}` test case per line.
+    #[arg(value_name = "CORPUS")]
+    pub corpus: PathBuf,
+
+    /// Pretty-print the JSON output.
+    #[arg(long)]
+    pub pretty: bool,
+}
+
+impl ExecPolicyTestCommand {
+    /// Run the corpus against the policies and print the report. Fails if any case mismatches.
+    pub fn run(&self) -> Result<()> {
+        let policy = self.layers.load()?;
+        let cases = load_test_cases(&self.corpus)?;
+        let report = run_policy_tests(&policy, &cases);
+
+        let json = if self.pretty {
+            serde_json::to_string_pretty(&report)?
+        } else {
+            serde_json::to_string(&report)?
+        };
+        println!("{json}");
+
+        if report.mismatches.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!(
+                "{} of {} test cases did not match their expected decision",
+                report.mismatches.len(),
+                report.total_cases
+            )
+        }
+    }
+}
+
+/// A command paired with the decision the policy is expected to produce for it. `None` means no
+/// rule is expected to match.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+pub struct PolicyTestCase {
+    #[serde(deserialize_with = "deserialize_command")]
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub decision: Option<Decision>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyTestReport {
+    pub total_cases: usize,
+    pub mismatches: Vec<PolicyTestMismatch>,
+    /// Rules that no test case matched.
+    pub dead_rules: Vec<RuleSummary>,
+    /// Rules that matched, but never changed the decision because a user rule overrode their
+    /// default layer, or because an earlier rule with the same or stricter decision, or any
+    /// rule with a stricter decision, matched as well.
+    pub shadowed_rules: Vec<RuleSummary>,
+    /// Rules with identical patterns but different decisions.
+    pub conflicts: Vec<PolicyConflict>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyTestMismatch {
+    /// 1-based index of the test case in the corpus.
+    pub case: usize,
+    pub command: Vec<String>,
+    pub expected: Option<Decision>,
+    pub actual: Option<Decision>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<RuleSource>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSummary {
+    pub pattern: Vec<String>,
+    pub decision: Decision,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<RuleSource>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyConflict {
+    pub pattern: Vec<String>,
+    pub decisions: Vec<Decision>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawCommand {
+    Shell(String),
+    Tokens(Vec<String>),
+}
+
+fn deserialize_command<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let tokens = match RawCommand::deserialize(deserializer)? {
+        RawCommand::Shell(raw) => shlex::split(&raw).ok_or_else(|| {
+            serde::de::Error::custom(format!("command has invalid shell syntax: {raw}"))
+        })?,
+        RawCommand::Tokens(tokens) => tokens,
+    };
+    if tokens.is_empty() {
+        Err(serde::de::Error::custom("command cannot be empty"))
+    } else {
+        Ok(tokens)
+    }
+}
+
+/// Reads test cases from a JSONL file, skipping blank lines.
+pub fn load_test_cases(path: &Path) -> Result<Vec<PolicyTestCase>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read test corpus at {}", path.display()))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("invalid test case at {}:{}", path.display(), index + 1))
+        })
+        .collect()
+}
+
+/// Evaluates every test case against `policy` and collects mismatches and rule coverage.
+pub fn run_policy_tests(policy: &Policy, cases: &[PolicyTestCase]) -> PolicyTestReport {
+    let mut rules: Vec<(&str, &[RuleRef])> = policy
+        .rules()
+        .iter_all()
+        .map(|(program, rules)| (program.as_str(), rules.as_slice()))
+        .collect();
+    rules.sort_by_key(|(program, _)| *program);
+    let mut coverage: BTreeMap<(&str, usize), RuleCoverage> = BTreeMap::new();
+    let mut mismatches = Vec::new();
+
+    for (case_index, case) in cases.iter().enumerate() {
+        let program_rules = case
+            .command
+            .first()
+            .and_then(|program| rules.iter().find(|(name, _)| *name == program.as_str()));
+
+        let mut matched: Vec<(&str, usize, Decision, PolicyLayer)> = Vec::new();
+        if let Some((program, program_rules)) = program_rules {
+            for (index, rule) in program_rules.iter().enumerate() {
+                if let Some(rule_match) = rule.matches(&case.command) {
+                    let layer = rule
+                        .source()
+                        .map_or(PolicyLayer::User, |source| source.layer);
+                    matched.push((program, index, rule_match.decision(), layer));
+                }
+            }
+        }
+
+        let evaluation = policy.evaluate(&case.command);
+        let actual = evaluation.as_ref().map(|evaluation| evaluation.decision);
+        let user_matched = matched
+            .iter()
+            .any(|(_, _, _, layer)| *layer == PolicyLayer::User);
+        for (position, (program, index, decision, layer)) in matched.iter().enumerate() {
+            let overridden = !layer_applies(*layer, user_matched)
+                || matched.iter().enumerate().any(
+                    |(other, (_, _, other_decision, other_layer))| {
+                        layer_applies(*other_layer, user_matched)
+                            && (other_decision > decision
+                                || (other < position && other_decision == decision))
+                    },
+                );
+            coverage.entry((program, *index)).or_default().decisive |= !overridden;
+        }
+
+        if actual != case.decision {
+            mismatches.push(PolicyTestMismatch {
+                case: case_index + 1,
+                command: case.command.clone(),
+                expected: case.decision,
+                actual,
+                decided_by: evaluation.and_then(|evaluation| evaluation.decided_by),
+            });
+        }
+    }
+
+    let mut dead_rules = Vec::new();
+    let mut shadowed_rules = Vec::new();
+    let mut decisions_by_pattern: BTreeMap<Vec<String>, Vec<Decision>> = BTreeMap::new();
+    for (program, program_rules) in &rules {
+        for (index, rule) in program_rules.iter().enumerate() {
+            let Some(summary) = summarize_rule(rule) else {
+                continue;
+            };
+            let decisions = decisions_by_pattern
+                .entry(summary.pattern.clone())
+                .or_default();
+            if !decisions.contains(&summary.decision) {
+                decisions.push(summary.decision);
+            }
+
+            match coverage.get(&(*program, index)) {
+                None => dead_rules.push(summary),
+                Some(rule_coverage) if !rule_coverage.decisive => shadowed_rules.push(summary),
+                Some(_) => {}
+            }
+        }
+    }
+
+    let conflicts = decisions_by_pattern
+        .into_iter()
+        .filter(|(_, decisions)| decisions.len() > 1)
+        .map(|(pattern, mut decisions)| {
+            decisions.sort();
+            PolicyConflict { pattern, decisions }
+        })
+        .collect();
+
+    PolicyTestReport {
+        total_cases: cases.len(),
+        mismatches,
+        dead_rules,
+        shadowed_rules,
+        conflicts,
+    }
+}
+
+/// Tracked for every rule that matched at least one case.
+#[derive(Default)]
+struct RuleCoverage {
+    /// Whether this rule determined the decision for at least one matched case.
+    decisive: bool,
+}
+
+fn summarize_rule(rule: &RuleRef) -> Option<RuleSummary> {
+    let prefix_rule = rule.as_any().downcast_ref::<PrefixRule>()?;
+    let mut pattern = Vec::with_capacity(prefix_rule.pattern.rest.len() + 1);
+    pattern.push(prefix_rule.pattern.first.to_string());
+    pattern.extend(prefix_rule.pattern.rest.iter().map(render_pattern_token));
+    Some(RuleSummary {
+        pattern,
+        decision: prefix_rule.decision,
+        source: prefix_rule.source.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PolicyParser;
+    use pretty_assertions::assert_eq;
+
+    fn tokens(raw: &str) -> Vec<String> {
+        raw.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn summary(pattern: &str, decision: Decision) -> RuleSummary {
+        RuleSummary {
+            pattern: tokens(pattern),
+            decision,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn reports_mismatches_dead_shadowed_and_conflicting_rules() {
+        let mut parser = PolicyParser::new();
+        parser
+            .parse(
+                "test.rules",
+                r#"
+prefix_rule(pattern = ["git", "status"])
+prefix_rule(pattern = ["git", "push"], decision = "prompt")
+prefix_rule(pattern = ["git", "push", "--force"], decision = "prompt")
+prefix_rule(pattern = ["git", "push"], decision = "forbidden")
+prefix_rule(pattern = ["rm"], decision = "forbidden")
+"#,
+            )
+            .expect("parse policy");
+        let policy = parser.build();
+
+        let cases: Vec<PolicyTestCase> = [
+            r#"{"command": "git status", "decision": "allow"}"#,
+            r#"{"command": ["git", "push", "--force"], "decision": "prompt"}"#,
+            r#"{"command": "ls"}"#,
+        ]
+        .iter()
+        .map(|line| serde_json::from_str(line).expect("parse case"))
+        .collect();
+
+        let report = run_policy_tests(&policy, &cases);
+
+        assert_eq!(
+            report,
+            PolicyTestReport {
+                total_cases: 3,
+                mismatches: vec![PolicyTestMismatch {
+                    case: 2,
+                    command: tokens("git push --force"),
+                    expected: Some(Decision::Prompt),
+                    actual: Some(Decision::Forbidden),
+                    decided_by: None,
+                }],
+                dead_rules: vec![summary("rm", Decision::Forbidden)],
+                shadowed_rules: vec![
+                    summary("git push", Decision::Prompt),
+                    summary("git push --force", Decision::Prompt),
+                ],
+                conflicts: vec![PolicyConflict {
+                    pattern: tokens("git push"),
+                    decisions: vec![Decision::Prompt, Decision::Forbidden],
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_empty_commands() {
+        let err = serde_json::from_str::<PolicyTestCase>(r#"{"command": [], "decision": "allow"}"#)
+            .expect_err("empty command should fail");
+        assert!(err.to_string().contains("command cannot be empty"));
+    }
+}
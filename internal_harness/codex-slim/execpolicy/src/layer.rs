@@ -0,0 +1,30 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Policy layers in increasing order of precedence.
+///
+/// When rules from several layers match a command, a user rule replaces what the default
+/// rules say, so it can relax or tighten them. Repository rules arrive with whatever was
+/// cloned, so they never replace anything: their decision is combined with the others and the
+/// strictest one wins, which lets a repository tighten default and user decisions but not loosen
+/// them. Admin rules always apply on top in the same way.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PolicyLayer {
+    /// Machine-wide defaults from the system config folder.
+    Default,
+    /// Rules checked into the repository under `.codex/policy/`.
+    Repo,
+    /// Rules from the user's own configuration.
+    User,
+    /// Rules enforced by an administrator.
+    Admin,
+}
+
+/// Layer and file a rule was declared in.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleSource {
+    pub layer: PolicyLayer,
+    pub path: String,
+}
@@ -2,6 +2,8 @@ pub mod amend;
 pub mod decision;
 pub mod error;
 pub mod execpolicycheck;
+pub mod execpolicytest;
+pub mod layer;
 pub mod parser;
 pub mod policy;
 pub mod rule;
@@ -16,6 +18,9 @@ pub use error::Result;
 pub use error::TextPosition;
 pub use error::TextRange;
 pub use execpolicycheck::ExecPolicyCheckCommand;
+pub use execpolicytest::ExecPolicyTestCommand;
+pub use layer::PolicyLayer;
+pub use layer::RuleSource;
 pub use parser::PolicyParser;
 pub use policy::Evaluation;
 pub use policy::Policy;
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 use codex_execpolicy::execpolicycheck::ExecPolicyCheckCommand;
+use codex_execpolicy::execpolicytest::ExecPolicyTestCommand;
 
 /// CLI for evaluating exec policies
 #[derive(Parser)]
@@ -8,11 +9,15 @@ use codex_execpolicy::execpolicycheck::ExecPolicyCheckCommand;
 enum Cli {
     /// Evaluate a command against a policy.
     Check(ExecPolicyCheckCommand),
+
+    /// Run a corpus of commands with expected decisions and report rule coverage.
+    Test(ExecPolicyTestCommand),
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
     match cli {
         Cli::Check(cmd) => cmd.run(),
+        Cli::Test(cmd) => cmd.run(),
     }
 }
@@ -1,34 +1,55 @@
+use allocative::Allocative;
 use multimap::MultiMap;
 use shlex;
 use starlark::any::ProvidesStaticType;
+use starlark::environment::FrozenModule;
 use starlark::environment::GlobalsBuilder;
 use starlark::environment::Module;
 use starlark::eval::Evaluator;
+use starlark::eval::FileLoader;
 use starlark::starlark_module;
+use starlark::starlark_simple_value;
 use starlark::syntax::AstModule;
 use starlark::syntax::Dialect;
+use starlark::values::NoSerialize;
+use starlark::values::StarlarkValue;
 use starlark::values::Value;
 use starlark::values::list::ListRef;
 use starlark::values::list::UnpackList;
 use starlark::values::none::NoneType;
+use starlark::values::starlark_value;
 use std::cell::RefCell;
 use std::cell::RefMut;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::decision::Decision;
 use crate::error::Error;
 use crate::error::Result;
+use crate::layer::PolicyLayer;
+use crate::layer::RuleSource;
+use crate::rule::GlobToken;
 use crate::rule::NetworkRule;
 use crate::rule::NetworkRuleProtocol;
 use crate::rule::PatternToken;
 use crate::rule::PrefixPattern;
 use crate::rule::PrefixRule;
+use crate::rule::RegexToken;
 use crate::rule::RuleRef;
 use crate::rule::validate_match_examples;
 use crate::rule::validate_not_match_examples;
 
 pub struct PolicyParser {
     builder: RefCell<PolicyBuilder>,
+    /// Modules imported via `load()`, keyed by resolved path and layer so each pack's top-level
+    /// rules are only registered once per layer.
+    loaded_modules: RefCell<HashMap<(PathBuf, Option<PolicyLayer>), FrozenModule>>,
+    /// Packs currently being evaluated, used to reject `load()` cycles.
+    loading: RefCell<Vec<PathBuf>>,
 }
 
 impl Default for PolicyParser {
@@ -41,12 +62,40 @@ impl PolicyParser {
     pub fn new() -> Self {
         Self {
             builder: RefCell::new(PolicyBuilder::new()),
+            loaded_modules: RefCell::new(HashMap::new()),
+            loading: RefCell::new(Vec::new()),
         }
     }
 
     /// Parses a policy, tagging parser errors with `policy_identifier` so failures include the
     /// identifier alongside line numbers.
     pub fn parse(&mut self, policy_identifier: &str, policy_file_contents: &str) -> Result<()> {
+        self.eval_policy(policy_identifier, policy_file_contents, None)
+            .map(drop)
+    }
+
+    /// Parses a policy as part of `layer`. Rules declared while evaluating it, including rules
+    /// declared by packs it imports with `load()`, remember the layer and file they came from.
+    pub fn parse_layer(
+        &mut self,
+        layer: PolicyLayer,
+        policy_identifier: &str,
+        policy_file_contents: &str,
+    ) -> Result<()> {
+        self.eval_policy(policy_identifier, policy_file_contents, Some(layer))
+            .map(drop)
+    }
+
+    pub fn build(self) -> crate::policy::Policy {
+        self.builder.into_inner().build()
+    }
+
+    fn eval_policy(
+        &self,
+        policy_identifier: &str,
+        policy_file_contents: &str,
+        layer: Option<PolicyLayer>,
+    ) -> Result<Module> {
         let mut dialect = Dialect::Extended.clone();
         dialect.enable_f_strings = true;
         let ast = AstModule::parse(
@@ -57,16 +106,72 @@ impl PolicyParser {
         .map_err(Error::Starlark)?;
         let globals = GlobalsBuilder::standard().with(policy_builtins).build();
         let module = Module::new();
-        {
+        let loader = PolicyFileLoader {
+            parser: self,
+            base_dir: Path::new(policy_identifier)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            layer,
+        };
+        let source = layer.map(|layer| RuleSource {
+            layer,
+            path: policy_identifier.to_string(),
+        });
+        let previous_source = std::mem::replace(&mut self.builder.borrow_mut().source, source);
+        let result = {
             let mut eval = Evaluator::new(&module);
             eval.extra = Some(&self.builder);
-            eval.eval_module(ast, &globals).map_err(Error::Starlark)?;
+            eval.set_loader(&loader);
+            eval.eval_module(ast, &globals).map(drop)
+        };
+        self.builder.borrow_mut().source = previous_source;
+        result.map_err(Error::Starlark)?;
+        Ok(module)
+    }
+
+    fn load_module(&self, path: &Path, layer: Option<PolicyLayer>) -> Result<FrozenModule> {
+        let load_error = |message: String| Error::PolicyLoad {
+            path: path.display().to_string(),
+            message,
+        };
+        let path = path
+            .canonicalize()
+            .map_err(|err| load_error(err.to_string()))?;
+        let key = (path.clone(), layer);
+        if let Some(module) = self.loaded_modules.borrow().get(&key) {
+            return Ok(module.clone());
+        }
+        if self.loading.borrow().contains(&path) {
+            return Err(load_error("load() cycle detected".to_string()));
         }
-        Ok(())
+
+        let contents = fs::read_to_string(&path).map_err(|err| load_error(err.to_string()))?;
+        self.loading.borrow_mut().push(path.clone());
+        let result = self.eval_policy(&path.to_string_lossy(), &contents, layer);
+        self.loading.borrow_mut().pop();
+
+        let module = result?
+            .freeze()
+            .map_err(|err| load_error(anyhow::Error::from(err).to_string()))?;
+        self.loaded_modules.borrow_mut().insert(key, module.clone());
+        Ok(module)
     }
+}
 
-    pub fn build(self) -> crate::policy::Policy {
-        self.builder.into_inner().build()
+/// Resolves `load()` paths relative to the file being evaluated. Loaded packs join the layer of
+/// the file that imports them.
+struct PolicyFileLoader<'a> {
+    parser: &'a PolicyParser,
+    base_dir: PathBuf,
+    layer: Option<PolicyLayer>,
+}
+
+impl FileLoader for PolicyFileLoader<'_> {
+    fn load(&self, path: &str) -> starlark::Result<FrozenModule> {
+        self.parser
+            .load_module(&self.base_dir.join(path), self.layer)
+            .map_err(starlark::Error::new_other)
     }
 }
 
@@ -74,6 +179,8 @@ impl PolicyParser {
 struct PolicyBuilder {
     rules_by_program: MultiMap<String, RuleRef>,
     network_rules: Vec<NetworkRule>,
+    /// Source attached to rules declared by the file currently being evaluated.
+    source: Option<RuleSource>,
 }
 
 impl PolicyBuilder {
@@ -81,6 +188,7 @@ impl PolicyBuilder {
         Self {
             rules_by_program: MultiMap::new(),
             network_rules: Vec::new(),
+            source: None,
         }
     }
 
@@ -98,21 +206,50 @@ impl PolicyBuilder {
     }
 }
 
+/// Pattern element produced by builtins such as `glob()` or `flag()`, usable in `prefix_rule`
+/// patterns alongside plain strings and lists of alternatives.
+#[derive(Debug, ProvidesStaticType, NoSerialize, Allocative)]
+struct PatternTokenValue(#[allocative(skip)] PatternToken);
+
+starlark_simple_value!(PatternTokenValue);
+
+#[starlark_value(type = "pattern_token")]
+impl<'v> StarlarkValue<'v> for PatternTokenValue {}
+
+impl fmt::Display for PatternTokenValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
 fn parse_pattern<'v>(pattern: UnpackList<Value<'v>>) -> Result<Vec<PatternToken>> {
     let tokens: Vec<PatternToken> = pattern
         .items
         .into_iter()
         .map(parse_pattern_token)
         .collect::<Result<_>>()?;
-    if tokens.is_empty() {
-        Err(Error::InvalidPattern("pattern cannot be empty".to_string()))
-    } else {
-        Ok(tokens)
+    let Some((first, rest)) = tokens.split_first() else {
+        return Err(Error::InvalidPattern("pattern cannot be empty".to_string()));
+    };
+    if first.alternatives().is_empty() {
+        return Err(Error::InvalidPattern(
+            "first pattern element must be a string or list of strings".to_string(),
+        ));
     }
+    if let Some((_, init)) = rest.split_last()
+        && init.iter().any(|token| matches!(token, PatternToken::End))
+    {
+        return Err(Error::InvalidPattern(
+            "end() must be the last pattern element".to_string(),
+        ));
+    }
+    Ok(tokens)
 }
 
 fn parse_pattern_token<'v>(value: Value<'v>) -> Result<PatternToken> {
-    if let Some(s) = value.unpack_str() {
+    if let Some(token) = PatternTokenValue::from_value(value) {
+        Ok(token.0.clone())
+    } else if let Some(s) = value.unpack_str() {
         Ok(PatternToken::Single(s.to_string()))
     } else if let Some(list) = ListRef::from_value(value) {
         let tokens: Vec<String> = list
@@ -140,7 +277,7 @@ fn parse_pattern_token<'v>(value: Value<'v>) -> Result<PatternToken> {
         }
     } else {
         Err(Error::InvalidPattern(format!(
-            "pattern element must be a string or list of strings (got {})",
+            "pattern element must be a string, list of strings or pattern token (got {})",
             value.get_type()
         )))
     }
@@ -271,6 +408,7 @@ fn policy_builtins(builder: &mut GlobalsBuilder) {
                     },
                     decision,
                     justification: justification.clone(),
+                    source: builder.source.clone(),
                 }) as RuleRef
             })
             .collect();
@@ -308,4 +446,277 @@ fn policy_builtins(builder: &mut GlobalsBuilder) {
         });
         Ok(NoneType)
     }
+
+    /// Matches one token against a shell-style glob, e.g. `glob("bot/*")`.
+    fn glob<'v>(pattern: &'v str) -> anyhow::Result<PatternTokenValue> {
+        Ok(PatternTokenValue(PatternToken::Glob(GlobToken::new(
+            pattern,
+        )?)))
+    }
+
+    /// Matches one token that the regular expression matches in full.
+    fn regex<'v>(pattern: &'v str) -> anyhow::Result<PatternTokenValue> {
+        Ok(PatternTokenValue(PatternToken::Regex(RegexToken::new(
+            pattern,
+        )?)))
+    }
+
+    /// Matches exactly `count` tokens of any value.
+    fn any_args(#[starlark(default = 1)] count: i32) -> anyhow::Result<PatternTokenValue> {
+        let count = usize::try_from(count)
+            .ok()
+            .filter(|count| *count > 0)
+            .ok_or_else(|| {
+                Error::InvalidPattern(format!("any_args count must be positive (got {count})"))
+            })?;
+        Ok(PatternTokenValue(PatternToken::AnyArgs(count)))
+    }
+
+    /// Requires `name` to appear (or, with `present=False`, not appear) anywhere after the
+    /// program name. Does not consume a token. Abbreviated long flags and bundled short flags
+    /// only count towards whichever outcome makes the rule stricter: they can make a prompt or
+    /// forbidden rule match, but never an allow rule.
+    fn flag<'v>(
+        name: &'v str,
+        #[starlark(require = named, default = true)] present: bool,
+    ) -> anyhow::Result<PatternTokenValue> {
+        if !name.starts_with('-') || name.chars().all(|c| c == '-') {
+            return Err(Error::InvalidPattern(format!(
+                "flag name must start with `-` (got {name:?})"
+            ))
+            .into());
+        }
+        Ok(PatternTokenValue(PatternToken::Flag {
+            name: name.to_string(),
+            present,
+        }))
+    }
+
+    /// Requires that no tokens follow the preceding pattern elements.
+    fn end() -> anyhow::Result<PatternTokenValue> {
+        Ok(PatternTokenValue(PatternToken::End))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Evaluation;
+    use crate::rule::RuleMatch;
+    use pretty_assertions::assert_eq;
+
+    fn parse_policy(source: &str) -> Result<crate::policy::Policy> {
+        let mut parser = PolicyParser::new();
+        parser.parse("test.rules", source)?;
+        Ok(parser.build())
+    }
+
+    fn command(raw: &str) -> Vec<String> {
+        raw.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn check(policy: &crate::policy::Policy, raw: &str) -> Evaluation {
+        policy.check(&command(raw), &|_| Decision::Prompt)
+    }
+
+    #[test]
+    fn flag_tokens_constrain_the_whole_command() {
+        let policy = parse_policy(
+            r#"
+prefix_rule(
+    pattern = ["cargo", "test", flag("--release", present = False)],
+    match = ["cargo test -p codex-core", "cargo test -- --release"],
+    not_match = ["cargo test -p codex-core --release", "cargo test --release=true"],
+)
+"#,
+        )
+        .expect("parse policy");
+
+        assert_eq!(
+            check(&policy, "cargo test -p codex-core"),
+            Evaluation {
+                decision: Decision::Allow,
+                matched_rules: vec![RuleMatch::PrefixRuleMatch {
+                    matched_prefix: command("cargo test"),
+                    decision: Decision::Allow,
+                    justification: None,
+                }],
+                decided_by: None,
+            }
+        );
+        assert_eq!(
+            check(&policy, "cargo test --release").decision,
+            Decision::Prompt
+        );
+    }
+
+    #[test]
+    fn flag_tokens_match_bundled_short_flags_and_long_prefixes() {
+        let policy = parse_policy(
+            r#"
+prefix_rule(
+    pattern = ["rm", flag("-f")],
+    decision = "forbidden",
+    match = ["rm -f x", "rm -rf x", "rm -fr x"],
+    not_match = ["rm -r x", "rm --recursive x", "rm -- -f"],
+)
+prefix_rule(
+    pattern = ["git", "push", flag("--force")],
+    decision = "forbidden",
+    match = ["git push --forc", "git push --f origin main", "git push --force=yes"],
+    not_match = ["git push --force-with-lease", "git push -f", "git push --"],
+)
+"#,
+        )
+        .expect("parse policy");
+
+        assert_eq!(
+            check(&policy, "rm -rf /tmp/x").decision,
+            Decision::Forbidden
+        );
+        assert_eq!(
+            check(&policy, "git push --forc").decision,
+            Decision::Forbidden
+        );
+        // Forbidden rules count any prefix, even one that a program would reject as
+        // ambiguous, so that they err towards matching.
+        assert_eq!(
+            check(&policy, "git push --fo").decision,
+            Decision::Forbidden
+        );
+    }
+
+    #[test]
+    fn allow_rules_only_match_unambiguous_flags() {
+        let policy = parse_policy(
+            r#"
+prefix_rule(
+    pattern = ["git", "push", flag("--force")],
+    decision = "allow",
+    match = ["git push --force", "git push origin --force=yes"],
+    not_match = ["git push --forc", "git push --f", "git push -- --force"],
+)
+prefix_rule(
+    pattern = ["tar", flag("-x")],
+    decision = "allow",
+    match = ["tar -x -f a.tar"],
+    not_match = ["tar -xf a.tar", "tar -ofilex"],
+)
+prefix_rule(
+    pattern = ["cargo", "test", flag("--release", present = False)],
+    decision = "allow",
+    match = ["cargo test"],
+    not_match = ["cargo test --release", "cargo test --rel"],
+)
+prefix_rule(
+    pattern = ["git", "clean", flag("--dry-run", present = False)],
+    decision = "forbidden",
+    match = ["git clean -f", "git clean -f --dry"],
+    not_match = ["git clean --dry-run", "git clean --dry-run=yes"],
+)
+"#,
+        )
+        .expect("parse policy");
+
+        assert_eq!(check(&policy, "git push --forc").decision, Decision::Prompt);
+        assert_eq!(check(&policy, "tar -xf a.tar").decision, Decision::Prompt);
+        assert_eq!(
+            check(&policy, "cargo test --rel").decision,
+            Decision::Prompt
+        );
+        assert_eq!(
+            check(&policy, "git clean -f --dry").decision,
+            Decision::Forbidden
+        );
+    }
+
+    #[test]
+    fn glob_and_end_tokens_restrict_arguments() {
+        let policy = parse_policy(
+            r#"
+prefix_rule(
+    pattern = ["git", "push", "origin", glob("bot/*"), end()],
+    match = ["git push origin bot/fix-ci"],
+    not_match = ["git push origin main", "git push origin bot/fix-ci --force"],
+)
+"#,
+        )
+        .expect("parse policy");
+
+        assert_eq!(
+            check(&policy, "git push origin bot/x").decision,
+            Decision::Allow
+        );
+        assert_eq!(
+            check(&policy, "git push upstream bot/x").decision,
+            Decision::Prompt
+        );
+        // Globs see the raw token, so `*` also covers the `:dst` half of a refspec.
+        assert_eq!(
+            check(&policy, "git push origin bot/x:main").decision,
+            Decision::Allow
+        );
+    }
+
+    #[test]
+    fn regex_and_any_args_tokens_match_positionally() {
+        let policy = parse_policy(
+            r#"
+prefix_rule(
+    pattern = ["kubectl", any_args(2), regex("pods?|deployments?")],
+    decision = "prompt",
+    match = ["kubectl -n prod pods", "kubectl --context dev deployment"],
+    not_match = ["kubectl get pods", "kubectl -n prod podsx"],
+)
+"#,
+        )
+        .expect("parse policy");
+
+        assert_eq!(
+            check(&policy, "kubectl -n prod pod -o yaml"),
+            Evaluation {
+                decision: Decision::Prompt,
+                matched_rules: vec![RuleMatch::PrefixRuleMatch {
+                    matched_prefix: command("kubectl -n prod pod"),
+                    decision: Decision::Prompt,
+                    justification: None,
+                }],
+                decided_by: None,
+            }
+        );
+    }
+
+    #[test]
+    fn examples_are_validated_against_pattern_tokens() {
+        let err = parse_policy(
+            r#"prefix_rule(pattern = ["git", "push", glob("bot/*")], match = ["git push main"])"#,
+        )
+        .expect_err("unmatched example should fail");
+        assert!(
+            err.to_string().contains("unmatched examples"),
+            "unexpected error: {err}"
+        );
+
+        let err = parse_policy(
+            r#"prefix_rule(pattern = ["rm", flag("-rf")], not_match = ["rm -rf /tmp/x"])"#,
+        )
+        .expect_err("matching negative example should fail");
+        assert!(
+            err.to_string().contains("expected example to not match"),
+            "unexpected error: {err}"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_pattern_tokens() {
+        for source in [
+            r#"prefix_rule(pattern = [glob("*"), "x"])"#,
+            r#"prefix_rule(pattern = ["git", end(), "push"])"#,
+            r#"prefix_rule(pattern = ["git", regex("(")])"#,
+            r#"prefix_rule(pattern = ["git", any_args(0)])"#,
+            r#"prefix_rule(pattern = ["git", flag("force")])"#,
+        ] {
+            assert!(parse_policy(source).is_err(), "expected error for {source}");
+        }
+    }
 }
@@ -1,6 +1,8 @@
 use crate::decision::Decision;
 use crate::error::Error;
 use crate::error::Result;
+use crate::layer::PolicyLayer;
+use crate::layer::RuleSource;
 use crate::rule::NetworkRule;
 use crate::rule::NetworkRuleProtocol;
 use crate::rule::PatternToken;
@@ -89,6 +91,7 @@ impl Policy {
             },
             decision,
             justification: None,
+            source: None,
         });
 
         self.rules_by_program.insert(first_token.clone(), rule);
@@ -144,10 +147,16 @@ impl Policy {
     where
         F: Fn(&[String]) -> Decision,
     {
-        let matched_rules = self.matches_for_command(cmd, Some(heuristics_fallback));
+        let matched_rules = self.sourced_matches_for_command(cmd, Some(heuristics_fallback));
         Evaluation::from_matches(matched_rules)
     }
 
+    /// Evaluates `cmd` against the policy rules alone, returning `None` if no rule matches.
+    pub fn evaluate(&self, cmd: &[String]) -> Option<Evaluation> {
+        let matched_rules = self.sourced_matches_for_command(cmd, None);
+        (!matched_rules.is_empty()).then(|| Evaluation::from_matches(matched_rules))
+    }
+
     /// Checks multiple commands and aggregates the results.
     pub fn check_multiple<Commands, F>(
         &self,
@@ -159,10 +168,10 @@ impl Policy {
         Commands::Item: AsRef<[String]>,
         F: Fn(&[String]) -> Decision,
     {
-        let matched_rules: Vec<RuleMatch> = commands
+        let matched_rules: Vec<(RuleMatch, Option<RuleSource>)> = commands
             .into_iter()
             .flat_map(|command| {
-                self.matches_for_command(command.as_ref(), Some(heuristics_fallback))
+                self.sourced_matches_for_command(command.as_ref(), Some(heuristics_fallback))
             })
             .collect();
 
@@ -181,37 +190,93 @@ impl Policy {
         cmd: &[String],
         heuristics_fallback: HeuristicsFallback<'_>,
     ) -> Vec<RuleMatch> {
-        let matched_rules: Vec<RuleMatch> = match cmd.first() {
+        self.sourced_matches_for_command(cmd, heuristics_fallback)
+            .into_iter()
+            .map(|(rule_match, _)| rule_match)
+            .collect()
+    }
+
+    /// Like [`Self::matches_for_command`], but keeps the source of each matched rule and drops
+    /// default-layer matches that a user rule overrode.
+    fn sourced_matches_for_command(
+        &self,
+        cmd: &[String],
+        heuristics_fallback: HeuristicsFallback<'_>,
+    ) -> Vec<(RuleMatch, Option<RuleSource>)> {
+        let mut matched_rules: Vec<(RuleMatch, Option<RuleSource>)> = match cmd.first() {
             Some(first) => self
                 .rules_by_program
                 .get_vec(first)
-                .map(|rules| rules.iter().filter_map(|rule| rule.matches(cmd)).collect())
+                .map(|rules| {
+                    rules
+                        .iter()
+                        .filter_map(|rule| {
+                            rule.matches(cmd)
+                                .map(|rule_match| (rule_match, rule.source().cloned()))
+                        })
+                        .collect()
+                })
                 .unwrap_or_default(),
             None => Vec::new(),
         };
+        retain_deciding_layers(&mut matched_rules);
 
         if matched_rules.is_empty()
             && let Some(heuristics_fallback) = heuristics_fallback
         {
-            vec![RuleMatch::HeuristicsRuleMatch {
-                command: cmd.to_vec(),
-                decision: heuristics_fallback(cmd),
-            }]
+            vec![(
+                RuleMatch::HeuristicsRuleMatch {
+                    command: cmd.to_vec(),
+                    decision: heuristics_fallback(cmd),
+                },
+                None,
+            )]
         } else {
             matched_rules
         }
     }
 }
 
+/// Drops default-layer matches when a user rule also matched, so the user layer can relax or
+/// tighten the default rules. Repo and admin matches are always kept: since the strictest
+/// remaining decision wins, a repository can only tighten default and user decisions, and only
+/// admin rules stand above the user. Rules loaded without a layer count as user rules.
+fn retain_deciding_layers(matched_rules: &mut Vec<(RuleMatch, Option<RuleSource>)>) {
+    let rule_layer =
+        |source: &Option<RuleSource>| source.as_ref().map_or(PolicyLayer::User, |s| s.layer);
+    let user_matched = matched_rules
+        .iter()
+        .any(|(_, source)| rule_layer(source) == PolicyLayer::User);
+    matched_rules.retain(|(_, source)| layer_applies(rule_layer(source), user_matched));
+}
+
+/// Whether matches from `layer` take part in the decision, given whether any user rule matched.
+pub(crate) fn layer_applies(layer: PolicyLayer, user_matched: bool) -> bool {
+    layer != PolicyLayer::Default || !user_matched
+}
+
 fn upsert_domain(entries: &mut Vec<String>, host: &str) {
     entries.retain(|entry| entry != host);
     entries.push(host.to_string());
 }
 
-fn render_pattern_token(token: &PatternToken) -> String {
+pub(crate) fn render_pattern_token(token: &PatternToken) -> String {
     match token {
         PatternToken::Single(value) => value.clone(),
         PatternToken::Alts(alternatives) => format!("[{}]", alternatives.join("|")),
+        PatternToken::Glob(glob) => glob.as_str().to_string(),
+        PatternToken::Regex(regex) => format!("<regex {}>", regex.as_str()),
+        PatternToken::AnyArgs(1) => "<arg>".to_string(),
+        PatternToken::AnyArgs(count) => format!("<{count} args>"),
+        PatternToken::Flag {
+            name,
+            present: true,
+        } => format!("<with {name}>"),
+        PatternToken::Flag {
+            name,
+            present: false,
+        } => format!("<without {name}>"),
+        PatternToken::End => "<end>".to_string(),
     }
 }
 
@@ -221,6 +286,10 @@ pub struct Evaluation {
     pub decision: Decision,
     #[serde(rename = "matchedRules")]
     pub matched_rules: Vec<RuleMatch>,
+    /// Layer and file of the rule that determined `decision`, if that rule was loaded as part
+    /// of a policy layer.
+    #[serde(rename = "decidedBy", default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<RuleSource>,
 }
 
 impl Evaluation {
@@ -231,14 +300,25 @@ impl Evaluation {
     }
 
     /// Caller is responsible for ensuring that `matched_rules` is non-empty.
-    fn from_matches(matched_rules: Vec<RuleMatch>) -> Self {
-        let decision = matched_rules.iter().map(RuleMatch::decision).max();
+    fn from_matches(matched_rules: Vec<(RuleMatch, Option<RuleSource>)>) -> Self {
+        let decision = matched_rules
+            .iter()
+            .map(|(rule_match, _)| rule_match.decision())
+            .max();
         #[expect(clippy::expect_used)]
         let decision = decision.expect("invariant failed: matched_rules must be non-empty");
+        let decided_by = matched_rules
+            .iter()
+            .find(|(rule_match, _)| rule_match.decision() == decision)
+            .and_then(|(_, source)| source.clone());
 
         Self {
             decision,
-            matched_rules,
+            matched_rules: matched_rules
+                .into_iter()
+                .map(|(rule_match, _)| rule_match)
+                .collect(),
+            decided_by,
         }
     }
 }
@@ -1,6 +1,10 @@
 use crate::decision::Decision;
 use crate::error::Error;
 use crate::error::Result;
+use crate::layer::RuleSource;
+use globset::Glob;
+use globset::GlobMatcher;
+use regex::Regex;
 use serde::Deserialize;
 use serde::Serialize;
 use shlex::try_join;
@@ -8,11 +12,21 @@ use std::any::Any;
 use std::fmt::Debug;
 use std::sync::Arc;
 
-/// Matches a single command token, either a fixed string or one of several allowed alternatives.
+/// Matches command tokens following the program name.
+///
+/// `Single`, `Alts`, `Glob` and `Regex` each consume exactly one token and `AnyArgs` consumes a
+/// fixed number of arbitrary tokens. `Flag` does not consume anything: it checks whether a flag
+/// appears anywhere after the program name (up to a `--` separator). `End` requires that no
+/// tokens remain.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PatternToken {
     Single(String),
     Alts(Vec<String>),
+    Glob(GlobToken),
+    Regex(RegexToken),
+    AnyArgs(usize),
+    Flag { name: String, present: bool },
+    End,
 }
 
 impl PatternToken {
@@ -20,17 +34,141 @@ impl PatternToken {
         match self {
             Self::Single(expected) => expected == token,
             Self::Alts(alternatives) => alternatives.iter().any(|alt| alt == token),
+            Self::Glob(glob) => glob.matcher.is_match(token),
+            Self::Regex(regex) => regex.regex.is_match(token),
+            Self::AnyArgs(_) | Self::Flag { .. } | Self::End => false,
         }
     }
 
+    /// Literal strings accepted by this token. Empty for tokens that are not literals.
     pub fn alternatives(&self) -> &[String] {
         match self {
             Self::Single(expected) => std::slice::from_ref(expected),
             Self::Alts(alternatives) => alternatives,
+            Self::Glob(_) | Self::Regex(_) | Self::AnyArgs(_) | Self::Flag { .. } | Self::End => {
+                &[]
+            }
         }
     }
 }
 
+/// Shell-style glob matched against a whole command token, e.g. `bot/*`.
+#[derive(Clone)]
+pub struct GlobToken {
+    source: String,
+    matcher: GlobMatcher,
+}
+
+impl GlobToken {
+    pub fn new(source: &str) -> Result<Self> {
+        let glob = Glob::new(source)
+            .map_err(|err| Error::InvalidPattern(format!("invalid glob `{source}`: {err}")))?;
+        Ok(Self {
+            source: source.to_string(),
+            matcher: glob.compile_matcher(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Debug for GlobToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("GlobToken").field(&self.source).finish()
+    }
+}
+
+impl PartialEq for GlobToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for GlobToken {}
+
+/// Regular expression that must match a whole command token.
+#[derive(Clone)]
+pub struct RegexToken {
+    source: String,
+    regex: Regex,
+}
+
+impl RegexToken {
+    pub fn new(source: &str) -> Result<Self> {
+        let regex = Regex::new(&format!("^(?:{source})$"))
+            .map_err(|err| Error::InvalidPattern(format!("invalid regex `{source}`: {err}")))?;
+        Ok(Self {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl Debug for RegexToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("RegexToken").field(&self.source).finish()
+    }
+}
+
+impl PartialEq for RegexToken {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for RegexToken {}
+
+/// Returns true if `flag` appears in `args` before any `--` separator.
+///
+/// Long flags always match with an attached value (`--force=yes`). With `loose`, they also
+/// match when abbreviated (`--forc`), as getopt-style parsers accept unambiguous prefixes; we
+/// cannot tell which prefixes are unambiguous without knowing every option the program takes,
+/// so any prefix of at least one character counts. Loose single-letter short flags also match
+/// inside a bundle such as `-rf`, which over-matches when the bundle ends in an attached value
+/// (`-ofile` contains `-f`). Loose matching therefore errs towards treating the flag as
+/// present, and exact matching towards treating it as absent.
+fn has_flag(args: &[String], flag: &str, loose: bool) -> bool {
+    args.iter()
+        .take_while(|arg| arg.as_str() != "--")
+        .any(|arg| arg_has_flag(arg, flag, loose))
+}
+
+fn arg_has_flag(arg: &str, flag: &str, loose: bool) -> bool {
+    if arg == flag {
+        return true;
+    }
+    if flag.starts_with("--")
+        && arg
+            .strip_prefix(flag)
+            .is_some_and(|value| value.starts_with('='))
+    {
+        return true;
+    }
+    if !loose {
+        return false;
+    }
+    if let Some(long_name) = flag.strip_prefix("--") {
+        let Some(arg_name) = arg.strip_prefix("--") else {
+            return false;
+        };
+        let arg_name = arg_name.split_once('=').map_or(arg_name, |(name, _)| name);
+        return !arg_name.is_empty() && long_name.starts_with(arg_name);
+    }
+    let mut short_name = flag.chars().skip(1);
+    match (short_name.next(), short_name.next()) {
+        (Some(letter), None) => {
+            arg.starts_with('-') && !arg.starts_with("--") && arg[1..].contains(letter)
+        }
+        _ => false,
+    }
+}
+
 /// Prefix matcher for commands with support for alternative match tokens.
 /// First token is fixed since we key by the first token in policy.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -40,19 +178,48 @@ pub struct PrefixPattern {
 }
 
 impl PrefixPattern {
-    pub fn matches_prefix(&self, cmd: &[String]) -> Option<Vec<String>> {
-        let pattern_length = self.rest.len() + 1;
-        if cmd.len() < pattern_length || cmd[0] != self.first.as_ref() {
+    /// Returns the prefix of `cmd` matched by the pattern of a rule with `decision`.
+    ///
+    /// Whether a flag is present can be ambiguous (see [`has_flag`]). Ambiguity is resolved in
+    /// whichever direction is stricter: an allow rule only matches if its flags are certainly
+    /// present or certainly absent, while prompt and forbidden rules also match when they might
+    /// be.
+    pub fn matches_prefix(&self, cmd: &[String], decision: Decision) -> Option<Vec<String>> {
+        let widen = decision != Decision::Allow;
+        if cmd.first().map(String::as_str) != Some(self.first.as_ref()) {
             return None;
         }
 
-        for (pattern_token, cmd_token) in self.rest.iter().zip(&cmd[1..pattern_length]) {
-            if !pattern_token.matches(cmd_token) {
-                return None;
+        let mut position = 1;
+        for pattern_token in self.rest.iter() {
+            match pattern_token {
+                PatternToken::AnyArgs(count) => {
+                    position += count;
+                    if position > cmd.len() {
+                        return None;
+                    }
+                }
+                PatternToken::Flag { name, present } => {
+                    if has_flag(&cmd[1..], name, widen == *present) != *present {
+                        return None;
+                    }
+                }
+                PatternToken::End => {
+                    if position != cmd.len() {
+                        return None;
+                    }
+                }
+                _ => {
+                    let cmd_token = cmd.get(position)?;
+                    if !pattern_token.matches(cmd_token) {
+                        return None;
+                    }
+                    position += 1;
+                }
             }
         }
 
-        Some(cmd[..pattern_length].to_vec())
+        Some(cmd[..position].to_vec())
     }
 }
 
@@ -90,6 +257,8 @@ pub struct PrefixRule {
     pub pattern: PrefixPattern,
     pub decision: Decision,
     pub justification: Option<String>,
+    /// Where the rule was declared, if it was loaded as part of a policy layer.
+    pub source: Option<RuleSource>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -194,6 +363,10 @@ pub trait Rule: Any + Debug + Send + Sync {
 
     fn matches(&self, cmd: &[String]) -> Option<RuleMatch>;
 
+    fn source(&self) -> Option<&RuleSource> {
+        None
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -206,7 +379,7 @@ impl Rule for PrefixRule {
 
     fn matches(&self, cmd: &[String]) -> Option<RuleMatch> {
         self.pattern
-            .matches_prefix(cmd)
+            .matches_prefix(cmd, self.decision)
             .map(|matched_prefix| RuleMatch::PrefixRuleMatch {
                 matched_prefix,
                 decision: self.decision,
@@ -214,6 +387,10 @@ impl Rule for PrefixRule {
             })
     }
 
+    fn source(&self) -> Option<&RuleSource> {
+        self.source.as_ref()
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
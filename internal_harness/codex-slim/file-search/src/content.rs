@@ -0,0 +1,611 @@
+//! In-process, gitignore-aware search of file contents.
+//!
+//! Files are visited with the same parallel walker as path search and matched
+//! line by line. Results stream back through [`ContentSearch`] as they are
+//! found, so callers can stop early; dropping it stops the walk.
+
+use std::fs::File;
+use std::io::Read;
+use std::num::NonZero;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_channel::unbounded;
+use regex::bytes::Regex;
+use regex::bytes::RegexBuilder;
+use serde::Serialize;
+
+use crate::build_override_matcher;
+use crate::build_walker;
+use crate::get_file_path;
+
+/// Number of leading bytes checked for NUL bytes to decide whether a file is
+/// binary. Binary files are skipped, as ripgrep does by default.
+pub(crate) const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Default for [`ContentSearchOptions::max_file_bytes`].
+const DEFAULT_MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// How the search pattern is interpreted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentSearchMode {
+    /// A regular expression using the `regex` crate syntax.
+    #[default]
+    Regex,
+    /// A literal string.
+    Literal,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentSearchOptions {
+    pub mode: ContentSearchMode,
+    pub case_insensitive: bool,
+    /// Globs a file must match to be searched. Empty means every file.
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    /// Lines of context reported before and after each matching line.
+    pub context_lines: usize,
+    /// Stop after this many matches.
+    pub max_matches: NonZero<usize>,
+    /// Stop once the matched and context lines reported add up to this many
+    /// bytes.
+    pub max_bytes: Option<NonZero<usize>>,
+    /// Report at most one match per file, like `rg --files-with-matches`.
+    pub first_match_per_file: bool,
+    pub threads: NonZero<usize>,
+    pub respect_gitignore: bool,
+    /// Also search hidden files and directories, whose names start with `.`.
+    /// Git's own `.git` directory is never searched.
+    pub include_hidden: bool,
+    /// Files larger than this are skipped without being read.
+    pub max_file_bytes: Option<u64>,
+}
+
+impl Default for ContentSearchOptions {
+    fn default() -> Self {
+        Self {
+            mode: ContentSearchMode::Regex,
+            case_insensitive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            context_lines: 0,
+            #[expect(clippy::unwrap_used)]
+            max_matches: NonZero::new(100).unwrap(),
+            max_bytes: None,
+            first_match_per_file: false,
+            #[expect(clippy::unwrap_used)]
+            threads: NonZero::new(2).unwrap(),
+            respect_gitignore: true,
+            include_hidden: false,
+            max_file_bytes: Some(DEFAULT_MAX_FILE_BYTES),
+        }
+    }
+}
+
+/// A single matching line.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ContentMatch {
+    /// Path to the file, relative to `root`.
+    pub path: PathBuf,
+    pub root: PathBuf,
+    /// 1-based line number of `line`.
+    pub line_number: usize,
+    /// The matching line without its line terminator. Invalid UTF-8 is
+    /// replaced.
+    pub line: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context_before: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context_after: Vec<String>,
+}
+
+impl ContentMatch {
+    pub fn full_path(&self) -> PathBuf {
+        self.root.join(&self.path)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentSearchSummary {
+    pub files_searched: usize,
+    pub match_count: usize,
+    /// True if a match or byte limit stopped the search early.
+    pub truncated: bool,
+}
+
+enum ContentSearchEvent {
+    Match(ContentMatch),
+    Complete(ContentSearchSummary),
+}
+
+/// Streaming results of [`search_content`]. Iterating yields matches as the
+/// walker finds them, in no particular order; [`ContentSearch::summary`] is
+/// available once iteration has finished.
+pub struct ContentSearch {
+    rx: Receiver<ContentSearchEvent>,
+    stopped: Arc<AtomicBool>,
+    summary: Option<ContentSearchSummary>,
+}
+
+impl ContentSearch {
+    pub fn summary(&self) -> Option<&ContentSearchSummary> {
+        self.summary.as_ref()
+    }
+}
+
+impl Iterator for ContentSearch {
+    type Item = ContentMatch;
+
+    fn next(&mut self) -> Option<ContentMatch> {
+        match self.rx.recv() {
+            Ok(ContentSearchEvent::Match(content_match)) => Some(content_match),
+            Ok(ContentSearchEvent::Complete(summary)) => {
+                self.summary = Some(summary);
+                None
+            }
+            Err(_) => None,
+        }
+    }
+}
+
+impl Drop for ContentSearch {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Searches the contents of every file under `roots` for `pattern`.
+///
+/// The worker threads periodically check `cancel_flag` to see if they should
+/// stop.
+pub fn search_content(
+    pattern: &str,
+    roots: Vec<PathBuf>,
+    options: ContentSearchOptions,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> anyhow::Result<ContentSearch> {
+    let Some(primary_root) = roots.first() else {
+        anyhow::bail!("at least one search directory is required");
+    };
+    let override_matcher =
+        build_override_matcher(primary_root, &options.include, &options.exclude)?;
+    let Some(mut walk_builder) = build_walker(
+        &roots,
+        options.threads.get(),
+        options.respect_gitignore,
+        override_matcher,
+    ) else {
+        anyhow::bail!("at least one search directory is required");
+    };
+    // Even when hidden files are searched, git's object store and refs are
+    // not.
+    walk_builder
+        .hidden(!options.include_hidden)
+        .filter_entry(|entry| entry.file_name() != ".git");
+    let (search, tx, content_search) = start_search(pattern, roots, &options, cancel_flag)?;
+
+    thread::spawn(move || {
+        walk_builder.build_parallel().run(|| {
+            let search = search.clone();
+            let tx = tx.clone();
+            Box::new(move |entry| {
+                let Ok(entry) = entry else {
+                    return ignore::WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                    return ignore::WalkState::Continue;
+                }
+                search.search_file(entry.path(), &tx)
+            })
+        });
+        let _ = tx.send(ContentSearchEvent::Complete(search.summary()));
+    });
+
+    Ok(content_search)
+}
+
+/// Searches the contents of `files`, which must all be under `root`, for
+/// `pattern`. The file filters in `options` (`include`, `exclude` and
+/// `respect_gitignore`) are the caller's responsibility.
+pub(crate) fn search_files(
+    pattern: &str,
+    root: PathBuf,
+    files: Vec<PathBuf>,
+    options: ContentSearchOptions,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> anyhow::Result<ContentSearch> {
+    let threads = options.threads.get();
+    let (search, tx, content_search) = start_search(pattern, vec![root], &options, cancel_flag)?;
+
+    thread::spawn(move || {
+        let next = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| {
+                    while let Some(path) = files.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if matches!(search.search_file(path, &tx), ignore::WalkState::Quit) {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+        let _ = tx.send(ContentSearchEvent::Complete(search.summary()));
+    });
+
+    Ok(content_search)
+}
+
+/// Compiles `pattern` and sets up the state shared by the search workers.
+fn start_search(
+    pattern: &str,
+    roots: Vec<PathBuf>,
+    options: &ContentSearchOptions,
+    cancel_flag: Option<Arc<AtomicBool>>,
+) -> anyhow::Result<(Arc<SearchState>, Sender<ContentSearchEvent>, ContentSearch)> {
+    let regex = RegexBuilder::new(&regex_pattern(pattern, options.mode))
+        .case_insensitive(options.case_insensitive)
+        .multi_line(true)
+        .crlf(true)
+        .build()?;
+    let (tx, rx) = unbounded();
+    let stopped = Arc::new(AtomicBool::new(false));
+    let search = Arc::new(SearchState {
+        regex,
+        roots,
+        context_lines: options.context_lines,
+        max_matches: options.max_matches.get(),
+        max_bytes: options.max_bytes.map(NonZero::get),
+        first_match_per_file: options.first_match_per_file,
+        max_file_bytes: options.max_file_bytes,
+        cancelled: cancel_flag.unwrap_or_else(|| Arc::new(AtomicBool::new(false))),
+        stopped: stopped.clone(),
+        files_searched: AtomicUsize::new(0),
+        match_count: AtomicUsize::new(0),
+        byte_count: AtomicUsize::new(0),
+        truncated: AtomicBool::new(false),
+    });
+    let content_search = ContentSearch {
+        rx,
+        stopped,
+        summary: None,
+    };
+    Ok((search, tx, content_search))
+}
+
+/// Returns `pattern` as regex syntax.
+pub(crate) fn regex_pattern(pattern: &str, mode: ContentSearchMode) -> String {
+    match mode {
+        ContentSearchMode::Regex => pattern.to_string(),
+        ContentSearchMode::Literal => regex::escape(pattern),
+    }
+}
+
+struct SearchState {
+    regex: Regex,
+    roots: Vec<PathBuf>,
+    context_lines: usize,
+    max_matches: usize,
+    max_bytes: Option<usize>,
+    first_match_per_file: bool,
+    max_file_bytes: Option<u64>,
+    cancelled: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+    files_searched: AtomicUsize,
+    match_count: AtomicUsize,
+    byte_count: AtomicUsize,
+    truncated: AtomicBool,
+}
+
+impl SearchState {
+    fn search_file(&self, path: &Path, tx: &Sender<ContentSearchEvent>) -> ignore::WalkState {
+        if self.should_stop() {
+            return ignore::WalkState::Quit;
+        }
+        let Some((root, relative_path)) = self.relative_path(path) else {
+            return ignore::WalkState::Continue;
+        };
+        // Unreadable, oversized and binary files are skipped, as ripgrep does
+        // with `--no-messages`.
+        let Some(contents) = self.read_text(path) else {
+            return ignore::WalkState::Continue;
+        };
+        self.files_searched.fetch_add(1, Ordering::Relaxed);
+        if !self.regex.is_match(&contents) {
+            return ignore::WalkState::Continue;
+        }
+
+        let lines: Vec<&[u8]> = split_lines(&contents).collect();
+        for (idx, line) in lines.iter().enumerate() {
+            if !self.regex.is_match(line) {
+                continue;
+            }
+            let before = &lines[idx.saturating_sub(self.context_lines)..idx];
+            let after = &lines[idx + 1..lines.len().min(idx + 1 + self.context_lines)];
+            let bytes = line.len() + before.iter().chain(after).map(|l| l.len()).sum::<usize>();
+            if !self.reserve(bytes) {
+                return ignore::WalkState::Quit;
+            }
+            let content_match = ContentMatch {
+                path: relative_path.clone(),
+                root: root.clone(),
+                line_number: idx + 1,
+                line: String::from_utf8_lossy(line).into_owned(),
+                context_before: before.iter().map(|l| lossy(l)).collect(),
+                context_after: after.iter().map(|l| lossy(l)).collect(),
+            };
+            if tx.send(ContentSearchEvent::Match(content_match)).is_err() {
+                return ignore::WalkState::Quit;
+            }
+            if self.first_match_per_file {
+                break;
+            }
+        }
+        ignore::WalkState::Continue
+    }
+
+    /// Reads `path` unless it is over the size limit or its first
+    /// [`BINARY_SNIFF_LEN`] bytes contain a NUL byte, in which case the rest of
+    /// the file is never read.
+    fn read_text(&self, path: &Path) -> Option<Vec<u8>> {
+        let file = File::open(path).ok()?;
+        let len = file.metadata().ok()?.len();
+        let max_file_bytes = self.max_file_bytes.unwrap_or(u64::MAX);
+        if len > max_file_bytes {
+            return None;
+        }
+        // Bound the read too, in case the file grows while we read it.
+        let mut file = file.take(max_file_bytes);
+        let mut contents = Vec::with_capacity(usize::try_from(len).unwrap_or_default());
+        file.by_ref()
+            .take(BINARY_SNIFF_LEN as u64)
+            .read_to_end(&mut contents)
+            .ok()?;
+        if contents.contains(&0) {
+            return None;
+        }
+        file.read_to_end(&mut contents).ok()?;
+        Some(contents)
+    }
+
+    /// Claims room for one more match of `bytes` bytes under the limits,
+    /// marking the search truncated when there is none.
+    fn reserve(&self, bytes: usize) -> bool {
+        if self.match_count.fetch_add(1, Ordering::Relaxed) >= self.max_matches {
+            self.match_count.fetch_sub(1, Ordering::Relaxed);
+            self.truncated.store(true, Ordering::Relaxed);
+            return false;
+        }
+        let previous_bytes = self.byte_count.fetch_add(bytes, Ordering::Relaxed);
+        if let Some(max_bytes) = self.max_bytes
+            && previous_bytes + bytes > max_bytes
+        {
+            self.match_count.fetch_sub(1, Ordering::Relaxed);
+            self.truncated.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    fn should_stop(&self) -> bool {
+        self.truncated.load(Ordering::Relaxed)
+            || self.cancelled.load(Ordering::Relaxed)
+            || self.stopped.load(Ordering::Relaxed)
+    }
+
+    /// Splits `path` into the search root it was found under and the path
+    /// relative to it. A root that is itself a file is reported as its file
+    /// name under its parent directory.
+    fn relative_path(&self, path: &Path) -> Option<(PathBuf, PathBuf)> {
+        let (root_idx, relative_path) = get_file_path(path, &self.roots)?;
+        if !relative_path.is_empty() {
+            return Some((self.roots[root_idx].clone(), PathBuf::from(relative_path)));
+        }
+        let parent = path.parent()?;
+        Some((parent.to_path_buf(), PathBuf::from(path.file_name()?)))
+    }
+
+    fn summary(&self) -> ContentSearchSummary {
+        ContentSearchSummary {
+            files_searched: self.files_searched.load(Ordering::Relaxed),
+            match_count: self.match_count.load(Ordering::Relaxed),
+            truncated: self.truncated.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Splits on `\n`, dropping a trailing `\r` from each line and the empty line
+/// after a final newline.
+fn split_lines(contents: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let contents = contents.strip_suffix(b"\n").unwrap_or(contents);
+    contents
+        .split(|byte| *byte == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(move |_| !contents.is_empty())
+}
+
+fn lossy(line: &[u8]) -> String {
+    String::from_utf8_lossy(line).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn options() -> ContentSearchOptions {
+        ContentSearchOptions::default()
+    }
+
+    fn collect(
+        pattern: &str,
+        dir: &TempDir,
+        options: ContentSearchOptions,
+    ) -> (Vec<ContentMatch>, ContentSearchSummary) {
+        let mut search =
+            search_content(pattern, vec![dir.path().to_path_buf()], options, None).unwrap();
+        let mut matches: Vec<ContentMatch> = search.by_ref().collect();
+        matches.sort_by(|a, b| (&a.path, a.line_number).cmp(&(&b.path, b.line_number)));
+        (matches, search.summary().cloned().unwrap())
+    }
+
+    #[test]
+    fn finds_regex_and_literal_matches_with_context() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.rs"), "fn main() {\n    foo(1);\n}\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "foo.bar\r\nfooxbar\r\n").unwrap();
+
+        let (matches, summary) = collect(
+            r"foo\(\d\)",
+            &dir,
+            ContentSearchOptions {
+                context_lines: 1,
+                ..options()
+            },
+        );
+        assert_eq!(
+            matches,
+            vec![ContentMatch {
+                path: PathBuf::from("a.rs"),
+                root: dir.path().to_path_buf(),
+                line_number: 2,
+                line: "    foo(1);".to_string(),
+                context_before: vec!["fn main() {".to_string()],
+                context_after: vec!["}".to_string()],
+            }]
+        );
+        assert_eq!(
+            summary,
+            ContentSearchSummary {
+                files_searched: 2,
+                match_count: 1,
+                truncated: false,
+            }
+        );
+
+        let (matches, _) = collect(
+            "FOO.BAR",
+            &dir,
+            ContentSearchOptions {
+                mode: ContentSearchMode::Literal,
+                case_insensitive: true,
+                ..options()
+            },
+        );
+        let lines: Vec<(usize, &str)> = matches
+            .iter()
+            .map(|m| (m.line_number, m.line.as_str()))
+            .collect();
+        assert_eq!(lines, vec![(1, "foo.bar")]);
+    }
+
+    #[test]
+    fn respects_gitignore_globs_and_binary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(".gitignore"), "ignored/\n").unwrap();
+        fs::create_dir(dir.path().join("ignored")).unwrap();
+        fs::write(dir.path().join("ignored/hit.rs"), "needle\n").unwrap();
+        fs::write(dir.path().join("hit.rs"), "needle\n").unwrap();
+        fs::write(dir.path().join("hit.md"), "needle\n").unwrap();
+        fs::write(dir.path().join("blob.rs"), b"needle\0\n").unwrap();
+
+        let (matches, _) = collect(
+            "needle",
+            &dir,
+            ContentSearchOptions {
+                include: vec!["*.rs".to_string()],
+                ..options()
+            },
+        );
+
+        let paths: Vec<PathBuf> = matches.into_iter().map(|m| m.path).collect();
+        assert_eq!(paths, vec![PathBuf::from("hit.rs")]);
+    }
+
+    #[test]
+    fn skips_hidden_files_git_dir_and_oversized_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join(".git/refs")).unwrap();
+        fs::write(dir.path().join(".git/refs/needle"), "needle\n").unwrap();
+        fs::write(dir.path().join(".env"), "needle\n").unwrap();
+        fs::write(
+            dir.path().join("big.txt"),
+            format!("needle\n{}", "x".repeat(64)),
+        )
+        .unwrap();
+
+        let (matches, summary) = collect(
+            "needle",
+            &dir,
+            ContentSearchOptions {
+                max_file_bytes: Some(32),
+                ..options()
+            },
+        );
+        assert_eq!(matches, Vec::new());
+        assert_eq!(summary.files_searched, 0);
+
+        let (matches, summary) = collect(
+            "needle",
+            &dir,
+            ContentSearchOptions {
+                max_file_bytes: Some(32),
+                include_hidden: true,
+                ..options()
+            },
+        );
+        let paths: Vec<PathBuf> = matches.into_iter().map(|m| m.path).collect();
+        assert_eq!(paths, vec![PathBuf::from(".env")]);
+        assert_eq!(summary.files_searched, 1);
+    }
+
+    #[test]
+    fn stops_at_match_and_byte_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("many.txt"), "hit 1\nhit 2\nhit 3\nhit 4\n").unwrap();
+
+        let (matches, summary) = collect(
+            "hit",
+            &dir,
+            ContentSearchOptions {
+                max_matches: NonZero::new(2).unwrap(),
+                ..options()
+            },
+        );
+        assert_eq!(matches.len(), 2);
+        assert!(summary.truncated);
+
+        let (matches, summary) = collect(
+            "hit",
+            &dir,
+            ContentSearchOptions {
+                max_bytes: NonZero::new(15),
+                ..options()
+            },
+        );
+        assert_eq!(matches.len(), 3);
+        assert!(summary.truncated);
+
+        let (matches, summary) = collect(
+            "hit",
+            &dir,
+            ContentSearchOptions {
+                first_match_per_file: true,
+                ..options()
+            },
+        );
+        assert_eq!(matches.len(), 1);
+        assert!(!summary.truncated);
+    }
+}
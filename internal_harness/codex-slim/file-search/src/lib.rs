@@ -35,8 +35,18 @@ use nucleo::pattern::AtomKind;
 use nucleo::pattern::Pattern;
 
 mod cli;
+mod content;
+mod trigram;
 
 pub use cli::Cli;
+pub use content::ContentMatch;
+pub use content::ContentSearch;
+pub use content::ContentSearchMode;
+pub use content::ContentSearchOptions;
+pub use content::ContentSearchSummary;
+pub use content::search_content;
+pub use trigram::TrigramIndex;
+pub use trigram::is_in_git_dir;
 
 /// A single match result returned from the search.
 ///
@@ -155,7 +165,7 @@ pub fn create_session(
     let Some(primary_search_directory) = search_directories.first() else {
         anyhow::bail!("at least one search directory is required");
     };
-    let override_matcher = build_override_matcher(primary_search_directory, &exclude)?;
+    let override_matcher = build_override_matcher(primary_search_directory, &[], &exclude)?;
     let (work_tx, work_rx) = unbounded();
 
     let notify_tx = work_tx.clone();
@@ -344,14 +354,21 @@ enum WorkSignal {
     Shutdown,
 }
 
+/// Builds glob overrides for the walker. When `include` is non-empty only
+/// files matching one of its globs are visited; files matching `exclude` are
+/// always skipped.
 fn build_override_matcher(
     search_directory: &Path,
+    include: &[String],
     exclude: &[String],
 ) -> anyhow::Result<Option<ignore::overrides::Override>> {
-    if exclude.is_empty() {
+    if include.is_empty() && exclude.is_empty() {
         return Ok(None);
     }
     let mut override_builder = OverrideBuilder::new(search_directory);
+    for include in include {
+        override_builder.add(include)?;
+    }
     for exclude in exclude {
         let exclude_pattern = format!("!{exclude}");
         override_builder.add(&exclude_pattern)?;
@@ -379,29 +396,28 @@ fn get_file_path<'a>(path: &'a Path, search_directories: &[PathBuf]) -> Option<(
     rel_path.to_str().map(|p| (root_idx, p))
 }
 
-fn walker_worker(
-    inner: Arc<SessionInner>,
+/// Configures the parallel walk over `roots` shared by path and content
+/// search. Returns `None` when there are no roots.
+fn build_walker(
+    roots: &[PathBuf],
+    threads: usize,
+    respect_gitignore: bool,
     override_matcher: Option<ignore::overrides::Override>,
-    injector: Injector<Arc<str>>,
-) {
-    let Some(first_root) = inner.search_directories.first() else {
-        let _ = inner.work_tx.send(WorkSignal::WalkComplete);
-        return;
-    };
-
+) -> Option<WalkBuilder> {
+    let (first_root, other_roots) = roots.split_first()?;
     let mut walk_builder = WalkBuilder::new(first_root);
-    for root in inner.search_directories.iter().skip(1) {
+    for root in other_roots {
         walk_builder.add(root);
     }
     walk_builder
-        .threads(inner.threads)
+        .threads(threads)
         // Allow hidden entries.
         .hidden(false)
         // Follow symlinks to search their contents.
         .follow_links(true)
         // Don't require git to be present to apply to apply git-related ignore rules.
         .require_git(false);
-    if !inner.respect_gitignore {
+    if !respect_gitignore {
         walk_builder
             .git_ignore(false)
             .git_global(false)
@@ -412,6 +428,23 @@ fn walker_worker(
     if let Some(override_matcher) = override_matcher {
         walk_builder.overrides(override_matcher);
     }
+    Some(walk_builder)
+}
+
+fn walker_worker(
+    inner: Arc<SessionInner>,
+    override_matcher: Option<ignore::overrides::Override>,
+    injector: Injector<Arc<str>>,
+) {
+    let Some(walk_builder) = build_walker(
+        &inner.search_directories,
+        inner.threads,
+        inner.respect_gitignore,
+        override_matcher,
+    ) else {
+        let _ = inner.work_tx.send(WorkSignal::WalkComplete);
+        return;
+    };
 
     let walker = walk_builder.build_parallel();
 
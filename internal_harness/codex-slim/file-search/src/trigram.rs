@@ -0,0 +1,1281 @@
+//! Persistent trigram index for content search in large repositories.
+//!
+//! The index maps every three-byte sequence in a file, ASCII-lowercased so one
+//! index serves both case-sensitive and case-insensitive queries, to the files
+//! containing it. A query's regex is reduced to trigrams that any matching file
+//! must contain, and only files whose posting lists satisfy that are read and
+//! verified with the regex, so results are the same as [`search_content`]'s.
+//!
+//! Indexes live under `CODEX_HOME/search_index/`, one per repository root, and
+//! record the git `HEAD` they were last refreshed at. On refresh, files changed
+//! since then are found with `git diff` and `git status` and reindexed; outside
+//! a git repository, or if git fails, the size and modification time of every
+//! file are compared instead. Changes reported by a file watcher can be applied
+//! between refreshes with [`TrigramIndex::update_paths`]. Only the
+//! [`MAX_INDEXES`] most recently opened indexes are kept; older ones are
+//! deleted whenever an index is opened.
+//!
+//! [`search_content`]: crate::search_content
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io::BufWriter;
+use std::io::Write;
+use std::num::NonZero;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use anyhow::Context;
+use regex_syntax::ParserBuilder;
+use regex_syntax::hir::Class;
+use regex_syntax::hir::Hir;
+use regex_syntax::hir::HirKind;
+use sha2::Digest;
+use sha2::Sha256;
+
+use crate::ContentSearch;
+use crate::ContentSearchOptions;
+use crate::FileSearchOptions;
+use crate::build_override_matcher;
+use crate::build_walker;
+use crate::content::BINARY_SNIFF_LEN;
+use crate::content::regex_pattern;
+use crate::content::search_files;
+
+/// Directory under `CODEX_HOME` holding the index files.
+const INDEX_DIR: &str = "search_index";
+/// Number of indexes kept under [`INDEX_DIR`].
+const MAX_INDEXES: usize = 16;
+/// Identifies the on-disk format; bump the trailing digit on any change.
+const INDEX_MAGIC: &[u8; 8] = b"CXTRIGR1";
+/// Files larger than this are not indexed and are searched on every query.
+const MAX_INDEXED_FILE_BYTES: u64 = 4 * 1024 * 1024;
+
+type Trigram = [u8; 3];
+type FileId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Text,
+    /// Skipped by content search, so never a candidate.
+    Binary,
+    /// Larger than [`MAX_INDEXED_FILE_BYTES`], so always a candidate.
+    Unindexed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexedFile {
+    /// Relative to the index root.
+    path: PathBuf,
+    size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    modified: u64,
+    kind: FileKind,
+}
+
+/// A trigram index of the files under one root, honoring the `exclude` and
+/// `respect_gitignore` settings of the [`FileSearchOptions`] it was opened
+/// with.
+pub struct TrigramIndex {
+    root: PathBuf,
+    index_path: PathBuf,
+    exclude: Vec<String>,
+    respect_gitignore: bool,
+    threads: NonZero<usize>,
+    /// `HEAD` at the last refresh, if the root is in a git repository.
+    head: Option<String>,
+    /// Paths with uncommitted changes at the last refresh. They are rechecked
+    /// on the next refresh, since `git status` no longer lists reverted files.
+    dirty: BTreeSet<PathBuf>,
+    /// Indexed by [`FileId`]; `None` for files removed since the index was
+    /// last compacted.
+    files: Vec<Option<IndexedFile>>,
+    ids: HashMap<PathBuf, FileId>,
+    /// Sorted file ids for each trigram.
+    postings: HashMap<Trigram, Vec<FileId>>,
+    modified: bool,
+}
+
+impl TrigramIndex {
+    /// Loads the index for `root` from `codex_home`, building it if there is
+    /// none or it was built with different options, and refreshes it. Fails
+    /// without saving if `cancel_flag` is set before the refresh completes.
+    pub fn open(
+        codex_home: &Path,
+        root: &Path,
+        options: &FileSearchOptions,
+        cancel_flag: Option<Arc<AtomicBool>>,
+    ) -> anyhow::Result<Self> {
+        let index_path = index_path(codex_home, root);
+        let mut index = Self {
+            root: root.to_path_buf(),
+            index_path,
+            exclude: options.exclude.clone(),
+            respect_gitignore: options.respect_gitignore,
+            threads: options.threads,
+            head: None,
+            dirty: BTreeSet::new(),
+            files: Vec::new(),
+            ids: HashMap::new(),
+            postings: HashMap::new(),
+            modified: true,
+        };
+        // An unreadable or outdated index is rebuilt from scratch.
+        if let Ok(contents) = std::fs::read(&index.index_path) {
+            let _ = index.decode(&contents);
+        }
+        index.refresh(cancel_flag)?;
+        // Mark the index as recently used, even if the refresh found nothing
+        // to save, so pruning removes the indexes of other roots first.
+        if let Ok(file) = std::fs::File::options().write(true).open(&index.index_path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        prune_indexes(codex_home, &index.index_path);
+        Ok(index)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Reindexes the files changed since the last refresh and saves the index
+    /// if anything changed. Fails without saving if `cancel_flag` is set
+    /// before the refresh completes; the index must then be reopened.
+    pub fn refresh(&mut self, cancel_flag: Option<Arc<AtomicBool>>) -> anyhow::Result<()> {
+        let cancel_flag = cancel_flag.as_deref();
+        let repo = GitRepo::discover(&self.root);
+        let changes = match (&repo, &self.head) {
+            (Some(repo), Some(head)) => repo.changes_since(head),
+            _ => None,
+        };
+        match changes {
+            Some((changed, dirty)) => {
+                let mut paths: BTreeSet<PathBuf> = std::mem::take(&mut self.dirty);
+                paths.extend(changed);
+                paths.extend(dirty.iter().cloned());
+                self.reindex(paths.into_iter().collect(), cancel_flag)?;
+                self.dirty = dirty;
+            }
+            None => {
+                self.rescan(cancel_flag)?;
+                self.dirty = repo
+                    .as_ref()
+                    .and_then(GitRepo::dirty_paths)
+                    .unwrap_or_default();
+            }
+        }
+        anyhow::ensure!(!is_cancelled(cancel_flag), "index refresh cancelled");
+        let head = repo.map(|repo| repo.head);
+        if head != self.head {
+            self.head = head;
+            self.modified = true;
+        }
+        if self.modified {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Reindexes `paths`, typically reported by a file watcher. Paths outside
+    /// the root are ignored; directories are reindexed recursively. The index
+    /// is not saved.
+    pub fn update_paths(&mut self, paths: &[PathBuf]) -> anyhow::Result<()> {
+        let mut relative: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok())
+            .map(Path::to_path_buf)
+            .collect();
+        if self.respect_gitignore
+            && let Some(ignored) = check_ignore(&self.root, &relative)
+        {
+            for path in relative.iter().filter(|path| ignored.contains(*path)) {
+                self.remove_under(path);
+            }
+            relative.retain(|path| !ignored.contains(path));
+        }
+        self.reindex(relative, None)
+    }
+
+    /// Searches the indexed files for `pattern`, reading only those that can
+    /// contain a match. `options.respect_gitignore` is ignored in favor of the
+    /// index's own setting.
+    pub fn search(
+        &self,
+        pattern: &str,
+        options: ContentSearchOptions,
+        cancel_flag: Option<Arc<AtomicBool>>,
+    ) -> anyhow::Result<ContentSearch> {
+        let query = plan_query(
+            &regex_pattern(pattern, options.mode),
+            options.case_insensitive,
+        );
+        let override_matcher =
+            build_override_matcher(&self.root, &options.include, &options.exclude)?;
+        let candidates = self.candidates(&query);
+        let files = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(id, file)| Some((id, file.as_ref()?)))
+            .filter(|(id, file)| match file.kind {
+                FileKind::Text => candidates
+                    .as_ref()
+                    .is_none_or(|candidates| candidates.binary_search(&(*id as FileId)).is_ok()),
+                FileKind::Binary => false,
+                FileKind::Unindexed => true,
+            })
+            .filter(|(_, file)| options.include_hidden || !is_hidden(&file.path))
+            .filter(|(_, file)| {
+                override_matcher
+                    .as_ref()
+                    .is_none_or(|matcher| !matcher.matched(&file.path, false).is_ignore())
+            })
+            .map(|(_, file)| self.root.join(&file.path))
+            .collect();
+        search_files(pattern, self.root.clone(), files, options, cancel_flag)
+    }
+
+    /// Writes the index to disk, compacting away removed files.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        self.compact();
+        let parent = self
+            .index_path
+            .parent()
+            .context("index path has no parent")?;
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+        let temp_path = self
+            .index_path
+            .with_extension(format!("tmp-{}", std::process::id()));
+        let mut writer = BufWriter::new(std::fs::File::create(&temp_path)?);
+        self.encode(&mut writer)?;
+        writer
+            .into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?
+            .sync_all()?;
+        std::fs::rename(&temp_path, &self.index_path)
+            .with_context(|| format!("failed to write index {}", self.index_path.display()))?;
+        self.modified = false;
+        Ok(())
+    }
+
+    /// Walks the whole root, reindexing files whose size or modification time
+    /// changed and removing those that no longer exist.
+    fn rescan(&mut self, cancel_flag: Option<&AtomicBool>) -> anyhow::Result<()> {
+        let found = self.walk(&self.root, cancel_flag)?;
+        let found_paths: HashSet<&PathBuf> = found.iter().map(|file| &file.path).collect();
+        let removed: Vec<PathBuf> = self
+            .ids
+            .keys()
+            .filter(|path| !found_paths.contains(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            self.remove(&path);
+        }
+        self.index_stale(found, cancel_flag)
+    }
+
+    /// Reindexes the files at or under the relative `paths`.
+    fn reindex(
+        &mut self,
+        paths: Vec<PathBuf>,
+        cancel_flag: Option<&AtomicBool>,
+    ) -> anyhow::Result<()> {
+        let exclude = build_override_matcher(&self.root, &[], &self.exclude)?;
+        let mut found = Vec::new();
+        for path in paths {
+            let full_path = self.root.join(&path);
+            let excluded = is_in_git_dir(&path)
+                || exclude
+                    .as_ref()
+                    .is_some_and(|matcher| matcher.matched(&path, full_path.is_dir()).is_ignore());
+            let metadata = std::fs::metadata(&full_path);
+            match metadata {
+                Ok(metadata) if !excluded && metadata.is_file() => {
+                    self.remove_under_dir(&path);
+                    found.push(stat(path, &metadata));
+                }
+                Ok(metadata) if !excluded && metadata.is_dir() => {
+                    self.remove_under(&path);
+                    found.extend(self.walk(&full_path, cancel_flag)?);
+                }
+                _ => self.remove_under(&path),
+            }
+        }
+        self.index_stale(found, cancel_flag)
+    }
+
+    /// Lists the files under `dir` that should be indexed, stopping early if
+    /// `cancel_flag` is set.
+    fn walk(
+        &self,
+        dir: &Path,
+        cancel_flag: Option<&AtomicBool>,
+    ) -> anyhow::Result<Vec<IndexedFile>> {
+        let override_matcher = build_override_matcher(&self.root, &[], &self.exclude)?;
+        let Some(mut walk_builder) = build_walker(
+            &[dir.to_path_buf()],
+            self.threads.get(),
+            self.respect_gitignore,
+            override_matcher,
+        ) else {
+            return Ok(Vec::new());
+        };
+        walk_builder.filter_entry(|entry| entry.file_name() != ".git");
+        let found = Mutex::new(Vec::new());
+        walk_builder.build_parallel().run(|| {
+            let found = &found;
+            Box::new(move |entry| {
+                if is_cancelled(cancel_flag) {
+                    return ignore::WalkState::Quit;
+                }
+                let Ok(entry) = entry else {
+                    return ignore::WalkState::Continue;
+                };
+                if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                    return ignore::WalkState::Continue;
+                }
+                let Ok(path) = entry.path().strip_prefix(&self.root) else {
+                    return ignore::WalkState::Continue;
+                };
+                if let Ok(metadata) = std::fs::metadata(entry.path()) {
+                    let file = stat(path.to_path_buf(), &metadata);
+                    found
+                        .lock()
+                        .unwrap_or_else(std::sync::PoisonError::into_inner)
+                        .push(file);
+                }
+                ignore::WalkState::Continue
+            })
+        });
+        Ok(found
+            .into_inner()
+            .unwrap_or_else(std::sync::PoisonError::into_inner))
+    }
+
+    /// Indexes those of `found` that are new or changed since they were last
+    /// indexed, reading them in parallel and stopping early if `cancel_flag`
+    /// is set.
+    fn index_stale(
+        &mut self,
+        found: Vec<IndexedFile>,
+        cancel_flag: Option<&AtomicBool>,
+    ) -> anyhow::Result<()> {
+        let stale: Vec<IndexedFile> = found
+            .into_iter()
+            .filter(|file| {
+                let indexed = self
+                    .ids
+                    .get(&file.path)
+                    .and_then(|id| self.files.get(*id as usize).and_then(Option::as_ref));
+                indexed.is_none_or(|indexed| {
+                    (indexed.size, indexed.modified) != (file.size, file.modified)
+                })
+            })
+            .collect();
+        if stale.is_empty() {
+            return Ok(());
+        }
+
+        let root = self.root.clone();
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = crossbeam_channel::bounded(64);
+        thread::scope(|scope| {
+            for _ in 0..self.threads.get().min(stale.len()) {
+                let tx = tx.clone();
+                let (root, stale, next) = (&root, &stale, &next);
+                scope.spawn(move || {
+                    while let Some(file) = stale.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        if is_cancelled(cancel_flag) {
+                            break;
+                        }
+                        let indexed = index_file(root, file);
+                        if tx.send(indexed).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+            for (file, trigrams) in rx {
+                self.insert(file, trigrams);
+            }
+        });
+        Ok(())
+    }
+
+    fn insert(&mut self, file: IndexedFile, trigrams: Vec<Trigram>) {
+        self.remove(&file.path);
+        let id = self.files.len() as FileId;
+        for trigram in trigrams {
+            // Ids only grow, so pushing keeps every posting list sorted.
+            self.postings.entry(trigram).or_default().push(id);
+        }
+        self.ids.insert(file.path.clone(), id);
+        self.files.push(Some(file));
+        self.modified = true;
+    }
+
+    /// Removes `path`; its postings are dropped on the next compaction.
+    fn remove(&mut self, path: &Path) {
+        if let Some(id) = self.ids.remove(path) {
+            self.files[id as usize] = None;
+            self.modified = true;
+        }
+    }
+
+    /// Removes `path` and every indexed file under it.
+    fn remove_under(&mut self, path: &Path) {
+        self.remove(path);
+        self.remove_under_dir(path);
+    }
+
+    /// Removes every indexed file under `path` but not `path` itself, for
+    /// when a directory has been replaced by a file.
+    fn remove_under_dir(&mut self, path: &Path) {
+        let under: Vec<PathBuf> = self
+            .ids
+            .keys()
+            .filter(|indexed| indexed.starts_with(path) && indexed.as_path() != path)
+            .cloned()
+            .collect();
+        for indexed in under {
+            self.remove(&indexed);
+        }
+    }
+
+    /// Returns the sorted ids of the text files that may match `query`, or
+    /// `None` if every file may.
+    fn candidates(&self, query: &Query) -> Option<Vec<FileId>> {
+        match query {
+            Query::All => None,
+            Query::Trigrams(trigrams) => {
+                let mut lists: Vec<&[FileId]> = trigrams
+                    .iter()
+                    .map(|trigram| self.postings.get(trigram).map_or(&[][..], Vec::as_slice))
+                    .collect();
+                lists.sort_by_key(|list| list.len());
+                let (first, rest) = lists.split_first()?;
+                Some(
+                    rest.iter()
+                        .fold(first.to_vec(), |acc, list| intersect(&acc, list)),
+                )
+            }
+            Query::And(queries) => queries
+                .iter()
+                .filter_map(|query| self.candidates(query))
+                .reduce(|acc, ids| intersect(&acc, &ids)),
+            Query::Or(queries) => queries
+                .iter()
+                .map(|query| self.candidates(query))
+                .try_fold(Vec::new(), |acc, ids| Some(union(&acc, &ids?))),
+        }
+    }
+
+    /// Drops removed files and renumbers the rest.
+    fn compact(&mut self) {
+        if self.files.iter().all(Option::is_some) {
+            return;
+        }
+        let mut remap: Vec<Option<FileId>> = Vec::with_capacity(self.files.len());
+        let mut files = Vec::with_capacity(self.ids.len());
+        for file in self.files.drain(..) {
+            remap.push(file.as_ref().map(|_| files.len() as FileId));
+            files.extend(file);
+        }
+        self.files = files.into_iter().map(Some).collect();
+        for ids in self.postings.values_mut() {
+            *ids = ids.iter().filter_map(|id| remap[*id as usize]).collect();
+        }
+        self.postings.retain(|_, ids| !ids.is_empty());
+        self.ids = self
+            .files
+            .iter()
+            .enumerate()
+            .filter_map(|(id, file)| Some((file.as_ref()?.path.clone(), id as FileId)))
+            .collect();
+    }
+
+    fn encode(&self, out: &mut impl Write) -> std::io::Result<()> {
+        out.write_all(INDEX_MAGIC)?;
+        write_str(out, &self.root.to_string_lossy())?;
+        write_varint(out, self.exclude.len() as u64)?;
+        for exclude in &self.exclude {
+            write_str(out, exclude)?;
+        }
+        out.write_all(&[u8::from(self.respect_gitignore)])?;
+        write_str(out, self.head.as_deref().unwrap_or_default())?;
+        write_varint(out, self.dirty.len() as u64)?;
+        for path in &self.dirty {
+            write_str(out, &path.to_string_lossy())?;
+        }
+        let files: Vec<&IndexedFile> = self.files.iter().flatten().collect();
+        write_varint(out, files.len() as u64)?;
+        for file in files {
+            write_str(out, &file.path.to_string_lossy())?;
+            write_varint(out, file.size)?;
+            write_varint(out, file.modified)?;
+            let kind = match file.kind {
+                FileKind::Text => 0,
+                FileKind::Binary => 1,
+                FileKind::Unindexed => 2,
+            };
+            out.write_all(&[kind])?;
+        }
+        write_varint(out, self.postings.len() as u64)?;
+        for (trigram, ids) in &self.postings {
+            out.write_all(trigram)?;
+            write_varint(out, ids.len() as u64)?;
+            // Delta-encode the sorted ids to keep the varints short.
+            let mut previous = 0;
+            for id in ids {
+                write_varint(out, u64::from(id - previous))?;
+                previous = *id;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the contents of `self` with the index in `contents`, if it was
+    /// built for the same root and options.
+    fn decode(&mut self, contents: &[u8]) -> anyhow::Result<()> {
+        let mut reader = Reader { contents };
+        anyhow::ensure!(reader.take(INDEX_MAGIC.len())? == INDEX_MAGIC, "bad magic");
+        anyhow::ensure!(
+            reader.read_str()? == self.root.to_string_lossy(),
+            "index is for another root"
+        );
+        let exclude = (0..reader.read_varint()?)
+            .map(|_| reader.read_str())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let respect_gitignore = reader.take(1)? == [1];
+        anyhow::ensure!(
+            exclude == self.exclude && respect_gitignore == self.respect_gitignore,
+            "index was built with other options"
+        );
+        let head = reader.read_str()?;
+        let dirty = (0..reader.read_varint()?)
+            .map(|_| reader.read_str().map(PathBuf::from))
+            .collect::<anyhow::Result<BTreeSet<_>>>()?;
+        let mut files = Vec::new();
+        for _ in 0..reader.read_varint()? {
+            let path = PathBuf::from(reader.read_str()?);
+            let size = reader.read_varint()?;
+            let modified = reader.read_varint()?;
+            let kind = match reader.take(1)? {
+                [0] => FileKind::Text,
+                [1] => FileKind::Binary,
+                [2] => FileKind::Unindexed,
+                _ => anyhow::bail!("unknown file kind"),
+            };
+            files.push(Some(IndexedFile {
+                path,
+                size,
+                modified,
+                kind,
+            }));
+        }
+        let mut postings = HashMap::new();
+        for _ in 0..reader.read_varint()? {
+            let trigram: Trigram = reader.take(3)?.try_into()?;
+            let mut ids = Vec::new();
+            let mut id = 0;
+            for _ in 0..reader.read_varint()? {
+                id += FileId::try_from(reader.read_varint()?)?;
+                anyhow::ensure!((id as usize) < files.len(), "file id out of range");
+                ids.push(id);
+            }
+            postings.insert(trigram, ids);
+        }
+
+        self.head = (!head.is_empty()).then_some(head);
+        self.dirty = dirty;
+        self.ids = files
+            .iter()
+            .enumerate()
+            .filter_map(|(id, file)| Some((file.as_ref()?.path.clone(), id as FileId)))
+            .collect();
+        self.files = files;
+        self.postings = postings;
+        self.modified = false;
+        Ok(())
+    }
+}
+
+/// Returns where the index for `root` is stored.
+fn index_path(codex_home: &Path, root: &Path) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(root.to_string_lossy().as_bytes());
+    let digest = hasher.finalize();
+    let hex = format!("{digest:x}");
+    let short = hex.get(..16).unwrap_or(hex.as_str());
+    codex_home.join(INDEX_DIR).join(format!("{short}.idx"))
+}
+
+/// Deletes all but the [`MAX_INDEXES`] most recently modified indexes in
+/// `codex_home`, never deleting `keep`. Failures are ignored, as another
+/// process may be pruning at the same time.
+fn prune_indexes(codex_home: &Path, keep: &Path) {
+    let Ok(entries) = std::fs::read_dir(codex_home.join(INDEX_DIR)) else {
+        return;
+    };
+    let mut indexes: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "idx") && path != keep)
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+    indexes.sort_by(|a, b| b.cmp(a));
+    for (_, path) in indexes.into_iter().skip(MAX_INDEXES.saturating_sub(1)) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn is_cancelled(cancel_flag: Option<&AtomicBool>) -> bool {
+    cancel_flag.is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+fn stat(path: PathBuf, metadata: &std::fs::Metadata) -> IndexedFile {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos() as u64);
+    IndexedFile {
+        path,
+        size: metadata.len(),
+        modified,
+        kind: FileKind::Text,
+    }
+}
+
+/// Reads `file` and returns it with its kind set and its sorted trigrams.
+/// Unreadable files are indexed as empty, as content search skips them.
+fn index_file(root: &Path, file: &IndexedFile) -> (IndexedFile, Vec<Trigram>) {
+    let mut file = file.clone();
+    if file.size > MAX_INDEXED_FILE_BYTES {
+        file.kind = FileKind::Unindexed;
+        return (file, Vec::new());
+    }
+    let contents = std::fs::read(root.join(&file.path)).unwrap_or_default();
+    if contents[..contents.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        file.kind = FileKind::Binary;
+        return (file, Vec::new());
+    }
+    let trigrams: HashSet<Trigram> = contents
+        .windows(3)
+        .map(|window| {
+            [
+                window[0].to_ascii_lowercase(),
+                window[1].to_ascii_lowercase(),
+                window[2].to_ascii_lowercase(),
+            ]
+        })
+        .collect();
+    let mut trigrams: Vec<Trigram> = trigrams.into_iter().collect();
+    trigrams.sort_unstable();
+    (file, trigrams)
+}
+
+/// Whether `path` is inside a `.git` directory, whose files are never indexed
+/// or searched.
+pub fn is_in_git_dir(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str() == ".git")
+}
+
+/// Whether any component of the relative `path` is hidden. Hidden files are
+/// indexed, so the index can serve searches that include them.
+fn is_hidden(path: &Path) -> bool {
+    path.components()
+        .any(|component| component.as_os_str().to_string_lossy().starts_with('.'))
+}
+
+fn intersect(a: &[FileId], b: &[FileId]) -> Vec<FileId> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+fn union(a: &[FileId], b: &[FileId]) -> Vec<FileId> {
+    let mut out: Vec<FileId> = a.iter().chain(b).copied().collect();
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// Trigrams that every file matching a regex must contain, after ASCII
+/// lowercasing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Query {
+    /// Any file may match.
+    All,
+    /// Files containing all of these trigrams.
+    Trigrams(BTreeSet<Trigram>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+/// Reduces `pattern` to a [`Query`]. Patterns that fail to parse match every
+/// file, leaving the regex compiler to report the error.
+fn plan_query(pattern: &str, case_insensitive: bool) -> Query {
+    ParserBuilder::new()
+        .case_insensitive(case_insensitive)
+        .multi_line(true)
+        .crlf(true)
+        .utf8(false)
+        .build()
+        .parse(pattern)
+        .map_or(Query::All, |hir| plan_hir(&hir))
+}
+
+fn plan_hir(hir: &Hir) -> Query {
+    match hir.kind() {
+        HirKind::Literal(literal) => literal_query(&literal.0),
+        HirKind::Repetition(repetition) if repetition.min > 0 => plan_hir(&repetition.sub),
+        HirKind::Capture(capture) => plan_hir(&capture.sub),
+        HirKind::Concat(subs) => {
+            // Adjacent literals, including case-folded letters, form one run
+            // so trigrams spanning them are used.
+            let mut queries = Vec::new();
+            let mut run = Vec::new();
+            for sub in subs {
+                match literal_bytes(sub) {
+                    Some(bytes) => run.extend(bytes),
+                    None => {
+                        queries.push(literal_query(&std::mem::take(&mut run)));
+                        queries.push(plan_hir(sub));
+                    }
+                }
+            }
+            queries.push(literal_query(&run));
+            and(queries)
+        }
+        HirKind::Alternation(subs) => or(subs.iter().map(plan_hir).collect()),
+        _ => Query::All,
+    }
+}
+
+/// Returns the lowercased bytes `hir` always matches, if it is a literal or
+/// a class of case variants of one ASCII character.
+fn literal_bytes(hir: &Hir) -> Option<Vec<u8>> {
+    match hir.kind() {
+        HirKind::Literal(literal) => Some(literal.0.to_ascii_lowercase()),
+        HirKind::Class(Class::Unicode(class)) => {
+            let mut chars = class
+                .ranges()
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .take(3);
+            let first = chars.next()?;
+            let byte = u8::try_from(first).ok()?.to_ascii_lowercase();
+            chars
+                .all(|c| u8::try_from(c).is_ok_and(|b| b.to_ascii_lowercase() == byte))
+                .then(|| vec![byte])
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let mut bytes = class
+                .ranges()
+                .iter()
+                .flat_map(|range| range.start()..=range.end())
+                .take(3);
+            let byte = bytes.next()?.to_ascii_lowercase();
+            bytes
+                .all(|b| b.to_ascii_lowercase() == byte)
+                .then(|| vec![byte])
+        }
+        _ => None,
+    }
+}
+
+fn literal_query(bytes: &[u8]) -> Query {
+    if bytes.len() < 3 {
+        return Query::All;
+    }
+    Query::Trigrams(
+        bytes
+            .windows(3)
+            .map(|window| {
+                [
+                    window[0].to_ascii_lowercase(),
+                    window[1].to_ascii_lowercase(),
+                    window[2].to_ascii_lowercase(),
+                ]
+            })
+            .collect(),
+    )
+}
+
+fn and(queries: Vec<Query>) -> Query {
+    let mut trigrams = BTreeSet::new();
+    let mut rest = Vec::new();
+    for query in queries {
+        match query {
+            Query::All => {}
+            Query::Trigrams(more) => trigrams.extend(more),
+            Query::And(queries) => rest.extend(queries),
+            Query::Or(_) => rest.push(query),
+        }
+    }
+    if !trigrams.is_empty() {
+        rest.push(Query::Trigrams(trigrams));
+    }
+    match rest.len() {
+        0 => Query::All,
+        1 => rest.pop().unwrap_or(Query::All),
+        _ => Query::And(rest),
+    }
+}
+
+fn or(mut queries: Vec<Query>) -> Query {
+    if queries.is_empty() || queries.contains(&Query::All) {
+        return Query::All;
+    }
+    if queries.len() == 1 {
+        return queries.pop().unwrap_or(Query::All);
+    }
+    Query::Or(queries)
+}
+
+/// The git repository containing an index root.
+struct GitRepo {
+    root: PathBuf,
+    /// The index root relative to the top of the work tree, with a trailing
+    /// slash unless empty.
+    prefix: String,
+    head: String,
+}
+
+impl GitRepo {
+    fn discover(root: &Path) -> Option<Self> {
+        let output = git(root, &["rev-parse", "--show-prefix", "HEAD"])?;
+        let output = String::from_utf8(output).ok()?;
+        let mut lines = output.lines();
+        let prefix = lines.next()?.to_string();
+        let head = lines.next()?.to_string();
+        Some(Self {
+            root: root.to_path_buf(),
+            prefix,
+            head,
+        })
+    }
+
+    /// Returns the paths changed between `old_head` and the work tree, and
+    /// the subset of those with uncommitted changes.
+    fn changes_since(&self, old_head: &str) -> Option<(Vec<PathBuf>, BTreeSet<PathBuf>)> {
+        let mut changed = Vec::new();
+        if old_head != self.head {
+            let output = git(
+                &self.root,
+                &[
+                    "diff",
+                    "--name-only",
+                    "--relative",
+                    "--no-renames",
+                    "-z",
+                    old_head,
+                    &self.head,
+                ],
+            )?;
+            changed.extend(
+                output
+                    .split(|byte| *byte == 0)
+                    .filter(|path| !path.is_empty())
+                    .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned())),
+            );
+        }
+        let dirty = self.dirty_paths()?;
+        Some((changed, dirty))
+    }
+
+    /// Paths under the index root that are modified, staged, deleted or
+    /// untracked (but not ignored).
+    fn dirty_paths(&self) -> Option<BTreeSet<PathBuf>> {
+        let output = git(
+            &self.root,
+            &[
+                "status",
+                "--porcelain",
+                "--untracked-files=all",
+                "--no-renames",
+                "-z",
+                "--",
+                ".",
+            ],
+        )?;
+        let paths = output
+            .split(|byte| *byte == 0)
+            .filter_map(|entry| entry.get(3..))
+            .filter_map(|path| std::str::from_utf8(path).ok())
+            // Porcelain paths are relative to the top of the work tree.
+            .filter_map(|path| path.strip_prefix(self.prefix.as_str()))
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .collect();
+        Some(paths)
+    }
+}
+
+/// Returns which of the relative `paths` git ignores, or `None` if git could
+/// not tell, for example outside a repository.
+fn check_ignore(root: &Path, paths: &[PathBuf]) -> Option<HashSet<PathBuf>> {
+    if paths.is_empty() {
+        return Some(HashSet::new());
+    }
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["check-ignore", "-z", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut input = Vec::new();
+    for path in paths {
+        input.extend_from_slice(path.to_string_lossy().as_bytes());
+        input.push(0);
+    }
+    let mut stdin = child.stdin.take()?;
+    // Write from another thread so a full stdout pipe cannot deadlock us.
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().ok()?;
+    writer.join().ok()?.ok()?;
+    // Exit status 1 means that no path is ignored.
+    if !matches!(output.status.code(), Some(0 | 1)) {
+        return None;
+    }
+    Some(
+        output
+            .stdout
+            .split(|byte| *byte == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(String::from_utf8_lossy(path).into_owned()))
+            .collect(),
+    )
+}
+
+fn git(root: &Path, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+fn write_varint(out: &mut impl Write, mut value: u64) -> std::io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn write_str(out: &mut impl Write, value: &str) -> std::io::Result<()> {
+    write_varint(out, value.len() as u64)?;
+    out.write_all(value.as_bytes())
+}
+
+struct Reader<'a> {
+    contents: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        anyhow::ensure!(self.contents.len() >= len, "index is truncated");
+        let (taken, rest) = self.contents.split_at(len);
+        self.contents = rest;
+        Ok(taken)
+    }
+
+    fn read_varint(&mut self) -> anyhow::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let [byte] = self.take(1)? else {
+                anyhow::bail!("index is truncated");
+            };
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        anyhow::bail!("varint is too long")
+    }
+
+    fn read_str(&mut self) -> anyhow::Result<String> {
+        let len = usize::try_from(self.read_varint()?)?;
+        Ok(String::from_utf8(self.take(len)?.to_vec())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::ContentMatch;
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    fn trigrams(literals: &[&str]) -> Query {
+        Query::Trigrams(
+            literals
+                .iter()
+                .flat_map(|literal| match literal_query(literal.as_bytes()) {
+                    Query::Trigrams(trigrams) => trigrams,
+                    _ => BTreeSet::new(),
+                })
+                .collect(),
+        )
+    }
+
+    fn search(index: &TrigramIndex, pattern: &str, options: ContentSearchOptions) -> Vec<String> {
+        let mut paths: Vec<String> = index
+            .search(pattern, options, None)
+            .unwrap()
+            .map(|content_match: ContentMatch| content_match.path.to_string_lossy().into_owned())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn plans_trigram_queries_from_regexes() {
+        assert_eq!(
+            plan_query("foo(bar|qux)", false),
+            and(vec![
+                or(vec![trigrams(&["bar"]), trigrams(&["qux"])]),
+                trigrams(&["foo"]),
+            ])
+        );
+        assert_eq!(plan_query("Hello", true), trigrams(&["hello"]));
+        assert_eq!(plan_query(r"ab\w+cd", false), Query::All);
+        assert_eq!(plan_query("(foo)?bar", false), trigrams(&["bar"]));
+        assert_eq!(plan_query("foo|.*", false), Query::All);
+        assert_eq!(plan_query("(unclosed", false), Query::All);
+        // The Kelvin sign folds to `k`, so a case-insensitive `k` is not a
+        // literal.
+        assert_eq!(plan_query("kid", true), Query::All);
+    }
+
+    #[test]
+    fn finds_matches_and_picks_up_changes_outside_git() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/a.rs"), "fn Alpha() {}\n").unwrap();
+        fs::write(root.join("src/b.rs"), "fn beta() {}\n").unwrap();
+        fs::write(root.join("skip.log"), "fn alpha() {}\n").unwrap();
+        fs::write(root.join("bin.dat"), b"alpha\0").unwrap();
+        let options = FileSearchOptions {
+            exclude: vec!["*.log".to_string()],
+            ..Default::default()
+        };
+
+        let mut index = TrigramIndex::open(codex_home.path(), root, &options, None).unwrap();
+        assert_eq!(
+            search(&index, "alpha", ContentSearchOptions::default()),
+            Vec::<String>::new()
+        );
+        let case_insensitive = ContentSearchOptions {
+            case_insensitive: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            search(&index, "alpha", case_insensitive.clone()),
+            vec!["src/a.rs"]
+        );
+        assert_eq!(
+            search(&index, r"fn \w+\(", ContentSearchOptions::default()),
+            vec!["src/a.rs", "src/b.rs"]
+        );
+
+        fs::write(root.join("src/b.rs"), "fn alphabet() {}\n").unwrap();
+        fs::remove_file(root.join("src/a.rs")).unwrap();
+        index
+            .update_paths(&[root.join("src/a.rs"), root.join("src/b.rs")])
+            .unwrap();
+        assert_eq!(
+            search(&index, "alpha", case_insensitive.clone()),
+            vec!["src/b.rs"]
+        );
+        index.save().unwrap();
+
+        fs::write(root.join("src/c.rs"), "const ALPHA: u8 = 1;\n").unwrap();
+        let reopened = TrigramIndex::open(codex_home.path(), root, &options, None).unwrap();
+        assert_eq!(
+            search(&reopened, "alpha", case_insensitive),
+            vec!["src/b.rs", "src/c.rs"]
+        );
+    }
+
+    #[test]
+    fn searches_hidden_files_only_when_asked() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".config")).unwrap();
+        fs::write(root.join(".config/app.toml"), "needle\n").unwrap();
+        fs::write(root.join(".env"), "needle\n").unwrap();
+        fs::write(root.join("visible.txt"), "needle\n").unwrap();
+
+        let index =
+            TrigramIndex::open(codex_home.path(), root, &FileSearchOptions::default(), None)
+                .unwrap();
+        assert_eq!(
+            search(&index, "needle", ContentSearchOptions::default()),
+            vec!["visible.txt"]
+        );
+        let include_hidden = ContentSearchOptions {
+            include_hidden: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            search(&index, "needle", include_hidden),
+            vec![".config/app.toml", ".env", "visible.txt"]
+        );
+    }
+
+    #[test]
+    fn refreshes_from_git_head_and_status() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git(root, &["init", "-q"]);
+        fs::write(root.join(".gitignore"), "ignored.txt\n").unwrap();
+        fs::write(root.join("tracked.txt"), "needle\n").unwrap();
+        fs::write(root.join("ignored.txt"), "needle\n").unwrap();
+        git(root, &["add", "."]);
+        git(root, &["commit", "-q", "-m", "initial"]);
+
+        let options = FileSearchOptions::default();
+        let index = TrigramIndex::open(codex_home.path(), root, &options, None).unwrap();
+        assert_eq!(
+            search(&index, "needle", ContentSearchOptions::default()),
+            vec!["tracked.txt"]
+        );
+
+        // A committed change and an untracked file are both picked up.
+        fs::write(root.join("tracked.txt"), "haystack\n").unwrap();
+        git(root, &["commit", "-q", "-am", "change"]);
+        fs::write(root.join("untracked.txt"), "needle\n").unwrap();
+        let mut index = TrigramIndex::open(codex_home.path(), root, &options, None).unwrap();
+        assert_eq!(
+            search(&index, "needle", ContentSearchOptions::default()),
+            vec!["untracked.txt"]
+        );
+
+        // A file that is no longer dirty is still rechecked.
+        fs::remove_file(root.join("untracked.txt")).unwrap();
+        index.refresh(None).unwrap();
+        assert_eq!(
+            search(&index, "needle", ContentSearchOptions::default()),
+            Vec::<String>::new()
+        );
+
+        // Watcher updates honor .gitignore.
+        index.update_paths(&[root.join("ignored.txt")]).unwrap();
+        assert_eq!(
+            search(&index, "needle", ContentSearchOptions::default()),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn cancelled_open_fails_without_saving() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.txt"), "needle\n").unwrap();
+
+        let cancel_flag = Arc::new(AtomicBool::new(true));
+        let options = FileSearchOptions::default();
+        assert!(TrigramIndex::open(codex_home.path(), root, &options, Some(cancel_flag)).is_err());
+        assert!(!index_path(codex_home.path(), root).exists());
+    }
+
+    #[test]
+    fn prunes_least_recently_opened_indexes() {
+        let codex_home = tempfile::tempdir().unwrap();
+        let roots: Vec<tempfile::TempDir> = (0..MAX_INDEXES)
+            .map(|_| tempfile::tempdir().unwrap())
+            .collect();
+        let options = FileSearchOptions::default();
+        let epoch = SystemTime::now() - std::time::Duration::from_secs(3600);
+        for (age, root) in roots.iter().enumerate() {
+            fs::write(root.path().join("a.txt"), "needle\n").unwrap();
+            TrigramIndex::open(codex_home.path(), root.path(), &options, None).unwrap();
+            // Give each index a distinct age, oldest first.
+            fs::File::options()
+                .write(true)
+                .open(index_path(codex_home.path(), root.path()))
+                .unwrap()
+                .set_modified(epoch + std::time::Duration::from_secs(age as u64))
+                .unwrap();
+        }
+
+        // Reopening the oldest index marks it as used, so opening one more
+        // prunes the next oldest instead.
+        TrigramIndex::open(codex_home.path(), roots[0].path(), &options, None).unwrap();
+        let extra = tempfile::tempdir().unwrap();
+        TrigramIndex::open(codex_home.path(), extra.path(), &options, None).unwrap();
+        let kept: Vec<bool> = roots
+            .iter()
+            .chain([&extra])
+            .map(|root| index_path(codex_home.path(), root.path()).exists())
+            .collect();
+        let mut expected = vec![true; MAX_INDEXES + 1];
+        expected[1] = false;
+        assert_eq!(kept, expected);
+    }
+}
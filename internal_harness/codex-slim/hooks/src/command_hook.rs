@@ -0,0 +1,297 @@
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+
+use crate::Hook;
+use crate::HookEvent;
+use crate::HookPayload;
+use crate::HookResult;
+use crate::HookToolIntervention;
+use crate::command_from_argv;
+
+/// How long a hook command may run when its config sets no timeout.
+pub const DEFAULT_HOOK_COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An external command run as a hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookCommand {
+    pub argv: Vec<String>,
+    /// Defaults to [`DEFAULT_HOOK_COMMAND_TIMEOUT`].
+    pub timeout: Option<Duration>,
+    /// Let a `before_tool_use` call proceed when the command fails instead of
+    /// blocking it.
+    pub fail_open: bool,
+}
+
+/// Builds a hook that runs `command` with the hook payload as JSON on stdin.
+///
+/// For `before_tool_use` the command may print a JSON [`HookToolIntervention`] on
+/// stdout to deny, rewrite or annotate the call; empty output lets the call proceed
+/// unchanged. Other events ignore stdout. A non-zero exit status, a timeout or
+/// unparsable output is reported as a failure and the operation proceeds, except
+/// that a failed `before_tool_use` command denies the call unless
+/// [`HookCommand::fail_open`] is set: a policy hook that crashes must not let
+/// every call through.
+pub fn command_hook(name: &str, command: HookCommand) -> Hook {
+    let command = Arc::new(command);
+    Hook {
+        name: name.to_string(),
+        func: Arc::new(move |payload: &HookPayload| {
+            let command = Arc::clone(&command);
+            Box::pin(async move {
+                let timeout = command.timeout.unwrap_or(DEFAULT_HOOK_COMMAND_TIMEOUT);
+                let stdout =
+                    match tokio::time::timeout(timeout, run_hook_command(&command.argv, payload))
+                        .await
+                    {
+                        Ok(Ok(stdout)) => stdout,
+                        Ok(Err(err)) => {
+                            return hook_failure(payload, command.fail_open, err.into());
+                        }
+                        Err(_) => {
+                            return hook_failure(
+                                payload,
+                                command.fail_open,
+                                format!("hook command timed out after {}ms", timeout.as_millis())
+                                    .into(),
+                            );
+                        }
+                    };
+                match payload.hook_event {
+                    HookEvent::BeforeToolUse { .. } => parse_intervention(&stdout)
+                        .unwrap_or_else(|err| hook_failure(payload, command.fail_open, err.into())),
+                    _ => HookResult::Success,
+                }
+            })
+        }),
+    }
+}
+
+/// Spawns `argv`, writes `payload` to its stdin and returns what it printed on stdout.
+async fn run_hook_command(argv: &[String], payload: &HookPayload) -> std::io::Result<String> {
+    let mut command =
+        command_from_argv(argv).ok_or_else(|| std::io::Error::other("hook command is empty"))?;
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command.spawn()?;
+    let input = serde_json::to_vec(payload).map_err(std::io::Error::other)?;
+    if let Some(mut stdin) = child.stdin.take()
+        && let Err(err) = stdin.write_all(&input).await
+        // Hooks that decide without reading the payload may exit first.
+        && err.kind() != std::io::ErrorKind::BrokenPipe
+    {
+        return Err(err);
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!(
+            "hook command exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The result of a command that failed with `error`.
+fn hook_failure(
+    payload: &HookPayload,
+    fail_open: bool,
+    error: Box<dyn std::error::Error + Send + Sync + 'static>,
+) -> HookResult {
+    match payload.hook_event {
+        HookEvent::BeforeToolUse { .. } if !fail_open => {
+            HookResult::Intervene(HookToolIntervention::Deny {
+                message: format!("Blocked because the before_tool_use hook failed: {error}"),
+            })
+        }
+        _ => HookResult::FailedContinue(error),
+    }
+}
+
+fn parse_intervention(stdout: &str) -> serde_json::Result<HookResult> {
+    let stdout = stdout.trim();
+    if stdout.is_empty() {
+        return Ok(HookResult::Success);
+    }
+    serde_json::from_str::<HookToolIntervention>(stdout).map(HookResult::Intervene)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::TimeZone;
+    use chrono::Utc;
+    use codex_protocol::ThreadId;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::HookEventBeforeToolUse;
+    use crate::HookEventError;
+    use crate::HookToolInput;
+    use crate::HookToolKind;
+
+    fn before_tool_use_payload(arguments: &str) -> HookPayload {
+        HookPayload {
+            session_id: ThreadId::new(),
+            cwd: PathBuf::from("/tmp"),
+            triggered_at: Utc
+                .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+                .single()
+                .expect("valid timestamp"),
+            hook_event: HookEvent::BeforeToolUse {
+                event: HookEventBeforeToolUse {
+                    turn_id: "turn-1".to_string(),
+                    call_id: "call-1".to_string(),
+                    tool_name: "shell".to_string(),
+                    tool_kind: HookToolKind::Function,
+                    tool_input: HookToolInput::Function {
+                        arguments: arguments.to_string(),
+                    },
+                    mutating: true,
+                    sandbox: "none".to_string(),
+                    sandbox_policy: "workspace-write".to_string(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn empty_output_lets_the_call_proceed() {
+        assert!(matches!(parse_intervention("\n"), Ok(HookResult::Success)));
+        assert!(parse_intervention("not json").is_err());
+    }
+
+    fn shell_hook(script: &str, timeout: Option<Duration>) -> Hook {
+        command_hook(
+            "test_command",
+            HookCommand {
+                argv: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+                timeout,
+                fail_open: true,
+            },
+        )
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn command_reads_payload_from_stdin_and_denies() {
+        let hook = shell_hook(
+            r#"if grep -q migrate; then echo '{"decision":"deny","message":"no migrations"}'; fi"#,
+            None,
+        );
+
+        let denied = hook
+            .execute(&before_tool_use_payload(r#"{"cmd":"make migrate"}"#))
+            .await;
+        let allowed = hook
+            .execute(&before_tool_use_payload(r#"{"cmd":"make test"}"#))
+            .await;
+
+        match denied.result {
+            HookResult::Intervene(intervention) => assert_eq!(
+                intervention,
+                HookToolIntervention::Deny {
+                    message: "no migrations".to_string(),
+                }
+            ),
+            other => panic!("expected deny, got {other:?}"),
+        }
+        assert!(matches!(allowed.result, HookResult::Success));
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn failing_fail_open_command_continues() {
+        let hook = shell_hook("cat >/dev/null; echo broken >&2; exit 3", None);
+
+        let outcome = hook.execute(&before_tool_use_payload("{}")).await;
+
+        match outcome.result {
+            HookResult::FailedContinue(err) => {
+                assert!(err.to_string().contains("broken"), "{err}");
+            }
+            other => panic!("expected failure, got {other:?}"),
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn failing_command_blocks_the_call_unless_fail_open() {
+        let hook = command_hook(
+            "test_command",
+            HookCommand {
+                argv: vec![
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    "echo '{'".to_string(),
+                ],
+                timeout: None,
+                fail_open: false,
+            },
+        );
+
+        let outcome = hook.execute(&before_tool_use_payload("{}")).await;
+
+        match outcome.result {
+            HookResult::Intervene(HookToolIntervention::Deny { message }) => {
+                assert!(message.starts_with("Blocked because the before_tool_use hook failed"));
+            }
+            other => panic!("expected deny, got {other:?}"),
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn slow_command_times_out() {
+        let hook = shell_hook("sleep 5", Some(Duration::from_millis(50)));
+
+        let outcome = hook.execute(&before_tool_use_payload("{}")).await;
+
+        match outcome.result {
+            HookResult::FailedContinue(err) => {
+                assert!(err.to_string().contains("timed out"), "{err}");
+            }
+            other => panic!("expected timeout, got {other:?}"),
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn lifecycle_events_ignore_command_output() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let log = dir.path().join("events.jsonl");
+        let hook = shell_hook(
+            &format!(
+                r#"cat >> "{}"; echo '{{"decision":"deny","message":"ignored"}}'"#,
+                log.display()
+            ),
+            None,
+        );
+        let payload = HookPayload {
+            hook_event: HookEvent::Error {
+                event: HookEventError {
+                    turn_id: "turn-1".to_string(),
+                    message: "stream disconnected".to_string(),
+                },
+            },
+            ..before_tool_use_payload("{}")
+        };
+
+        let outcome = hook.execute(&payload).await;
+
+        assert!(matches!(outcome.result, HookResult::Success));
+        let logged: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&log).expect("read log"))
+                .expect("payload is json");
+        assert_eq!(logged["hook_event"]["event_type"], "error");
+        assert_eq!(logged["hook_event"]["message"], "stream disconnected");
+    }
+}
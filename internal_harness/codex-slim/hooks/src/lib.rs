@@ -1,19 +1,33 @@
+mod command_hook;
 mod registry;
 mod types;
 mod user_notification;
 
+pub use command_hook::DEFAULT_HOOK_COMMAND_TIMEOUT;
+pub use command_hook::HookCommand;
+pub use command_hook::command_hook;
 pub use registry::Hooks;
 pub use registry::HooksConfig;
 pub use registry::command_from_argv;
 pub use types::Hook;
+pub use types::HookApprovalRequest;
+pub use types::HookCompactionTrigger;
 pub use types::HookEvent;
 pub use types::HookEventAfterAgent;
 pub use types::HookEventAfterToolUse;
+pub use types::HookEventApprovalRequested;
+pub use types::HookEventBeforeCompaction;
+pub use types::HookEventBeforeToolUse;
+pub use types::HookEventError;
+pub use types::HookEventSessionEnd;
+pub use types::HookEventSessionStart;
+pub use types::HookEventTurnStarted;
 pub use types::HookPayload;
 pub use types::HookResponse;
 pub use types::HookResult;
 pub use types::HookToolInput;
 pub use types::HookToolInputLocalShell;
+pub use types::HookToolIntervention;
 pub use types::HookToolKind;
 pub use user_notification::legacy_notify_json;
 pub use user_notification::notify_hook;
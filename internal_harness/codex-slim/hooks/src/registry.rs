@@ -1,18 +1,36 @@
 use tokio::process::Command;
 
+use crate::command_hook::HookCommand;
 use crate::types::Hook;
 use crate::types::HookEvent;
 use crate::types::HookPayload;
 use crate::types::HookResponse;
+use crate::types::HookResult;
+use crate::types::HookToolIntervention;
 
+/// Hook configuration. Each command runs as described in [`crate::command_hook`].
 #[derive(Default, Clone)]
 pub struct HooksConfig {
     pub legacy_notify_argv: Option<Vec<String>>,
+    pub session_start: Option<HookCommand>,
+    pub session_end: Option<HookCommand>,
+    pub turn_started: Option<HookCommand>,
+    pub before_compaction: Option<HookCommand>,
+    pub before_tool_use: Option<HookCommand>,
+    pub approval_requested: Option<HookCommand>,
+    pub error: Option<HookCommand>,
 }
 
 #[derive(Clone)]
 pub struct Hooks {
+    session_start: Vec<Hook>,
+    session_end: Vec<Hook>,
+    turn_started: Vec<Hook>,
+    before_compaction: Vec<Hook>,
+    approval_requested: Vec<Hook>,
+    error: Vec<Hook>,
     after_agent: Vec<Hook>,
+    before_tool_use: Vec<Hook>,
     after_tool_use: Vec<Hook>,
 }
 
@@ -23,7 +41,7 @@ impl Default for Hooks {
 }
 
 // Hooks are arbitrary, user-specified functions that are deterministically
-// executed after specific events in the Codex lifecycle.
+// executed around specific events in the Codex lifecycle.
 impl Hooks {
     pub fn new(config: HooksConfig) -> Self {
         let after_agent = config
@@ -33,26 +51,50 @@ impl Hooks {
             .into_iter()
             .collect();
         Self {
+            session_start: command_hooks("session_start_command", config.session_start),
+            session_end: command_hooks("session_end_command", config.session_end),
+            turn_started: command_hooks("turn_started_command", config.turn_started),
+            before_compaction: command_hooks("before_compaction_command", config.before_compaction),
+            approval_requested: command_hooks(
+                "approval_requested_command",
+                config.approval_requested,
+            ),
+            error: command_hooks("error_command", config.error),
             after_agent,
+            before_tool_use: command_hooks("before_tool_use_command", config.before_tool_use),
             after_tool_use: Vec::new(),
         }
     }
 
     fn hooks_for_event(&self, hook_event: &HookEvent) -> &[Hook] {
         match hook_event {
+            HookEvent::SessionStart { .. } => &self.session_start,
+            HookEvent::SessionEnd { .. } => &self.session_end,
+            HookEvent::TurnStarted { .. } => &self.turn_started,
+            HookEvent::BeforeCompaction { .. } => &self.before_compaction,
+            HookEvent::ApprovalRequested { .. } => &self.approval_requested,
+            HookEvent::Error { .. } => &self.error,
             HookEvent::AfterAgent { .. } => &self.after_agent,
+            HookEvent::BeforeToolUse { .. } => &self.before_tool_use,
             HookEvent::AfterToolUse { .. } => &self.after_tool_use,
         }
     }
 
-    pub async fn dispatch(&self, hook_payload: HookPayload) -> Vec<HookResponse> {
+    pub async fn dispatch(&self, mut hook_payload: HookPayload) -> Vec<HookResponse> {
         let hooks = self.hooks_for_event(&hook_payload.hook_event);
         let mut outcomes = Vec::with_capacity(hooks.len());
         for hook in hooks {
             let outcome = hook.execute(&hook_payload).await;
-            let should_abort_operation = outcome.result.should_abort_operation();
+            let should_stop_dispatch = outcome.result.should_stop_dispatch();
+            // Later hooks see the tool input as rewritten by earlier ones.
+            if let HookResult::Intervene(HookToolIntervention::ReplaceInput { tool_input }) =
+                &outcome.result
+                && let HookEvent::BeforeToolUse { event } = &mut hook_payload.hook_event
+            {
+                event.tool_input = tool_input.clone();
+            }
             outcomes.push(outcome);
-            if should_abort_operation {
+            if should_stop_dispatch {
                 break;
             }
         }
@@ -61,6 +103,14 @@ impl Hooks {
     }
 }
 
+fn command_hooks(name: &str, command: Option<HookCommand>) -> Vec<Hook> {
+    command
+        .filter(|command| !command.argv.is_empty() && !command.argv[0].is_empty())
+        .map(|command| crate::command_hook(name, command))
+        .into_iter()
+        .collect()
+}
+
 pub fn command_from_argv(argv: &[String]) -> Option<Command> {
     let (program, args) = argv.split_first()?;
     if program.is_empty() {
@@ -93,7 +143,7 @@ mod tests {
     use super::*;
     use crate::types::HookEventAfterAgent;
     use crate::types::HookEventAfterToolUse;
-    use crate::types::HookResult;
+    use crate::types::HookEventBeforeToolUse;
     use crate::types::HookToolInput;
     use crate::types::HookToolKind;
 
@@ -197,6 +247,41 @@ mod tests {
         }
     }
 
+    fn before_tool_use_payload(arguments: &str) -> HookPayload {
+        HookPayload {
+            session_id: ThreadId::new(),
+            cwd: PathBuf::from(CWD),
+            triggered_at: Utc
+                .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+                .single()
+                .expect("valid timestamp"),
+            hook_event: HookEvent::BeforeToolUse {
+                event: HookEventBeforeToolUse {
+                    turn_id: "turn-before".to_string(),
+                    call_id: "call-before".to_string(),
+                    tool_name: "shell".to_string(),
+                    tool_kind: HookToolKind::Function,
+                    tool_input: HookToolInput::Function {
+                        arguments: arguments.to_string(),
+                    },
+                    mutating: true,
+                    sandbox: "none".to_string(),
+                    sandbox_policy: "workspace-write".to_string(),
+                },
+            },
+        }
+    }
+
+    fn intervening_hook(name: &str, intervention: HookToolIntervention) -> Hook {
+        Hook {
+            name: name.to_string(),
+            func: Arc::new(move |_| {
+                let intervention = intervention.clone();
+                Box::pin(async move { HookResult::Intervene(intervention) })
+            }),
+        }
+    }
+
     #[test]
     fn command_from_argv_returns_none_for_empty_args() {
         assert!(command_from_argv(&[]).is_none());
@@ -229,6 +314,7 @@ mod tests {
         assert!(
             Hooks::new(HooksConfig {
                 legacy_notify_argv: Some(vec![]),
+                ..HooksConfig::default()
             })
             .after_agent
             .is_empty()
@@ -236,6 +322,7 @@ mod tests {
         assert!(
             Hooks::new(HooksConfig {
                 legacy_notify_argv: Some(vec!["".to_string()]),
+                ..HooksConfig::default()
             })
             .after_agent
             .is_empty()
@@ -243,6 +330,7 @@ mod tests {
         assert_eq!(
             Hooks::new(HooksConfig {
                 legacy_notify_argv: Some(vec!["notify-send".to_string()]),
+                ..HooksConfig::default()
             })
             .after_agent
             .len(),
@@ -250,6 +338,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hooks_new_registers_lifecycle_commands_per_event() {
+        let command = |program: &str| {
+            Some(HookCommand {
+                argv: vec![program.to_string()],
+                timeout: None,
+                fail_open: false,
+            })
+        };
+        let hooks = Hooks::new(HooksConfig {
+            session_start: command("audit-log"),
+            error: command("page-oncall"),
+            turn_started: command(""),
+            ..HooksConfig::default()
+        });
+
+        let names = |hooks: &[Hook]| -> Vec<String> {
+            hooks.iter().map(|hook| hook.name.clone()).collect()
+        };
+        assert_eq!(names(&hooks.session_start), vec!["session_start_command"]);
+        assert_eq!(names(&hooks.error), vec!["error_command"]);
+        assert!(hooks.turn_started.is_empty());
+        assert!(hooks.session_end.is_empty());
+    }
+
     #[tokio::test]
     async fn dispatch_executes_hook() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -326,6 +439,68 @@ mod tests {
         assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
+    #[tokio::test]
+    async fn dispatch_stops_before_tool_use_hooks_after_deny() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let hooks = Hooks {
+            before_tool_use: vec![
+                intervening_hook(
+                    "deny",
+                    HookToolIntervention::Deny {
+                        message: "no migrations".to_string(),
+                    },
+                ),
+                counting_success_hook(&calls, "counting"),
+            ],
+            ..Hooks::default()
+        };
+
+        let outcomes = hooks
+            .dispatch(before_tool_use_payload(r#"{"cmd":"make migrate"}"#))
+            .await;
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].hook_name, "deny");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn dispatch_passes_replaced_tool_input_to_later_hooks() {
+        let seen = Arc::new(std::sync::Mutex::new(None));
+        let seen_by_hook = Arc::clone(&seen);
+        let recording_hook = Hook {
+            name: "recording".to_string(),
+            func: Arc::new(move |payload: &HookPayload| {
+                if let HookEvent::BeforeToolUse { event } = &payload.hook_event
+                    && let Ok(mut seen) = seen_by_hook.lock()
+                {
+                    *seen = Some(event.tool_input.clone());
+                }
+                Box::pin(async { HookResult::Success })
+            }),
+        };
+        let rewritten = HookToolInput::Function {
+            arguments: r#"{"cmd":"make test"}"#.to_string(),
+        };
+        let hooks = Hooks {
+            before_tool_use: vec![
+                intervening_hook(
+                    "rewrite",
+                    HookToolIntervention::ReplaceInput {
+                        tool_input: rewritten.clone(),
+                    },
+                ),
+                recording_hook,
+            ],
+            ..Hooks::default()
+        };
+
+        let outcomes = hooks
+            .dispatch(before_tool_use_payload(r#"{"cmd":"make migrate"}"#))
+            .await;
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(*seen.lock().expect("lock"), Some(rewritten));
+    }
+
     #[tokio::test]
     async fn dispatch_continues_after_continueable_failure() {
         let calls = Arc::new(AtomicUsize::new(0));
@@ -7,6 +7,7 @@ use chrono::Utc;
 use codex_protocol::ThreadId;
 use codex_protocol::models::SandboxPermissions;
 use futures::future::BoxFuture;
+use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
 
@@ -16,6 +17,9 @@ pub type HookFn = Arc<dyn for<'a> Fn(&'a HookPayload) -> BoxFuture<'a, HookResul
 pub enum HookResult {
     /// Success: hook completed successfully.
     Success,
+    /// Intervene: hook completed successfully and asks to change the pending tool call.
+    /// Only honored for `before_tool_use`; other events treat it as `Success`.
+    Intervene(HookToolIntervention),
     /// FailedContinue: hook failed, but other subsequent hooks should still execute and the
     /// operation should continue.
     FailedContinue(Box<dyn std::error::Error + Send + Sync + 'static>),
@@ -28,6 +32,27 @@ impl HookResult {
     pub fn should_abort_operation(&self) -> bool {
         matches!(self, Self::FailedAbort(_))
     }
+
+    /// Whether hooks registered after this one should be skipped.
+    pub fn should_stop_dispatch(&self) -> bool {
+        matches!(
+            self,
+            Self::FailedAbort(_) | Self::Intervene(HookToolIntervention::Deny { .. })
+        )
+    }
+}
+
+/// How a `before_tool_use` hook changes the pending tool call.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum HookToolIntervention {
+    /// Do not run the tool; `message` is returned to the model as the call's output.
+    Deny { message: String },
+    /// Run the tool with `tool_input` instead of the arguments the model sent. The input
+    /// must be of the same kind as the original.
+    ReplaceInput { tool_input: HookToolInput },
+    /// Run the tool and append `context` to the output the model sees.
+    AddContext { context: String },
 }
 
 #[derive(Debug)]
@@ -88,7 +113,7 @@ pub enum HookToolKind {
     Mcp,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct HookToolInputLocalShell {
     pub command: Vec<String>,
@@ -99,7 +124,7 @@ pub struct HookToolInputLocalShell {
     pub justification: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "input_type", rename_all = "snake_case")]
 pub enum HookToolInput {
     Function {
@@ -118,6 +143,19 @@ pub enum HookToolInput {
     },
 }
 
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventBeforeToolUse {
+    pub turn_id: String,
+    pub call_id: String,
+    pub tool_name: String,
+    pub tool_kind: HookToolKind,
+    pub tool_input: HookToolInput,
+    pub mutating: bool,
+    pub sandbox: String,
+    pub sandbox_policy: String,
+}
+
 #[derive(Debug, Clone, Serialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct HookEventAfterToolUse {
@@ -135,6 +173,81 @@ pub struct HookEventAfterToolUse {
     pub output_preview: String,
 }
 
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventSessionStart {
+    pub thread_id: ThreadId,
+    pub forked_from_id: Option<ThreadId>,
+    pub model: String,
+    pub model_provider_id: String,
+    pub approval_policy: String,
+    pub sandbox_policy: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventSessionEnd {
+    pub thread_id: ThreadId,
+    pub turn_count: u64,
+    pub total_tokens: i64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventTurnStarted {
+    pub turn_id: String,
+    pub model: String,
+    pub model_context_window: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HookCompactionTrigger {
+    /// The context window filled up during a turn.
+    Auto,
+    /// The user asked for compaction.
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventBeforeCompaction {
+    pub turn_id: String,
+    pub trigger: HookCompactionTrigger,
+    pub total_tokens: i64,
+    pub model_context_window: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "approval_type", rename_all = "snake_case")]
+pub enum HookApprovalRequest {
+    Exec {
+        command: Vec<String>,
+        cwd: PathBuf,
+    },
+    ApplyPatch {
+        changed_paths: Vec<PathBuf>,
+        grant_root: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventApprovalRequested {
+    pub turn_id: String,
+    pub call_id: String,
+    #[serde(flatten)]
+    pub request: HookApprovalRequest,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct HookEventError {
+    pub turn_id: String,
+    pub message: String,
+}
+
 fn serialize_triggered_at<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -145,10 +258,38 @@ where
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "event_type", rename_all = "snake_case")]
 pub enum HookEvent {
+    SessionStart {
+        #[serde(flatten)]
+        event: HookEventSessionStart,
+    },
+    SessionEnd {
+        #[serde(flatten)]
+        event: HookEventSessionEnd,
+    },
+    TurnStarted {
+        #[serde(flatten)]
+        event: HookEventTurnStarted,
+    },
+    BeforeCompaction {
+        #[serde(flatten)]
+        event: HookEventBeforeCompaction,
+    },
+    ApprovalRequested {
+        #[serde(flatten)]
+        event: HookEventApprovalRequested,
+    },
+    Error {
+        #[serde(flatten)]
+        event: HookEventError,
+    },
     AfterAgent {
         #[serde(flatten)]
         event: HookEventAfterAgent,
     },
+    BeforeToolUse {
+        #[serde(flatten)]
+        event: HookEventBeforeToolUse,
+    },
     AfterToolUse {
         #[serde(flatten)]
         event: HookEventAfterToolUse,
@@ -166,12 +307,16 @@ mod tests {
     use pretty_assertions::assert_eq;
     use serde_json::json;
 
+    use super::HookApprovalRequest;
     use super::HookEvent;
     use super::HookEventAfterAgent;
     use super::HookEventAfterToolUse;
+    use super::HookEventApprovalRequested;
+    use super::HookEventBeforeToolUse;
     use super::HookPayload;
     use super::HookToolInput;
     use super::HookToolInputLocalShell;
+    use super::HookToolIntervention;
     use super::HookToolKind;
 
     #[test]
@@ -283,4 +428,128 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn before_tool_use_payload_serializes_stable_wire_shape() {
+        let session_id = ThreadId::new();
+        let payload = HookPayload {
+            session_id,
+            cwd: PathBuf::from("tmp"),
+            triggered_at: Utc
+                .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+                .single()
+                .expect("valid timestamp"),
+            hook_event: HookEvent::BeforeToolUse {
+                event: HookEventBeforeToolUse {
+                    turn_id: "turn-3".to_string(),
+                    call_id: "call-2".to_string(),
+                    tool_name: "exec_command".to_string(),
+                    tool_kind: HookToolKind::Function,
+                    tool_input: HookToolInput::Function {
+                        arguments: "{\"cmd\":\"make migrate\"}".to_string(),
+                    },
+                    mutating: true,
+                    sandbox: "none".to_string(),
+                    sandbox_policy: "workspace-write".to_string(),
+                },
+            },
+        };
+
+        let actual = serde_json::to_value(payload).expect("serialize hook payload");
+        let expected = json!({
+            "session_id": session_id.to_string(),
+            "cwd": "tmp",
+            "triggered_at": "2025-01-01T00:00:00Z",
+            "hook_event": {
+                "event_type": "before_tool_use",
+                "turn_id": "turn-3",
+                "call_id": "call-2",
+                "tool_name": "exec_command",
+                "tool_kind": "function",
+                "tool_input": {
+                    "input_type": "function",
+                    "arguments": "{\"cmd\":\"make migrate\"}",
+                },
+                "mutating": true,
+                "sandbox": "none",
+                "sandbox_policy": "workspace-write",
+            },
+        });
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn tool_interventions_deserialize_from_hook_output() {
+        let deny: HookToolIntervention = serde_json::from_value(json!({
+            "decision": "deny",
+            "message": "Never run migrations from the agent.",
+        }))
+        .expect("deserialize deny");
+        let replace: HookToolIntervention = serde_json::from_value(json!({
+            "decision": "replace_input",
+            "tool_input": {
+                "input_type": "function",
+                "arguments": "{\"cmd\":\"make test\"}",
+            },
+        }))
+        .expect("deserialize replace_input");
+
+        assert_eq!(
+            deny,
+            HookToolIntervention::Deny {
+                message: "Never run migrations from the agent.".to_string(),
+            }
+        );
+        assert_eq!(
+            replace,
+            HookToolIntervention::ReplaceInput {
+                tool_input: HookToolInput::Function {
+                    arguments: "{\"cmd\":\"make test\"}".to_string(),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn approval_requested_payload_serializes_stable_wire_shape() {
+        let session_id = ThreadId::new();
+        let payload = HookPayload {
+            session_id,
+            cwd: PathBuf::from("tmp"),
+            triggered_at: Utc
+                .with_ymd_and_hms(2025, 1, 1, 0, 0, 0)
+                .single()
+                .expect("valid timestamp"),
+            hook_event: HookEvent::ApprovalRequested {
+                event: HookEventApprovalRequested {
+                    turn_id: "turn-3".to_string(),
+                    call_id: "call-3".to_string(),
+                    request: HookApprovalRequest::Exec {
+                        command: vec!["rm".to_string(), "-rf".to_string(), "build".to_string()],
+                        cwd: PathBuf::from("tmp"),
+                    },
+                    reason: Some("clean build outputs".to_string()),
+                },
+            },
+        };
+
+        let actual = serde_json::to_value(payload).expect("serialize hook payload");
+        let expected = json!({
+            "session_id": session_id.to_string(),
+            "cwd": "tmp",
+            "triggered_at": "2025-01-01T00:00:00Z",
+            "hook_event": {
+                "event_type": "approval_requested",
+                "turn_id": "turn-3",
+                "call_id": "call-3",
+                "approval_type": "exec",
+                "command": ["rm", "-rf", "build"],
+                "cwd": "tmp",
+                "reason": "clean build outputs",
+            },
+        });
+
+        assert_eq!(actual, expected);
+    }
 }
@@ -10,6 +10,7 @@ use rama_http::Body;
 use rama_http::Request;
 use rama_http::Response;
 use rama_http::StatusCode;
+use rama_http::header;
 use rama_http_backend::server::HttpServer;
 use rama_tcp::server::TcpListener;
 use serde::Deserialize;
@@ -18,12 +19,15 @@ use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::net::TcpListener as StdTcpListener;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tracing::error;
 use tracing::info;
+use tracing::warn;
 
 pub async fn run_admin_api(state: Arc<NetworkProxyState>, addr: SocketAddr) -> Result<()> {
-    // Debug-only admin API (health/config/patterns/blocked + mode/reload). Policy is config-driven
-    // and constraint-enforced; this endpoint should not become a second policy/approval plane.
+    // Debug-only admin API (health/config/patterns/blocked/events/audit + mode/reload). Policy is
+    // config-driven and constraint-enforced; this endpoint should not become a second
+    // policy/approval plane.
     let listener = TcpListener::build()
         .bind(addr)
         .await
@@ -96,6 +100,8 @@ async fn handle_admin_request(
                 text_response(StatusCode::INTERNAL_SERVER_ERROR, "error")
             }
         },
+        ("GET", "/events") => traffic_events_response(&state),
+        ("GET", "/audit") => json_response(&state.traffic_audit()),
         ("POST", "/mode") => {
             let mut body = req.into_body();
             let mut buf: Vec<u8> = Vec::new();
@@ -153,6 +159,36 @@ async fn handle_admin_request(
     Ok(response)
 }
 
+/// Streams each connection decision as one JSON object per line until the client disconnects.
+fn traffic_events_response(state: &NetworkProxyState) -> Response {
+    let events = futures::stream::unfold(state.subscribe_traffic(), |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    let mut line = match serde_json::to_vec(&event) {
+                        Ok(line) => line,
+                        Err(err) => {
+                            error!("failed to serialize traffic event: {err}");
+                            continue;
+                        }
+                    };
+                    line.push(b'\n');
+                    return Some((Ok::<_, Infallible>(line), events));
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("traffic event stream lagged; dropped {skipped} events");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(events))
+        .unwrap_or_else(|_| Response::new(Body::empty()))
+}
+
 #[derive(Deserialize)]
 struct ModeUpdate {
     mode: NetworkMode,
@@ -0,0 +1,636 @@
+use crate::config::CassetteMode;
+use crate::config::NetworkCassette;
+use anyhow::Context;
+use anyhow::Result;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use futures::StreamExt as _;
+use futures::TryStreamExt as _;
+use rama_http::Body;
+use rama_http::Response;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest as _;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read as _;
+use std::io::Seek as _;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tracing::info;
+use tracing::warn;
+
+/// Response headers that describe the upstream connection rather than the response itself.
+const UNRECORDED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// One recorded exchange, stored as a line of the cassette file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CassetteEntry {
+    method: String,
+    url: String,
+    /// Hex SHA-256 of the request body, so requests to the same URL with different bodies are
+    /// replayed separately.
+    request_sha256: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    /// Base64-encoded response body, exactly as received from upstream, or `None` when it was
+    /// larger than the cassette's `max_body_bytes`.
+    body: Option<String>,
+}
+
+/// Recorded responses for one request, served in the order they were recorded.
+#[derive(Debug)]
+struct ReplayTrack {
+    entries: Vec<CassetteEntry>,
+    next: usize,
+}
+
+enum CassetteState {
+    Record(Arc<Mutex<fs::File>>),
+    Replay(Mutex<HashMap<String, ReplayTrack>>),
+}
+
+/// Records proxied HTTP exchanges to a JSONL cassette, or replays them from one.
+///
+/// Requests are keyed by method, absolute URL (including the port) and a hash of the request body.
+/// When the same request was recorded several times, replay serves the responses in recorded order
+/// and then keeps repeating the last one.
+pub struct Cassette {
+    path: PathBuf,
+    max_body_bytes: u64,
+    state: CassetteState,
+}
+
+impl std::fmt::Debug for Cassette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cassette")
+            .field("path", &self.path)
+            .field("mode", &self.mode())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cassette {
+    /// Opens the cassette for `config.mode`. Recording truncates any existing file.
+    pub fn open(config: &NetworkCassette) -> Result<Self> {
+        let path = config.path.clone();
+        let state = match config.mode {
+            CassetteMode::Record => {
+                if let Some(parent) = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty())
+                {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("failed to create cassette dir {}", parent.display())
+                    })?;
+                }
+                let file = fs::File::create(&path)
+                    .with_context(|| format!("failed to create cassette {}", path.display()))?;
+                info!("recording network traffic to {}", path.display());
+                CassetteState::Record(Arc::new(Mutex::new(file)))
+            }
+            CassetteMode::Replay => {
+                let tracks = load_tracks(&path)?;
+                info!(
+                    "replaying network traffic from {} ({} recorded requests)",
+                    path.display(),
+                    tracks.len()
+                );
+                CassetteState::Replay(Mutex::new(tracks))
+            }
+        };
+        Ok(Self {
+            path,
+            max_body_bytes: config.max_body_bytes,
+            state,
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        match self.state {
+            CassetteState::Record(_) => CassetteMode::Record,
+            CassetteState::Replay(_) => CassetteMode::Replay,
+        }
+    }
+
+    /// Returns `response` with its body streamed through to the client unchanged, spooling a copy
+    /// to a temporary file and appending the exchange to the cassette once the body ends.
+    ///
+    /// Bodies over the cassette's `max_body_bytes` are recorded without their body, so replaying
+    /// them fails instead of silently missing. Exchanges whose body is not read to the end are not
+    /// recorded. Fails only if the spool file cannot be created.
+    pub(crate) fn record(
+        &self,
+        method: &str,
+        url: &str,
+        request_body: RequestBodyHash,
+        response: Response,
+    ) -> Result<Response> {
+        let CassetteState::Record(file) = &self.state else {
+            return Ok(response);
+        };
+        let spool = tempfile::tempfile().context("failed to create cassette spool file")?;
+        let (parts, body) = response.into_parts();
+        let recording = Recording {
+            file: Arc::clone(file),
+            cassette_path: self.path.clone(),
+            entry: CassetteEntry {
+                method: method.to_string(),
+                url: url.to_string(),
+                request_sha256: String::new(),
+                status: parts.status.as_u16(),
+                headers: parts
+                    .headers
+                    .iter()
+                    .filter(|(name, _)| !UNRECORDED_HEADERS.contains(&name.as_str()))
+                    .map(|(name, value)| {
+                        (
+                            name.as_str().to_string(),
+                            String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                        )
+                    })
+                    .collect(),
+                body: None,
+            },
+            request_body,
+            max_body_bytes: self.max_body_bytes,
+            body_bytes: 0,
+            body: RecordedBody::Spooled(spool),
+            finished: false,
+        };
+        let stream = futures::stream::unfold(
+            Some((Box::pin(body.into_data_stream()), recording)),
+            |state| async move {
+                let (mut stream, mut recording) = state?;
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        recording.tee(&chunk);
+                        Some((Ok(chunk), Some((stream, recording))))
+                    }
+                    Some(Err(err)) => Some((Err(err), None)),
+                    None => {
+                        recording.finish();
+                        None
+                    }
+                }
+            },
+        );
+        Ok(Response::from_parts(parts, Body::from_stream(stream)))
+    }
+
+    /// Returns the next recorded response for `method`, `url` and the request body hashed to
+    /// `request_sha256`, if any. Fails if the recorded response is unusable, e.g. because its body
+    /// was too large to record.
+    pub(crate) fn replay(
+        &self,
+        method: &str,
+        url: &str,
+        request_sha256: &str,
+    ) -> Result<Option<Response>> {
+        let CassetteState::Replay(tracks) = &self.state else {
+            return Ok(None);
+        };
+        let mut tracks = tracks
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let Some(track) = tracks.get_mut(&cassette_key(method, url, request_sha256)) else {
+            return Ok(None);
+        };
+        let Some(entry) = track.entries.get(track.next).or(track.entries.last()) else {
+            return Ok(None);
+        };
+        track.next = (track.next + 1).min(track.entries.len());
+        entry_response(entry).map(Some)
+    }
+}
+
+/// Running SHA-256 of a request body that is streamed upstream while it is being recorded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RequestBodyHash(Arc<Mutex<Sha256>>);
+
+impl RequestBodyHash {
+    /// Returns `body` unchanged, hashing each chunk as it streams through.
+    pub(crate) fn tee(body: Body) -> (Body, Self) {
+        let hash = Self::default();
+        let hasher = hash.clone();
+        let body = Body::from_stream(body.into_data_stream().inspect_ok(move |chunk| {
+            hasher
+                .0
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .update(chunk);
+        }));
+        (body, hash)
+    }
+
+    /// Hex digest of the body streamed so far.
+    fn hex(&self) -> String {
+        let hasher = self
+            .0
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        hex_digest(hasher)
+    }
+}
+
+/// Reads `body` to the end and returns its hex SHA-256, for looking up a replayed request.
+pub(crate) async fn hash_request_body(mut body: Body) -> Result<String> {
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = body
+        .chunk()
+        .await
+        .map_err(|err| anyhow::anyhow!("failed to read request body: {err}"))?
+    {
+        hasher.update(&chunk);
+    }
+    Ok(hex_digest(hasher))
+}
+
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Where a response body being recorded is kept until it has been read to the end.
+enum RecordedBody {
+    Spooled(fs::File),
+    /// The body exceeded `max_body_bytes`; the exchange is recorded without it.
+    TooLarge,
+    /// The spool file could not be written; the exchange is not recorded.
+    Failed,
+}
+
+/// A response being streamed to the client, appended to the cassette when its body ends.
+struct Recording {
+    file: Arc<Mutex<fs::File>>,
+    cassette_path: PathBuf,
+    entry: CassetteEntry,
+    request_body: RequestBodyHash,
+    max_body_bytes: u64,
+    body_bytes: u64,
+    body: RecordedBody,
+    finished: bool,
+}
+
+impl Recording {
+    fn tee(&mut self, chunk: &[u8]) {
+        self.body_bytes += chunk.len() as u64;
+        let RecordedBody::Spooled(spool) = &mut self.body else {
+            return;
+        };
+        let (method, url) = (&self.entry.method, &self.entry.url);
+        if self.body_bytes > self.max_body_bytes {
+            warn!(
+                "recording {method} {url} without its body: response body exceeds the cassette's max_body_bytes ({}); replaying it will fail",
+                self.max_body_bytes
+            );
+            self.body = RecordedBody::TooLarge;
+        } else if let Err(err) = spool.write_all(chunk) {
+            warn!("not recording {method} {url}: failed to spool response body: {err}");
+            self.body = RecordedBody::Failed;
+        }
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        let (method, url) = (&self.entry.method, &self.entry.url);
+        self.entry.body = match &mut self.body {
+            RecordedBody::Spooled(spool) => match read_spool(spool) {
+                Ok(body) => Some(BASE64_STANDARD.encode(body)),
+                Err(err) => {
+                    warn!("not recording {method} {url}: {err:#}");
+                    return;
+                }
+            },
+            RecordedBody::TooLarge => None,
+            RecordedBody::Failed => return,
+        };
+        self.entry.request_sha256 = self.request_body.hex();
+        if let Err(err) = append_entry(&self.file, &self.entry) {
+            warn!(
+                "failed to record {method} {url} to cassette {}: {err:#}",
+                self.cassette_path.display()
+            );
+        }
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        if !self.finished {
+            warn!(
+                "not recording {} {}: response body was not read to the end",
+                self.entry.method, self.entry.url
+            );
+        }
+    }
+}
+
+fn read_spool(spool: &mut fs::File) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    spool
+        .rewind()
+        .and_then(|()| spool.read_to_end(&mut body))
+        .context("failed to read spooled response body")?;
+    Ok(body)
+}
+
+fn cassette_key(method: &str, url: &str, request_sha256: &str) -> String {
+    format!("{} {url} {request_sha256}", method.to_ascii_uppercase())
+}
+
+fn load_tracks(path: &Path) -> Result<HashMap<String, ReplayTrack>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read cassette {}", path.display()))?;
+    let mut tracks: HashMap<String, ReplayTrack> = HashMap::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: CassetteEntry = serde_json::from_str(line).with_context(|| {
+            format!("invalid cassette entry at {}:{}", path.display(), index + 1)
+        })?;
+        tracks
+            .entry(cassette_key(
+                &entry.method,
+                &entry.url,
+                &entry.request_sha256,
+            ))
+            .or_insert_with(|| ReplayTrack {
+                entries: Vec::new(),
+                next: 0,
+            })
+            .entries
+            .push(entry);
+    }
+    Ok(tracks)
+}
+
+fn append_entry(file: &Mutex<fs::File>, entry: &CassetteEntry) -> Result<()> {
+    let mut line = serde_json::to_vec(entry).context("failed to serialize cassette entry")?;
+    line.push(b'\n');
+    let mut file = file
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    file.write_all(&line)
+        .and_then(|()| file.flush())
+        .context("failed to write cassette entry")
+}
+
+fn entry_response(entry: &CassetteEntry) -> Result<Response> {
+    let Some(body) = &entry.body else {
+        anyhow::bail!(
+            "response body was larger than the cassette's max_body_bytes when it was recorded"
+        );
+    };
+    let body = BASE64_STANDARD
+        .decode(body)
+        .context("invalid base64 body")?;
+    let mut builder = Response::builder().status(entry.status);
+    for (name, value) in &entry.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    builder
+        .body(Body::from(body))
+        .context("invalid recorded response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::SinkExt as _;
+    use pretty_assertions::assert_eq;
+    use rama_http::StatusCode;
+    use tempfile::TempDir;
+
+    const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+    async fn read_body(mut body: Body) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        while let Some(chunk) = body
+            .chunk()
+            .await
+            .map_err(|err| anyhow::anyhow!("failed to read body: {err}"))?
+        {
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf)
+    }
+
+    fn cassette_with_limit(dir: &TempDir, mode: CassetteMode, max_body_bytes: u64) -> Cassette {
+        Cassette::open(&NetworkCassette {
+            mode,
+            path: dir.path().join("cassettes/npm.jsonl"),
+            max_body_bytes,
+        })
+        .unwrap()
+    }
+
+    fn cassette(dir: &TempDir, mode: CassetteMode) -> Cassette {
+        cassette_with_limit(dir, mode, 1024)
+    }
+
+    fn response(status: StatusCode, body: &str) -> Response {
+        Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .header("transfer-encoding", "chunked")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    async fn record(
+        cassette: &Cassette,
+        method: &str,
+        url: &str,
+        request: &str,
+        response: Response,
+    ) -> Vec<u8> {
+        let (request, request_body) = RequestBodyHash::tee(Body::from(request.to_string()));
+        read_body(request).await.unwrap();
+        let recorded = cassette
+            .record(method, url, request_body, response)
+            .unwrap();
+        read_body(recorded.into_body()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn replay_serves_recorded_responses_in_order() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://registry.npmjs.org:443/left-pad";
+
+        let recorder = cassette(&dir, CassetteMode::Record);
+        let body = record(
+            &recorder,
+            "GET",
+            url,
+            "",
+            response(StatusCode::OK, "{\"v\":1}"),
+        )
+        .await;
+        assert_eq!(body, b"{\"v\":1}");
+        record(
+            &recorder,
+            "GET",
+            url,
+            "",
+            response(StatusCode::NOT_MODIFIED, ""),
+        )
+        .await;
+
+        let player = cassette(&dir, CassetteMode::Replay);
+        let first = player.replay("get", url, EMPTY_SHA256).unwrap().unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(
+            first.headers().get("content-type").unwrap(),
+            "application/json"
+        );
+        assert!(first.headers().get("transfer-encoding").is_none());
+        assert_eq!(read_body(first.into_body()).await.unwrap(), b"{\"v\":1}");
+        for _ in 0..2 {
+            assert_eq!(
+                player
+                    .replay("GET", url, EMPTY_SHA256)
+                    .unwrap()
+                    .unwrap()
+                    .status(),
+                StatusCode::NOT_MODIFIED
+            );
+        }
+
+        assert!(player.replay("POST", url, EMPTY_SHA256).unwrap().is_none());
+        assert!(
+            player
+                .replay(
+                    "GET",
+                    "https://registry.npmjs.org:443/is-even",
+                    EMPTY_SHA256
+                )
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_keys_requests_by_body() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://api.example.com:443/graphql";
+
+        let recorder = cassette(&dir, CassetteMode::Record);
+        record(
+            &recorder,
+            "POST",
+            url,
+            "{\"q\":1}",
+            response(StatusCode::OK, "one"),
+        )
+        .await;
+        record(
+            &recorder,
+            "POST",
+            url,
+            "{\"q\":2}",
+            response(StatusCode::OK, "two"),
+        )
+        .await;
+
+        let player = cassette(&dir, CassetteMode::Replay);
+        for (request, expected) in [("{\"q\":2}", b"two"), ("{\"q\":1}", b"one")] {
+            let request_sha256 = hash_request_body(Body::from(request.to_string()))
+                .await
+                .unwrap();
+            let replayed = player
+                .replay("POST", url, &request_sha256)
+                .unwrap()
+                .unwrap();
+            assert_eq!(read_body(replayed.into_body()).await.unwrap(), expected);
+        }
+        assert!(player.replay("POST", url, EMPTY_SHA256).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn record_streams_the_body_before_it_ends() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://api.example.com:443/events";
+
+        let recorder = cassette(&dir, CassetteMode::Record);
+        let (mut upstream, chunks) =
+            futures::channel::mpsc::channel::<Result<Vec<u8>, std::io::Error>>(1);
+        let streamed = recorder
+            .record(
+                "GET",
+                url,
+                RequestBodyHash::default(),
+                Response::new(Body::from_stream(chunks)),
+            )
+            .unwrap();
+        let mut body = streamed.into_body();
+
+        upstream.send(Ok(b"data: 1\n\n".to_vec())).await.unwrap();
+        let first = body.chunk().await.unwrap().unwrap();
+        assert_eq!(first.as_ref(), b"data: 1\n\n");
+        upstream.send(Ok(b"data: 2\n\n".to_vec())).await.unwrap();
+        drop(upstream);
+        assert_eq!(read_body(body).await.unwrap(), b"data: 2\n\n");
+
+        let player = cassette(&dir, CassetteMode::Replay);
+        let replayed = player.replay("GET", url, EMPTY_SHA256).unwrap().unwrap();
+        assert_eq!(
+            read_body(replayed.into_body()).await.unwrap(),
+            b"data: 1\n\ndata: 2\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn oversized_bodies_stream_through_but_fail_to_replay() {
+        let dir = TempDir::new().unwrap();
+        let url = "https://registry.npmjs.org:443/big.tgz";
+
+        let recorder = cassette_with_limit(&dir, CassetteMode::Record, 4);
+        let body = record(
+            &recorder,
+            "GET",
+            url,
+            "",
+            response(StatusCode::OK, "0123456789"),
+        )
+        .await;
+        assert_eq!(body, b"0123456789");
+
+        let player = cassette_with_limit(&dir, CassetteMode::Replay, 4);
+        let err = player
+            .replay("GET", url, EMPTY_SHA256)
+            .map(|_| ())
+            .unwrap_err();
+        assert!(err.to_string().contains("max_body_bytes"), "{err:#}");
+    }
+
+    #[test]
+    fn open_replay_requires_an_existing_cassette() {
+        let dir = TempDir::new().unwrap();
+        let err = Cassette::open(&NetworkCassette {
+            mode: CassetteMode::Replay,
+            path: dir.path().join("missing.jsonl"),
+            max_body_bytes: 1024,
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("failed to read cassette"));
+    }
+}
@@ -7,6 +7,7 @@ use serde::Serialize;
 use std::net::IpAddr;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::path::PathBuf;
 use tracing::warn;
 use url::Url;
 
@@ -38,13 +39,23 @@ pub struct NetworkProxySettings {
     pub dangerously_allow_all_unix_sockets: bool,
     #[serde(default)]
     pub mode: NetworkMode,
+    /// Terminate HTTPS CONNECT tunnels with a local CA (kept under `CODEX_HOME`) so method and
+    /// host policy can be applied to the inner requests.
+    #[serde(default)]
+    pub mitm: bool,
     #[serde(default)]
     pub allowed_domains: Vec<String>,
     #[serde(default)]
     pub denied_domains: Vec<String>,
+    /// Method, host, port and path rules layered on top of the domain lists.
+    #[serde(default)]
+    pub rules: Vec<NetworkRule>,
     #[serde(default)]
     pub allow_unix_sockets: Vec<String>,
     pub allow_local_binding: bool,
+    /// Record proxied HTTP exchanges to, or replay them from, a cassette file.
+    #[serde(default)]
+    pub cassette: Option<NetworkCassette>,
 }
 
 impl Default for NetworkProxySettings {
@@ -61,22 +72,101 @@ impl Default for NetworkProxySettings {
             dangerously_allow_non_loopback_admin: false,
             dangerously_allow_all_unix_sockets: false,
             mode: NetworkMode::default(),
+            mitm: false,
             allowed_domains: Vec::new(),
             denied_domains: Vec::new(),
+            rules: Vec::new(),
             allow_unix_sockets: Vec::new(),
             allow_local_binding: true,
+            cassette: None,
+        }
+    }
+}
+
+/// A request-level rule, e.g. "allow GET registry.npmjs.org/*".
+///
+/// A matching deny rule blocks the request even if the host is allowlisted. Once any allow rule
+/// names a host, requests to that host must match one of its allow rules, whether or not the host
+/// is in `allowed_domains`. Methods and paths are only visible for plain HTTP and, with `mitm`
+/// enabled, HTTPS. Tunnels that cannot be inspected fail closed: they are refused whenever the host
+/// has a deny rule or an unmatched allow rule that depends on `methods` or `path`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkRule {
+    pub action: NetworkRuleAction,
+    /// HTTP methods the rule applies to. Empty matches any method.
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Host pattern, using the same syntax as `allowed_domains`.
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Glob over the request path, where `*` also matches `/`. Unset matches any path.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl std::fmt::Display for NetworkRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self.action {
+            NetworkRuleAction::Allow => "allow",
+            NetworkRuleAction::Deny => "deny",
+        };
+        write!(f, "{action} ")?;
+        if !self.methods.is_empty() {
+            write!(f, "{} ", self.methods.join(","))?;
+        }
+        f.write_str(&self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{port}")?;
+        }
+        if let Some(path) = &self.path {
+            f.write_str(path)?;
         }
+        Ok(())
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NetworkRuleAction {
+    Allow,
+    Deny,
+}
+
+/// A JSONL file of recorded HTTP exchanges, one request/response pair per line.
+///
+/// Only traffic the proxy can see in the clear is captured: plain HTTP, and HTTPS when `mitm` is
+/// enabled. Opaque tunnels are forwarded unrecorded in `record` mode and denied in `replay` mode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NetworkCassette {
+    pub mode: CassetteMode,
+    pub path: PathBuf,
+    /// Largest response body recorded, in bytes. Bigger responses still stream to the client, but
+    /// are recorded without their body, and replaying them fails.
+    #[serde(default = "default_cassette_max_body_bytes")]
+    pub max_body_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteMode {
+    /// Forward requests upstream and append each exchange to the cassette, replacing any previous
+    /// recording.
+    Record,
+    /// Serve responses from the cassette without contacting upstream; unrecorded requests are
+    /// denied.
+    Replay,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum NetworkMode {
     /// Limited (read-only) access: only GET/HEAD/OPTIONS are allowed for HTTP. HTTPS CONNECT is
-    /// blocked unless MITM is enabled so the proxy can enforce method policy on inner requests.
+    /// blocked unless `mitm` is enabled, in which case the proxy terminates TLS and enforces method
+    /// policy on the inner requests.
     Limited,
     /// Full network access: all HTTP methods are allowed, and HTTPS CONNECTs are tunneled without
-    /// MITM interception.
+    /// interception unless `mitm` is enabled.
     #[default]
     Full,
 }
@@ -102,6 +192,10 @@ fn default_socks_url() -> String {
     "http://127.0.0.1:8081".to_string()
 }
 
+fn default_cassette_max_body_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
 /// Clamp non-loopback bind addresses to loopback unless explicitly allowed.
 fn clamp_non_loopback(addr: SocketAddr, allow_non_loopback: bool, name: &str) -> SocketAddr {
     if addr.ip().is_loopback() {
@@ -389,14 +483,38 @@ mod tests {
                 dangerously_allow_non_loopback_admin: false,
                 dangerously_allow_all_unix_sockets: false,
                 mode: NetworkMode::Full,
+                mitm: false,
                 allowed_domains: Vec::new(),
                 denied_domains: Vec::new(),
+                rules: Vec::new(),
                 allow_unix_sockets: Vec::new(),
                 allow_local_binding: true,
+                cassette: None,
             }
         );
     }
 
+    #[test]
+    fn network_rule_display_renders_method_host_port_and_path() {
+        let rule = NetworkRule {
+            action: NetworkRuleAction::Allow,
+            methods: vec!["GET".to_string(), "HEAD".to_string()],
+            host: "registry.npmjs.org".to_string(),
+            port: Some(443),
+            path: Some("/*".to_string()),
+        };
+        assert_eq!(rule.to_string(), "allow GET,HEAD registry.npmjs.org:443/*");
+
+        let rule = NetworkRule {
+            action: NetworkRuleAction::Deny,
+            methods: Vec::new(),
+            host: "github.com".to_string(),
+            port: None,
+            path: None,
+        };
+        assert_eq!(rule.to_string(), "deny github.com");
+    }
+
     #[test]
     fn partial_network_config_uses_struct_defaults_for_missing_fields() {
         let config: NetworkProxyConfig = serde_json::from_str(
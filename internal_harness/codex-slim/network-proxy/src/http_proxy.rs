@@ -1,4 +1,9 @@
+use crate::cassette::Cassette;
+use crate::cassette::RequestBodyHash;
+use crate::cassette::hash_request_body;
+use crate::config::CassetteMode;
 use crate::config::NetworkMode;
+use crate::mitm::MitmCertificateAuthority;
 use crate::network_policy::NetworkDecision;
 use crate::network_policy::NetworkDecisionSource;
 use crate::network_policy::NetworkPolicyDecider;
@@ -10,6 +15,7 @@ use crate::network_policy::evaluate_host_policy;
 use crate::policy::normalize_host;
 use crate::reasons::REASON_METHOD_NOT_ALLOWED;
 use crate::reasons::REASON_NOT_ALLOWED;
+use crate::reasons::REASON_NOT_RECORDED;
 use crate::reasons::REASON_PROXY_DISABLED;
 use crate::responses::PolicyDecisionDetails;
 use crate::responses::blocked_header_value;
@@ -20,10 +26,12 @@ use crate::runtime::unix_socket_permissions_supported;
 use crate::state::BlockedRequest;
 use crate::state::BlockedRequestArgs;
 use crate::state::NetworkProxyState;
+use crate::traffic::TrafficKey;
 use crate::upstream::UpstreamClient;
 use crate::upstream::proxy_for_connect;
 use anyhow::Context as _;
 use anyhow::Result;
+use futures::TryStreamExt as _;
 use rama_core::Layer;
 use rama_core::Service;
 use rama_core::error::BoxError;
@@ -41,6 +49,7 @@ use rama_http::HeaderValue;
 use rama_http::Request;
 use rama_http::Response;
 use rama_http::StatusCode;
+use rama_http::Uri;
 use rama_http::header;
 use rama_http::layer::remove_header::RemoveResponseHeaderLayer;
 use rama_http::matcher::MethodMatcher;
@@ -49,24 +58,31 @@ use rama_http_backend::server::HttpServer;
 use rama_http_backend::server::layer::upgrade::UpgradeLayer;
 use rama_http_backend::server::layer::upgrade::Upgraded;
 use rama_net::Protocol;
+use rama_net::address::HostWithPort;
 use rama_net::address::ProxyAddress;
 use rama_net::client::ConnectorService;
 use rama_net::client::EstablishedClientConnection;
 use rama_net::http::RequestContext;
-use rama_net::proxy::ProxyRequest;
 use rama_net::proxy::ProxyTarget;
-use rama_net::proxy::StreamForwardService;
 use rama_net::stream::SocketInfo;
 use rama_tcp::client::Request as TcpRequest;
 use rama_tcp::client::service::TcpConnector;
 use rama_tcp::server::TcpListener;
 use rama_tls_rustls::client::TlsConnectorDataBuilder;
 use rama_tls_rustls::client::TlsConnectorLayer;
+use rama_tls_rustls::server::TlsAcceptorData;
+use rama_tls_rustls::server::TlsAcceptorLayer;
 use serde::Serialize;
 use std::convert::Infallible;
 use std::net::SocketAddr;
 use std::net::TcpListener as StdTcpListener;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::Context as TaskContext;
+use std::task::Poll;
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+use tokio::io::ReadBuf;
 use tracing::error;
 use tracing::info;
 use tracing::warn;
@@ -75,6 +91,8 @@ pub async fn run_http_proxy(
     state: Arc<NetworkProxyState>,
     addr: SocketAddr,
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    mitm: Option<Arc<MitmCertificateAuthority>>,
+    cassette: Option<Arc<Cassette>>,
 ) -> Result<()> {
     let listener = TcpListener::build()
         .bind(addr)
@@ -87,23 +105,27 @@ pub async fn run_http_proxy(
         .map_err(anyhow::Error::from)
         .with_context(|| format!("bind HTTP proxy: {addr}"))?;
 
-    run_http_proxy_with_listener(state, listener, policy_decider).await
+    run_http_proxy_with_listener(state, listener, policy_decider, mitm, cassette).await
 }
 
 pub async fn run_http_proxy_with_std_listener(
     state: Arc<NetworkProxyState>,
     listener: StdTcpListener,
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    mitm: Option<Arc<MitmCertificateAuthority>>,
+    cassette: Option<Arc<Cassette>>,
 ) -> Result<()> {
     let listener =
         TcpListener::try_from(listener).context("convert std listener to HTTP proxy listener")?;
-    run_http_proxy_with_listener(state, listener, policy_decider).await
+    run_http_proxy_with_listener(state, listener, policy_decider, mitm, cassette).await
 }
 
 async fn run_http_proxy_with_listener(
     state: Arc<NetworkProxyState>,
     listener: TcpListener,
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    mitm: Option<Arc<MitmCertificateAuthority>>,
+    cassette: Option<Arc<Cassette>>,
 ) -> Result<()> {
     let addr = listener
         .local_addr()
@@ -115,15 +137,26 @@ async fn run_http_proxy_with_listener(
                 MethodMatcher::CONNECT,
                 service_fn({
                     let policy_decider = policy_decider.clone();
-                    move |req| http_connect_accept(policy_decider.clone(), req)
+                    let cassette = cassette.clone();
+                    move |req| {
+                        http_connect_accept(
+                            policy_decider.clone(),
+                            mitm.clone(),
+                            cassette.clone(),
+                            req,
+                        )
+                    }
+                }),
+                service_fn({
+                    let policy_decider = policy_decider.clone();
+                    move |upgraded| http_connect_proxy(policy_decider.clone(), upgraded)
                 }),
-                service_fn(http_connect_proxy),
             ),
             RemoveResponseHeaderLayer::hop_by_hop(),
         )
             .into_layer(service_fn({
                 let policy_decider = policy_decider.clone();
-                move |req| http_plain_proxy(policy_decider.clone(), req)
+                move |req| http_plain_proxy(policy_decider.clone(), cassette.clone(), req)
             })),
     );
 
@@ -137,6 +170,8 @@ async fn run_http_proxy_with_listener(
 
 async fn http_connect_accept(
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    mitm: Option<Arc<MitmCertificateAuthority>>,
+    cassette: Option<Arc<Cassette>>,
     mut req: Request,
 ) -> Result<(Response, Request), Response> {
     let app_state = req
@@ -183,15 +218,24 @@ async fn http_connect_accept(
         port: authority.port,
         client_addr: client.clone(),
         method: Some("CONNECT".to_string()),
+        path: None,
         command: None,
         exec_policy_hint: None,
     });
 
-    match evaluate_host_policy(&app_state, policy_decider.as_ref(), &request).await {
+    let allow_rule = match evaluate_host_policy(
+        &app_state,
+        policy_decider.as_ref(),
+        &request,
+        mitm.is_some(),
+    )
+    .await
+    {
         Ok(NetworkDecision::Deny {
             reason,
             source,
             decision,
+            rule,
         }) => {
             let details = PolicyDecisionDetails {
                 decision,
@@ -200,6 +244,7 @@ async fn http_connect_accept(
                 protocol: NetworkProtocol::HttpsConnect,
                 host: &host,
                 port: authority.port,
+                rule: rule.as_deref(),
             };
             let _ = app_state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -211,6 +256,7 @@ async fn http_connect_accept(
                     protocol: "http-connect".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: rule.clone(),
                     port: Some(authority.port),
                 }))
                 .await;
@@ -218,14 +264,49 @@ async fn http_connect_accept(
             warn!("CONNECT blocked (client={client}, host={host}, reason={reason})");
             return Err(blocked_text_with_details(&reason, &details));
         }
-        Ok(NetworkDecision::Allow) => {
+        Ok(NetworkDecision::Allow { rule }) => {
             let client = client.as_deref().unwrap_or_default();
             info!("CONNECT allowed (client={client}, host={host})");
+            rule
         }
         Err(err) => {
             error!("failed to evaluate host for CONNECT {host}: {err}");
             return Err(text_response(StatusCode::INTERNAL_SERVER_ERROR, "error"));
         }
+    };
+
+    // Replay can only answer requests it can see, so opaque tunnels have nothing to serve.
+    if mitm.is_none()
+        && cassette
+            .as_ref()
+            .is_some_and(|cassette| cassette.mode() == CassetteMode::Replay)
+    {
+        let details = PolicyDecisionDetails {
+            decision: NetworkPolicyDecision::Deny,
+            reason: REASON_NOT_RECORDED,
+            source: NetworkDecisionSource::Cassette,
+            protocol: NetworkProtocol::HttpsConnect,
+            host: &host,
+            port: authority.port,
+            rule: None,
+        };
+        let _ = app_state
+            .record_blocked(BlockedRequest::new(BlockedRequestArgs {
+                host: host.clone(),
+                reason: REASON_NOT_RECORDED.to_string(),
+                client: client.clone(),
+                method: Some("CONNECT".to_string()),
+                mode: None,
+                protocol: "http-connect".to_string(),
+                decision: Some(details.decision.as_str().to_string()),
+                source: Some(details.source.as_str().to_string()),
+                rule: None,
+                port: Some(authority.port),
+            }))
+            .await;
+        let client = client.as_deref().unwrap_or_default();
+        warn!("CONNECT blocked; replay requires mitm (client={client}, host={host})");
+        return Err(blocked_text_with_details(REASON_NOT_RECORDED, &details));
     }
 
     let mode = app_state
@@ -233,7 +314,9 @@ async fn http_connect_accept(
         .await
         .map_err(|err| internal_error("failed to read network mode", err))?;
 
-    if mode == NetworkMode::Limited {
+    // With MITM enabled the tunnel is terminated locally, so method policy is enforced on each
+    // inner request instead of rejecting the CONNECT outright.
+    if mode == NetworkMode::Limited && mitm.is_none() {
         let details = PolicyDecisionDetails {
             decision: NetworkPolicyDecision::Deny,
             reason: REASON_METHOD_NOT_ALLOWED,
@@ -241,6 +324,7 @@ async fn http_connect_accept(
             protocol: NetworkProtocol::HttpsConnect,
             host: &host,
             port: authority.port,
+            rule: None,
         };
         let _ = app_state
             .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -252,6 +336,7 @@ async fn http_connect_accept(
                 protocol: "http-connect".to_string(),
                 decision: Some(details.decision.as_str().to_string()),
                 source: Some(details.source.as_str().to_string()),
+                rule: None,
                 port: Some(authority.port),
             }))
             .await;
@@ -263,8 +348,25 @@ async fn http_connect_accept(
         ));
     }
 
-    req.extensions_mut().insert(ProxyTarget(authority));
+    req.extensions_mut().insert(ProxyTarget(authority.clone()));
     req.extensions_mut().insert(mode);
+    if let Some(mitm) = mitm {
+        req.extensions_mut().insert(mitm);
+        if let Some(cassette) = cassette {
+            req.extensions_mut().insert(cassette);
+        }
+    } else {
+        // Intercepted tunnels are accounted per inner request; opaque ones per tunnel.
+        let traffic_key = app_state.record_allowed(
+            &host,
+            authority.port,
+            "http-connect",
+            Some("CONNECT"),
+            client.as_deref(),
+            allow_rule,
+        );
+        req.extensions_mut().insert(traffic_key);
+    }
 
     Ok((
         Response::builder()
@@ -275,12 +377,27 @@ async fn http_connect_accept(
     ))
 }
 
-async fn http_connect_proxy(upgraded: Upgraded) -> Result<(), Infallible> {
+async fn http_connect_proxy(
+    policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    upgraded: Upgraded,
+) -> Result<(), Infallible> {
     if upgraded.extensions().get::<ProxyTarget>().is_none() {
         warn!("CONNECT missing proxy target");
         return Ok(());
     }
 
+    if let Some(mitm) = upgraded
+        .extensions()
+        .get::<Arc<MitmCertificateAuthority>>()
+        .cloned()
+    {
+        let cassette = upgraded.extensions().get::<Arc<Cassette>>().cloned();
+        if let Err(err) = mitm_connect_tunnel(upgraded, mitm, cassette, policy_decider).await {
+            warn!("MITM tunnel error: {err}");
+        }
+        return Ok(());
+    }
+
     let allow_upstream_proxy = match upgraded
         .extensions()
         .get::<Arc<NetworkProxyState>>()
@@ -342,22 +459,207 @@ async fn forward_connect_tunnel(
                 .into_boxed()
         })?;
 
-    let proxy_req = ProxyRequest {
-        source: upgraded,
-        target,
+    let traffic = upgraded
+        .extensions()
+        .get::<Arc<NetworkProxyState>>()
+        .map(|state| state.traffic_log())
+        .zip(upgraded.extensions().get::<TrafficKey>().cloned());
+    let record_bytes = |bytes_in, bytes_out| {
+        if let Some((traffic, traffic_key)) = &traffic {
+            traffic.record_bytes(traffic_key, bytes_in, bytes_out);
+        }
+    };
+    // Bytes are counted as they are read so that tunnels which end in an error or are
+    // reset by either side are still accounted for.
+    let upgraded = std::pin::pin!(upgraded);
+    let target = std::pin::pin!(target);
+    let mut upgraded = CountReads {
+        inner: upgraded,
+        count: |bytes: u64| record_bytes(0, bytes),
     };
-    StreamForwardService::default()
-        .serve(proxy_req)
+    let mut target = CountReads {
+        inner: target,
+        count: |bytes: u64| record_bytes(bytes, 0),
+    };
+    tokio::io::copy_bidirectional(&mut upgraded, &mut target)
         .await
         .map_err(|err| {
-            OpaqueError::from_boxed(err.into())
+            OpaqueError::from_std(err)
                 .with_context(|| format!("forward CONNECT tunnel to {authority}"))
                 .into_boxed()
+        })?;
+    Ok(())
+}
+
+/// Passes I/O through to `inner`, reporting the size of each read to `count`.
+struct CountReads<'a, T, F> {
+    inner: Pin<&'a mut T>,
+    count: F,
+}
+
+impl<T: AsyncRead, F: FnMut(u64) + Unpin> AsyncRead for CountReads<'_, T, F> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        let filled = buf.filled().len();
+        let poll = this.inner.as_mut().poll_read(cx, buf);
+        let read = buf.filled().len().saturating_sub(filled);
+        if read > 0 {
+            (this.count)(read as u64);
+        }
+        poll
+    }
+}
+
+impl<T: AsyncWrite, F: Unpin> AsyncWrite for CountReads<'_, T, F> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.inner.as_mut().poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        self.inner.as_mut().poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        self.inner.as_mut().poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        self.inner.as_mut().poll_shutdown(cx)
+    }
+}
+
+/// Terminates the CONNECT tunnel with a certificate minted for the target host and serves the
+/// decrypted requests through the same policy checks as plain HTTP requests.
+async fn mitm_connect_tunnel(
+    upgraded: Upgraded,
+    mitm: Arc<MitmCertificateAuthority>,
+    cassette: Option<Arc<Cassette>>,
+    policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+) -> Result<(), BoxError> {
+    let authority = upgraded
+        .extensions()
+        .get::<ProxyTarget>()
+        .map(|target| target.0.clone())
+        .ok_or_else(|| OpaqueError::from_display("missing forward authority").into_boxed())?;
+    let app_state = upgraded
+        .extensions()
+        .get::<Arc<NetworkProxyState>>()
+        .cloned()
+        .ok_or_else(|| OpaqueError::from_display("missing app state").into_boxed())?;
+    let client = client_addr(&upgraded);
+
+    let host = normalize_host(&authority.host.to_string());
+    let server_config = mitm.server_config_for_host(&host).map_err(|err| {
+        OpaqueError::from_display(format!("mint MITM certificate for {host}: {err:#}")).into_boxed()
+    })?;
+
+    let http_service = HttpServer::auto(Executor::new()).service(
+        RemoveResponseHeaderLayer::hop_by_hop().into_layer(service_fn(move |req| {
+            mitm_inner_request(
+                app_state.clone(),
+                policy_decider.clone(),
+                cassette.clone(),
+                authority.clone(),
+                client.clone(),
+                req,
+            )
+        })),
+    );
+    TlsAcceptorLayer::new(TlsAcceptorData::from(server_config))
+        .into_layer(http_service)
+        .serve(upgraded)
+        .await
+        .map_err(|err| {
+            OpaqueError::from_boxed(err.into())
+                .with_context(|| format!("serve MITM tunnel to {host}"))
+                .into_boxed()
         })
 }
 
+async fn mitm_inner_request(
+    app_state: Arc<NetworkProxyState>,
+    policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    cassette: Option<Arc<Cassette>>,
+    target: HostWithPort,
+    client: Option<String>,
+    req: Request,
+) -> Result<Response, Infallible> {
+    let target_host = normalize_host(&target.host.to_string());
+    let request_host = RequestContext::try_from(&req)
+        .map(|ctx| normalize_host(&ctx.host_with_port().host.to_string()))
+        .ok();
+    // The upstream connection is made to the CONNECT target, so a different inner Host would let a
+    // client reach a host the policy never saw.
+    if request_host.as_deref() != Some(target_host.as_str()) {
+        let client = client.as_deref().unwrap_or_default();
+        let request_host = request_host.unwrap_or_default();
+        warn!(
+            "MITM request host does not match CONNECT target (client={client}, target={target_host}, host={request_host})"
+        );
+        return Ok(text_response(
+            StatusCode::MISDIRECTED_REQUEST,
+            "request host does not match CONNECT target",
+        ));
+    }
+
+    let (mut parts, body) = req.into_parts();
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(rama_http::uri::PathAndQuery::as_str)
+        .unwrap_or("/");
+    parts.uri = match Uri::builder()
+        .scheme("https")
+        .authority(target.to_string())
+        .path_and_query(path)
+        .build()
+    {
+        Ok(uri) => uri,
+        Err(err) => {
+            warn!("invalid MITM request uri for {target}: {err}");
+            return Ok(text_response(
+                StatusCode::BAD_REQUEST,
+                "invalid request uri",
+            ));
+        }
+    };
+    // The unix socket escape hatch is only offered to plain proxy requests.
+    parts.headers.remove("x-unix-socket");
+
+    let req = Request::from_parts(parts, body);
+    Ok(proxy_http_request(
+        &app_state,
+        policy_decider,
+        cassette,
+        req,
+        client,
+        NetworkProtocol::HttpsConnect,
+    )
+    .await)
+}
+
 async fn http_plain_proxy(
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    cassette: Option<Arc<Cassette>>,
     mut req: Request,
 ) -> Result<Response, Infallible> {
     let app_state = match req.extensions().get::<Arc<NetworkProxyState>>().cloned() {
@@ -368,14 +670,6 @@ async fn http_plain_proxy(
         }
     };
     let client = client_addr(&req);
-    let method_allowed = match app_state
-        .method_allowed(req.method().as_str())
-        .await
-        .map_err(|err| internal_error("failed to evaluate method policy", err))
-    {
-        Ok(allowed) => allowed,
-        Err(resp) => return Ok(resp),
-    };
 
     // `x-unix-socket` is an escape hatch for talking to local daemons. We keep it tightly scoped:
     // macOS-only + explicit allowlist by default, to avoid turning the proxy into a general local
@@ -412,6 +706,14 @@ async fn http_plain_proxy(
             )
             .await);
         }
+        let method_allowed = match app_state
+            .method_allowed(req.method().as_str())
+            .await
+            .map_err(|err| internal_error("failed to evaluate method policy", err))
+        {
+            Ok(allowed) => allowed,
+            Err(resp) => return Ok(resp),
+        };
         if !method_allowed {
             let client = client.as_deref().unwrap_or_default();
             let method = req.method();
@@ -421,6 +723,16 @@ async fn http_plain_proxy(
             return Ok(json_blocked("unix-socket", REASON_METHOD_NOT_ALLOWED, None));
         }
 
+        // A replayed run must not reach anything live, and local daemons are never recorded.
+        if cassette
+            .as_ref()
+            .is_some_and(|cassette| cassette.mode() == CassetteMode::Replay)
+        {
+            let client = client.as_deref().unwrap_or_default();
+            warn!("unix socket blocked; replay is offline (client={client}, path={socket_path})");
+            return Ok(json_blocked("unix-socket", REASON_NOT_RECORDED, None));
+        }
+
         if !unix_socket_permissions_supported() {
             warn!("unix socket proxy unsupported on this platform (path={socket_path})");
             return Ok(text_response(
@@ -456,11 +768,45 @@ async fn http_plain_proxy(
         };
     }
 
+    Ok(proxy_http_request(
+        &app_state,
+        policy_decider,
+        cassette,
+        req,
+        client,
+        NetworkProtocol::Http,
+    )
+    .await)
+}
+
+/// Applies host and method policy to a proxied request and forwards the allowed ones upstream, or
+/// serves them from the cassette in replay mode.
+async fn proxy_http_request(
+    app_state: &NetworkProxyState,
+    policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
+    cassette: Option<Arc<Cassette>>,
+    mut req: Request,
+    client: Option<String>,
+    protocol: NetworkProtocol,
+) -> Response {
+    let blocked_protocol = match protocol {
+        NetworkProtocol::HttpsConnect => "https",
+        _ => "http",
+    };
+    let method_allowed = match app_state
+        .method_allowed(req.method().as_str())
+        .await
+        .map_err(|err| internal_error("failed to evaluate method policy", err))
+    {
+        Ok(allowed) => allowed,
+        Err(resp) => return resp,
+    };
+
     let authority = match RequestContext::try_from(&req).map(|ctx| ctx.host_with_port()) {
         Ok(authority) => authority,
         Err(err) => {
             warn!("missing host: {err}");
-            return Ok(text_response(StatusCode::BAD_REQUEST, "missing host"));
+            return text_response(StatusCode::BAD_REQUEST, "missing host");
         }
     };
     let host = normalize_host(&authority.host.to_string());
@@ -471,79 +817,87 @@ async fn http_plain_proxy(
         .map_err(|err| internal_error("failed to read enabled state", err))
     {
         Ok(enabled) => enabled,
-        Err(resp) => return Ok(resp),
+        Err(resp) => return resp,
     };
     if !enabled {
-        let client = client.as_deref().unwrap_or_default();
+        let client_label = client.as_deref().unwrap_or_default();
         let method = req.method();
-        warn!("request blocked; proxy disabled (client={client}, host={host}, method={method})");
-        return Ok(proxy_disabled_response(
-            &app_state,
+        warn!(
+            "request blocked; proxy disabled (client={client_label}, host={host}, method={method})"
+        );
+        return proxy_disabled_response(
+            app_state,
             host,
             port,
-            client_addr(&req),
+            client,
             Some(req.method().as_str().to_string()),
-            NetworkProtocol::Http,
+            protocol,
         )
-        .await);
+        .await;
     }
 
     let request = NetworkPolicyRequest::new(NetworkPolicyRequestArgs {
-        protocol: NetworkProtocol::Http,
+        protocol,
         host: host.clone(),
         port,
         client_addr: client.clone(),
         method: Some(req.method().as_str().to_string()),
+        path: Some(req.uri().path().to_string()),
         command: None,
         exec_policy_hint: None,
     });
 
-    match evaluate_host_policy(&app_state, policy_decider.as_ref(), &request).await {
-        Ok(NetworkDecision::Deny {
-            reason,
-            source,
-            decision,
-        }) => {
-            let details = PolicyDecisionDetails {
-                decision,
-                reason: &reason,
+    let allow_rule =
+        match evaluate_host_policy(app_state, policy_decider.as_ref(), &request, false).await {
+            Ok(NetworkDecision::Deny {
+                reason,
                 source,
-                protocol: NetworkProtocol::Http,
-                host: &host,
-                port,
-            };
-            let _ = app_state
-                .record_blocked(BlockedRequest::new(BlockedRequestArgs {
-                    host: host.clone(),
-                    reason: reason.clone(),
-                    client: client.clone(),
-                    method: Some(req.method().as_str().to_string()),
-                    mode: None,
-                    protocol: "http".to_string(),
-                    decision: Some(details.decision.as_str().to_string()),
-                    source: Some(details.source.as_str().to_string()),
-                    port: Some(port),
-                }))
-                .await;
-            let client = client.as_deref().unwrap_or_default();
-            warn!("request blocked (client={client}, host={host}, reason={reason})");
-            return Ok(json_blocked(&host, &reason, Some(&details)));
-        }
-        Ok(NetworkDecision::Allow) => {}
-        Err(err) => {
-            error!("failed to evaluate host for {host}: {err}");
-            return Ok(text_response(StatusCode::INTERNAL_SERVER_ERROR, "error"));
-        }
-    }
+                decision,
+                rule,
+            }) => {
+                let details = PolicyDecisionDetails {
+                    decision,
+                    reason: &reason,
+                    source,
+                    protocol,
+                    host: &host,
+                    port,
+                    rule: rule.as_deref(),
+                };
+                let _ = app_state
+                    .record_blocked(BlockedRequest::new(BlockedRequestArgs {
+                        host: host.clone(),
+                        reason: reason.clone(),
+                        client: client.clone(),
+                        method: Some(req.method().as_str().to_string()),
+                        mode: None,
+                        protocol: blocked_protocol.to_string(),
+                        decision: Some(details.decision.as_str().to_string()),
+                        source: Some(details.source.as_str().to_string()),
+                        rule: rule.clone(),
+                        port: Some(port),
+                    }))
+                    .await;
+                let client = client.as_deref().unwrap_or_default();
+                warn!("request blocked (client={client}, host={host}, reason={reason})");
+                return json_blocked(&host, &reason, Some(&details));
+            }
+            Ok(NetworkDecision::Allow { rule }) => rule,
+            Err(err) => {
+                error!("failed to evaluate host for {host}: {err}");
+                return text_response(StatusCode::INTERNAL_SERVER_ERROR, "error");
+            }
+        };
 
     if !method_allowed {
         let details = PolicyDecisionDetails {
             decision: NetworkPolicyDecision::Deny,
             reason: REASON_METHOD_NOT_ALLOWED,
             source: NetworkDecisionSource::ModeGuard,
-            protocol: NetworkProtocol::Http,
+            protocol,
             host: &host,
             port,
+            rule: None,
         };
         let _ = app_state
             .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -552,9 +906,10 @@ async fn http_plain_proxy(
                 client: client.clone(),
                 method: Some(req.method().as_str().to_string()),
                 mode: Some(NetworkMode::Limited),
-                protocol: "http".to_string(),
+                protocol: blocked_protocol.to_string(),
                 decision: Some(details.decision.as_str().to_string()),
                 source: Some(details.source.as_str().to_string()),
+                rule: None,
                 port: Some(port),
             }))
             .await;
@@ -563,13 +918,83 @@ async fn http_plain_proxy(
         warn!(
             "request blocked by method policy (client={client}, host={host}, method={method}, mode=limited, allowed_methods=GET, HEAD, OPTIONS)"
         );
-        return Ok(json_blocked(
-            &host,
-            REASON_METHOD_NOT_ALLOWED,
-            Some(&details),
-        ));
+        return json_blocked(&host, REASON_METHOD_NOT_ALLOWED, Some(&details));
     }
 
+    let cassette_url = cassette_url(protocol, &host, port, req.uri());
+    let method_name = req.method().as_str().to_string();
+    if let Some(cassette) = cassette
+        .as_ref()
+        .filter(|cassette| cassette.mode() == CassetteMode::Replay)
+    {
+        let replayed = hash_request_body(req.into_body())
+            .await
+            .and_then(|request_sha256| {
+                cassette.replay(&method_name, &cassette_url, &request_sha256)
+            });
+        let replayed = match replayed {
+            Ok(replayed) => replayed,
+            Err(err) => {
+                warn!("failed to replay {method_name} {cassette_url}: {err:#}");
+                return text_response(StatusCode::BAD_GATEWAY, "recorded response unavailable");
+            }
+        };
+        if let Some(resp) = replayed {
+            let traffic_key = app_state.record_allowed(
+                &host,
+                port,
+                blocked_protocol,
+                Some(&method_name),
+                client.as_deref(),
+                allow_rule,
+            );
+            let client = client.as_deref().unwrap_or_default();
+            info!("request replayed (client={client}, method={method_name}, url={cassette_url})");
+            let traffic = app_state.traffic_log();
+            return resp.map(|body| {
+                count_body_bytes(body, move |bytes| {
+                    traffic.record_bytes(&traffic_key, bytes, 0);
+                })
+            });
+        }
+        let details = PolicyDecisionDetails {
+            decision: NetworkPolicyDecision::Deny,
+            reason: REASON_NOT_RECORDED,
+            source: NetworkDecisionSource::Cassette,
+            protocol,
+            host: &host,
+            port,
+            rule: None,
+        };
+        let _ = app_state
+            .record_blocked(BlockedRequest::new(BlockedRequestArgs {
+                host: host.clone(),
+                reason: REASON_NOT_RECORDED.to_string(),
+                client: client.clone(),
+                method: Some(method_name.clone()),
+                mode: None,
+                protocol: blocked_protocol.to_string(),
+                decision: Some(details.decision.as_str().to_string()),
+                source: Some(details.source.as_str().to_string()),
+                rule: None,
+                port: Some(port),
+            }))
+            .await;
+        let client = client.as_deref().unwrap_or_default();
+        warn!(
+            "request blocked; not in cassette (client={client}, method={method_name}, url={cassette_url})"
+        );
+        return json_blocked(&host, REASON_NOT_RECORDED, Some(&details));
+    }
+
+    let traffic_key = app_state.record_allowed(
+        &host,
+        port,
+        blocked_protocol,
+        Some(&method_name),
+        client.as_deref(),
+        allow_rule,
+    );
     let client = client.as_deref().unwrap_or_default();
     let method = req.method();
     info!("request allowed (client={client}, host={host}, method={method})");
@@ -580,7 +1005,7 @@ async fn http_plain_proxy(
         .map_err(|err| internal_error("failed to read upstream proxy config", err))
     {
         Ok(allow) => allow,
-        Err(resp) => return Ok(resp),
+        Err(resp) => return resp,
     };
     let client = if allow_upstream_proxy {
         UpstreamClient::from_env_proxy()
@@ -590,12 +1015,73 @@ async fn http_plain_proxy(
 
     // Strip hop-by-hop headers only after extracting metadata used for policy correlation.
     remove_hop_by_hop_request_headers(req.headers_mut());
-    match client.serve(req).await {
-        Ok(resp) => Ok(resp),
+    let traffic = app_state.traffic_log();
+    let (req, request_body) = match cassette.as_ref() {
+        Some(cassette) if cassette.mode() == CassetteMode::Record => {
+            let (parts, body) = req.into_parts();
+            let (body, request_body) = RequestBodyHash::tee(body);
+            (Request::from_parts(parts, body), Some(request_body))
+        }
+        _ => (req, None),
+    };
+    let req = {
+        let traffic = traffic.clone();
+        let traffic_key = traffic_key.clone();
+        req.map(|body| {
+            count_body_bytes(body, move |bytes| {
+                traffic.record_bytes(&traffic_key, 0, bytes);
+            })
+        })
+    };
+    let resp = match client.serve(req).await {
+        Ok(resp) => resp,
         Err(err) => {
             warn!("upstream request failed: {err}");
-            Ok(text_response(StatusCode::BAD_GATEWAY, "upstream failure"))
+            return text_response(StatusCode::BAD_GATEWAY, "upstream failure");
+        }
+    };
+    let resp = match (cassette, request_body) {
+        (Some(cassette), Some(request_body)) => {
+            match cassette.record(&method_name, &cassette_url, request_body, resp) {
+                Ok(resp) => resp,
+                Err(err) => {
+                    warn!("failed to record {method_name} {cassette_url}: {err:#}");
+                    return text_response(StatusCode::BAD_GATEWAY, "upstream failure");
+                }
+            }
         }
+        _ => resp,
+    };
+    resp.map(|body| {
+        count_body_bytes(body, move |bytes| {
+            traffic.record_bytes(&traffic_key, bytes, 0);
+        })
+    })
+}
+
+/// Reports the size of each body chunk to `count` as it streams through.
+fn count_body_bytes(body: Body, count: impl Fn(u64) + Send + Sync + 'static) -> Body {
+    Body::from_stream(
+        body.into_data_stream()
+            .inspect_ok(move |chunk| count(chunk.len() as u64)),
+    )
+}
+
+/// Absolute URL a request is recorded under, with the port always spelled out so plain and
+/// intercepted requests key the same way regardless of how the client wrote the URI.
+fn cassette_url(protocol: NetworkProtocol, host: &str, port: u16, uri: &Uri) -> String {
+    let scheme = match protocol {
+        NetworkProtocol::HttpsConnect => "https",
+        _ => "http",
+    };
+    let path = uri
+        .path_and_query()
+        .map(rama_http::uri::PathAndQuery::as_str)
+        .unwrap_or("/");
+    if host.contains(':') {
+        format!("{scheme}://[{host}]:{port}{path}")
+    } else {
+        format!("{scheme}://{host}:{port}{path}")
     }
 }
 
@@ -670,7 +1156,7 @@ fn remove_hop_by_hop_request_headers(headers: &mut HeaderMap) {
 }
 
 fn json_blocked(host: &str, reason: &str, details: Option<&PolicyDecisionDetails<'_>>) -> Response {
-    let (message, decision, source, protocol, port) = details
+    let (message, decision, source, protocol, port, rule) = details
         .map(|details| {
             (
                 Some(blocked_message_with_policy(reason, details)),
@@ -678,9 +1164,10 @@ fn json_blocked(host: &str, reason: &str, details: Option<&PolicyDecisionDetails
                 Some(details.source.as_str()),
                 Some(details.protocol.as_policy_protocol()),
                 Some(details.port),
+                details.rule,
             )
         })
-        .unwrap_or((None, None, None, None, None));
+        .unwrap_or((None, None, None, None, None, None));
     let response = BlockedResponse {
         status: "blocked",
         host,
@@ -689,6 +1176,7 @@ fn json_blocked(host: &str, reason: &str, details: Option<&PolicyDecisionDetails
         source,
         protocol,
         port,
+        rule,
         message,
     };
     let mut resp = json_response(&response);
@@ -723,6 +1211,7 @@ async fn proxy_disabled_response(
             protocol: protocol.as_policy_protocol().to_string(),
             decision: Some("deny".to_string()),
             source: Some("proxy_state".to_string()),
+            rule: None,
             port: Some(port),
         }))
         .await;
@@ -734,6 +1223,7 @@ async fn proxy_disabled_response(
         protocol,
         host: &host,
         port,
+        rule: None,
     };
     text_response(
         StatusCode::SERVICE_UNAVAILABLE,
@@ -768,6 +1258,8 @@ struct BlockedResponse<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     port: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     message: Option<String>,
 }
 
@@ -775,6 +1267,7 @@ struct BlockedResponse<'a> {
 mod tests {
     use super::*;
 
+    use crate::config::NetworkCassette;
     use crate::config::NetworkMode;
     use crate::config::NetworkProxySettings;
     use crate::runtime::network_proxy_state_for_policy;
@@ -782,6 +1275,7 @@ mod tests {
     use rama_http::Method;
     use rama_http::Request;
     use std::sync::Arc;
+    use tempfile::TempDir;
 
     #[tokio::test]
     async fn http_connect_accept_blocks_in_limited_mode() {
@@ -800,7 +1294,9 @@ mod tests {
             .unwrap();
         req.extensions_mut().insert(state);
 
-        let response = http_connect_accept(None, req).await.unwrap_err();
+        let response = http_connect_accept(None, None, None, req)
+            .await
+            .unwrap_err();
         assert_eq!(response.status(), StatusCode::FORBIDDEN);
         assert_eq!(
             response.headers().get("x-proxy-error").unwrap(),
@@ -808,6 +1304,65 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn http_connect_accept_allows_limited_mode_with_mitm() {
+        let policy = NetworkProxySettings {
+            allowed_domains: vec!["example.com".to_string()],
+            mitm: true,
+            ..Default::default()
+        };
+        let state = Arc::new(network_proxy_state_for_policy(policy));
+        state.set_network_mode(NetworkMode::Limited).await.unwrap();
+        let codex_home = TempDir::new().unwrap();
+        let mitm = Arc::new(MitmCertificateAuthority::load_or_create(codex_home.path()).unwrap());
+
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .uri("https://example.com:443")
+            .header("host", "example.com:443")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(state);
+
+        let (response, request) = http_connect_accept(None, Some(mitm.clone()), None, req)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let attached = request
+            .extensions()
+            .get::<Arc<MitmCertificateAuthority>>()
+            .unwrap();
+        assert!(Arc::ptr_eq(attached, &mitm));
+    }
+
+    #[tokio::test]
+    async fn mitm_inner_request_rejects_host_other_than_connect_target() {
+        let policy = NetworkProxySettings {
+            allowed_domains: vec!["example.com".to_string(), "other.com".to_string()],
+            mitm: true,
+            ..Default::default()
+        };
+        let state = Arc::new(network_proxy_state_for_policy(policy));
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri("/")
+            .header("host", "other.com")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = mitm_inner_request(
+            state,
+            None,
+            None,
+            HostWithPort::new("example.com".parse().unwrap(), 443),
+            None,
+            req,
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::MISDIRECTED_REQUEST);
+    }
+
     #[tokio::test]
     async fn http_connect_accept_allows_allowlisted_host_in_full_mode() {
         let policy = NetworkProxySettings {
@@ -824,7 +1379,7 @@ mod tests {
             .unwrap();
         req.extensions_mut().insert(state);
 
-        let (response, _request) = http_connect_accept(None, req).await.unwrap();
+        let (response, _request) = http_connect_accept(None, None, None, req).await.unwrap();
         assert_eq!(response.status(), StatusCode::OK);
     }
 
@@ -845,7 +1400,9 @@ mod tests {
             .unwrap();
         req.extensions_mut().insert(state);
 
-        let response = http_connect_accept(None, req).await.unwrap_err();
+        let response = http_connect_accept(None, None, None, req)
+            .await
+            .unwrap_err();
         assert_eq!(response.status(), StatusCode::FORBIDDEN);
         assert_eq!(
             response.headers().get("x-proxy-error").unwrap(),
@@ -853,6 +1410,123 @@ mod tests {
         );
     }
 
+    fn replay_cassette(dir: &TempDir, entries: &str) -> Arc<Cassette> {
+        let path = dir.path().join("cassette.jsonl");
+        std::fs::write(&path, entries).unwrap();
+        Arc::new(
+            Cassette::open(&NetworkCassette {
+                mode: CassetteMode::Replay,
+                path,
+                max_body_bytes: 1024,
+            })
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn http_connect_accept_denies_replay_without_mitm() {
+        let policy = NetworkProxySettings {
+            allowed_domains: vec!["example.com".to_string()],
+            ..Default::default()
+        };
+        let state = Arc::new(network_proxy_state_for_policy(policy));
+        let dir = TempDir::new().unwrap();
+        let cassette = replay_cassette(&dir, "");
+
+        let mut req = Request::builder()
+            .method(Method::CONNECT)
+            .uri("https://example.com:443")
+            .header("host", "example.com:443")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(state);
+
+        let response = http_connect_accept(None, None, Some(cassette), req)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers().get("x-proxy-error").unwrap(),
+            "blocked-by-replay"
+        );
+    }
+
+    #[tokio::test]
+    async fn http_plain_proxy_denies_unix_sockets_during_replay() {
+        let state = Arc::new(network_proxy_state_for_policy(
+            NetworkProxySettings::default(),
+        ));
+        let dir = TempDir::new().unwrap();
+        let cassette = replay_cassette(&dir, "");
+
+        let mut req = Request::builder()
+            .method(Method::GET)
+            .uri("http://localhost/_ping")
+            .header("x-unix-socket", "/var/run/docker.sock")
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut().insert(state);
+
+        let response = http_plain_proxy(None, Some(cassette), req).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers().get("x-proxy-error").unwrap(),
+            "blocked-by-replay"
+        );
+    }
+
+    #[tokio::test]
+    async fn proxy_http_request_serves_replayed_responses_and_denies_unrecorded() {
+        let policy = NetworkProxySettings {
+            allowed_domains: vec!["example.com".to_string()],
+            ..Default::default()
+        };
+        let state = network_proxy_state_for_policy(policy);
+        let dir = TempDir::new().unwrap();
+        let cassette = replay_cassette(
+            &dir,
+            r#"{"method":"GET","url":"http://example.com:80/pkg?v=1","request_sha256":"e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855","status":200,"headers":[["content-type","text/plain"]],"body":"aGVsbG8="}"#,
+        );
+        let request = |uri: &str| {
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .header("host", "example.com")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = proxy_http_request(
+            &state,
+            None,
+            Some(cassette.clone()),
+            request("http://example.com/pkg?v=1"),
+            None,
+            NetworkProtocol::Http,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/plain"
+        );
+
+        let response = proxy_http_request(
+            &state,
+            None,
+            Some(cassette),
+            request("http://example.com/pkg?v=2"),
+            None,
+            NetworkProtocol::Http,
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            response.headers().get("x-proxy-error").unwrap(),
+            "blocked-by-replay"
+        );
+    }
+
     #[test]
     fn remove_hop_by_hop_request_headers_keeps_forwarding_headers() {
         let mut headers = HeaderMap::new();
@@ -1,21 +1,30 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod admin;
+mod cassette;
 mod config;
 mod http_proxy;
+mod mitm;
 mod network_policy;
 mod policy;
 mod proxy;
 mod reasons;
 mod responses;
+mod rules;
 mod runtime;
 mod socks5;
 mod state;
+mod traffic;
 mod upstream;
 
+pub use config::CassetteMode;
+pub use config::NetworkCassette;
 pub use config::NetworkMode;
 pub use config::NetworkProxyConfig;
+pub use config::NetworkRule;
+pub use config::NetworkRuleAction;
 pub use config::host_and_port_from_network_addr;
+pub use mitm::MITM_CA_ENV_KEYS;
 pub use network_policy::NetworkDecision;
 pub use network_policy::NetworkDecisionSource;
 pub use network_policy::NetworkPolicyDecider;
@@ -35,6 +44,7 @@ pub use proxy::NetworkProxyHandle;
 pub use proxy::PROXY_URL_ENV_KEYS;
 pub use proxy::has_proxy_url_env_vars;
 pub use proxy::proxy_url_env_value;
+pub use rules::NetworkRuleSet;
 pub use runtime::BlockedRequest;
 pub use runtime::BlockedRequestArgs;
 pub use runtime::BlockedRequestObserver;
@@ -47,3 +57,6 @@ pub use state::PartialNetworkConfig;
 pub use state::PartialNetworkProxyConfig;
 pub use state::build_config_state;
 pub use state::validate_policy_against_constraints;
+pub use traffic::TrafficAudit;
+pub use traffic::TrafficCounters;
+pub use traffic::TrafficEvent;
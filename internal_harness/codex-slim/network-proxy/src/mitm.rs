@@ -0,0 +1,377 @@
+use anyhow::Context;
+use anyhow::Result;
+use rama_tls_rustls::dep::pki_types::CertificateDer;
+use rama_tls_rustls::dep::pki_types::PrivateKeyDer;
+use rama_tls_rustls::dep::pki_types::PrivatePkcs8KeyDer;
+use rama_tls_rustls::dep::pki_types::pem::PemObject;
+use rama_tls_rustls::dep::rcgen::BasicConstraints;
+use rama_tls_rustls::dep::rcgen::CertificateParams;
+use rama_tls_rustls::dep::rcgen::DistinguishedName;
+use rama_tls_rustls::dep::rcgen::DnType;
+use rama_tls_rustls::dep::rcgen::ExtendedKeyUsagePurpose;
+use rama_tls_rustls::dep::rcgen::IsCa;
+use rama_tls_rustls::dep::rcgen::Issuer;
+use rama_tls_rustls::dep::rcgen::KeyPair;
+use rama_tls_rustls::dep::rcgen::KeyUsagePurpose;
+use rama_tls_rustls::dep::rustls::ServerConfig;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use time::Duration;
+use time::OffsetDateTime;
+use tracing::info;
+
+/// Environment variables pointing TLS clients at the MITM CA certificate.
+pub const MITM_CA_ENV_KEYS: &[&str] =
+    &["SSL_CERT_FILE", "NODE_EXTRA_CA_CERTS", "REQUESTS_CA_BUNDLE"];
+
+/// Variables in [`MITM_CA_ENV_KEYS`] that replace the client's trust store rather than add to
+/// it, so they point at [`MitmCertificateAuthority::ca_bundle_path`].
+pub(crate) const MITM_CA_BUNDLE_ENV_KEYS: &[&str] = &["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE"];
+
+const MITM_DIR: &[&str] = &["network-proxy", "mitm"];
+const CA_CERT_FILE: &str = "ca.pem";
+const CA_KEY_FILE: &str = "ca.key";
+const CA_BUNDLE_FILE: &str = "ca-bundle.pem";
+const CA_COMMON_NAME: &str = "Codex Network Proxy CA";
+const CA_VALIDITY: Duration = Duration::days(3650);
+const LEAF_VALIDITY: Duration = Duration::days(30);
+/// Cached leaf certificates are re-minted once they are this close to expiring.
+const LEAF_RENEWAL: Duration = Duration::days(2);
+/// Where OpenSSL-based clients usually find the system trust store, in the order
+/// `openssl-probe` checks them.
+const SYSTEM_CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/pki/tls/cacert.pem",
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+/// Backdate certificates so clients with a slightly skewed clock still accept them.
+const CLOCK_SKEW: Duration = Duration::days(1);
+
+/// Local certificate authority used to terminate intercepted HTTPS CONNECT tunnels.
+///
+/// The CA key and certificate are created once under `CODEX_HOME/network-proxy/mitm/` and reused
+/// across runs; leaf certificates are minted per host on demand and cached until they near
+/// expiry.
+pub struct MitmCertificateAuthority {
+    ca_cert_path: PathBuf,
+    ca_bundle_path: PathBuf,
+    ca_cert: CertificateDer<'static>,
+    issuer: Issuer<'static, KeyPair>,
+    server_configs: Mutex<HashMap<String, CachedServerConfig>>,
+}
+
+struct CachedServerConfig {
+    server_config: Arc<ServerConfig>,
+    not_after: OffsetDateTime,
+}
+
+impl std::fmt::Debug for MitmCertificateAuthority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MitmCertificateAuthority")
+            .field("ca_cert_path", &self.ca_cert_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MitmCertificateAuthority {
+    /// Loads the CA stored under `codex_home`, generating a new one if none exists yet.
+    pub fn load_or_create(codex_home: &Path) -> Result<Self> {
+        let dir = MITM_DIR
+            .iter()
+            .fold(codex_home.to_path_buf(), |dir, part| dir.join(part));
+        let ca_cert_path = dir.join(CA_CERT_FILE);
+        let ca_key_path = dir.join(CA_KEY_FILE);
+
+        let (key_pair, ca_cert) = if ca_key_path.exists() && ca_cert_path.exists() {
+            let key_pem = fs::read_to_string(&ca_key_path)
+                .with_context(|| format!("failed to read MITM CA key {}", ca_key_path.display()))?;
+            let key_pair = KeyPair::from_pem(&key_pem).with_context(|| {
+                format!("failed to parse MITM CA key {}", ca_key_path.display())
+            })?;
+            let ca_cert = CertificateDer::from_pem_file(&ca_cert_path).with_context(|| {
+                format!(
+                    "failed to parse MITM CA certificate {}",
+                    ca_cert_path.display()
+                )
+            })?;
+            (key_pair, ca_cert)
+        } else {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("failed to create MITM CA dir {}", dir.display()))?;
+            let key_pair = KeyPair::generate().context("failed to generate MITM CA key")?;
+            let cert = ca_params(OffsetDateTime::now_utc())
+                .self_signed(&key_pair)
+                .context("failed to self-sign MITM CA certificate")?;
+            write_private_file(&ca_key_path, key_pair.serialize_pem().as_bytes())?;
+            fs::write(&ca_cert_path, cert.pem()).with_context(|| {
+                format!(
+                    "failed to write MITM CA certificate {}",
+                    ca_cert_path.display()
+                )
+            })?;
+            info!("generated MITM CA at {}", ca_cert_path.display());
+            (key_pair, cert.der().clone())
+        };
+
+        let ca_bundle_path = dir.join(CA_BUNDLE_FILE);
+        write_ca_bundle(
+            &ca_bundle_path,
+            &ca_cert_path,
+            existing_ca_bundle(&ca_bundle_path).as_deref(),
+        )?;
+
+        // Leaf certificates only need the issuer's name and key, so rebuilding the parameters
+        // the stored certificate was created with is enough to sign under it.
+        let issuer = Issuer::new(ca_params(OffsetDateTime::now_utc()), key_pair);
+        Ok(Self {
+            ca_cert_path,
+            ca_bundle_path,
+            ca_cert,
+            issuer,
+            server_configs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// PEM file holding the CA certificate that sandboxed clients must trust.
+    pub fn ca_cert_path(&self) -> &Path {
+        &self.ca_cert_path
+    }
+
+    /// PEM file holding the trust store clients had before plus the MITM CA, for clients that
+    /// only trust the one file they are pointed at.
+    pub fn ca_bundle_path(&self) -> &Path {
+        &self.ca_bundle_path
+    }
+
+    /// Returns a TLS server config presenting a certificate for `host`, minting one if none is
+    /// cached or the cached one is about to expire.
+    pub(crate) fn server_config_for_host(&self, host: &str) -> Result<Arc<ServerConfig>> {
+        self.server_config_for_host_at(host, OffsetDateTime::now_utc())
+    }
+
+    fn server_config_for_host_at(
+        &self,
+        host: &str,
+        now: OffsetDateTime,
+    ) -> Result<Arc<ServerConfig>> {
+        let mut server_configs = self
+            .server_configs
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(cached) = server_configs.get(host)
+            && now + LEAF_RENEWAL < cached.not_after
+        {
+            return Ok(cached.server_config.clone());
+        }
+
+        let not_after = now + LEAF_VALIDITY;
+        let server_config = Arc::new(self.mint_server_config(host, now, not_after)?);
+        server_configs.insert(
+            host.to_string(),
+            CachedServerConfig {
+                server_config: server_config.clone(),
+                not_after,
+            },
+        );
+        Ok(server_config)
+    }
+
+    fn mint_server_config(
+        &self,
+        host: &str,
+        now: OffsetDateTime,
+        not_after: OffsetDateTime,
+    ) -> Result<ServerConfig> {
+        let mut params = CertificateParams::new(vec![host.to_string()])
+            .with_context(|| format!("invalid MITM certificate host {host}"))?;
+        params.distinguished_name.push(DnType::CommonName, host);
+        params.is_ca = IsCa::NoCa;
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.use_authority_key_identifier_extension = true;
+        params.not_before = now - CLOCK_SKEW;
+        params.not_after = not_after;
+
+        let key_pair = KeyPair::generate().context("failed to generate MITM leaf key")?;
+        let cert = params
+            .signed_by(&key_pair, &self.issuer)
+            .with_context(|| format!("failed to sign MITM certificate for {host}"))?;
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+
+        let mut server_config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.der().clone(), self.ca_cert.clone()], key)
+            .with_context(|| format!("failed to build MITM TLS config for {host}"))?;
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(server_config)
+    }
+}
+
+fn ca_params(now: OffsetDateTime) -> CertificateParams {
+    let mut params = CertificateParams::default();
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, CA_COMMON_NAME);
+    params.distinguished_name = distinguished_name;
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    params.not_before = now - CLOCK_SKEW;
+    params.not_after = now + CA_VALIDITY;
+    params
+}
+
+/// The roots clients trusted before the proxy pointed them elsewhere: the bundle named by
+/// `SSL_CERT_FILE` or `REQUESTS_CA_BUNDLE`, else the first system bundle that exists. Our own
+/// `bundle_path`, inherited when running under another proxy, is skipped.
+fn existing_ca_bundle(bundle_path: &Path) -> Option<PathBuf> {
+    let from_env = MITM_CA_BUNDLE_ENV_KEYS
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(PathBuf::from);
+    let system = SYSTEM_CA_BUNDLES.iter().map(PathBuf::from);
+    from_env
+        .chain(system)
+        .find(|path| path != bundle_path && path.is_file())
+}
+
+/// Writes `base_bundle` followed by the CA certificate to `bundle_path`, replacing it atomically
+/// so clients never read a partial bundle.
+fn write_ca_bundle(
+    bundle_path: &Path,
+    ca_cert_path: &Path,
+    base_bundle: Option<&Path>,
+) -> Result<()> {
+    let mut bundle = match base_bundle.filter(|base| *base != bundle_path) {
+        Some(base) => fs::read(base)
+            .with_context(|| format!("failed to read CA bundle {}", base.display()))?,
+        None => Vec::new(),
+    };
+    if !bundle.is_empty() && !bundle.ends_with(b"\n") {
+        bundle.push(b'\n');
+    }
+    bundle.extend(fs::read(ca_cert_path).with_context(|| {
+        format!(
+            "failed to read MITM CA certificate {}",
+            ca_cert_path.display()
+        )
+    })?);
+
+    let tmp_path = bundle_path.with_extension(format!("pem.{}.tmp", std::process::id()));
+    fs::write(&tmp_path, &bundle)
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, bundle_path)
+        .with_context(|| format!("failed to write {}", bundle_path.display()))
+}
+
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("failed to create {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use codex_utils_rustls_provider::ensure_rustls_crypto_provider;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn load_or_create_reuses_the_stored_ca() {
+        let codex_home = TempDir::new().unwrap();
+
+        let created = MitmCertificateAuthority::load_or_create(codex_home.path()).unwrap();
+        assert_eq!(
+            created.ca_cert_path(),
+            codex_home.path().join("network-proxy/mitm/ca.pem")
+        );
+        let loaded = MitmCertificateAuthority::load_or_create(codex_home.path()).unwrap();
+
+        assert_eq!(loaded.ca_cert, created.ca_cert);
+    }
+
+    #[test]
+    fn server_config_for_host_caches_minted_certificates() {
+        ensure_rustls_crypto_provider();
+        let codex_home = TempDir::new().unwrap();
+        let ca = MitmCertificateAuthority::load_or_create(codex_home.path()).unwrap();
+
+        let first = ca.server_config_for_host("example.com").unwrap();
+        let second = ca.server_config_for_host("example.com").unwrap();
+        let other = ca.server_config_for_host("127.0.0.1").unwrap();
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+
+    #[test]
+    fn server_config_for_host_renews_certificates_near_expiry() {
+        ensure_rustls_crypto_provider();
+        let codex_home = TempDir::new().unwrap();
+        let ca = MitmCertificateAuthority::load_or_create(codex_home.path()).unwrap();
+        let now = OffsetDateTime::now_utc();
+
+        let first = ca.server_config_for_host_at("example.com", now).unwrap();
+        let later = now + LEAF_VALIDITY - LEAF_RENEWAL - Duration::hours(1);
+        let still_valid = ca.server_config_for_host_at("example.com", later).unwrap();
+        let renewed = ca
+            .server_config_for_host_at("example.com", now + LEAF_VALIDITY - Duration::hours(1))
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&first, &still_valid));
+        assert!(!Arc::ptr_eq(&first, &renewed));
+    }
+
+    #[test]
+    fn write_ca_bundle_appends_the_ca_to_the_existing_roots() {
+        let dir = TempDir::new().unwrap();
+        let roots = dir.path().join("roots.pem");
+        fs::write(
+            &roots,
+            "-----BEGIN CERTIFICATE-----\nroot\n-----END CERTIFICATE-----",
+        )
+        .unwrap();
+        let ca_cert = dir.path().join("ca.pem");
+        fs::write(
+            &ca_cert,
+            "-----BEGIN CERTIFICATE-----\nmitm\n-----END CERTIFICATE-----\n",
+        )
+        .unwrap();
+        let bundle = dir.path().join("ca-bundle.pem");
+
+        write_ca_bundle(&bundle, &ca_cert, Some(&roots)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&bundle).unwrap(),
+            "-----BEGIN CERTIFICATE-----\nroot\n-----END CERTIFICATE-----\n\
+             -----BEGIN CERTIFICATE-----\nmitm\n-----END CERTIFICATE-----\n"
+        );
+
+        // Rewriting from the bundle itself must not nest the CA twice.
+        write_ca_bundle(&bundle, &ca_cert, Some(&bundle)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&bundle).unwrap(),
+            "-----BEGIN CERTIFICATE-----\nmitm\n-----END CERTIFICATE-----\n"
+        );
+    }
+}
@@ -1,4 +1,8 @@
 use crate::reasons::REASON_POLICY_DENIED;
+use crate::reasons::REASON_RULE_DENIED;
+use crate::reasons::REASON_RULE_NOT_MATCHED;
+use crate::rules::RequestLine;
+use crate::rules::RuleDecision;
 use crate::runtime::HostBlockDecision;
 use crate::runtime::HostBlockReason;
 use crate::state::NetworkProxyState;
@@ -49,6 +53,10 @@ pub enum NetworkDecisionSource {
     ModeGuard,
     ProxyState,
     Decider,
+    /// A `network.rules` entry decided.
+    Rule,
+    /// Replay mode found no recorded response.
+    Cassette,
 }
 
 impl NetworkDecisionSource {
@@ -58,6 +66,8 @@ impl NetworkDecisionSource {
             Self::ModeGuard => "mode_guard",
             Self::ProxyState => "proxy_state",
             Self::Decider => "decider",
+            Self::Rule => "rule",
+            Self::Cassette => "cassette",
         }
     }
 }
@@ -69,6 +79,8 @@ pub struct NetworkPolicyRequest {
     pub port: u16,
     pub client_addr: Option<String>,
     pub method: Option<String>,
+    /// Request path, when the proxy can see it (plain HTTP, or intercepted HTTPS).
+    pub path: Option<String>,
     pub command: Option<String>,
     pub exec_policy_hint: Option<String>,
 }
@@ -79,6 +91,8 @@ pub struct NetworkPolicyRequestArgs {
     pub port: u16,
     pub client_addr: Option<String>,
     pub method: Option<String>,
+    /// Request path, when the proxy can see it (plain HTTP, or intercepted HTTPS).
+    pub path: Option<String>,
     pub command: Option<String>,
     pub exec_policy_hint: Option<String>,
 }
@@ -91,6 +105,7 @@ impl NetworkPolicyRequest {
             port,
             client_addr,
             method,
+            path,
             command,
             exec_policy_hint,
         } = args;
@@ -100,23 +115,41 @@ impl NetworkPolicyRequest {
             port,
             client_addr,
             method,
+            path,
             command,
             exec_policy_hint,
         }
     }
+
+    fn request_line(&self) -> Option<RequestLine<'_>> {
+        Some(RequestLine {
+            method: self.method.as_deref()?,
+            path: self.path.as_deref()?,
+        })
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum NetworkDecision {
-    Allow,
+    Allow {
+        /// The `network.rules` entry that allowed the request, if any.
+        rule: Option<String>,
+    },
     Deny {
         reason: String,
         source: NetworkDecisionSource,
         decision: NetworkPolicyDecision,
+        /// The `network.rules` entry that denied the request, or the host's allow rules, separated
+        /// by `; `, when none of them matched.
+        rule: Option<String>,
     },
 }
 
 impl NetworkDecision {
+    pub fn allow() -> Self {
+        Self::Allow { rule: None }
+    }
+
     pub fn deny(reason: impl Into<String>) -> Self {
         Self::deny_with_source(reason, NetworkDecisionSource::Decider)
     }
@@ -136,6 +169,7 @@ impl NetworkDecision {
             reason,
             source,
             decision: NetworkPolicyDecision::Deny,
+            rule: None,
         }
     }
 
@@ -150,6 +184,16 @@ impl NetworkDecision {
             reason,
             source,
             decision: NetworkPolicyDecision::Ask,
+            rule: None,
+        }
+    }
+
+    fn rule_denied(reason: &str, rule: Option<String>) -> Self {
+        Self::Deny {
+            reason: reason.to_string(),
+            source: NetworkDecisionSource::Rule,
+            decision: NetworkPolicyDecision::Deny,
+            rule,
         }
     }
 }
@@ -182,13 +226,54 @@ where
     }
 }
 
+/// Evaluates the domain lists, `network.rules` and the decider for `request`.
+///
+/// `inner_requests_inspected` is set for intercepted tunnels: rules that depend on the method or
+/// path are then left to the inner requests instead of failing the tunnel.
 pub(crate) async fn evaluate_host_policy(
     state: &NetworkProxyState,
     decider: Option<&Arc<dyn NetworkPolicyDecider>>,
     request: &NetworkPolicyRequest,
+    inner_requests_inspected: bool,
 ) -> Result<NetworkDecision> {
-    match state.host_blocked(&request.host, request.port).await? {
-        HostBlockDecision::Allowed => Ok(NetworkDecision::Allow),
+    let host_decision = state.host_blocked(&request.host, request.port).await?;
+    if matches!(
+        host_decision,
+        HostBlockDecision::Allowed | HostBlockDecision::Blocked(HostBlockReason::NotAllowed)
+    ) {
+        match state
+            .rule_decision(&request.host, request.port, request.request_line())
+            .await?
+        {
+            RuleDecision::NotApplicable => {}
+            RuleDecision::Allowed { rule } => {
+                return Ok(NetworkDecision::Allow { rule: Some(rule) });
+            }
+            RuleDecision::NeedsRequestLine { .. } if inner_requests_inspected => {
+                return Ok(NetworkDecision::allow());
+            }
+            RuleDecision::Denied { rule }
+            | RuleDecision::NeedsRequestLine {
+                deny_rule: Some(rule),
+                ..
+            } => {
+                return Ok(NetworkDecision::rule_denied(REASON_RULE_DENIED, Some(rule)));
+            }
+            RuleDecision::Unmatched { allow_rules }
+            | RuleDecision::NeedsRequestLine {
+                deny_rule: None,
+                allow_rules,
+            } => {
+                return Ok(NetworkDecision::rule_denied(
+                    REASON_RULE_NOT_MATCHED,
+                    Some(allow_rules.join("; ")),
+                ));
+            }
+        }
+    }
+
+    match host_decision {
+        HostBlockDecision::Allowed => Ok(NetworkDecision::allow()),
         HostBlockDecision::Blocked(HostBlockReason::NotAllowed) => {
             if let Some(decider) = decider {
                 Ok(map_decider_decision(decider.decide(request.clone()).await))
@@ -208,13 +293,14 @@ pub(crate) async fn evaluate_host_policy(
 
 fn map_decider_decision(decision: NetworkDecision) -> NetworkDecision {
     match decision {
-        NetworkDecision::Allow => NetworkDecision::Allow,
+        NetworkDecision::Allow { .. } => NetworkDecision::allow(),
         NetworkDecision::Deny {
             reason, decision, ..
         } => NetworkDecision::Deny {
             reason,
             source: NetworkDecisionSource::Decider,
             decision,
+            rule: None,
         },
     }
 }
@@ -223,6 +309,8 @@ fn map_decider_decision(decision: NetworkDecision) -> NetworkDecision {
 mod tests {
     use super::*;
     use crate::config::NetworkProxySettings;
+    use crate::config::NetworkRule;
+    use crate::config::NetworkRuleAction;
     use crate::reasons::REASON_DENIED;
     use crate::reasons::REASON_NOT_ALLOWED;
     use crate::reasons::REASON_NOT_ALLOWED_LOCAL;
@@ -242,7 +330,7 @@ mod tests {
                 calls.fetch_add(1, Ordering::SeqCst);
                 // The default policy denies all; the decider is consulted for not_allowed
                 // requests and can override that decision.
-                async { NetworkDecision::Allow }
+                async { NetworkDecision::allow() }
             }
         });
 
@@ -252,14 +340,15 @@ mod tests {
             port: 80,
             client_addr: None,
             method: Some("GET".to_string()),
+            path: Some("/".to_string()),
             command: None,
             exec_policy_hint: None,
         });
 
-        let decision = evaluate_host_policy(&state, Some(&decider), &request)
+        let decision = evaluate_host_policy(&state, Some(&decider), &request, false)
             .await
             .unwrap();
-        assert_eq!(decision, NetworkDecision::Allow);
+        assert_eq!(decision, NetworkDecision::allow());
         assert_eq!(calls.load(Ordering::SeqCst), 1);
     }
 
@@ -275,7 +364,7 @@ mod tests {
             let calls = calls.clone();
             move |_req| {
                 calls.fetch_add(1, Ordering::SeqCst);
-                async { NetworkDecision::Allow }
+                async { NetworkDecision::allow() }
             }
         });
 
@@ -285,11 +374,12 @@ mod tests {
             port: 80,
             client_addr: None,
             method: Some("GET".to_string()),
+            path: Some("/".to_string()),
             command: None,
             exec_policy_hint: None,
         });
 
-        let decision = evaluate_host_policy(&state, Some(&decider), &request)
+        let decision = evaluate_host_policy(&state, Some(&decider), &request, false)
             .await
             .unwrap();
         assert_eq!(
@@ -298,6 +388,7 @@ mod tests {
                 reason: REASON_DENIED.to_string(),
                 source: NetworkDecisionSource::BaselinePolicy,
                 decision: NetworkPolicyDecision::Deny,
+                rule: None,
             }
         );
         assert_eq!(calls.load(Ordering::SeqCst), 0);
@@ -315,7 +406,7 @@ mod tests {
             let calls = calls.clone();
             move |_req| {
                 calls.fetch_add(1, Ordering::SeqCst);
-                async { NetworkDecision::Allow }
+                async { NetworkDecision::allow() }
             }
         });
 
@@ -325,11 +416,12 @@ mod tests {
             port: 80,
             client_addr: None,
             method: Some("GET".to_string()),
+            path: Some("/".to_string()),
             command: None,
             exec_policy_hint: None,
         });
 
-        let decision = evaluate_host_policy(&state, Some(&decider), &request)
+        let decision = evaluate_host_policy(&state, Some(&decider), &request, false)
             .await
             .unwrap();
         assert_eq!(
@@ -338,11 +430,142 @@ mod tests {
                 reason: REASON_NOT_ALLOWED_LOCAL.to_string(),
                 source: NetworkDecisionSource::BaselinePolicy,
                 decision: NetworkPolicyDecision::Deny,
+                rule: None,
             }
         );
         assert_eq!(calls.load(Ordering::SeqCst), 0);
     }
 
+    fn http_request(host: &str, method: &str, path: Option<&str>) -> NetworkPolicyRequest {
+        NetworkPolicyRequest::new(NetworkPolicyRequestArgs {
+            protocol: NetworkProtocol::Http,
+            host: host.to_string(),
+            port: 443,
+            client_addr: None,
+            method: Some(method.to_string()),
+            path: path.map(str::to_string),
+            command: None,
+            exec_policy_hint: None,
+        })
+    }
+
+    async fn evaluate(
+        state: &NetworkProxyState,
+        request: NetworkPolicyRequest,
+        inner_requests_inspected: bool,
+    ) -> NetworkDecision {
+        evaluate_host_policy(state, None, &request, inner_requests_inspected)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn evaluate_host_policy_applies_network_rules() {
+        let state = network_proxy_state_for_policy(NetworkProxySettings {
+            allowed_domains: vec!["github.com".to_string()],
+            rules: vec![
+                NetworkRule {
+                    action: NetworkRuleAction::Allow,
+                    methods: vec!["GET".to_string()],
+                    host: "registry.npmjs.org".to_string(),
+                    port: None,
+                    path: Some("/*".to_string()),
+                },
+                NetworkRule {
+                    action: NetworkRuleAction::Deny,
+                    methods: Vec::new(),
+                    host: "github.com".to_string(),
+                    port: None,
+                    path: Some("/*/archive/*".to_string()),
+                },
+            ],
+            ..NetworkProxySettings::default()
+        });
+
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("registry.npmjs.org", "GET", Some("/left-pad")),
+                false
+            )
+            .await,
+            NetworkDecision::Allow {
+                rule: Some("allow GET registry.npmjs.org/*".to_string())
+            }
+        );
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("registry.npmjs.org", "PUT", Some("/left-pad")),
+                false
+            )
+            .await,
+            NetworkDecision::rule_denied(
+                REASON_RULE_NOT_MATCHED,
+                Some("allow GET registry.npmjs.org/*".to_string())
+            )
+        );
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("github.com", "GET", Some("/openai/codex/archive/main.zip")),
+                false
+            )
+            .await,
+            NetworkDecision::Deny {
+                reason: REASON_RULE_DENIED.to_string(),
+                source: NetworkDecisionSource::Rule,
+                decision: NetworkPolicyDecision::Deny,
+                rule: Some("deny github.com/*/archive/*".to_string()),
+            }
+        );
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("github.com", "GET", Some("/openai/codex")),
+                false
+            )
+            .await,
+            NetworkDecision::allow()
+        );
+
+        // Tunnels only see the host: path rules are deferred to the inner requests when the
+        // tunnel is intercepted and fail closed otherwise.
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("registry.npmjs.org", "CONNECT", None),
+                true
+            )
+            .await,
+            NetworkDecision::allow()
+        );
+        assert_eq!(
+            evaluate(
+                &state,
+                http_request("registry.npmjs.org", "CONNECT", None),
+                false
+            )
+            .await,
+            NetworkDecision::rule_denied(
+                REASON_RULE_NOT_MATCHED,
+                Some("allow GET registry.npmjs.org/*".to_string())
+            )
+        );
+        // A path-scoped deny rule fails closed for tunnels that cannot be inspected.
+        assert_eq!(
+            evaluate(&state, http_request("github.com", "CONNECT", None), false).await,
+            NetworkDecision::rule_denied(
+                REASON_RULE_DENIED,
+                Some("deny github.com/*/archive/*".to_string())
+            )
+        );
+        assert_eq!(
+            evaluate(&state, http_request("github.com", "CONNECT", None), true).await,
+            NetworkDecision::allow()
+        );
+    }
+
     #[test]
     fn ask_uses_decider_source_and_ask_decision() {
         assert_eq!(
@@ -351,6 +574,7 @@ mod tests {
                 reason: REASON_NOT_ALLOWED.to_string(),
                 source: NetworkDecisionSource::Decider,
                 decision: NetworkPolicyDecision::Ask,
+                rule: None,
             }
         );
     }
@@ -1,6 +1,10 @@
 use crate::admin;
+use crate::cassette::Cassette;
 use crate::config;
+use crate::config::CassetteMode;
 use crate::http_proxy;
+use crate::mitm::MITM_CA_BUNDLE_ENV_KEYS;
+use crate::mitm::MitmCertificateAuthority;
 use crate::network_policy::NetworkPolicyDecider;
 use crate::runtime::BlockedRequestObserver;
 use crate::runtime::unix_socket_permissions_supported;
@@ -13,6 +17,8 @@ use codex_utils_rustls_provider::ensure_rustls_crypto_provider;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::net::TcpListener as StdTcpListener;
+use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use tokio::task::JoinHandle;
@@ -70,6 +76,7 @@ pub struct NetworkProxyBuilder {
     socks_addr: Option<SocketAddr>,
     admin_addr: Option<SocketAddr>,
     managed_by_codex: bool,
+    codex_home: Option<PathBuf>,
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
     blocked_request_observer: Option<Arc<dyn BlockedRequestObserver>>,
 }
@@ -82,6 +89,7 @@ impl Default for NetworkProxyBuilder {
             socks_addr: None,
             admin_addr: None,
             managed_by_codex: true,
+            codex_home: None,
             policy_decider: None,
             blocked_request_observer: None,
         }
@@ -114,6 +122,12 @@ impl NetworkProxyBuilder {
         self
     }
 
+    /// Directory the MITM CA is stored under when `network.mitm` is enabled.
+    pub fn codex_home(mut self, codex_home: impl Into<PathBuf>) -> Self {
+        self.codex_home = Some(codex_home.into());
+        self
+    }
+
     pub fn policy_decider<D>(mut self, decider: D) -> Self
     where
         D: NetworkPolicyDecider,
@@ -200,6 +214,37 @@ impl NetworkProxyBuilder {
             &current_cfg.network,
         );
 
+        let mitm = if current_cfg.network.mitm {
+            let codex_home = self.codex_home.as_deref().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "network.mitm requires a CODEX_HOME for the CA; supply one via builder.codex_home(...)"
+                )
+            })?;
+            Some(Arc::new(
+                MitmCertificateAuthority::load_or_create(codex_home)
+                    .context("load MITM certificate authority")?,
+            ))
+        } else {
+            None
+        };
+
+        let cassette = match current_cfg.network.cassette.as_ref() {
+            Some(cassette) => {
+                if cassette.mode == CassetteMode::Replay && current_cfg.network.enable_socks5 {
+                    anyhow::bail!(
+                        "network.cassette replay mode cannot serve SOCKS5 traffic; set network.enable_socks5 = false"
+                    );
+                }
+                if cassette.mode == CassetteMode::Record && current_cfg.network.enable_socks5 {
+                    warn!("SOCKS5 traffic is not recorded to the network cassette");
+                }
+                Some(Arc::new(
+                    Cassette::open(cassette).context("open network cassette")?,
+                ))
+            }
+            None => None,
+        };
+
         Ok(NetworkProxy {
             state,
             http_addr,
@@ -212,6 +257,8 @@ impl NetworkProxyBuilder {
                 .dangerously_allow_all_unix_sockets,
             admin_addr,
             reserved_listeners,
+            mitm,
+            cassette,
             policy_decider: self.policy_decider,
         })
     }
@@ -248,6 +295,8 @@ pub struct NetworkProxy {
     dangerously_allow_all_unix_sockets: bool,
     admin_addr: SocketAddr,
     reserved_listeners: Option<Arc<ReservedListeners>>,
+    mitm: Option<Arc<MitmCertificateAuthority>>,
+    cassette: Option<Arc<Cassette>>,
     policy_decider: Option<Arc<dyn NetworkPolicyDecider>>,
 }
 
@@ -408,6 +457,25 @@ fn apply_proxy_env_overrides(
     }
 }
 
+/// Points TLS clients at the MITM CA so intercepted HTTPS connections verify. Variables that
+/// replace the trust store get the bundle that keeps the existing roots; Node adds
+/// `NODE_EXTRA_CA_CERTS` to its built-in roots, so it gets the CA alone.
+fn apply_mitm_ca_env(
+    env: &mut HashMap<String, String>,
+    ca_cert_path: &Path,
+    ca_bundle_path: &Path,
+) {
+    set_env_keys(
+        env,
+        MITM_CA_BUNDLE_ENV_KEYS,
+        &ca_bundle_path.to_string_lossy(),
+    );
+    env.insert(
+        "NODE_EXTRA_CA_CERTS".to_string(),
+        ca_cert_path.to_string_lossy().into_owned(),
+    );
+}
+
 impl NetworkProxy {
     pub fn builder() -> NetworkProxyBuilder {
         NetworkProxyBuilder::default()
@@ -455,6 +523,9 @@ impl NetworkProxy {
             self.socks_enabled,
             self.allow_local_binding,
         );
+        if let Some(mitm) = self.mitm.as_ref() {
+            apply_mitm_ca_env(env, mitm.ca_cert_path(), mitm.ca_bundle_path());
+        }
     }
 
     pub async fn run(&self) -> Result<NetworkProxyHandle> {
@@ -479,14 +550,31 @@ impl NetworkProxy {
 
         let http_state = self.state.clone();
         let http_decider = self.policy_decider.clone();
+        let http_mitm = self.mitm.clone();
+        let http_cassette = self.cassette.clone();
         let http_addr = self.http_addr;
         let http_task = tokio::spawn(async move {
             match http_listener {
                 Some(listener) => {
-                    http_proxy::run_http_proxy_with_std_listener(http_state, listener, http_decider)
-                        .await
+                    http_proxy::run_http_proxy_with_std_listener(
+                        http_state,
+                        listener,
+                        http_decider,
+                        http_mitm,
+                        http_cassette,
+                    )
+                    .await
+                }
+                None => {
+                    http_proxy::run_http_proxy(
+                        http_state,
+                        http_addr,
+                        http_decider,
+                        http_mitm,
+                        http_cassette,
+                    )
+                    .await
                 }
-                None => http_proxy::run_http_proxy(http_state, http_addr, http_decider).await,
             }
         });
 
@@ -622,6 +710,7 @@ impl Drop for NetworkProxyHandle {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::NetworkCassette;
     use crate::config::NetworkProxySettings;
     use crate::state::network_proxy_state_for_policy;
     use pretty_assertions::assert_eq;
@@ -822,6 +911,72 @@ mod tests {
         assert_eq!(env.get(ALLOW_LOCAL_BINDING_ENV_KEY), Some(&"1".to_string()));
     }
 
+    #[test]
+    fn apply_mitm_ca_env_points_tls_clients_at_ca() {
+        let mut env = HashMap::new();
+        apply_mitm_ca_env(
+            &mut env,
+            Path::new("/tmp/codex/network-proxy/mitm/ca.pem"),
+            Path::new("/tmp/codex/network-proxy/mitm/ca-bundle.pem"),
+        );
+
+        for key in ["SSL_CERT_FILE", "REQUESTS_CA_BUNDLE"] {
+            assert_eq!(
+                env.get(key),
+                Some(&"/tmp/codex/network-proxy/mitm/ca-bundle.pem".to_string())
+            );
+        }
+        assert_eq!(
+            env.get("NODE_EXTRA_CA_CERTS"),
+            Some(&"/tmp/codex/network-proxy/mitm/ca.pem".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn mitm_proxy_builder_requires_codex_home() {
+        let settings = NetworkProxySettings {
+            mitm: true,
+            ..NetworkProxySettings::default()
+        };
+        let state = Arc::new(network_proxy_state_for_policy(settings));
+        let err = NetworkProxy::builder()
+            .state(state)
+            .managed_by_codex(false)
+            .build()
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("network.mitm requires a CODEX_HOME")
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_cassette_builder_rejects_socks5() {
+        let settings = NetworkProxySettings {
+            enable_socks5: true,
+            cassette: Some(NetworkCassette {
+                mode: CassetteMode::Replay,
+                path: PathBuf::from("/tmp/codex/cassette.jsonl"),
+                max_body_bytes: 1024,
+            }),
+            ..NetworkProxySettings::default()
+        };
+        let state = Arc::new(network_proxy_state_for_policy(settings));
+        let err = NetworkProxy::builder()
+            .state(state)
+            .managed_by_codex(false)
+            .build()
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("network.cassette replay mode cannot serve SOCKS5 traffic")
+        );
+    }
+
     #[test]
     fn apply_proxy_env_overrides_uses_plain_http_proxy_url() {
         let mut env = HashMap::new();
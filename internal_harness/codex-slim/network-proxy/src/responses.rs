@@ -5,6 +5,9 @@ use crate::reasons::REASON_DENIED;
 use crate::reasons::REASON_METHOD_NOT_ALLOWED;
 use crate::reasons::REASON_NOT_ALLOWED;
 use crate::reasons::REASON_NOT_ALLOWED_LOCAL;
+use crate::reasons::REASON_NOT_RECORDED;
+use crate::reasons::REASON_RULE_DENIED;
+use crate::reasons::REASON_RULE_NOT_MATCHED;
 use rama_http::Body;
 use rama_http::Response;
 use rama_http::StatusCode;
@@ -18,6 +21,7 @@ pub struct PolicyDecisionDetails<'a> {
     pub protocol: NetworkProtocol,
     pub host: &'a str,
     pub port: u16,
+    pub rule: Option<&'a str>,
 }
 
 pub fn text_response(status: StatusCode, body: &str) -> Response {
@@ -51,6 +55,8 @@ pub fn blocked_header_value(reason: &str) -> &'static str {
         REASON_NOT_ALLOWED | REASON_NOT_ALLOWED_LOCAL => "blocked-by-allowlist",
         REASON_DENIED => "blocked-by-denylist",
         REASON_METHOD_NOT_ALLOWED => "blocked-by-method-policy",
+        REASON_RULE_DENIED | REASON_RULE_NOT_MATCHED => "blocked-by-rule",
+        REASON_NOT_RECORDED => "blocked-by-replay",
         _ => "blocked-by-policy",
     }
 }
@@ -67,6 +73,13 @@ pub fn blocked_message(reason: &str) -> &'static str {
         REASON_METHOD_NOT_ALLOWED => {
             "Codex blocked this request: method not allowed in limited mode."
         }
+        REASON_RULE_DENIED => "Codex blocked this request: denied by a network rule.",
+        REASON_RULE_NOT_MATCHED => {
+            "Codex blocked this request: no network rule allows this method or path."
+        }
+        REASON_NOT_RECORDED => {
+            "Codex blocked this request: no recorded response in the replay cassette."
+        }
         _ => "Codex blocked this request by network policy.",
     }
 }
@@ -103,6 +116,7 @@ mod tests {
             protocol: NetworkProtocol::HttpsConnect,
             host: "api.example.com",
             port: 443,
+            rule: None,
         };
 
         let message = blocked_message_with_policy(REASON_NOT_ALLOWED, &details);
@@ -0,0 +1,409 @@
+use crate::config::NetworkRule;
+use crate::config::NetworkRuleAction;
+use crate::policy::compile_globset;
+use anyhow::Context;
+use anyhow::Result;
+use globset::Glob;
+use globset::GlobMatcher;
+use globset::GlobSet;
+
+/// Compiled form of `network.rules`.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkRuleSet {
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    action: NetworkRuleAction,
+    methods: Vec<String>,
+    host: GlobSet,
+    port: Option<u16>,
+    path: Option<GlobMatcher>,
+    label: String,
+}
+
+/// The method and path of a request, when the proxy can see them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RequestLine<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+}
+
+/// Outcome of checking a connection against `network.rules`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RuleDecision {
+    /// No rule names this host, so the domain lists decide.
+    NotApplicable,
+    Allowed {
+        rule: String,
+    },
+    Denied {
+        rule: String,
+    },
+    /// The host has allow rules, but none of them matches. `allow_rules` names them.
+    Unmatched {
+        allow_rules: Vec<String>,
+    },
+    /// The host has rules that depend on the method or path, which are not visible yet.
+    /// `deny_rule` names the first deny rule that might match once they are; the connection
+    /// must not be allowed without inspecting the request line. `allow_rules` names the host's
+    /// allow rules.
+    NeedsRequestLine {
+        deny_rule: Option<String>,
+        allow_rules: Vec<String>,
+    },
+}
+
+enum RuleMatch {
+    OtherHost,
+    Matched,
+    NotMatched,
+    NeedsRequestLine,
+}
+
+impl NetworkRuleSet {
+    pub(crate) fn compile(rules: &[NetworkRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let host = compile_globset(std::slice::from_ref(&rule.host))
+                    .with_context(|| format!("invalid host in network rule `{rule}`"))?;
+                let path = rule
+                    .path
+                    .as_deref()
+                    .map(|path| {
+                        Glob::new(path)
+                            .map(|glob| glob.compile_matcher())
+                            .with_context(|| format!("invalid path in network rule `{rule}`"))
+                    })
+                    .transpose()?;
+                Ok(CompiledRule {
+                    action: rule.action,
+                    methods: rule
+                        .methods
+                        .iter()
+                        .map(|method| method.to_ascii_uppercase())
+                        .collect(),
+                    host,
+                    port: rule.port,
+                    path,
+                    label: rule.to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Deny rules win over allow rules; within each action the first matching rule is reported.
+    /// A deny rule that depends on the method or path defers the decision even when an allow
+    /// rule already matched, so an uninspected connection cannot slip past it.
+    pub(crate) fn evaluate(
+        &self,
+        host: &str,
+        port: u16,
+        request_line: Option<RequestLine<'_>>,
+    ) -> RuleDecision {
+        let normalized_path = request_line.map(|line| normalize_path(line.path));
+        let request_line = request_line
+            .zip(normalized_path.as_deref())
+            .map(|(line, path)| RequestLine {
+                method: line.method,
+                path,
+            });
+        let mut allowed = None;
+        let mut allow_rules = Vec::new();
+        let mut needs_request_line = false;
+        let mut pending_deny: Option<String> = None;
+        for rule in &self.rules {
+            let rule_match = rule.matches(host, port, request_line);
+            match (rule.action, rule_match) {
+                (_, RuleMatch::OtherHost) => {}
+                (NetworkRuleAction::Deny, RuleMatch::Matched) => {
+                    return RuleDecision::Denied {
+                        rule: rule.label.clone(),
+                    };
+                }
+                (NetworkRuleAction::Deny, RuleMatch::NeedsRequestLine) => {
+                    pending_deny.get_or_insert_with(|| rule.label.clone());
+                }
+                (NetworkRuleAction::Deny, RuleMatch::NotMatched) => {}
+                (NetworkRuleAction::Allow, rule_match) => {
+                    allow_rules.push(rule.label.clone());
+                    match rule_match {
+                        RuleMatch::Matched => {
+                            allowed.get_or_insert_with(|| rule.label.clone());
+                        }
+                        RuleMatch::NeedsRequestLine => needs_request_line = true,
+                        RuleMatch::OtherHost | RuleMatch::NotMatched => {}
+                    }
+                }
+            }
+        }
+
+        if pending_deny.is_some() {
+            return RuleDecision::NeedsRequestLine {
+                deny_rule: pending_deny,
+                allow_rules,
+            };
+        }
+        match allowed {
+            Some(rule) => RuleDecision::Allowed { rule },
+            None if needs_request_line => RuleDecision::NeedsRequestLine {
+                deny_rule: None,
+                allow_rules,
+            },
+            None if !allow_rules.is_empty() => RuleDecision::Unmatched { allow_rules },
+            None => RuleDecision::NotApplicable,
+        }
+    }
+}
+
+/// Brings a request path into the form the rules are written against, so that equivalent
+/// spellings such as `/a/%61rchive` or `/a/./archive` cannot dodge a path glob: percent-encoded
+/// unreserved characters are decoded and `.`/`..` segments are removed (RFC 3986, 6.2.2).
+fn normalize_path(path: &str) -> String {
+    remove_dot_segments(&decode_unreserved(path))
+}
+
+fn decode_unreserved(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(hex) = bytes.get(index + 1..index + 3)
+            && let Ok(hex) = std::str::from_utf8(hex)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+            && (byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~'))
+        {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    // Only ASCII bytes were substituted for ASCII escapes, so the result is still UTF-8.
+    String::from_utf8(decoded).unwrap_or_else(|_| path.to_string())
+}
+
+fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let segments: Vec<&str> = path.split('/').collect();
+    let last = segments.len() - 1;
+    let mut output: Vec<&str> = Vec::new();
+    for (index, segment) in segments.iter().enumerate().skip(usize::from(absolute)) {
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    output.pop();
+                }
+                // A trailing dot segment still names a directory.
+                if index == last {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    let joined = output.join("/");
+    if absolute {
+        format!("/{joined}")
+    } else {
+        joined
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, host: &str, port: u16, request_line: Option<RequestLine<'_>>) -> RuleMatch {
+        if !self.host.is_match(host) || self.port.is_some_and(|rule_port| rule_port != port) {
+            return RuleMatch::OtherHost;
+        }
+        let Some(request_line) = request_line else {
+            return if self.methods.is_empty() && self.path.is_none() {
+                RuleMatch::Matched
+            } else {
+                RuleMatch::NeedsRequestLine
+            };
+        };
+        let method_matches = self.methods.is_empty()
+            || self
+                .methods
+                .iter()
+                .any(|method| method.eq_ignore_ascii_case(request_line.method));
+        let path_matches = self
+            .path
+            .as_ref()
+            .is_none_or(|path| path.is_match(request_line.path));
+        if method_matches && path_matches {
+            RuleMatch::Matched
+        } else {
+            RuleMatch::NotMatched
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn rule(action: NetworkRuleAction, methods: &[&str], host: &str, path: &str) -> NetworkRule {
+        NetworkRule {
+            action,
+            methods: methods.iter().map(ToString::to_string).collect(),
+            host: host.to_string(),
+            port: None,
+            path: (!path.is_empty()).then(|| path.to_string()),
+        }
+    }
+
+    fn line<'a>(method: &'a str, path: &'a str) -> Option<RequestLine<'a>> {
+        Some(RequestLine { method, path })
+    }
+
+    #[test]
+    fn evaluate_applies_method_and_path_rules() {
+        let rules = NetworkRuleSet::compile(&[
+            rule(
+                NetworkRuleAction::Allow,
+                &["GET"],
+                "registry.npmjs.org",
+                "/*",
+            ),
+            rule(
+                NetworkRuleAction::Allow,
+                &["POST"],
+                "api.internal",
+                "/v1/review/*",
+            ),
+            rule(NetworkRuleAction::Deny, &[], "github.com", "/*/archive/*"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            rules.evaluate("registry.npmjs.org", 443, line("GET", "/left-pad")),
+            RuleDecision::Allowed {
+                rule: "allow GET registry.npmjs.org/*".to_string()
+            }
+        );
+        assert_eq!(
+            rules.evaluate("registry.npmjs.org", 443, line("PUT", "/left-pad")),
+            RuleDecision::Unmatched {
+                allow_rules: vec!["allow GET registry.npmjs.org/*".to_string()]
+            }
+        );
+        assert_eq!(
+            rules.evaluate("api.internal", 443, line("POST", "/v1/review/42")),
+            RuleDecision::Allowed {
+                rule: "allow POST api.internal/v1/review/*".to_string()
+            }
+        );
+        assert_eq!(
+            rules.evaluate("api.internal", 443, line("POST", "/v1/admin")),
+            RuleDecision::Unmatched {
+                allow_rules: vec!["allow POST api.internal/v1/review/*".to_string()]
+            }
+        );
+        assert_eq!(
+            rules.evaluate(
+                "github.com",
+                443,
+                line("GET", "/openai/codex/archive/main.zip")
+            ),
+            RuleDecision::Denied {
+                rule: "deny github.com/*/archive/*".to_string()
+            }
+        );
+        assert_eq!(
+            rules.evaluate("github.com", 443, line("GET", "/openai/codex")),
+            RuleDecision::NotApplicable
+        );
+    }
+
+    #[test]
+    fn evaluate_without_request_line_defers_method_and_path_rules() {
+        let rules = NetworkRuleSet::compile(&[
+            rule(NetworkRuleAction::Allow, &["GET"], "registry.npmjs.org", ""),
+            rule(NetworkRuleAction::Deny, &[], "*.evil.com", ""),
+            rule(NetworkRuleAction::Deny, &["POST"], "example.com", ""),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            rules.evaluate("registry.npmjs.org", 443, None),
+            RuleDecision::NeedsRequestLine {
+                deny_rule: None,
+                allow_rules: vec!["allow GET registry.npmjs.org".to_string()]
+            }
+        );
+        assert_eq!(
+            rules.evaluate("cdn.evil.com", 443, None),
+            RuleDecision::Denied {
+                rule: "deny *.evil.com".to_string()
+            }
+        );
+        assert_eq!(
+            rules.evaluate("example.com", 443, None),
+            RuleDecision::NeedsRequestLine {
+                deny_rule: Some("deny POST example.com".to_string()),
+                allow_rules: Vec::new()
+            }
+        );
+        assert_eq!(
+            rules.evaluate("example.com", 443, line("GET", "/")),
+            RuleDecision::NotApplicable
+        );
+    }
+
+    #[test]
+    fn evaluate_normalizes_encoded_and_dot_segment_paths() {
+        let rules = NetworkRuleSet::compile(&[rule(
+            NetworkRuleAction::Deny,
+            &[],
+            "github.com",
+            "/*/archive/*",
+        )])
+        .unwrap();
+        let denied = RuleDecision::Denied {
+            rule: "deny github.com/*/archive/*".to_string(),
+        };
+
+        for path in [
+            "/openai/codex/%61rchive/main.zip",
+            "/openai/codex/%61%72%63hive/main.zip",
+            "/x/./archive/main.zip",
+            "/x/y/../archive/main.zip",
+            "/x/%2e/archive/main.zip",
+        ] {
+            assert_eq!(
+                rules.evaluate("github.com", 443, line("GET", path)),
+                denied,
+                "{path}"
+            );
+        }
+        // Reserved characters keep their encoding, so an encoded slash stays inside a segment.
+        assert_eq!(
+            rules.evaluate("github.com", 443, line("GET", "/x%2Farchive")),
+            RuleDecision::NotApplicable
+        );
+    }
+
+    #[test]
+    fn normalize_path_removes_dot_segments() {
+        assert_eq!(normalize_path("/"), "/");
+        assert_eq!(normalize_path("/a/b/.."), "/a/");
+        assert_eq!(normalize_path("/../a/./b/."), "/a/b/");
+        assert_eq!(normalize_path("/a/%7Euser/%20x"), "/a/~user/%20x");
+    }
+
+    #[test]
+    fn compile_rejects_invalid_path_globs() {
+        let err =
+            NetworkRuleSet::compile(&[rule(NetworkRuleAction::Deny, &[], "example.com", "/[")])
+                .unwrap_err();
+        assert!(err.to_string().contains("invalid path in network rule"));
+    }
+}
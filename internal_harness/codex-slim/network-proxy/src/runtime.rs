@@ -1,6 +1,7 @@
 use crate::config::NetworkMode;
 use crate::config::NetworkProxyConfig;
 use crate::config::ValidatedUnixSocketPath;
+use crate::network_policy::NetworkDecisionSource;
 use crate::policy::Host;
 use crate::policy::is_loopback_host;
 use crate::policy::is_non_public_ip;
@@ -8,10 +9,17 @@ use crate::policy::normalize_host;
 use crate::reasons::REASON_DENIED;
 use crate::reasons::REASON_NOT_ALLOWED;
 use crate::reasons::REASON_NOT_ALLOWED_LOCAL;
+use crate::rules::NetworkRuleSet;
+use crate::rules::RequestLine;
+use crate::rules::RuleDecision;
 use crate::state::NetworkProxyConstraintError;
 use crate::state::NetworkProxyConstraints;
 use crate::state::build_config_state;
 use crate::state::validate_policy_against_constraints;
+use crate::traffic::TrafficAudit;
+use crate::traffic::TrafficEvent;
+use crate::traffic::TrafficKey;
+use crate::traffic::TrafficLog;
 use anyhow::Context;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -28,6 +36,7 @@ use std::time::Duration;
 use time::OffsetDateTime;
 use tokio::net::lookup_host;
 use tokio::sync::RwLock;
+use tokio::sync::broadcast;
 use tokio::time::timeout;
 use tracing::debug;
 use tracing::info;
@@ -78,6 +87,9 @@ pub struct BlockedRequest {
     pub decision: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source: Option<String>,
+    /// The `network.rules` entry that blocked the request, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub port: Option<u16>,
     pub timestamp: i64,
@@ -92,6 +104,7 @@ pub struct BlockedRequestArgs {
     pub protocol: String,
     pub decision: Option<String>,
     pub source: Option<String>,
+    pub rule: Option<String>,
     pub port: Option<u16>,
 }
 
@@ -106,6 +119,7 @@ impl BlockedRequest {
             protocol,
             decision,
             source,
+            rule,
             port,
         } = args;
         Self {
@@ -117,6 +131,7 @@ impl BlockedRequest {
             protocol,
             decision,
             source,
+            rule,
             port,
             timestamp: unix_timestamp(),
         }
@@ -141,6 +156,7 @@ pub struct ConfigState {
     pub config: NetworkProxyConfig,
     pub allow_set: GlobSet,
     pub deny_set: GlobSet,
+    pub rules: NetworkRuleSet,
     pub constraints: NetworkProxyConstraints,
     pub blocked: VecDeque<BlockedRequest>,
     pub blocked_total: u64,
@@ -185,6 +201,7 @@ pub struct NetworkProxyState {
     state: Arc<RwLock<ConfigState>>,
     reloader: Arc<dyn ConfigReloader>,
     blocked_request_observer: Arc<RwLock<Option<Arc<dyn BlockedRequestObserver>>>>,
+    traffic: Arc<TrafficLog>,
 }
 
 impl std::fmt::Debug for NetworkProxyState {
@@ -201,6 +218,7 @@ impl Clone for NetworkProxyState {
             state: self.state.clone(),
             reloader: self.reloader.clone(),
             blocked_request_observer: self.blocked_request_observer.clone(),
+            traffic: self.traffic.clone(),
         }
     }
 }
@@ -219,6 +237,7 @@ impl NetworkProxyState {
             state: Arc::new(RwLock::new(state)),
             reloader,
             blocked_request_observer: Arc::new(RwLock::new(blocked_request_observer)),
+            traffic: Arc::new(TrafficLog::new()),
         }
     }
 
@@ -348,8 +367,36 @@ impl NetworkProxyState {
         }
     }
 
+    pub(crate) async fn rule_decision(
+        &self,
+        host: &str,
+        port: u16,
+        request_line: Option<RequestLine<'_>>,
+    ) -> Result<RuleDecision> {
+        self.reload_if_needed().await?;
+        let host = match Host::parse(host) {
+            Ok(host) => host,
+            Err(_) => return Ok(RuleDecision::NotApplicable),
+        };
+        let guard = self.state.read().await;
+        Ok(guard.rules.evaluate(host.as_str(), port, request_line))
+    }
+
     pub async fn record_blocked(&self, entry: BlockedRequest) -> Result<()> {
         self.reload_if_needed().await?;
+        self.traffic.record(TrafficEvent {
+            timestamp: entry.timestamp,
+            decision: entry.decision.clone().unwrap_or_else(|| "deny".to_string()),
+            host: entry.host.clone(),
+            port: entry.port,
+            protocol: entry.protocol.clone(),
+            method: entry.method.clone(),
+            client: entry.client.clone(),
+            process: None,
+            reason: Some(entry.reason.clone()),
+            source: entry.source.clone(),
+            rule: entry.rule.clone(),
+        });
         let blocked_for_observer = entry.clone();
         let blocked_request_observer = self.blocked_request_observer.read().await.clone();
         let violation_line = blocked_request_violation_log_line(&entry);
@@ -386,6 +433,48 @@ impl NetworkProxyState {
         Ok(())
     }
 
+    /// Records an allowed connection or request and returns the key its bytes are counted under.
+    /// `rule` is the `network.rules` entry that allowed it, if any.
+    pub(crate) fn record_allowed(
+        &self,
+        host: &str,
+        port: u16,
+        protocol: &str,
+        method: Option<&str>,
+        client: Option<&str>,
+        rule: Option<String>,
+    ) -> TrafficKey {
+        self.traffic.record(TrafficEvent {
+            timestamp: unix_timestamp(),
+            decision: "allow".to_string(),
+            host: host.to_string(),
+            port: Some(port),
+            protocol: protocol.to_string(),
+            method: method.map(str::to_string),
+            client: client.map(str::to_string),
+            process: None,
+            reason: None,
+            source: rule
+                .is_some()
+                .then(|| NetworkDecisionSource::Rule.as_str().to_string()),
+            rule,
+        })
+    }
+
+    pub(crate) fn traffic_log(&self) -> Arc<TrafficLog> {
+        self.traffic.clone()
+    }
+
+    /// Subscribes to every connection decision made from now on.
+    pub fn subscribe_traffic(&self) -> broadcast::Receiver<TrafficEvent> {
+        self.traffic.subscribe()
+    }
+
+    /// Returns the per-host and per-process counters and the decision log for this session.
+    pub fn traffic_audit(&self) -> TrafficAudit {
+        self.traffic.snapshot()
+    }
+
     /// Returns a snapshot of buffered blocked-request entries without consuming
     /// them.
     pub async fn blocked_snapshot(&self) -> Result<Vec<BlockedRequest>> {
@@ -760,6 +849,8 @@ mod tests {
 
     use crate::config::NetworkProxyConfig;
     use crate::config::NetworkProxySettings;
+    use crate::config::NetworkRule;
+    use crate::config::NetworkRuleAction;
     use crate::policy::compile_globset;
     use crate::state::NetworkProxyConstraints;
     use crate::state::validate_policy_against_constraints;
@@ -848,6 +939,7 @@ mod tests {
                 protocol: "http".to_string(),
                 decision: Some("ask".to_string()),
                 source: Some("decider".to_string()),
+                rule: None,
                 port: Some(80),
             }))
             .await
@@ -873,6 +965,50 @@ mod tests {
         assert_eq!(drained[0].port, snapshot[0].port);
     }
 
+    #[tokio::test]
+    async fn traffic_audit_counts_allowed_and_blocked_requests() {
+        let state = network_proxy_state_for_policy(NetworkProxySettings::default());
+        let mut events = state.subscribe_traffic();
+
+        state.record_allowed(
+            "example.com",
+            443,
+            "http-connect",
+            Some("CONNECT"),
+            None,
+            Some("allow example.com".to_string()),
+        );
+        state
+            .record_blocked(BlockedRequest::new(BlockedRequestArgs {
+                host: "evil.com".to_string(),
+                reason: "denied".to_string(),
+                client: None,
+                method: Some("GET".to_string()),
+                mode: None,
+                protocol: "http".to_string(),
+                decision: None,
+                source: Some("baseline_policy".to_string()),
+                rule: None,
+                port: Some(80),
+            }))
+            .await
+            .expect("entry should be recorded");
+
+        let received = [events.recv().await.unwrap(), events.recv().await.unwrap()];
+        assert_eq!(received[0].decision, "allow");
+        assert_eq!(received[0].source.as_deref(), Some("rule"));
+        assert_eq!(received[0].rule.as_deref(), Some("allow example.com"));
+        assert_eq!(received[1].decision, "deny");
+        assert_eq!(received[1].reason.as_deref(), Some("denied"));
+
+        let audit = state.traffic_audit();
+        assert_eq!(audit.events.len(), 2);
+        assert_eq!(audit.hosts["example.com"].requests, 1);
+        assert_eq!(audit.hosts["example.com"].denials, 0);
+        assert_eq!(audit.hosts["evil.com"].denials, 1);
+        assert_eq!(audit.processes["unknown"].requests, 2);
+    }
+
     #[tokio::test]
     async fn drain_blocked_returns_buffered_window() {
         let state = network_proxy_state_for_policy(NetworkProxySettings::default());
@@ -888,6 +1024,7 @@ mod tests {
                     protocol: "http".to_string(),
                     decision: Some("ask".to_string()),
                     source: Some("decider".to_string()),
+                    rule: None,
                     port: Some(80),
                 }))
                 .await
@@ -1085,6 +1222,31 @@ mod tests {
         assert!(validate_policy_against_constraints(&config, &constraints).is_err());
     }
 
+    #[test]
+    fn validate_policy_against_constraints_disallows_allow_rules_outside_allowed_domains() {
+        let constraints = NetworkProxyConstraints {
+            allowed_domains: Some(vec!["example.com".to_string()]),
+            ..NetworkProxyConstraints::default()
+        };
+
+        let config = NetworkProxyConfig {
+            network: NetworkProxySettings {
+                enabled: true,
+                allowed_domains: vec!["example.com".to_string()],
+                rules: vec![NetworkRule {
+                    action: NetworkRuleAction::Allow,
+                    methods: vec!["GET".to_string()],
+                    host: "evil.com".to_string(),
+                    port: None,
+                    path: None,
+                }],
+                ..NetworkProxySettings::default()
+            },
+        };
+
+        assert!(validate_policy_against_constraints(&config, &constraints).is_err());
+    }
+
     #[test]
     fn validate_policy_against_constraints_disallows_widening_mode() {
         let constraints = NetworkProxyConstraints {
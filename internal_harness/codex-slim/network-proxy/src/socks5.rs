@@ -159,6 +159,7 @@ async fn handle_socks5_tcp(
                 protocol: NetworkProtocol::Socks5Tcp,
                 host: &host,
                 port,
+                rule: None,
             };
             let _ = app_state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -170,6 +171,7 @@ async fn handle_socks5_tcp(
                     protocol: "socks5".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: None,
                     port: Some(port),
                 }))
                 .await;
@@ -192,6 +194,7 @@ async fn handle_socks5_tcp(
                 protocol: NetworkProtocol::Socks5Tcp,
                 host: &host,
                 port,
+                rule: None,
             };
             let _ = app_state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -203,6 +206,7 @@ async fn handle_socks5_tcp(
                     protocol: "socks5".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: None,
                     port: Some(port),
                 }))
                 .await;
@@ -225,15 +229,17 @@ async fn handle_socks5_tcp(
         port,
         client_addr: client.clone(),
         method: None,
+        path: None,
         command: None,
         exec_policy_hint: None,
     });
 
-    match evaluate_host_policy(&app_state, policy_decider.as_ref(), &request).await {
+    match evaluate_host_policy(&app_state, policy_decider.as_ref(), &request, false).await {
         Ok(NetworkDecision::Deny {
             reason,
             source,
             decision,
+            rule,
         }) => {
             let details = PolicyDecisionDetails {
                 decision,
@@ -242,6 +248,7 @@ async fn handle_socks5_tcp(
                 protocol: NetworkProtocol::Socks5Tcp,
                 host: &host,
                 port,
+                rule: rule.as_deref(),
             };
             let _ = app_state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -253,6 +260,7 @@ async fn handle_socks5_tcp(
                     protocol: "socks5".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: rule.clone(),
                     port: Some(port),
                 }))
                 .await;
@@ -260,7 +268,8 @@ async fn handle_socks5_tcp(
             warn!("SOCKS blocked (client={client}, host={host}, reason={reason})");
             return Err(policy_denied_error(&reason, &details).into());
         }
-        Ok(NetworkDecision::Allow) => {
+        Ok(NetworkDecision::Allow { rule }) => {
+            app_state.record_allowed(&host, port, "socks5", None, client.as_deref(), rule);
             let client = client.as_deref().unwrap_or_default();
             info!("SOCKS allowed (client={client}, host={host}, port={port})");
         }
@@ -305,6 +314,7 @@ async fn inspect_socks5_udp(
                 protocol: NetworkProtocol::Socks5Udp,
                 host: &host,
                 port,
+                rule: None,
             };
             let _ = state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -316,6 +326,7 @@ async fn inspect_socks5_udp(
                     protocol: "socks5-udp".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: None,
                     port: Some(port),
                 }))
                 .await;
@@ -338,6 +349,7 @@ async fn inspect_socks5_udp(
                 protocol: NetworkProtocol::Socks5Udp,
                 host: &host,
                 port,
+                rule: None,
             };
             let _ = state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -349,6 +361,7 @@ async fn inspect_socks5_udp(
                     protocol: "socks5-udp".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: None,
                     port: Some(port),
                 }))
                 .await;
@@ -367,15 +380,17 @@ async fn inspect_socks5_udp(
         port,
         client_addr: client.clone(),
         method: None,
+        path: None,
         command: None,
         exec_policy_hint: None,
     });
 
-    match evaluate_host_policy(&state, policy_decider.as_ref(), &request).await {
+    match evaluate_host_policy(&state, policy_decider.as_ref(), &request, false).await {
         Ok(NetworkDecision::Deny {
             reason,
             source,
             decision,
+            rule,
         }) => {
             let details = PolicyDecisionDetails {
                 decision,
@@ -384,6 +399,7 @@ async fn inspect_socks5_udp(
                 protocol: NetworkProtocol::Socks5Udp,
                 host: &host,
                 port,
+                rule: rule.as_deref(),
             };
             let _ = state
                 .record_blocked(BlockedRequest::new(BlockedRequestArgs {
@@ -395,6 +411,7 @@ async fn inspect_socks5_udp(
                     protocol: "socks5-udp".to_string(),
                     decision: Some(details.decision.as_str().to_string()),
                     source: Some(details.source.as_str().to_string()),
+                    rule: rule.clone(),
                     port: Some(port),
                 }))
                 .await;
@@ -402,10 +419,18 @@ async fn inspect_socks5_udp(
             warn!("SOCKS UDP blocked (client={client}, host={host}, reason={reason})");
             Err(policy_denied_error(&reason, &details))
         }
-        Ok(NetworkDecision::Allow) => Ok(RelayResponse {
-            maybe_payload: Some(payload),
-            extensions,
-        }),
+        Ok(NetworkDecision::Allow { rule }) => {
+            if state
+                .traffic_log()
+                .first_udp_datagram(client.as_deref(), &host, port)
+            {
+                state.record_allowed(&host, port, "socks5-udp", None, client.as_deref(), rule);
+            }
+            Ok(RelayResponse {
+                maybe_payload: Some(payload),
+                extensions,
+            })
+        }
         Err(err) => {
             error!("failed to evaluate UDP host: {err}");
             Err(io::Error::other("proxy error"))
@@ -1,7 +1,9 @@
 use crate::config::NetworkMode;
 use crate::config::NetworkProxyConfig;
+use crate::config::NetworkRuleAction;
 use crate::policy::DomainPattern;
 use crate::policy::compile_globset;
+use crate::rules::NetworkRuleSet;
 use crate::runtime::ConfigState;
 use serde::Deserialize;
 use std::collections::HashSet;
@@ -57,10 +59,12 @@ pub fn build_config_state(
     crate::config::validate_unix_socket_allowlist_paths(&config)?;
     let deny_set = compile_globset(&config.network.denied_domains)?;
     let allow_set = compile_globset(&config.network.allowed_domains)?;
+    let rules = NetworkRuleSet::compile(&config.network.rules)?;
     Ok(ConfigState {
         config,
         allow_set,
         deny_set,
+        rules,
         constraints,
         blocked: std::collections::VecDeque::new(),
         blocked_total: 0,
@@ -213,6 +217,35 @@ pub fn validate_policy_against_constraints(
             .iter()
             .map(|entry| DomainPattern::parse_for_constraints(entry))
             .collect();
+        // Allow rules grant access to their hosts, so they must stay within the managed allowlist
+        // just like `allowed_domains` entries.
+        let rule_hosts: Vec<String> = config
+            .network
+            .rules
+            .iter()
+            .filter(|rule| rule.action == NetworkRuleAction::Allow)
+            .map(|rule| rule.host.clone())
+            .collect();
+        validate(rule_hosts, |candidate| {
+            let invalid: Vec<&String> = candidate
+                .iter()
+                .filter(|entry| {
+                    let candidate_pattern = DomainPattern::parse_for_constraints(entry);
+                    !managed_patterns
+                        .iter()
+                        .any(|managed| managed.allows(&candidate_pattern))
+                })
+                .collect();
+            if invalid.is_empty() {
+                Ok(())
+            } else {
+                Err(invalid_value(
+                    "network.rules",
+                    format!("{invalid:?}"),
+                    "allow rule hosts within managed allowed_domains",
+                ))
+            }
+        })?;
         validate(config.network.allowed_domains.clone(), move |candidate| {
             let mut invalid = Vec::new();
             for entry in candidate {
@@ -0,0 +1,555 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// Events kept for `/audit`; later events only update the counters.
+const MAX_AUDIT_EVENTS: usize = 10_000;
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+/// Processes that recently owned a client socket, checked before scanning every process.
+const MAX_RECENT_OWNERS: usize = 64;
+/// UDP destinations remembered so that each is logged once rather than once per datagram.
+const MAX_UDP_DESTINATIONS: usize = 4096;
+const UNKNOWN_PROCESS: &str = "unknown";
+
+/// One connection decision, as streamed from `/events` and kept for `/audit`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TrafficEvent {
+    pub timestamp: i64,
+    /// `allow`, `deny` or `ask`.
+    pub decision: String,
+    pub host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub protocol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    /// Local process that owns the client socket, when it can be resolved. It is resolved after
+    /// the event is streamed from `/events`, so only `/audit` carries it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+}
+
+/// Per-host or per-process totals. Bytes are counted for HTTP bodies and CONNECT tunnels; SOCKS5
+/// traffic only counts requests and denials.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TrafficCounters {
+    pub requests: u64,
+    pub denials: u64,
+    /// Bytes received from upstream.
+    pub bytes_in: u64,
+    /// Bytes sent upstream.
+    pub bytes_out: u64,
+}
+
+/// Everything the proxy has seen this session.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TrafficAudit {
+    pub hosts: BTreeMap<String, TrafficCounters>,
+    pub processes: BTreeMap<String, TrafficCounters>,
+    pub events: Vec<TrafficEvent>,
+    /// Events that were counted but not kept because the event log was full.
+    pub dropped_events: u64,
+}
+
+/// Identifies the counters an allowed connection's bytes are added to.
+#[derive(Clone, Debug)]
+pub(crate) struct TrafficKey {
+    host: String,
+    process: Arc<Mutex<ProcessSlot>>,
+}
+
+/// The process counters of a connection, which are only known once its client
+/// socket has been resolved in the background.
+#[derive(Debug)]
+enum ProcessSlot {
+    /// Bytes counted while the process was still being resolved.
+    Pending {
+        bytes_in: u64,
+        bytes_out: u64,
+    },
+    Resolved(String),
+}
+
+pub(crate) struct TrafficLog {
+    audit: Mutex<TrafficAudit>,
+    /// Pids that recently owned a client socket, most recent first. Clients tend to open many
+    /// connections, so their fds are searched before falling back to every process in `/proc`.
+    recent_owners: Mutex<VecDeque<u32>>,
+    udp_destinations: Mutex<HashSet<(Option<String>, String, u16)>>,
+    events: broadcast::Sender<TrafficEvent>,
+}
+
+impl TrafficLog {
+    pub(crate) fn new() -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            audit: Mutex::new(TrafficAudit::default()),
+            recent_owners: Mutex::new(VecDeque::new()),
+            udp_destinations: Mutex::new(HashSet::new()),
+            events,
+        }
+    }
+
+    /// Records `event` straight away, so `/events` and `/audit` keep decision order, and fills in
+    /// the process behind its client once it has been resolved in the background. Bytes counted
+    /// under the returned key before then are added to the process once it is known.
+    pub(crate) fn record(self: &Arc<Self>, event: TrafficEvent) -> TrafficKey {
+        if event
+            .client
+            .as_deref()
+            .and_then(|client| client.parse::<SocketAddr>().ok())
+            .is_none()
+        {
+            return self.record_event(event);
+        }
+        let key = TrafficKey {
+            host: event.host.clone(),
+            process: Arc::new(Mutex::new(ProcessSlot::Pending {
+                bytes_in: 0,
+                bytes_out: 0,
+            })),
+        };
+        let client = event.client.clone();
+        let denied = is_denied(&event);
+        let mut resolved = event.clone();
+        let audit_index = self.publish(event);
+        let log = Arc::clone(self);
+        let slot = Arc::clone(&key.process);
+        tokio::spawn(async move {
+            resolved.process = log.process_for_client(client.as_deref()).await;
+            let process = process_key(&resolved);
+            let pending =
+                std::mem::replace(&mut *lock(&slot), ProcessSlot::Resolved(process.clone()));
+            let (bytes_in, bytes_out) = match pending {
+                ProcessSlot::Pending {
+                    bytes_in,
+                    bytes_out,
+                } => (bytes_in, bytes_out),
+                ProcessSlot::Resolved(_) => (0, 0),
+            };
+            let mut audit = log.lock_audit();
+            count_process(&mut audit, process, denied, bytes_in, bytes_out);
+            if let Some(event) = audit_index.and_then(|index| audit.events.get_mut(index)) {
+                event.process = resolved.process;
+            }
+        });
+        key
+    }
+
+    /// Returns whether `client` is sending its first datagram to `host:port`. UDP is relayed
+    /// datagram by datagram, so only the first one to each destination is recorded.
+    pub(crate) fn first_udp_datagram(&self, client: Option<&str>, host: &str, port: u16) -> bool {
+        let mut destinations = lock(&self.udp_destinations);
+        let destination = (client.map(str::to_string), host.to_string(), port);
+        if destinations.contains(&destination) {
+            return false;
+        }
+        if destinations.len() >= MAX_UDP_DESTINATIONS {
+            destinations.clear();
+        }
+        destinations.insert(destination)
+    }
+
+    /// Resolves the process that owns a local client socket.
+    pub(crate) async fn process_for_client(&self, client: Option<&str>) -> Option<String> {
+        let peer: SocketAddr = client?.parse().ok()?;
+        let recent_owners: Vec<u32> = lock(&self.recent_owners).iter().copied().collect();
+        let (pid, process) =
+            tokio::task::spawn_blocking(move || socket_owner(&socket_inode(peer)?, &recent_owners))
+                .await
+                .ok()
+                .flatten()?;
+        let mut recent_owners = lock(&self.recent_owners);
+        recent_owners.retain(|recent| *recent != pid);
+        recent_owners.push_front(pid);
+        recent_owners.truncate(MAX_RECENT_OWNERS);
+        Some(process)
+    }
+
+    pub(crate) fn record_event(&self, event: TrafficEvent) -> TrafficKey {
+        let process = process_key(&event);
+        let key = TrafficKey {
+            host: event.host.clone(),
+            process: Arc::new(Mutex::new(ProcessSlot::Resolved(process.clone()))),
+        };
+        let denied = is_denied(&event);
+        self.publish(event);
+        count_process(&mut self.lock_audit(), process, denied, 0, 0);
+        key
+    }
+
+    /// Counts `event` against its host, keeps it for `/audit` and streams it to `/events`.
+    /// Returns its index in the audit log, if it was kept.
+    fn publish(&self, event: TrafficEvent) -> Option<usize> {
+        let index = {
+            let mut audit = self.lock_audit();
+            let counters = audit.hosts.entry(event.host.clone()).or_default();
+            counters.requests = counters.requests.saturating_add(1);
+            if is_denied(&event) {
+                counters.denials = counters.denials.saturating_add(1);
+            }
+            if audit.events.len() < MAX_AUDIT_EVENTS {
+                audit.events.push(event.clone());
+                Some(audit.events.len() - 1)
+            } else {
+                audit.dropped_events = audit.dropped_events.saturating_add(1);
+                None
+            }
+        };
+        // Nobody may be listening on `/events`; that is not an error.
+        let _ = self.events.send(event);
+        index
+    }
+
+    pub(crate) fn record_bytes(&self, key: &TrafficKey, bytes_in: u64, bytes_out: u64) {
+        if bytes_in == 0 && bytes_out == 0 {
+            return;
+        }
+        let process = match &mut *lock(&key.process) {
+            ProcessSlot::Pending {
+                bytes_in: pending_in,
+                bytes_out: pending_out,
+            } => {
+                *pending_in = pending_in.saturating_add(bytes_in);
+                *pending_out = pending_out.saturating_add(bytes_out);
+                None
+            }
+            ProcessSlot::Resolved(process) => Some(process.clone()),
+        };
+        let mut audit = self.lock_audit();
+        add_bytes(
+            audit.hosts.entry(key.host.clone()).or_default(),
+            bytes_in,
+            bytes_out,
+        );
+        if let Some(process) = process {
+            add_bytes(
+                audit.processes.entry(process).or_default(),
+                bytes_in,
+                bytes_out,
+            );
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<TrafficEvent> {
+        self.events.subscribe()
+    }
+
+    pub(crate) fn snapshot(&self) -> TrafficAudit {
+        self.lock_audit().clone()
+    }
+
+    fn lock_audit(&self) -> std::sync::MutexGuard<'_, TrafficAudit> {
+        lock(&self.audit)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Counts one request, and any bytes seen before it was resolved, against `process`.
+fn count_process(
+    audit: &mut TrafficAudit,
+    process: String,
+    denied: bool,
+    bytes_in: u64,
+    bytes_out: u64,
+) {
+    let counters = audit.processes.entry(process).or_default();
+    counters.requests = counters.requests.saturating_add(1);
+    if denied {
+        counters.denials = counters.denials.saturating_add(1);
+    }
+    add_bytes(counters, bytes_in, bytes_out);
+}
+
+fn is_denied(event: &TrafficEvent) -> bool {
+    event.decision != "allow"
+}
+
+fn add_bytes(counters: &mut TrafficCounters, bytes_in: u64, bytes_out: u64) {
+    counters.bytes_in = counters.bytes_in.saturating_add(bytes_in);
+    counters.bytes_out = counters.bytes_out.saturating_add(bytes_out);
+}
+
+/// Counters are grouped by process when it is known, otherwise by client IP.
+fn process_key(event: &TrafficEvent) -> String {
+    if let Some(process) = event.process.as_ref() {
+        return process.clone();
+    }
+    event
+        .client
+        .as_deref()
+        .map(|client| {
+            client
+                .parse::<SocketAddr>()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_else(|_| client.to_string())
+        })
+        .unwrap_or_else(|| UNKNOWN_PROCESS.to_string())
+}
+
+/// Finds the local process that has the socket with `inode` open, trying `recent_owners` before
+/// scanning every process. Returns its pid and its name formatted as `comm (pid)`.
+#[cfg(target_os = "linux")]
+fn socket_owner(inode: &str, recent_owners: &[u32]) -> Option<(u32, String)> {
+    use std::fs;
+
+    let needle = format!("socket:[{inode}]");
+    let owns_socket = |pid: &u32| {
+        fs::read_dir(format!("/proc/{pid}/fd")).is_ok_and(|fds| {
+            fds.flatten().any(|fd| {
+                fs::read_link(fd.path()).is_ok_and(|target| target.as_os_str() == needle.as_str())
+            })
+        })
+    };
+    let pid = match recent_owners.iter().copied().find(owns_socket) {
+        Some(pid) => pid,
+        None => fs::read_dir("/proc")
+            .ok()?
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| !recent_owners.contains(pid))
+            .find(owns_socket)?,
+    };
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+    Some((pid, format!("{} ({pid})", comm.trim())))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn socket_owner(_inode: &str, _recent_owners: &[u32]) -> Option<(u32, String)> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+fn socket_inode(_addr: SocketAddr) -> Option<String> {
+    None
+}
+
+/// Looks up the inode of the TCP socket whose local address is `addr` in `/proc/net/tcp{,6}`.
+#[cfg(target_os = "linux")]
+fn socket_inode(addr: SocketAddr) -> Option<String> {
+    // The kernel prints each 32-bit word of the address in host byte order.
+    let (table, local_address) = match addr {
+        SocketAddr::V4(addr) => (
+            "/proc/net/tcp",
+            format!(
+                "{:08X}:{:04X}",
+                u32::from_ne_bytes(addr.ip().octets()),
+                addr.port()
+            ),
+        ),
+        SocketAddr::V6(addr) => {
+            let words: String = addr
+                .ip()
+                .octets()
+                .chunks_exact(4)
+                .map(|word| {
+                    format!(
+                        "{:08X}",
+                        u32::from_ne_bytes([word[0], word[1], word[2], word[3]])
+                    )
+                })
+                .collect();
+            ("/proc/net/tcp6", format!("{words}:{:04X}", addr.port()))
+        }
+    };
+    let contents = std::fs::read_to_string(table).ok()?;
+    contents.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        (fields.get(1) == Some(&local_address.as_str()))
+            .then(|| fields.get(9).map(ToString::to_string))
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn event(host: &str, decision: &str, process: Option<&str>) -> TrafficEvent {
+        TrafficEvent {
+            timestamp: 0,
+            decision: decision.to_string(),
+            host: host.to_string(),
+            port: Some(443),
+            protocol: "https".to_string(),
+            method: Some("GET".to_string()),
+            client: Some("127.0.0.1:50000".to_string()),
+            process: process.map(str::to_string),
+            reason: None,
+            source: None,
+            rule: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn record_event_updates_host_and_process_counters() {
+        let log = TrafficLog::new();
+        let mut events = log.subscribe();
+
+        let key = log.record_event(event("registry.npmjs.org", "allow", Some("npm (42)")));
+        log.record_bytes(&key, 1200, 80);
+        log.record_event(event("evil.com", "deny", None));
+
+        let audit = log.snapshot();
+        assert_eq!(
+            audit.hosts,
+            BTreeMap::from([
+                (
+                    "evil.com".to_string(),
+                    TrafficCounters {
+                        requests: 1,
+                        denials: 1,
+                        bytes_in: 0,
+                        bytes_out: 0,
+                    }
+                ),
+                (
+                    "registry.npmjs.org".to_string(),
+                    TrafficCounters {
+                        requests: 1,
+                        denials: 0,
+                        bytes_in: 1200,
+                        bytes_out: 80,
+                    }
+                ),
+            ])
+        );
+        assert_eq!(
+            audit.processes.keys().collect::<Vec<_>>(),
+            vec!["127.0.0.1", "npm (42)"]
+        );
+        assert_eq!(audit.events.len(), 2);
+        assert_eq!(events.recv().await.unwrap().host, "registry.npmjs.org");
+        assert_eq!(events.recv().await.unwrap().host, "evil.com");
+    }
+
+    async fn wait_for_process(log: &TrafficLog, process: &str) -> TrafficAudit {
+        for _ in 0..500 {
+            let audit = log.snapshot();
+            if audit.processes.contains_key(process) {
+                return audit;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("process {process} was never resolved");
+    }
+
+    #[tokio::test]
+    async fn record_counts_bytes_seen_before_the_process_is_resolved() {
+        let log = Arc::new(TrafficLog::new());
+
+        let key = log.record(event("example.com", "allow", None));
+        log.record_bytes(&key, 10, 5);
+        wait_for_process(&log, "127.0.0.1").await;
+        log.record_bytes(&key, 1, 0);
+
+        let audit = log.snapshot();
+        let expected = TrafficCounters {
+            requests: 1,
+            denials: 0,
+            bytes_in: 11,
+            bytes_out: 5,
+        };
+        assert_eq!(audit.hosts["example.com"], expected);
+        assert_eq!(audit.processes["127.0.0.1"], expected);
+    }
+
+    #[tokio::test]
+    async fn record_publishes_events_in_decision_order() {
+        let log = Arc::new(TrafficLog::new());
+        let mut events = log.subscribe();
+
+        for host in ["one.example", "two.example", "three.example"] {
+            log.record(event(host, "allow", None));
+        }
+
+        let hosts = [
+            events.try_recv().unwrap().host,
+            events.try_recv().unwrap().host,
+            events.try_recv().unwrap().host,
+        ];
+        assert_eq!(hosts, ["one.example", "two.example", "three.example"]);
+        let audit = log.snapshot();
+        assert_eq!(
+            audit
+                .events
+                .iter()
+                .map(|event| event.host.as_str())
+                .collect::<Vec<_>>(),
+            vec!["one.example", "two.example", "three.example"]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn record_fills_in_the_process_after_publishing() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut local = event("example.com", "allow", None);
+        local.client = Some(stream.local_addr().unwrap().to_string());
+        let log = Arc::new(TrafficLog::new());
+        let mut events = log.subscribe();
+
+        log.record(local);
+
+        assert_eq!(events.try_recv().unwrap().process, None);
+        let process = format!("{} ({})", current_comm(), std::process::id());
+        let audit = wait_for_process(&log, &process).await;
+        assert_eq!(audit.events[0].process.as_deref(), Some(process.as_str()));
+    }
+
+    #[test]
+    fn first_udp_datagram_is_reported_once_per_destination() {
+        let log = TrafficLog::new();
+        let client = Some("127.0.0.1:5353");
+
+        assert!(log.first_udp_datagram(client, "1.1.1.1", 53));
+        assert!(!log.first_udp_datagram(client, "1.1.1.1", 53));
+        assert!(log.first_udp_datagram(client, "8.8.8.8", 53));
+        assert!(log.first_udp_datagram(Some("127.0.0.1:5354"), "1.1.1.1", 53));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn process_for_client_resolves_the_local_socket_owner() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let client = stream.local_addr().unwrap().to_string();
+
+        let log = TrafficLog::new();
+        let process = log.process_for_client(Some(&client)).await.unwrap();
+        assert_eq!(
+            process,
+            format!("{} ({})", current_comm(), std::process::id())
+        );
+        assert_eq!(lock(&log.recent_owners).front(), Some(&std::process::id()));
+    }
+
+    #[cfg(target_os = "linux")]
+    fn current_comm() -> String {
+        std::fs::read_to_string("/proc/self/comm")
+            .unwrap()
+            .trim()
+            .to_string()
+    }
+}
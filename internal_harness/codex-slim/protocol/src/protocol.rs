@@ -2194,6 +2194,21 @@ pub enum ReviewTarget {
         title: Option<String>,
     },
 
+    /// Review the changes `head_sha` would merge into `base_sha`, as a pull request
+    /// would: everything on the head side since the two diverged.
+    #[serde(rename_all = "camelCase")]
+    #[ts(rename_all = "camelCase")]
+    CommitRange {
+        base_sha: String,
+        head_sha: String,
+        /// Only review paths matching at least one of these globs. Empty means every path.
+        #[serde(default)]
+        include_paths: Vec<String>,
+        /// Leave out paths matching any of these globs, e.g. vendored or generated code.
+        #[serde(default)]
+        exclude_paths: Vec<String>,
+    },
+
     /// Arbitrary instructions provided by the user.
     #[serde(rename_all = "camelCase")]
     #[ts(rename_all = "camelCase")]
@@ -2207,6 +2222,23 @@ pub struct ReviewRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[ts(optional)]
     pub user_facing_hint: Option<String>,
+    /// Outcome of an earlier review of the same change. When present, only the
+    /// hunks changed since `reviewed_sha` are reviewed and untouched findings are
+    /// carried forward.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub previous_review: Option<PreviousReview>,
+}
+
+/// State persisted between incremental reviews of the same change.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema, TS)]
+pub struct PreviousReview {
+    /// Revision the previous review looked at. Reviews of the working tree
+    /// record the commit their diff was taken against instead.
+    pub reviewed_sha: String,
+    /// Findings reported by the previous review, located against `reviewed_sha`.
+    #[serde(default)]
+    pub findings: Vec<ReviewFinding>,
 }
 
 /// Structured review result produced by a child review session.
@@ -2237,6 +2269,11 @@ pub struct ReviewFinding {
     pub confidence_score: f32,
     pub priority: i32,
     pub code_location: ReviewCodeLocation,
+    /// Stable identity of the finding across review runs, derived from its file,
+    /// its title and the code it points at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub fingerprint: Option<String>,
 }
 
 /// Location of the code related to a review finding.
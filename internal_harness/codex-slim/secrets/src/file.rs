@@ -0,0 +1,383 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::io::Write;
+use std::iter;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use age::Decryptor;
+use age::Encryptor;
+use age::secrecy::ExposeSecret;
+use age::x25519::Identity;
+use anyhow::Context;
+use anyhow::Result;
+
+use super::SecretListEntry;
+use super::SecretName;
+use super::SecretScope;
+use super::SecretsBackend;
+use super::local::SecretsFile;
+use super::local::write_file_atomically;
+
+/// Environment variable holding the age identity (`AGE-SECRET-KEY-1...`) directly.
+pub const SECRETS_KEY_ENV_VAR: &str = "CODEX_SECRETS_KEY";
+/// Environment variable pointing at a file that holds the age identity.
+pub const SECRETS_KEY_FILE_ENV_VAR: &str = "CODEX_SECRETS_KEY_FILE";
+
+const FILE_SECRETS_FILENAME: &str = "file.age";
+const DEFAULT_KEY_FILENAME: &str = "file.key";
+
+/// Where [`FileSecretsBackend`] reads its age X25519 identity from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretsKeySource {
+    /// The named environment variable holds the identity.
+    Env(String),
+    /// The file holds the identity. It is generated on first write when missing.
+    File(PathBuf),
+}
+
+impl SecretsKeySource {
+    /// Prefers [`SECRETS_KEY_ENV_VAR`], then [`SECRETS_KEY_FILE_ENV_VAR`], then a key
+    /// file under `codex_home/secrets`.
+    pub fn from_env(codex_home: &Path) -> Self {
+        if env::var_os(SECRETS_KEY_ENV_VAR).is_some_and(|value| !value.is_empty()) {
+            return Self::Env(SECRETS_KEY_ENV_VAR.to_string());
+        }
+        match env::var_os(SECRETS_KEY_FILE_ENV_VAR) {
+            Some(path) if !path.is_empty() => Self::File(PathBuf::from(path)),
+            _ => Self::File(codex_home.join("secrets").join(DEFAULT_KEY_FILENAME)),
+        }
+    }
+}
+
+/// Secrets stored in an age-encrypted file, keyed by an identity from the environment
+/// or a key file rather than the OS keyring. Meant for headless hosts such as CI
+/// containers that have no keyring service.
+#[derive(Debug, Clone)]
+pub struct FileSecretsBackend {
+    codex_home: PathBuf,
+    key_source: SecretsKeySource,
+}
+
+impl FileSecretsBackend {
+    pub fn new(codex_home: PathBuf, key_source: SecretsKeySource) -> Self {
+        Self {
+            codex_home,
+            key_source,
+        }
+    }
+
+    /// Generates a fresh identity suitable for [`SECRETS_KEY_ENV_VAR`] or a key file.
+    pub fn generate_key() -> String {
+        Identity::generate().to_string().expose_secret().to_string()
+    }
+
+    pub fn set(&self, scope: &SecretScope, name: &SecretName, value: &str) -> Result<()> {
+        anyhow::ensure!(!value.is_empty(), "secret value must not be empty");
+        let canonical_key = scope.canonical_key(name);
+        let mut file = self.load_file()?;
+        file.secrets.insert(canonical_key, value.to_string());
+        self.save_file(&file)
+    }
+
+    pub fn get(&self, scope: &SecretScope, name: &SecretName) -> Result<Option<String>> {
+        let canonical_key = scope.canonical_key(name);
+        let file = self.load_file()?;
+        Ok(file.secrets.get(&canonical_key).cloned())
+    }
+
+    pub fn delete(&self, scope: &SecretScope, name: &SecretName) -> Result<bool> {
+        let canonical_key = scope.canonical_key(name);
+        let mut file = self.load_file()?;
+        let removed = file.secrets.remove(&canonical_key).is_some();
+        if removed {
+            self.save_file(&file)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn list(&self, scope_filter: Option<&SecretScope>) -> Result<Vec<SecretListEntry>> {
+        let file = self.load_file()?;
+        Ok(file.list(scope_filter))
+    }
+
+    /// Re-encrypts every secret to `new_key`.
+    ///
+    /// With a key file the file is replaced as well. The secrets are first written for
+    /// both keys, so an interruption leaves them readable with whichever key the key
+    /// file holds. With an environment key the caller must update the variable
+    /// afterwards; the old key no longer decrypts the secrets.
+    pub fn rotate_key(&self, new_key: &str) -> Result<()> {
+        let new_identity = parse_identity(new_key).context("invalid new secrets key")?;
+        let file = self.load_file()?;
+        let plaintext = serde_json::to_vec(&file).context("failed to serialize secrets file")?;
+        let dir = self.secrets_dir();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create secrets dir {}", dir.display()))?;
+        let secrets_path = self.secrets_path();
+
+        if let Some(old_identity) = self.load_identity()? {
+            let ciphertext = encrypt_to_identities(&plaintext, &[&old_identity, &new_identity])?;
+            write_file_atomically(&secrets_path, &ciphertext)?;
+        }
+        if let SecretsKeySource::File(key_path) = &self.key_source {
+            write_key_file(key_path, &new_identity)?;
+        }
+
+        let ciphertext = encrypt_to_identities(&plaintext, &[&new_identity])?;
+        write_file_atomically(&secrets_path, &ciphertext)
+    }
+
+    fn secrets_dir(&self) -> PathBuf {
+        self.codex_home.join("secrets")
+    }
+
+    fn secrets_path(&self) -> PathBuf {
+        self.secrets_dir().join(FILE_SECRETS_FILENAME)
+    }
+
+    fn load_file(&self) -> Result<SecretsFile> {
+        let path = self.secrets_path();
+        if !path.exists() {
+            return Ok(SecretsFile::new_empty());
+        }
+
+        let ciphertext = fs::read(&path)
+            .with_context(|| format!("failed to read secrets file at {}", path.display()))?;
+        let identity = self.load_identity()?.with_context(|| {
+            format!(
+                "secrets file at {} exists but no secrets key is configured",
+                path.display()
+            )
+        })?;
+        let plaintext = decrypt_with_identity(&ciphertext, &identity)?;
+        SecretsFile::from_plaintext(&plaintext, &path)
+    }
+
+    fn save_file(&self, file: &SecretsFile) -> Result<()> {
+        let dir = self.secrets_dir();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create secrets dir {}", dir.display()))?;
+
+        let identity = self.load_or_create_identity()?;
+        let plaintext = serde_json::to_vec(file).context("failed to serialize secrets file")?;
+        let ciphertext = encrypt_to_identities(&plaintext, &[&identity])?;
+        write_file_atomically(&self.secrets_path(), &ciphertext)
+    }
+
+    /// Returns `None` when the key file does not exist yet.
+    fn load_identity(&self) -> Result<Option<Identity>> {
+        match &self.key_source {
+            SecretsKeySource::Env(var) => {
+                let value = env::var(var).with_context(|| {
+                    format!("secrets key environment variable {var} is not set")
+                })?;
+                parse_identity(&value)
+                    .with_context(|| format!("invalid secrets key in {var}"))
+                    .map(Some)
+            }
+            SecretsKeySource::File(path) => {
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let contents = fs::read_to_string(path).with_context(|| {
+                    format!("failed to read secrets key file at {}", path.display())
+                })?;
+                parse_identity(&contents)
+                    .with_context(|| format!("invalid secrets key file at {}", path.display()))
+                    .map(Some)
+            }
+        }
+    }
+
+    fn load_or_create_identity(&self) -> Result<Identity> {
+        if let Some(identity) = self.load_identity()? {
+            return Ok(identity);
+        }
+        let SecretsKeySource::File(path) = &self.key_source else {
+            anyhow::bail!("secrets key is not configured");
+        };
+        let identity = Identity::generate();
+        write_key_file(path, &identity)?;
+        Ok(identity)
+    }
+}
+
+impl SecretsBackend for FileSecretsBackend {
+    fn set(&self, scope: &SecretScope, name: &SecretName, value: &str) -> Result<()> {
+        FileSecretsBackend::set(self, scope, name, value)
+    }
+
+    fn get(&self, scope: &SecretScope, name: &SecretName) -> Result<Option<String>> {
+        FileSecretsBackend::get(self, scope, name)
+    }
+
+    fn delete(&self, scope: &SecretScope, name: &SecretName) -> Result<bool> {
+        FileSecretsBackend::delete(self, scope, name)
+    }
+
+    fn list(&self, scope_filter: Option<&SecretScope>) -> Result<Vec<SecretListEntry>> {
+        FileSecretsBackend::list(self, scope_filter)
+    }
+}
+
+/// Accepts a bare identity or `age-keygen` output, whose comment lines are skipped.
+fn parse_identity(contents: &str) -> Result<Identity> {
+    let key = contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .context("no age identity found")?;
+    Identity::from_str(key).map_err(|err| anyhow::anyhow!("{err}"))
+}
+
+fn write_key_file(path: &Path, identity: &Identity) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create secrets key dir {}", dir.display()))?;
+    }
+    let contents = format!("{}\n", identity.to_string().expose_secret());
+    write_file_atomically(path, contents.as_bytes())
+}
+
+fn encrypt_to_identities(plaintext: &[u8], identities: &[&Identity]) -> Result<Vec<u8>> {
+    let recipients: Vec<_> = identities
+        .iter()
+        .map(|identity| identity.to_public())
+        .collect();
+    let encryptor = Encryptor::with_recipients(recipients.iter().map(|recipient| recipient as _))
+        .context("failed to encrypt secrets file")?;
+    let mut ciphertext = Vec::with_capacity(plaintext.len());
+    let mut writer = encryptor
+        .wrap_output(&mut ciphertext)
+        .context("failed to encrypt secrets file")?;
+    writer
+        .write_all(plaintext)
+        .context("failed to encrypt secrets file")?;
+    writer.finish().context("failed to encrypt secrets file")?;
+    Ok(ciphertext)
+}
+
+fn decrypt_with_identity(ciphertext: &[u8], identity: &Identity) -> Result<Vec<u8>> {
+    let decryptor =
+        Decryptor::new_buffered(ciphertext).context("failed to decrypt secrets file")?;
+    let mut reader = decryptor
+        .decrypt(iter::once(identity as _))
+        .context("failed to decrypt secrets file")?;
+    let mut plaintext = Vec::new();
+    reader
+        .read_to_end(&mut plaintext)
+        .context("failed to decrypt secrets file")?;
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn key_file_backend(codex_home: &Path) -> FileSecretsBackend {
+        FileSecretsBackend::new(
+            codex_home.to_path_buf(),
+            SecretsKeySource::File(codex_home.join("keys").join("codex.key")),
+        )
+    }
+
+    #[test]
+    fn round_trips_scoped_secrets_with_generated_key_file() -> Result<()> {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let backend = key_file_backend(codex_home.path());
+        let global = SecretScope::Global;
+        let env = SecretScope::environment("repo")?;
+        let name = SecretName::new("GITHUB_TOKEN")?;
+
+        backend.set(&global, &name, "global-token")?;
+        backend.set(&env, &name, "env-token")?;
+
+        assert_eq!(backend.get(&env, &name)?, Some("env-token".to_string()));
+        assert_eq!(
+            backend.list(Some(&env))?,
+            vec![SecretListEntry {
+                scope: env.clone(),
+                name: name.clone(),
+            }]
+        );
+        let ciphertext = fs::read(backend.secrets_path())?;
+        assert!(
+            !String::from_utf8_lossy(&ciphertext).contains("env-token"),
+            "secrets file must be encrypted"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let key_path = codex_home.path().join("keys").join("codex.key");
+            let mode = fs::metadata(key_path)?.permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(backend.delete(&env, &name)?);
+        assert_eq!(backend.get(&env, &name)?, None);
+        assert_eq!(
+            backend.get(&global, &name)?,
+            Some("global-token".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rotate_key_re_encrypts_secrets_for_the_new_key_only() -> Result<()> {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let backend = key_file_backend(codex_home.path());
+        let name = SecretName::new("NPM_TOKEN")?;
+        backend.set(&SecretScope::Global, &name, "npm-1")?;
+        let old_identity = backend.load_identity()?.expect("key file was generated");
+
+        let new_key = FileSecretsBackend::generate_key();
+        backend.rotate_key(&new_key)?;
+
+        assert_eq!(
+            backend.get(&SecretScope::Global, &name)?,
+            Some("npm-1".to_string())
+        );
+        let key_file = fs::read_to_string(codex_home.path().join("keys").join("codex.key"))?;
+        assert_eq!(key_file.trim(), new_key);
+        let ciphertext = fs::read(backend.secrets_path())?;
+        assert!(decrypt_with_identity(&ciphertext, &old_identity).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn load_fails_with_the_wrong_key() -> Result<()> {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let backend = key_file_backend(codex_home.path());
+        let name = SecretName::new("TEST_SECRET")?;
+        backend.set(&SecretScope::Global, &name, "value")?;
+        let other_identity = parse_identity(&FileSecretsBackend::generate_key())?;
+        write_key_file(
+            &codex_home.path().join("keys").join("codex.key"),
+            &other_identity,
+        )?;
+
+        let error = backend
+            .get(&SecretScope::Global, &name)
+            .expect_err("must fail to decrypt with another key");
+        assert!(
+            error.to_string().contains("failed to decrypt secrets file"),
+            "unexpected error: {error:#}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_identity_skips_age_keygen_comments() -> Result<()> {
+        let key = FileSecretsBackend::generate_key();
+        let identity = parse_identity(&format!(
+            "# created: 2025-01-01T00:00:00Z\n# public key: age1example\n{key}\n"
+        ))?;
+        assert_eq!(identity.to_string().expose_secret(), key.as_str());
+        assert!(parse_identity("# only a comment\n").is_err());
+        Ok(())
+    }
+}
@@ -12,10 +12,23 @@ use serde::Serialize;
 use sha2::Digest;
 use sha2::Sha256;
 
+mod file;
 mod local;
 mod sanitizer;
 
+pub use file::FileSecretsBackend;
+pub use file::SECRETS_KEY_ENV_VAR;
+pub use file::SECRETS_KEY_FILE_ENV_VAR;
+pub use file::SecretsKeySource;
 pub use local::LocalSecretsBackend;
+pub use sanitizer::EntropyDetectorConfig;
+pub use sanitizer::HIGH_ENTROPY_RULE_ID;
+pub use sanitizer::REDACTED_SECRET;
+pub use sanitizer::Redaction;
+pub use sanitizer::RedactionConfig;
+pub use sanitizer::RedactionHit;
+pub use sanitizer::RedactionRuleConfig;
+pub use sanitizer::SecretRedactor;
 pub use sanitizer::redact_secrets;
 
 const KEYRING_SERVICE: &str = "codex";
@@ -83,6 +96,8 @@ pub struct SecretListEntry {
 pub enum SecretsBackendKind {
     #[default]
     Local,
+    /// Age-encrypted file keyed from the environment, for hosts without a keyring.
+    File,
 }
 
 pub trait SecretsBackend: Send + Sync {
@@ -104,6 +119,7 @@ impl SecretsManager {
                 let keyring_store: Arc<dyn KeyringStore> = Arc::new(DefaultKeyringStore);
                 Arc::new(LocalSecretsBackend::new(codex_home, keyring_store))
             }
+            SecretsBackendKind::File => file_backend(codex_home),
         };
         Self { backend }
     }
@@ -117,6 +133,7 @@ impl SecretsManager {
             SecretsBackendKind::Local => {
                 Arc::new(LocalSecretsBackend::new(codex_home, keyring_store))
             }
+            SecretsBackendKind::File => file_backend(codex_home),
         };
         Self { backend }
     }
@@ -138,6 +155,11 @@ impl SecretsManager {
     }
 }
 
+fn file_backend(codex_home: PathBuf) -> Arc<dyn SecretsBackend> {
+    let key_source = SecretsKeySource::from_env(&codex_home);
+    Arc::new(FileSecretsBackend::new(codex_home, key_source))
+}
+
 pub fn environment_id_from_cwd(cwd: &Path) -> String {
     if let Some(repo_root) = get_git_repo_root(cwd)
         && let Some(name) = repo_root.file_name()
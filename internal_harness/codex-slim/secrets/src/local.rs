@@ -33,22 +33,59 @@ use super::SecretsBackend;
 use super::compute_keyring_account;
 use super::keyring_service;
 
-const SECRETS_VERSION: u8 = 1;
+pub(super) const SECRETS_VERSION: u8 = 1;
 const LOCAL_SECRETS_FILENAME: &str = "local.age";
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
-struct SecretsFile {
-    version: u8,
-    secrets: BTreeMap<String, String>,
+pub(super) struct SecretsFile {
+    pub(super) version: u8,
+    pub(super) secrets: BTreeMap<String, String>,
 }
 
 impl SecretsFile {
-    fn new_empty() -> Self {
+    pub(super) fn new_empty() -> Self {
         Self {
             version: SECRETS_VERSION,
             secrets: BTreeMap::new(),
         }
     }
+
+    /// Parses decrypted file contents, rejecting schema versions newer than this build.
+    pub(super) fn from_plaintext(plaintext: &[u8], path: &Path) -> Result<Self> {
+        let mut parsed: SecretsFile = serde_json::from_slice(plaintext).with_context(|| {
+            format!(
+                "failed to deserialize decrypted secrets file at {}",
+                path.display()
+            )
+        })?;
+        if parsed.version == 0 {
+            parsed.version = SECRETS_VERSION;
+        }
+        anyhow::ensure!(
+            parsed.version <= SECRETS_VERSION,
+            "secrets file version {} is newer than supported version {}",
+            parsed.version,
+            SECRETS_VERSION
+        );
+        Ok(parsed)
+    }
+
+    pub(super) fn list(&self, scope_filter: Option<&SecretScope>) -> Vec<SecretListEntry> {
+        let mut entries = Vec::new();
+        for canonical_key in self.secrets.keys() {
+            let Some(entry) = parse_canonical_key(canonical_key) else {
+                warn!("skipping invalid canonical secret key: {canonical_key}");
+                continue;
+            };
+            if let Some(scope) = scope_filter
+                && entry.scope != *scope
+            {
+                continue;
+            }
+            entries.push(entry);
+        }
+        entries
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -91,20 +128,7 @@ impl LocalSecretsBackend {
 
     pub fn list(&self, scope_filter: Option<&SecretScope>) -> Result<Vec<SecretListEntry>> {
         let file = self.load_file()?;
-        let mut entries = Vec::new();
-        for canonical_key in file.secrets.keys() {
-            let Some(entry) = parse_canonical_key(canonical_key) else {
-                warn!("skipping invalid canonical secret key: {canonical_key}");
-                continue;
-            };
-            if let Some(scope) = scope_filter
-                && entry.scope != *scope
-            {
-                continue;
-            }
-            entries.push(entry);
-        }
-        Ok(entries)
+        Ok(file.list(scope_filter))
     }
 
     fn secrets_dir(&self) -> PathBuf {
@@ -125,22 +149,7 @@ impl LocalSecretsBackend {
             .with_context(|| format!("failed to read secrets file at {}", path.display()))?;
         let passphrase = self.load_or_create_passphrase()?;
         let plaintext = decrypt_with_passphrase(&ciphertext, &passphrase)?;
-        let mut parsed: SecretsFile = serde_json::from_slice(&plaintext).with_context(|| {
-            format!(
-                "failed to deserialize decrypted secrets file at {}",
-                path.display()
-            )
-        })?;
-        if parsed.version == 0 {
-            parsed.version = SECRETS_VERSION;
-        }
-        anyhow::ensure!(
-            parsed.version <= SECRETS_VERSION,
-            "secrets file version {} is newer than supported version {}",
-            parsed.version,
-            SECRETS_VERSION
-        );
-        Ok(parsed)
+        SecretsFile::from_plaintext(&plaintext, &path)
     }
 
     fn save_file(&self, file: &SecretsFile) -> Result<()> {
@@ -198,32 +207,38 @@ impl SecretsBackend for LocalSecretsBackend {
     }
 }
 
-fn write_file_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+/// Replaces `path` with `contents` via a synced temp file and a rename. The file is
+/// created owner-only on Unix.
+pub(super) fn write_file_atomically(path: &Path, contents: &[u8]) -> Result<()> {
     let dir = path.parent().with_context(|| {
         format!(
             "failed to compute parent directory for secrets file at {}",
             path.display()
         )
     })?;
+    let file_name = path.file_name().map_or_else(
+        || LOCAL_SECRETS_FILENAME.into(),
+        |name| name.to_string_lossy(),
+    );
     let nonce = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map_or(0, |duration| duration.as_nanos());
-    let tmp_path = dir.join(format!(
-        ".{LOCAL_SECRETS_FILENAME}.tmp-{}-{nonce}",
-        std::process::id()
-    ));
+    let tmp_path = dir.join(format!(".{file_name}.tmp-{}-{nonce}", std::process::id()));
 
     {
-        let mut tmp_file = fs::OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(&tmp_path)
-            .with_context(|| {
-                format!(
-                    "failed to create temp secrets file at {}",
-                    tmp_path.display()
-                )
-            })?;
+        let mut options = fs::OpenOptions::new();
+        options.create_new(true).write(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut tmp_file = options.open(&tmp_path).with_context(|| {
+            format!(
+                "failed to create temp secrets file at {}",
+                tmp_path.display()
+            )
+        })?;
         tmp_file.write_all(contents).with_context(|| {
             format!(
                 "failed to write temp secrets file at {}",
@@ -0,0 +1,82 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use crate::GitToolingError;
+use crate::operations::ensure_git_repository;
+use crate::operations::run_git_for_stdout_all;
+
+/// Returns the contents of a blob in the repository containing `repo_path`.
+///
+/// `object` is anything `git cat-file blob` accepts, e.g. a blob OID or a
+/// `<rev>:<path>` spec such as `HEAD~1:src/lib.rs`; it is never parsed as an
+/// option, even if it starts with `-`. The contents are returned untrimmed.
+pub fn read_blob(repo_path: &Path, object: &str) -> Result<String, GitToolingError> {
+    ensure_git_repository(repo_path)?;
+    run_git_for_stdout_all(
+        repo_path,
+        vec![
+            OsString::from("cat-file"),
+            OsString::from("blob"),
+            OsString::from("--end-of-options"),
+            OsString::from(object),
+        ],
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_blob;
+    use crate::GitToolingError;
+    use pretty_assertions::assert_eq;
+    use std::path::Path;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn run_git_stdout(repo_path: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .output()
+            .expect("git command");
+        assert!(output.status.success(), "git command failed: {args:?}");
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn read_blob_returns_untrimmed_contents() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        run_git_stdout(repo, &["init", "--initial-branch=main"]);
+        std::fs::write(repo.join("base.txt"), "one\ntwo\n\n")?;
+        let oid = run_git_stdout(repo, &["hash-object", "-w", "base.txt"]);
+
+        assert_eq!(read_blob(repo, &oid)?, "one\ntwo\n\n");
+        Ok(())
+    }
+
+    #[test]
+    fn read_blob_treats_object_as_a_name() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        run_git_stdout(repo, &["init", "--initial-branch=main"]);
+
+        let err = read_blob(repo, "--batch").expect_err("not an object");
+
+        assert!(
+            matches!(&err, GitToolingError::GitCommand { stderr, .. } if stderr.contains("Not a valid object name --batch")),
+            "{err:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn read_blob_requires_repository() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+
+        let err = read_blob(temp.path(), "HEAD:missing.txt").expect_err("not a repository");
+
+        assert!(matches!(err, GitToolingError::NotAGitRepository { .. }));
+        Ok(())
+    }
+}
@@ -47,6 +47,38 @@ pub fn merge_base_with_head(
     Ok(Some(merge_base))
 }
 
+/// Returns the merge-base commit between two revisions, e.g. the base and head of a
+/// pull request.
+///
+/// Unlike [`merge_base_with_head`], both sides are taken as given: no upstream is
+/// consulted. Returns `Ok(None)` when either revision cannot be resolved.
+pub fn merge_base(
+    repo_path: &Path,
+    base: &str,
+    head: &str,
+) -> Result<Option<String>, GitToolingError> {
+    ensure_git_repository(repo_path)?;
+    let repo_root = resolve_repository_root(repo_path)?;
+    let Some(base_ref) = resolve_branch_ref(repo_root.as_path(), base)? else {
+        return Ok(None);
+    };
+    let Some(head_ref) = resolve_branch_ref(repo_root.as_path(), head)? else {
+        return Ok(None);
+    };
+
+    let merge_base = run_git_for_stdout(
+        repo_root.as_path(),
+        vec![
+            OsString::from("merge-base"),
+            OsString::from(base_ref),
+            OsString::from(head_ref),
+        ],
+        None,
+    )?;
+
+    Ok(Some(merge_base))
+}
+
 fn resolve_branch_ref(repo_root: &Path, branch: &str) -> Result<Option<String>, GitToolingError> {
     let rev = run_git_for_stdout(
         repo_root,
@@ -118,6 +150,7 @@ fn resolve_upstream_if_remote_ahead(
 
 #[cfg(test)]
 mod tests {
+    use super::merge_base;
     use super::merge_base_with_head;
     use crate::GitToolingError;
     use pretty_assertions::assert_eq;
@@ -238,6 +271,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn merge_base_between_revisions_ignores_checked_out_head() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        init_test_repo(repo);
+
+        std::fs::write(repo.join("base.txt"), "base\n")?;
+        run_git_in(repo, &["add", "base.txt"]);
+        commit(repo, "base commit");
+        let fork_point = run_git_stdout(repo, &["rev-parse", "HEAD"]);
+
+        run_git_in(repo, &["checkout", "-b", "feature"]);
+        std::fs::write(repo.join("feature.txt"), "feature change\n")?;
+        run_git_in(repo, &["add", "feature.txt"]);
+        commit(repo, "feature commit");
+        let head_sha = run_git_stdout(repo, &["rev-parse", "HEAD"]);
+
+        run_git_in(repo, &["checkout", "main"]);
+        std::fs::write(repo.join("main.txt"), "main change\n")?;
+        run_git_in(repo, &["add", "main.txt"]);
+        commit(repo, "main commit");
+        let base_sha = run_git_stdout(repo, &["rev-parse", "HEAD"]);
+
+        assert_eq!(merge_base(repo, &base_sha, &head_sha)?, Some(fork_point));
+        assert_eq!(merge_base(repo, &base_sha, "missing-branch")?, None);
+
+        Ok(())
+    }
+
     #[test]
     fn merge_base_returns_none_when_branch_missing() -> Result<(), GitToolingError> {
         let temp = tempdir()?;
@@ -0,0 +1,327 @@
+use std::ffi::OsString;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::GitToolingError;
+use crate::operations::ensure_git_repository;
+use crate::operations::resolve_repository_root;
+use crate::operations::run_git_for_stdout_all;
+
+/// A contiguous block of changed lines between two revisions, as reported by
+/// `git diff --unified=0`.
+///
+/// Line numbers are 1-based. When `old_lines` is zero the hunk is a pure
+/// insertion after line `old_start`; when `new_lines` is zero it is a pure
+/// deletion after line `new_start`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// Path of the file relative to the repository root, as named on the new
+    /// side of the diff (or the old side when the file was deleted).
+    pub path: PathBuf,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+/// Restricts the paths [`diff_hunks`] reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffFilter {
+    /// Globs (git `:(glob)` pathspecs) a path must match; empty matches every path.
+    pub include_paths: Vec<String>,
+    /// Globs for paths to leave out even when they match `include_paths`.
+    pub exclude_paths: Vec<String>,
+    /// Reports untracked files as additions when diffing against the working
+    /// tree, so the hunks cover what a review of uncommitted changes sees.
+    pub include_untracked: bool,
+}
+
+impl DiffFilter {
+    fn pathspecs(&self) -> Vec<OsString> {
+        self.include_paths
+            .iter()
+            .map(|glob| OsString::from(format!(":(glob){glob}")))
+            .chain(
+                self.exclude_paths
+                    .iter()
+                    .map(|glob| OsString::from(format!(":(glob,exclude){glob}"))),
+            )
+            .collect()
+    }
+}
+
+/// Returns the changed hunks between `from` and `to` without context lines.
+///
+/// When `to` is `None` the diff is taken against the working tree, mirroring
+/// `git diff <from>`. Renames are reported as a deletion plus an addition so
+/// every hunk maps to exactly one path. Only paths passing `filter` are
+/// reported.
+pub fn diff_hunks(
+    repo_path: &Path,
+    from: &str,
+    to: Option<&str>,
+    filter: &DiffFilter,
+) -> Result<Vec<DiffHunk>, GitToolingError> {
+    ensure_git_repository(repo_path)?;
+    let repo_root = resolve_repository_root(repo_path)?;
+    let mut args = vec![
+        OsString::from("diff"),
+        OsString::from("--unified=0"),
+        OsString::from("--no-color"),
+        OsString::from("--no-ext-diff"),
+        OsString::from("--no-renames"),
+        OsString::from(from),
+    ];
+    if let Some(to) = to {
+        args.push(OsString::from(to));
+    }
+    args.push(OsString::from("--"));
+    args.extend(filter.pathspecs());
+    let diff = run_git_for_stdout_all(repo_root.as_path(), args, None)?;
+    let mut hunks = parse_unified_diff_hunks(&diff);
+    if to.is_none() && filter.include_untracked {
+        hunks.extend(untracked_file_hunks(repo_root.as_path(), filter)?);
+    }
+    Ok(hunks)
+}
+
+/// One whole-file addition per untracked, non-ignored text file, matching what
+/// `git diff` would report once the file was added.
+fn untracked_file_hunks(
+    repo_root: &Path,
+    filter: &DiffFilter,
+) -> Result<Vec<DiffHunk>, GitToolingError> {
+    let mut args = vec![
+        OsString::from("ls-files"),
+        OsString::from("--others"),
+        OsString::from("--exclude-standard"),
+        OsString::from("-z"),
+        OsString::from("--"),
+    ];
+    args.extend(filter.pathspecs());
+    let listing = run_git_for_stdout_all(repo_root, args, None)?;
+    let mut hunks = Vec::new();
+    for path in listing.split('\0').filter(|path| !path.is_empty()) {
+        let Ok(contents) = std::fs::read(repo_root.join(path)) else {
+            continue;
+        };
+        // Git reports no hunks for binary files.
+        if contents.contains(&0) {
+            continue;
+        }
+        let lines = contents.split_inclusive(|byte| *byte == b'\n').count();
+        if lines == 0 {
+            continue;
+        }
+        hunks.push(DiffHunk {
+            path: PathBuf::from(path),
+            old_start: 0,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: u32::try_from(lines).unwrap_or(u32::MAX),
+        });
+    }
+    Ok(hunks)
+}
+
+fn parse_unified_diff_hunks(diff: &str) -> Vec<DiffHunk> {
+    let mut hunks = Vec::new();
+    let mut old_path: Option<&str> = None;
+    let mut current_path: Option<PathBuf> = None;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            old_path = None;
+            current_path = None;
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            old_path = path.strip_prefix("a/");
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            current_path = path.strip_prefix("b/").or(old_path).map(PathBuf::from);
+        } else if let Some(header) = line.strip_prefix("@@ ")
+            && let Some(path) = current_path.as_ref()
+            && let Some((old, new)) = parse_hunk_header(header)
+        {
+            hunks.push(DiffHunk {
+                path: path.clone(),
+                old_start: old.0,
+                old_lines: old.1,
+                new_start: new.0,
+                new_lines: new.1,
+            });
+        }
+    }
+    hunks
+}
+
+/// Parses `-a,b +c,d @@ ...` into `((a, b), (c, d))`; a missing count means one line.
+fn parse_hunk_header(header: &str) -> Option<((u32, u32), (u32, u32))> {
+    let mut parts = header.split_whitespace();
+    let old = parse_hunk_range(parts.next()?.strip_prefix('-')?)?;
+    let new = parse_hunk_range(parts.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+fn parse_hunk_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::process::Command;
+    use tempfile::tempdir;
+
+    fn run_git_in(repo_path: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .current_dir(repo_path)
+            .args(args)
+            .status()
+            .expect("git command");
+        assert!(status.success(), "git command failed: {args:?}");
+    }
+
+    fn commit_all(repo_path: &Path, message: &str) {
+        run_git_in(repo_path, &["add", "-A"]);
+        run_git_in(
+            repo_path,
+            &[
+                "-c",
+                "user.name=Tester",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-m",
+                message,
+            ],
+        );
+    }
+
+    fn hunk(path: &str, old: (u32, u32), new: (u32, u32)) -> DiffHunk {
+        DiffHunk {
+            path: PathBuf::from(path),
+            old_start: old.0,
+            old_lines: old.1,
+            new_start: new.0,
+            new_lines: new.1,
+        }
+    }
+
+    #[test]
+    fn parses_modified_added_and_deleted_files() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3 +3 @@ fn main() {
+-    old();
++    new();
+@@ -10,0 +11,2 @@ fn helper() {
++    added();
++    added();
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,3 @@
++a
++b
++c
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1,2 +0,0 @@
+-x
+-y
+";
+
+        assert_eq!(
+            parse_unified_diff_hunks(diff),
+            vec![
+                hunk("src/lib.rs", (3, 1), (3, 1)),
+                hunk("src/lib.rs", (10, 0), (11, 2)),
+                hunk("new.txt", (0, 0), (1, 3)),
+                hunk("gone.txt", (1, 2), (0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_hunks_between_commits_and_worktree() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        run_git_in(repo, &["init", "--initial-branch=main"]);
+        run_git_in(repo, &["config", "core.autocrlf", "false"]);
+
+        std::fs::write(repo.join("file.txt"), "one\ntwo\nthree\n")?;
+        commit_all(repo, "initial");
+        std::fs::write(repo.join("file.txt"), "one\nTWO\nthree\nfour\n")?;
+        commit_all(repo, "update");
+
+        assert_eq!(
+            diff_hunks(repo, "HEAD~1", Some("HEAD"), &DiffFilter::default())?,
+            vec![
+                hunk("file.txt", (2, 1), (2, 1)),
+                hunk("file.txt", (3, 0), (4, 1)),
+            ]
+        );
+
+        std::fs::write(repo.join("file.txt"), "zero\none\nTWO\nthree\nfour\n")?;
+        assert_eq!(
+            diff_hunks(repo, "HEAD", None, &DiffFilter::default())?,
+            vec![hunk("file.txt", (0, 0), (1, 1))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn diff_hunks_apply_path_filters_and_untracked_files() -> Result<(), GitToolingError> {
+        let temp = tempdir()?;
+        let repo = temp.path();
+        run_git_in(repo, &["init", "--initial-branch=main"]);
+        run_git_in(repo, &["config", "core.autocrlf", "false"]);
+
+        std::fs::create_dir_all(repo.join("src"))?;
+        std::fs::create_dir_all(repo.join("vendor"))?;
+        std::fs::write(repo.join("src/lib.rs"), "one\n")?;
+        std::fs::write(repo.join("vendor/dep.rs"), "one\n")?;
+        std::fs::write(repo.join(".gitignore"), "*.log\n")?;
+        commit_all(repo, "initial");
+        std::fs::write(repo.join("src/lib.rs"), "one\ntwo\n")?;
+        std::fs::write(repo.join("vendor/dep.rs"), "one\ntwo\n")?;
+        commit_all(repo, "update");
+        std::fs::write(repo.join("src/new.rs"), "a\nb\nc")?;
+        std::fs::write(repo.join("vendor/new.rs"), "a\n")?;
+        std::fs::write(repo.join("src/debug.log"), "ignored\n")?;
+        std::fs::write(repo.join("src/blob.bin"), b"\0\x01")?;
+
+        let vendored = DiffFilter {
+            exclude_paths: vec!["vendor/**".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            diff_hunks(repo, "HEAD~1", Some("HEAD"), &vendored)?,
+            vec![hunk("src/lib.rs", (1, 0), (2, 1))]
+        );
+        // Untracked files only count when diffing against the working tree.
+        let untracked = DiffFilter {
+            include_untracked: true,
+            ..vendored
+        };
+        assert_eq!(
+            diff_hunks(repo, "HEAD~1", Some("HEAD"), &untracked)?,
+            vec![hunk("src/lib.rs", (1, 0), (2, 1))]
+        );
+        assert_eq!(
+            diff_hunks(repo, "HEAD", None, &untracked)?,
+            vec![hunk("src/new.rs", (0, 0), (1, 3))]
+        );
+
+        Ok(())
+    }
+}
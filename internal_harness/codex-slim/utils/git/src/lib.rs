@@ -2,7 +2,9 @@ use std::fmt;
 use std::path::PathBuf;
 
 mod apply;
+mod blob;
 mod branch;
+mod diff;
 mod errors;
 mod ghost_commits;
 mod operations;
@@ -14,7 +16,12 @@ pub use apply::apply_git_patch;
 pub use apply::extract_paths_from_patch;
 pub use apply::parse_git_apply_output;
 pub use apply::stage_paths;
+pub use blob::read_blob;
+pub use branch::merge_base;
 pub use branch::merge_base_with_head;
+pub use diff::DiffFilter;
+pub use diff::DiffHunk;
+pub use diff::diff_hunks;
 pub use errors::GitToolingError;
 pub use ghost_commits::CreateGhostCommitOptions;
 pub use ghost_commits::GhostSnapshotConfig;